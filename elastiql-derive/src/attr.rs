@@ -0,0 +1,346 @@
+//! Parses the `serde` and `elastiql` attributes of structs and their fields.
+
+use syn::{ext::IdentExt, Attribute, Ident, Lit, Meta, NestedMeta, Path};
+
+/// The attributes of a struct.
+#[derive(Default)]
+pub(crate) struct Container {
+    /// How to rename all of the fields, from `#[serde(rename_all = "...")]`.
+    pub(crate) rename_all: Option<RenameRule>,
+}
+
+impl Container {
+    /// Parses the attributes of a struct.
+    pub(crate) fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut container = Container::default();
+        for meta in metas(attrs, "serde")? {
+            if let Some(value) = string_value(&meta, "rename_all")? {
+                let rule = RenameRule::from_str(&value).ok_or_else(|| {
+                    syn::Error::new_spanned(&meta, format!("unknown rename rule `{}`", value))
+                })?;
+                container.rename_all = Some(rule);
+            }
+        }
+        Ok(container)
+    }
+}
+
+/// How a field's type relates to its own fields.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Kind {
+    /// A leaf value, e.g. a string or a number.
+    Value,
+
+    /// An object whose fields are part of the parent document.
+    Object,
+
+    /// An object that is indexed as a separate (nested) document.
+    Nested,
+}
+
+/// The attributes of a field.
+pub(crate) struct Field {
+    /// The name of the field in Elasticsearch, if it was renamed.
+    pub(crate) rename: Option<String>,
+
+    /// Whether to skip the field.
+    pub(crate) skip: bool,
+
+    /// Whether the field's fields are part of its parent, from
+    /// `#[serde(flatten)]`.
+    pub(crate) flatten: bool,
+
+    /// How the field's type relates to its own fields.
+    pub(crate) kind: Kind,
+
+    /// The data type of the field, e.g. `text`.
+    pub(crate) ty: Option<Lit>,
+
+    /// A function returning the (entire) mapping of the field.
+    pub(crate) mapping: Option<Path>,
+
+    /// Any other mapping parameters, e.g. `analyzer = "english"`.
+    pub(crate) parameters: Vec<(Ident, Lit)>,
+}
+
+impl Field {
+    /// Parses the attributes of a field.
+    pub(crate) fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut field = Field {
+            rename: None,
+            skip: false,
+            flatten: false,
+            kind: Kind::Value,
+            ty: None,
+            mapping: None,
+            parameters: Vec::new(),
+        };
+
+        for meta in metas(attrs, "serde")? {
+            if let Some(value) = string_value(&meta, "rename")? {
+                field.rename = Some(value);
+            } else if meta.path().is_ident("skip") || meta.path().is_ident("skip_serializing") {
+                field.skip = true;
+            } else if meta.path().is_ident("flatten") {
+                field.flatten = true;
+            }
+        }
+
+        // `elastiql` attributes take precedence over `serde` ones
+        for meta in metas(attrs, "elastiql")? {
+            if let Some(value) = string_value(&meta, "rename")? {
+                field.rename = Some(value);
+            } else if meta.path().is_ident("skip") {
+                field.skip = true;
+            } else if meta.path().is_ident("object") {
+                field.kind = Kind::Object;
+            } else if meta.path().is_ident("nested") {
+                field.kind = Kind::Nested;
+            } else if let Some(path) = string_value(&meta, "mapping")? {
+                let path = syn::parse_str(&path)
+                    .map_err(|err| syn::Error::new_spanned(&meta, err.to_string()))?;
+                field.mapping = Some(path);
+            } else if let Meta::NameValue(pair) = meta {
+                if pair.path.is_ident("type") {
+                    field.ty = Some(pair.lit);
+                } else if let Some(name) = pair.path.get_ident() {
+                    field.parameters.push((name.clone(), pair.lit));
+                } else {
+                    return Err(syn::Error::new_spanned(pair.path, "expected a parameter"));
+                }
+            } else {
+                return Err(syn::Error::new_spanned(meta, "unknown elastiql attribute"));
+            }
+        }
+
+        Ok(field)
+    }
+
+    /// Returns the name of the field `ident` in Elasticsearch.
+    pub(crate) fn name(&self, ident: &Ident, container: &Container) -> String {
+        match (&self.rename, container.rename_all) {
+            (Some(name), _) => name.clone(),
+            (None, Some(rule)) => rule.apply(&ident.unraw().to_string()),
+            (None, None) => ident.unraw().to_string(),
+        }
+    }
+}
+
+/// A [`#[serde(rename_all = "...")]`](https://serde.rs/container-attrs.html#rename_all)
+/// rule.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum RenameRule {
+    /// `lowercase`
+    Lower,
+    /// `UPPERCASE`
+    Upper,
+    /// `PascalCase`
+    Pascal,
+    /// `camelCase`
+    Camel,
+    /// `snake_case`
+    Snake,
+    /// `SCREAMING_SNAKE_CASE`
+    ScreamingSnake,
+    /// `kebab-case`
+    Kebab,
+    /// `SCREAMING-KEBAB-CASE`
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    /// Parses a rule by its `serde` name.
+    fn from_str(rule: &str) -> Option<Self> {
+        let rule = match rule {
+            "lowercase" => RenameRule::Lower,
+            "UPPERCASE" => RenameRule::Upper,
+            "PascalCase" => RenameRule::Pascal,
+            "camelCase" => RenameRule::Camel,
+            "snake_case" => RenameRule::Snake,
+            "SCREAMING_SNAKE_CASE" => RenameRule::ScreamingSnake,
+            "kebab-case" => RenameRule::Kebab,
+            "SCREAMING-KEBAB-CASE" => RenameRule::ScreamingKebab,
+            _ => return None,
+        };
+        Some(rule)
+    }
+
+    /// Renames a (`snake_case`) field the same way `serde` does.
+    pub(crate) fn apply(self, field: &str) -> String {
+        match self {
+            RenameRule::Lower | RenameRule::Snake => field.to_string(),
+            RenameRule::Upper | RenameRule::ScreamingSnake => field.to_ascii_uppercase(),
+            RenameRule::Pascal => {
+                let mut pascal = String::with_capacity(field.len());
+                let mut capitalize = true;
+                for c in field.chars() {
+                    if c == '_' {
+                        capitalize = true;
+                    } else if capitalize {
+                        pascal.push(c.to_ascii_uppercase());
+                        capitalize = false;
+                    } else {
+                        pascal.push(c);
+                    }
+                }
+                pascal
+            }
+            RenameRule::Camel => {
+                let pascal = RenameRule::Pascal.apply(field);
+                let mut chars = pascal.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                    None => pascal,
+                }
+            }
+            RenameRule::Kebab => field.replace('_', "-"),
+            RenameRule::ScreamingKebab => field.replace('_', "-").to_ascii_uppercase(),
+        }
+    }
+}
+
+/// Returns the items of all of the `#[name(...)]` attributes.
+fn metas(attrs: &[Attribute], name: &str) -> syn::Result<Vec<Meta>> {
+    let mut metas = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident(name)) {
+        if let Meta::List(list) = attr.parse_meta()? {
+            for nested in list.nested {
+                match nested {
+                    NestedMeta::Meta(meta) => metas.push(meta),
+                    NestedMeta::Lit(lit) => {
+                        return Err(syn::Error::new_spanned(lit, "expected an attribute"))
+                    }
+                }
+            }
+        }
+    }
+    Ok(metas)
+}
+
+/// Returns the string value of `name = "..."` or `name(serialize = "...")`
+/// if `meta` is `name`.
+fn string_value(meta: &Meta, name: &str) -> syn::Result<Option<String>> {
+    if !meta.path().is_ident(name) {
+        return Ok(None);
+    }
+    match meta {
+        Meta::NameValue(pair) => match &pair.lit {
+            Lit::Str(value) => Ok(Some(value.value())),
+            lit => Err(syn::Error::new_spanned(lit, "expected a string")),
+        },
+        Meta::List(list) => {
+            for nested in &list.nested {
+                if let NestedMeta::Meta(meta) = nested {
+                    if let Some(value) = string_value(meta, "serialize")? {
+                        return Ok(Some(value));
+                    }
+                }
+            }
+            Ok(None)
+        }
+        Meta::Path(path) => Err(syn::Error::new_spanned(path, "expected a value")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_rename_fields() {
+        let field = "created_at";
+        let cases = [
+            ("lowercase", "created_at"),
+            ("UPPERCASE", "CREATED_AT"),
+            ("PascalCase", "CreatedAt"),
+            ("camelCase", "createdAt"),
+            ("snake_case", "created_at"),
+            ("SCREAMING_SNAKE_CASE", "CREATED_AT"),
+            ("kebab-case", "created-at"),
+            ("SCREAMING-KEBAB-CASE", "CREATED-AT"),
+        ];
+        for (rule, expected) in &cases {
+            let rule = RenameRule::from_str(rule).unwrap();
+            assert_eq!(rule.apply(field), *expected, "{:?}", rule);
+        }
+        assert_eq!(RenameRule::from_str("Title Case"), None);
+    }
+
+    #[test]
+    fn can_parse_field_attributes() {
+        let input: syn::DeriveInput = syn::parse_quote! {
+            #[serde(rename_all = "camelCase")]
+            struct User {
+                #[serde(rename(serialize = "userId", deserialize = "id"))]
+                id: String,
+                #[serde(default, flatten)]
+                flattened: Extra,
+                #[serde(rename = "ignored")]
+                #[elastiql(rename = "address", nested)]
+                addresses: Vec<Address>,
+                #[serde(skip)]
+                cache: (),
+            }
+        };
+        let container = Container::from_attrs(&input.attrs).unwrap();
+        assert_eq!(container.rename_all, Some(RenameRule::Camel));
+
+        let fields = match input.data {
+            syn::Data::Struct(data) => data.fields,
+            _ => unreachable!(),
+        };
+        let fields: Vec<_> = fields
+            .iter()
+            .map(|field| Field::from_attrs(&field.attrs).unwrap())
+            .collect();
+        assert_eq!(fields[0].rename.as_deref(), Some("userId"));
+        assert!(fields[1].flatten);
+        assert_eq!(fields[2].rename.as_deref(), Some("address"));
+        assert_eq!(fields[2].kind, Kind::Nested);
+        assert!(fields[3].skip);
+    }
+
+    #[test]
+    fn can_parse_mapping_attributes() {
+        let input: syn::DeriveInput = syn::parse_quote! {
+            struct User {
+                #[elastiql(type = "text", analyzer = "english", index = false)]
+                bio: String,
+                #[elastiql(mapping = "mappings::name")]
+                name: String,
+            }
+        };
+        let fields = match input.data {
+            syn::Data::Struct(data) => data.fields,
+            _ => unreachable!(),
+        };
+        let fields: Vec<_> = fields
+            .iter()
+            .map(|field| Field::from_attrs(&field.attrs).unwrap())
+            .collect();
+
+        assert!(matches!(&fields[0].ty, Some(Lit::Str(ty)) if ty.value() == "text"));
+        let parameters: Vec<_> = fields[0]
+            .parameters
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect();
+        assert_eq!(parameters, vec!["analyzer", "index"]);
+        assert!(fields[1].mapping.is_some());
+    }
+
+    #[test]
+    fn unknown_attributes_are_errors() {
+        let input: syn::DeriveInput = syn::parse_quote! {
+            struct User {
+                #[elastiql(nope)]
+                id: String,
+            }
+        };
+        let field = match input.data {
+            syn::Data::Struct(data) => data.fields.into_iter().next().unwrap(),
+            _ => unreachable!(),
+        };
+        assert!(Field::from_attrs(&field.attrs).is_err());
+    }
+}
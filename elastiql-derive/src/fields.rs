@@ -0,0 +1,147 @@
+//! `#[derive(Fields)]`
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{ext::IdentExt, DeriveInput, GenericArgument, PathArguments, Type, TypePath};
+
+use crate::attr::{self, Kind};
+
+/// Expands `#[derive(Fields)]` for `input`.
+pub(crate) fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let fields = crate::named_fields(input, "Fields")?;
+    let container = attr::Container::from_attrs(&input.attrs)?;
+
+    let vis = &input.vis;
+    let ident = &input.ident;
+    let paths = format_ident!("{}Fields", ident);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut path_fields = Vec::new();
+    let mut path_values = Vec::new();
+    let mut constants = Vec::new();
+    for field in fields {
+        let attrs = attr::Field::from_attrs(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+
+        let field_ident = field.ident.as_ref().expect("named fields have an ident");
+        let unraw = field_ident.unraw().to_string();
+        let name = attrs.name(field_ident, &container);
+
+        if attrs.flatten {
+            let ty = inner_type(&field.ty);
+            path_fields.push(quote! {
+                #[doc = "The paths to the flattened fields."]
+                #vis #field_ident: <#ty as ::elastiql::field::Fields>::Paths
+            });
+            path_values.push(quote! {
+                #field_ident: <#ty as ::elastiql::field::Fields>::fields_at(prefix)
+            });
+            continue;
+        }
+
+        let doc = format!("The path to the `{}` field.", name);
+        let constant = format_ident!("{}", unraw.to_uppercase(), span = field_ident.span());
+        constants.push(quote! {
+            #[doc = #doc]
+            #vis const #constant: ::elastiql::field::Field = ::elastiql::field::Field::new(#name);
+        });
+
+        let path = quote! { ::elastiql::field::Field::with_prefix(prefix, #name) };
+        match attrs.kind {
+            Kind::Value => {
+                path_fields.push(quote! {
+                    #[doc = #doc]
+                    #vis #field_ident: ::elastiql::field::Field
+                });
+                path_values.push(quote! { #field_ident: #path });
+            }
+            Kind::Object | Kind::Nested => {
+                let ty = inner_type(&field.ty);
+                let doc = format!("The paths to the fields of the `{}` field.", name);
+                path_fields.push(quote! {
+                    #[doc = #doc]
+                    #vis #field_ident: <#ty as ::elastiql::field::Fields>::Paths
+                });
+                path_values.push(quote! {
+                    #field_ident: <#ty as ::elastiql::field::Fields>::fields_at(Some(&#path))
+                });
+            }
+        }
+    }
+
+    let doc = format!("The paths to the fields of [`{}`].", ident);
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Clone, Debug)]
+        #vis struct #paths {
+            #(#path_fields,)*
+        }
+
+        impl #impl_generics ::elastiql::field::Fields for #ident #ty_generics #where_clause {
+            type Paths = #paths;
+
+            fn fields_at(prefix: Option<&::elastiql::field::Field>) -> Self::Paths {
+                #paths {
+                    #(#path_values,)*
+                }
+            }
+        }
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #(#constants)*
+        }
+    })
+}
+
+/// Returns the type of the object(s) in `ty`, looking through `Option`, `Vec`
+/// and `Box`.
+pub(crate) fn inner_type(ty: &Type) -> &Type {
+    if let Type::Path(TypePath { qself: None, path }) = ty {
+        if let Some(segment) = path.segments.last() {
+            let is_wrapper = ["Option", "Vec", "Box"]
+                .iter()
+                .any(|wrapper| segment.ident == wrapper);
+            if let (true, PathArguments::AngleBracketed(arguments)) =
+                (is_wrapper, &segment.arguments)
+            {
+                if let Some(GenericArgument::Type(ty)) = arguments.args.first() {
+                    return inner_type(ty);
+                }
+            }
+        }
+    }
+    ty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_through_wrappers() {
+        let ty: Type = syn::parse_quote!(Option<Vec<Box<Address>>>);
+        let inner = inner_type(&ty);
+        assert_eq!(quote!(#inner).to_string(), "Address");
+
+        let ty: Type = syn::parse_quote!(HashMap<String, Address>);
+        let inner = inner_type(&ty);
+        assert_eq!(quote!(#inner).to_string(), quote!(#ty).to_string());
+    }
+
+    #[test]
+    fn only_structs_with_named_fields_are_supported() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct Point(f64, f64);
+        );
+        assert!(expand(&input).is_err());
+
+        let input: DeriveInput = syn::parse_quote!(
+            enum Status {
+                Active,
+            }
+        );
+        assert!(expand(&input).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+#![forbid(unsafe_code)]
+#![warn(missing_docs, clippy::all)]
+
+//! Derive macros for [elastiql].
+//!
+//! [elastiql]: https://github.com/voxjar/elastiql
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, punctuated::Punctuated, token::Comma, Data, DeriveInput};
+
+mod attr;
+mod fields;
+mod mapping;
+
+/// Derives `elastiql::field::Fields` for a struct with named fields.
+///
+/// Each field gets an associated `Field` constant named after it (e.g.
+/// `User::CREATED_AT`) and a field of the same name on the generated
+/// `{Struct}Fields` type, which is returned by `Fields::fields()`.
+///
+/// The paths respect `#[serde(rename = "...")]`, `#[serde(rename_all =
+/// "...")]`, `#[serde(skip)]` and `#[serde(flatten)]`. They can be
+/// overridden with the `elastiql` attribute:
+///
+/// * `#[elastiql(rename = "...")]` uses a different name for the field.
+/// * `#[elastiql(skip)]` skips the field.
+/// * `#[elastiql(object)]` or `#[elastiql(nested)]` includes the paths of
+///   the fields of the field's type (which must implement `Fields` too),
+///   e.g. `User::fields().address.city` is `address.city`. `Option`, `Vec`
+///   and `Box` are looked through.
+#[proc_macro_derive(Fields, attributes(elastiql))]
+pub fn derive_fields(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    fields::expand(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Derives `elastiql::mapping::Document` (and `MappingType`, as an `object`)
+/// for a struct with named fields, so `User::mapping()` returns the mapping
+/// of the documents that `User` (de)serializes.
+///
+/// The data type of each field is inferred from its Rust type via
+/// `MappingType`, e.g. a `String` is a `keyword` and a `Vec<f64>` is a
+/// `double`. The names of the fields respect the same `serde` attributes as
+/// `#[derive(Fields)]`, and the mappings can be customized with the
+/// `elastiql` attribute:
+///
+/// * `#[elastiql(type = "...", ...)]` uses a different data type (e.g.
+///   `text`, `date` or `geo_point`), with any other mapping parameters (e.g.
+///   `analyzer = "english"` or `index = false`).
+/// * `#[elastiql(mapping = "path::to::function")]` uses the mapping returned
+///   by a function, e.g. for multi-fields or `join` fields.
+/// * `#[elastiql(nested)]` maps the field as `nested` instead of `object`.
+/// * `#[elastiql(rename = "...")]` and `#[elastiql(skip)]` as above.
+#[proc_macro_derive(Mapping, attributes(elastiql))]
+pub fn derive_mapping(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    mapping::expand(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Returns the fields of a struct with named fields, which is all that
+/// `derive` (e.g. `Fields`) supports.
+fn named_fields<'a>(
+    input: &'a DeriveInput,
+    derive: &str,
+) -> syn::Result<&'a Punctuated<syn::Field, Comma>> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            syn::Fields::Named(fields) => Ok(&fields.named),
+            _ => Err(syn::Error::new_spanned(
+                input,
+                format!(
+                    "`{}` can only be derived for structs with named fields",
+                    derive
+                ),
+            )),
+        },
+        _ => Err(syn::Error::new_spanned(
+            input,
+            format!("`{}` can only be derived for structs", derive),
+        )),
+    }
+}
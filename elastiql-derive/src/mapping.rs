@@ -0,0 +1,170 @@
+//! `#[derive(Mapping)]`
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{DeriveInput, Lit};
+
+use crate::{
+    attr::{self, Kind},
+    fields::inner_type,
+};
+
+/// Expands `#[derive(Mapping)]` for `input`.
+pub(crate) fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let fields = crate::named_fields(input, "Mapping")?;
+    let container = attr::Container::from_attrs(&input.attrs)?;
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut properties = Vec::new();
+    for field in fields {
+        let attrs = attr::Field::from_attrs(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+
+        if attrs.flatten {
+            let ty = inner_type(&field.ty);
+            properties.push(quote! {
+                properties.extend(<#ty as ::elastiql::mapping::Document>::properties());
+            });
+            continue;
+        }
+
+        let field_ident = field.ident.as_ref().expect("named fields have an ident");
+        let name = attrs.name(field_ident, &container);
+        let mapping = field_mapping(&field.ty, &attrs)?;
+        properties.push(quote! {
+            properties.insert(::std::string::String::from(#name), #mapping);
+        });
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::elastiql::mapping::Document for #ident #ty_generics #where_clause {
+            #[allow(unused_mut, clippy::needless_update)]
+            fn properties() -> ::std::collections::BTreeMap<
+                ::std::string::String,
+                ::elastiql::mapping::FieldMapping,
+            > {
+                let mut properties = ::std::collections::BTreeMap::new();
+                #(#properties)*
+                properties
+            }
+        }
+
+        impl #impl_generics ::elastiql::mapping::MappingType for #ident #ty_generics #where_clause {
+            #[inline]
+            fn field_mapping() -> ::elastiql::mapping::FieldMapping {
+                ::elastiql::mapping::FieldMapping::object(
+                    <Self as ::elastiql::mapping::Document>::properties(),
+                )
+            }
+        }
+    })
+}
+
+/// Returns an expression that evaluates to the `FieldMapping` of a field.
+fn field_mapping(ty: &syn::Type, attrs: &attr::Field) -> syn::Result<TokenStream> {
+    if let Some(mapping) = &attrs.mapping {
+        return Ok(quote! { #mapping() });
+    }
+
+    let data_type = match (&attrs.ty, attrs.kind) {
+        (Some(Lit::Str(data_type)), _) => data_type.value(),
+        (Some(lit), _) => return Err(syn::Error::new_spanned(lit, "expected a string")),
+        (None, Kind::Object) => "object".to_string(),
+        (None, Kind::Nested) => "nested".to_string(),
+        (None, Kind::Value) => {
+            if let Some((name, _)) = attrs.parameters.first() {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "mapping parameters require a `type`",
+                ));
+            }
+            return Ok(quote! { <#ty as ::elastiql::mapping::MappingType>::field_mapping() });
+        }
+    };
+
+    let (variant, parameters) = match data_type.as_str() {
+        "keyword" => ("Keyword", "KeywordMapping"),
+        "text" => ("Text", "TextMapping"),
+        "long" => ("Long", "NumericMapping"),
+        "integer" => ("Integer", "NumericMapping"),
+        "short" => ("Short", "NumericMapping"),
+        "byte" => ("Byte", "NumericMapping"),
+        "double" => ("Double", "NumericMapping"),
+        "float" => ("Float", "NumericMapping"),
+        "half_float" => ("HalfFloat", "NumericMapping"),
+        "scaled_float" => ("ScaledFloat", "NumericMapping"),
+        "unsigned_long" => ("UnsignedLong", "NumericMapping"),
+        "date" => ("Date", "DateMapping"),
+        "boolean" => ("Boolean", "BooleanMapping"),
+        "object" => ("Object", "ObjectMapping"),
+        "nested" => ("Nested", "ObjectMapping"),
+        "geo_point" => ("GeoPoint", "GeoPointMapping"),
+        "join" => ("Join", "JoinMapping"),
+        "dense_vector" => ("DenseVector", "DenseVectorMapping"),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &attrs.ty,
+                format!(
+                    "unsupported data type `{}`; use `mapping = \"...\"` instead",
+                    data_type
+                ),
+            ))
+        }
+    };
+    let variant = format_ident!("{}", variant);
+    let parameters_ty = format_ident!("{}", parameters);
+
+    let mut values: Vec<_> = attrs
+        .parameters
+        .iter()
+        .map(|(name, value)| match value {
+            Lit::Str(_) => {
+                quote! { #name: ::std::option::Option::Some(::std::convert::Into::into(#value)) }
+            }
+            // so the type of numeric literals is inferred
+            _ => quote! { #name: ::std::option::Option::Some(#value) },
+        })
+        .collect();
+    if variant == "Object" || variant == "Nested" {
+        let ty = inner_type(ty);
+        values.push(quote! {
+            properties: <#ty as ::elastiql::mapping::Document>::properties()
+        });
+    }
+
+    Ok(quote! {
+        ::elastiql::mapping::FieldMapping::#variant(::elastiql::mapping::#parameters_ty {
+            #(#values,)*
+            ..::std::default::Default::default()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parameters_require_a_type() {
+        let input: DeriveInput = syn::parse_quote! {
+            struct User {
+                #[elastiql(analyzer = "english")]
+                bio: String,
+            }
+        };
+        let err = expand(&input).unwrap_err();
+        assert_eq!(err.to_string(), "mapping parameters require a `type`");
+
+        let input: DeriveInput = syn::parse_quote! {
+            struct User {
+                #[elastiql(type = "ip")]
+                ip: String,
+            }
+        };
+        assert!(expand(&input).is_err());
+    }
+}
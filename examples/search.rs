@@ -48,8 +48,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             for hit in results.hits.hits.iter() {
                 println!("{:#?}", &hit);
 
-                let user: &User = &hit.source;
-                println!("{:#?}", &user);
+                if let Some(user) = &hit.source {
+                    println!("{:#?}", user);
+                }
             }
 
             Ok(())
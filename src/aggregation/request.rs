@@ -321,7 +321,7 @@ pub struct RequestInput {
 #[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
 #[cfg_attr(feature = "graphql", graphql(name = "Aggregation"))]
 #[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
-#[derive(Clone, Debug)]
+#[derive(Default, Clone, Debug)]
 pub struct Request {
     /// The name for this aggregation.
     ///
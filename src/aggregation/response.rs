@@ -46,9 +46,9 @@ pub struct ComputedResult {
 
 // TODO: generate this with proc-macro from Aggregation struct
 /// The type of aggregation.
-#[cfg_attr(feature = "graphql", derive(async_graphql::Enum, Eq, PartialEq, Copy))]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum, Eq, PartialEq))]
 #[cfg_attr(feature = "graphql", graphql(name = "AggregationType"))]
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Clone, Copy, Debug)]
 pub enum Ty {
     /// metric
     Avg,
@@ -81,7 +81,6 @@ pub enum Ty {
     Filters,
     /// bucket
     Terms,
-    // TODO: is this `sterms`?
     /// bucket
     SignificantTerms,
     /// bucket
@@ -89,6 +88,8 @@ pub enum Ty {
     /// bucket
     DateRange,
     /// bucket
+    Histogram,
+    /// bucket
     DateHistogram,
     /// bucket
     AutoDateHistogram,
@@ -128,10 +129,11 @@ impl From<&str> for Ty {
             "value_count" => ValueCount,
             "filter" => Filter,
             "filters" => Filters,
-            "terms" => Terms,
-            "sterms" => SignificantTerms,
+            "terms" | "sterms" | "lterms" | "dterms" => Terms,
+            "significant_terms" | "sigsterms" | "siglterms" => SignificantTerms,
             "range" => Range,
             "date_range" => DateRange,
+            "histogram" => Histogram,
             "date_histogram" => DateHistogram,
             "auto_date_histogram" => AutoDateHistogram,
             "bucket_script" => BucketScript,
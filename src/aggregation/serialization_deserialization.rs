@@ -425,7 +425,7 @@ impl<'de> serde::Deserialize<'de> for ElasticAggregationResult {
                             }
                         }
                         "key_as_string" => result.key = Some(map.next_value()?),
-                        "value" => result.value = Some(map.next_value()?),
+                        "value" => result.value = map.next_value()?,
                         "buckets" => result.buckets = map.next_value()?,
                         "doc_count" => result.doc_count = Some(map.next_value()?),
                         "doc_count_error_upper_bound" | "sum_other_doc_count" | "interval" => {
@@ -549,6 +549,16 @@ mod tests {
         let _: Response = serde_json::from_value(result).unwrap();
     }
 
+    #[test]
+    fn metrics_over_no_values_are_null() {
+        let result = json!({ "aggregations": { "avg#agg": { "value": null } } });
+        let response: Response = serde_json::from_value(result).unwrap();
+        assert!(response
+            .aggregations
+            .iter()
+            .all(|agg| agg.values.is_empty()));
+    }
+
     mod aggregation_input {
         use super::*;
 
@@ -16,6 +16,7 @@ use serde::{Deserialize, Serialize};
 #[derive(async_graphql::InputObject, Serialize, Clone, Debug)]
 pub struct AutoDateHistogramAggregationInput {
     /// The field to perform the aggregation over.
+    #[cfg_attr(feature = "builder", builder(setter(into)))]
     pub field: String,
 
     /// Target number of buckets needed; the interval of the buckets is
@@ -62,6 +63,7 @@ pub struct AutoDateHistogramAggregationInput {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct AutoDateHistogramAggregation {
     /// The field to perform the aggregation over.
+    #[cfg_attr(feature = "builder", builder(setter(into)))]
     pub field: String,
 
     /// Target number of buckets needed; the interval of the buckets is
@@ -165,8 +165,8 @@ impl From<DateHistogramAggregationInput> for DateHistogramAggregation {
 
 /// Calendar aware interval.
 #[cfg_attr(all(test, not(feature = "graphql")), derive(PartialEq))]
-#[cfg_attr(feature = "graphql", derive(async_graphql::Enum, Eq, PartialEq, Copy))]
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum, Eq, PartialEq))]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum CalendarInterval {
     /// One *minute* is the interval between `00` seconds of the first minute
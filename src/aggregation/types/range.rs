@@ -94,12 +94,12 @@ pub struct RangeInput {
     /// The value to return results *from* and including.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    from: Option<f64>,
+    pub from: Option<f64>,
 
     /// The value to return results up *to* but *not* including.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    to: Option<f64>,
+    pub to: Option<f64>,
 }
 
 /// A range/span of data.
@@ -111,12 +111,46 @@ pub struct Range {
     /// The value to return results *from* and including.
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    from: Option<f64>,
+    pub from: Option<f64>,
 
     /// The value to return results up *to* but *not* including.
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    to: Option<f64>,
+    pub to: Option<f64>,
+}
+
+impl Range {
+    /// Returns the key of the bucket of this range in the response, e.g.
+    /// `*-100.0` or `100.0-200.0`.
+    #[inline]
+    pub fn key(&self) -> String {
+        format!(
+            "{}-{}",
+            self.from.map_or_else(|| "*".to_string(), format_double),
+            self.to.map_or_else(|| "*".to_string(), format_double),
+        )
+    }
+}
+
+/// Formats a number the same way Java formats a `double`, e.g. `100.0` or
+/// `1.5E12`, as Elasticsearch uses this for the keys of `range` buckets.
+fn format_double(value: f64) -> String {
+    if value == 0.0 || (1e-3..1e7).contains(&value.abs()) {
+        let formatted = value.to_string();
+        if formatted.contains('.') {
+            formatted
+        } else {
+            format!("{}.0", formatted)
+        }
+    } else {
+        let formatted = format!("{:E}", value);
+        match formatted.find('E') {
+            Some(i) if !formatted[..i].contains('.') => {
+                format!("{}.0{}", &formatted[..i], &formatted[i..])
+            }
+            _ => formatted,
+        }
+    }
 }
 
 // TODO: generate this with a proc-macro?
@@ -130,3 +164,30 @@ impl From<RangeInput> for Range {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_doubles_like_java() {
+        assert_eq!(format_double(100.0), "100.0");
+        assert_eq!(format_double(-0.5), "-0.5");
+        assert_eq!(format_double(1.5778368e12), "1.5778368E12");
+        assert_eq!(format_double(1e7), "1.0E7");
+    }
+
+    #[test]
+    fn can_compute_keys() {
+        let range = Range {
+            from: Some(100.0),
+            to: None,
+        };
+        assert_eq!(range.key(), "100.0-*");
+        let range = Range {
+            from: None,
+            to: Some(0.5),
+        };
+        assert_eq!(range.key(), "*-0.5");
+    }
+}
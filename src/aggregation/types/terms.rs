@@ -23,6 +23,7 @@ use crate::search::ScriptInput;
 #[derive(async_graphql::InputObject, Serialize, Clone, Debug)]
 pub struct TermsAggregationInput {
     /// The field to perform the aggregation over.
+    #[cfg_attr(feature = "builder", builder(setter(into)))]
     pub field: Option<String>,
 
     /// The script to use.
@@ -64,6 +65,7 @@ pub struct TermsAggregationInput {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct TermsAggregation {
     /// The field to perform the aggregation over.
+    #[cfg_attr(feature = "builder", builder(setter(into)))]
     pub field: Option<String>,
 
     /// The script to use.
@@ -48,9 +48,8 @@ pub enum Action<T> {
 #[derive(Deserialize, Debug)]
 #[serde(untagged)]
 pub enum Item<T> {
-    /// An `Ok` item.
-    Ok(OkItem<T>),
-
+    // NOTE: `Err` must come first, as failed items have the fields of an
+    // `OkItem` too
     /// An `Err` item.
     Err {
         /// The error.
@@ -59,6 +58,9 @@ pub enum Item<T> {
         /// The [HTTP status code](https://developer.mozilla.org/en-US/docs/Web/HTTP/Status).
         status: u16,
     },
+
+    /// An `Ok` item.
+    Ok(OkItem<T>),
 }
 
 /// An individual item from performing a successful bulk operation.
@@ -85,3 +87,42 @@ pub struct Get<T> {
     #[serde(rename = "_source")]
     pub source: Option<T>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn can_deserialize_failed_items() {
+        let j = json!({
+            "took": 3,
+            "errors": true,
+            "items": [
+                { "index": { "_index": "users", "_id": "1", "status": 201, "result": "created" } },
+                { "create": {
+                    "_index": "users",
+                    "_id": "2",
+                    "status": 409,
+                    "error": {
+                        "type": "version_conflict_engine_exception",
+                        "reason": "[2]: version conflict, document already exists (current version [1])",
+                        "index": "users",
+                        "index_uuid": "3YfD7_0mQ6yCnV8T2u0mCg"
+                    }
+                } }
+            ]
+        });
+
+        let response: Response<serde_json::Value> = serde_json::from_value(j).unwrap();
+        assert!(matches!(&response.items[0], Action::Index(Item::Ok(item)) if item.id == "1"));
+        match &response.items[1] {
+            Action::Create(Item::Err { error, status }) => {
+                assert_eq!(*status, 409);
+                assert_eq!(error.ty, "version_conflict_engine_exception");
+            }
+            item => panic!("expected a failed item, got {:?}", item),
+        }
+    }
+}
@@ -0,0 +1,1081 @@
+//! Limits on the complexity and cost of requests built from untrusted input.
+//!
+//! Nothing stops a client from sending deeply nested `bool` queries, thousands
+//! of `terms` values, leading-wildcard `regexp` queries or aggregations that
+//! would produce millions of buckets. [`ComplexityLimits`] rejects such
+//! requests *before* they are sent to Elasticsearch.
+//!
+//! Like an [`AccessPolicy`], limits are checked against the *converted*
+//! [`search::Request`] and [`aggregation::Request`] types and report every
+//! offending clause along with its path in the request body.
+//!
+//! ```
+//! use elastiql::complexity::{ComplexityLimits, ViolationKind};
+//! use elastiql::search::query::{CompoundQuery, RegexpQuery};
+//!
+//! let mut query = CompoundQuery::default();
+//! query.push(RegexpQuery::new("name", ".*son", None));
+//!
+//! let violations = ComplexityLimits::default().check_query(&query).unwrap_err();
+//! assert_eq!(violations[0].path, "query.bool.filter[0].regexp");
+//! assert_eq!(violations[0].kind, ViolationKind::LeadingWildcard);
+//! ```
+//!
+//! [`AccessPolicy`]: crate::policy::AccessPolicy
+//! [`search::Request`]: crate::search::Request
+//! [`aggregation::Request`]: crate::aggregation::Request
+
+use std::{convert::TryFrom, fmt};
+
+use crate::{
+    aggregation,
+    search::{
+        self,
+        query::{BooleanQuery, CompoundQuery, Query, QueryStringQuery, RegexpQuery},
+        InnerHits,
+    },
+};
+
+/// Limits on the complexity of a request.
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[derive(Clone, Debug)]
+pub struct ComplexityLimits {
+    /// The maximum depth of nested `bool`/`nested` queries, where the top-level
+    /// `bool` query has a depth of `1`.
+    ///
+    /// Defaults to `20`.
+    #[cfg_attr(feature = "builder", builder(default = 20))]
+    pub max_depth: usize,
+
+    /// The maximum total number of query clauses in the request, including
+    /// those in `filter` aggregations.
+    ///
+    /// Defaults to `1024`, the default of Elasticsearch's
+    /// [`indices.query.bool.max_clause_count`] setting.
+    ///
+    /// [`indices.query.bool.max_clause_count`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-settings.html
+    #[cfg_attr(feature = "builder", builder(default = 1024))]
+    pub max_clause_count: usize,
+
+    /// The maximum number of values in a single `terms` query.
+    ///
+    /// Defaults to `65536`, the default of Elasticsearch's
+    /// [`index.max_terms_count`] setting.
+    ///
+    /// [`index.max_terms_count`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/index-modules.html#dynamic-index-settings
+    #[cfg_attr(feature = "builder", builder(default = 65_536))]
+    pub max_terms: usize,
+
+    /// The maximum depth of nested aggregations, where a top-level aggregation
+    /// has a depth of `1`.
+    ///
+    /// Defaults to `5`.
+    #[cfg_attr(feature = "builder", builder(default = 5))]
+    pub max_aggregation_depth: usize,
+
+    /// The maximum (estimated) number of buckets the aggregations may return,
+    /// or `None` to not limit the number of buckets.
+    ///
+    /// The estimate is based on the `size` of `terms` aggregations, the number
+    /// of `ranges` and, for `histogram` aggregations with `hard_bounds`, the
+    /// number of intervals between the bounds. The number of buckets of a
+    /// `histogram` without `hard_bounds` (`extended_bounds` don't limit the
+    /// buckets) or of a `date_histogram` depends on the data and can't be
+    /// estimated, so they are rejected; an `auto_date_histogram` can be used
+    /// instead.
+    ///
+    /// Defaults to `65535`, the default of Elasticsearch's
+    /// [`search.max_buckets`] setting.
+    ///
+    /// [`search.max_buckets`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-settings.html#search-settings-max-buckets
+    #[cfg_attr(feature = "builder", builder(default = Some(65_535)))]
+    pub max_buckets: Option<u64>,
+
+    /// The maximum value of `from + size` of the search request.
+    ///
+    /// Defaults to `10000`, the default of Elasticsearch's
+    /// [`index.max_result_window`] setting.
+    ///
+    /// [`index.max_result_window`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/index-modules.html#dynamic-index-settings
+    #[cfg_attr(feature = "builder", builder(default = 10_000))]
+    pub max_result_window: u64,
+
+    /// The maximum value of `from + size` of the `inner_hits` of `nested`
+    /// queries and of `collapse`.
+    ///
+    /// Defaults to `100`, the default of Elasticsearch's
+    /// [`index.max_inner_result_window`] setting.
+    ///
+    /// [`index.max_inner_result_window`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/index-modules.html#dynamic-index-settings
+    #[cfg_attr(feature = "builder", builder(default = 100))]
+    pub max_inner_result_window: u64,
+
+    /// The maximum number of `inner_hits` of `collapse`, each of which is
+    /// retrieved by another search per collapsed hit.
+    ///
+    /// Defaults to `5`.
+    #[cfg_attr(feature = "builder", builder(default = 5))]
+    pub max_collapse_inner_hits: usize,
+
+    /// Whether or not leading wildcards are allowed, i.e. `regexp` queries
+    /// without a literal prefix (e.g. `.*son` or `[a-z]+son`) and
+    /// `query_string` queries with `allow_leading_wildcard` set or with such a
+    /// regular expression in their text (e.g. `name:/.*son/`).
+    ///
+    /// Defaults to `false`.
+    #[cfg_attr(feature = "builder", builder(default))]
+    pub allow_leading_wildcards: bool,
+
+    /// Whether or not [expensive queries] (`regexp`, `prefix` and
+    /// `query_string`) are allowed. Raw queries can't be inspected, so they
+    /// count as expensive.
+    ///
+    /// Defaults to `true`.
+    ///
+    /// [expensive queries]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl.html#query-dsl-allow-expensive-queries
+    #[cfg_attr(feature = "builder", builder(default = true))]
+    pub allow_expensive_queries: bool,
+}
+
+impl Default for ComplexityLimits {
+    #[inline]
+    fn default() -> Self {
+        ComplexityLimits {
+            max_depth: 20,
+            max_clause_count: 1024,
+            max_terms: 65_536,
+            max_aggregation_depth: 5,
+            max_buckets: Some(65_535),
+            max_result_window: 10_000,
+            max_inner_result_window: 100,
+            max_collapse_inner_hits: 5,
+            allow_leading_wildcards: false,
+            allow_expensive_queries: true,
+        }
+    }
+}
+
+/// The limit that was exceeded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// The query is nested deeper than `max_depth`.
+    Depth {
+        /// The maximum allowed depth.
+        max: usize,
+    },
+
+    /// The request has more than `max_clause_count` query clauses.
+    ClauseCount {
+        /// The maximum allowed number of clauses.
+        max: usize,
+
+        /// The actual number of clauses.
+        actual: usize,
+    },
+
+    /// A `terms` query has more than `max_terms` values.
+    Terms {
+        /// The maximum allowed number of values.
+        max: usize,
+
+        /// The actual number of values.
+        actual: usize,
+    },
+
+    /// The aggregations are nested deeper than `max_aggregation_depth`.
+    AggregationDepth {
+        /// The maximum allowed depth.
+        max: usize,
+    },
+
+    /// The aggregations would return more than `max_buckets` buckets.
+    Buckets {
+        /// The maximum allowed number of buckets.
+        max: u64,
+
+        /// The estimated number of buckets.
+        estimated: u64,
+    },
+
+    /// The number of buckets of an aggregation can't be estimated, e.g. of a
+    /// `histogram` without `hard_bounds`, while `max_buckets` is set.
+    UnboundedBuckets,
+
+    /// The `from + size` of the request or its `inner_hits` exceeds
+    /// `max_result_window` or `max_inner_result_window`, respectively.
+    ResultWindow {
+        /// The maximum allowed `from + size`.
+        max: u64,
+
+        /// The actual `from + size`.
+        actual: u64,
+    },
+
+    /// The `collapse` has more than `max_collapse_inner_hits` inner hits.
+    InnerHits {
+        /// The maximum allowed number of inner hits.
+        max: usize,
+
+        /// The actual number of inner hits.
+        actual: usize,
+    },
+
+    /// The query uses a leading wildcard.
+    LeadingWildcard,
+
+    /// The query is an expensive query.
+    ExpensiveQuery,
+}
+
+/// A single clause of a request that exceeds the [`ComplexityLimits`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// The path to the offending clause in the request body, e.g.
+    /// `query.bool.filter[0].terms` or `aggs.by_owner.terms`.
+    pub path: String,
+
+    /// The limit that was exceeded.
+    pub kind: ViolationKind,
+}
+
+impl Violation {
+    /// Creates a new `Violation`.
+    #[inline]
+    fn new(path: impl Into<String>, kind: ViolationKind) -> Self {
+        Violation {
+            path: path.into(),
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.path)?;
+        match &self.kind {
+            ViolationKind::Depth { max } => write!(f, "query is nested deeper than {}", max),
+            ViolationKind::ClauseCount { max, actual } => {
+                write!(f, "{} query clauses exceeds the maximum of {}", actual, max)
+            }
+            ViolationKind::Terms { max, actual } => {
+                write!(f, "{} terms exceeds the maximum of {}", actual, max)
+            }
+            ViolationKind::AggregationDepth { max } => {
+                write!(f, "aggregations are nested deeper than {}", max)
+            }
+            ViolationKind::Buckets { max, estimated } => write!(
+                f,
+                "an estimated {} buckets exceeds the maximum of {}",
+                estimated, max
+            ),
+            ViolationKind::UnboundedBuckets => {
+                f.write_str("the number of buckets can't be estimated, e.g. without `hard_bounds`")
+            }
+            ViolationKind::ResultWindow { max, actual } => write!(
+                f,
+                "a result window (`from + size`) of {} exceeds the maximum of {}",
+                actual, max
+            ),
+            ViolationKind::InnerHits { max, actual } => {
+                write!(f, "{} inner hits exceeds the maximum of {}", actual, max)
+            }
+            ViolationKind::LeadingWildcard => f.write_str("leading wildcards are not allowed"),
+            ViolationKind::ExpensiveQuery => f.write_str("expensive queries are not allowed"),
+        }
+    }
+}
+
+impl std::error::Error for Violation {}
+
+/// The state accumulated while checking a request.
+#[derive(Default)]
+struct Checker {
+    /// The number of query clauses seen so far.
+    clauses: usize,
+
+    /// The violations found so far.
+    violations: Vec<Violation>,
+}
+
+impl ComplexityLimits {
+    /// Checks a search request against these limits, returning every violation.
+    pub fn check_search(&self, request: &search::Request) -> Result<(), Vec<Violation>> {
+        let mut checker = Checker::default();
+        self.visit_compound(&request.query, "query".to_string(), 0, &mut checker);
+        let path = "post_filter".to_string();
+        self.visit_compound(&request.post_filter, path, 0, &mut checker);
+
+        // https://www.elastic.co/guide/en/elasticsearch/reference/current/search-search.html#search-search-api-query-params
+        let window = request
+            .from
+            .unwrap_or(0)
+            .saturating_add(request.size.unwrap_or(10));
+        if window > self.max_result_window {
+            checker.violations.push(Violation::new(
+                "size",
+                ViolationKind::ResultWindow {
+                    max: self.max_result_window,
+                    actual: window,
+                },
+            ));
+        }
+
+        if let Some(collapse) = &request.collapse {
+            let actual = collapse.inner_hits.len();
+            if actual > self.max_collapse_inner_hits {
+                checker.violations.push(Violation::new(
+                    "collapse.inner_hits",
+                    ViolationKind::InnerHits {
+                        max: self.max_collapse_inner_hits,
+                        actual,
+                    },
+                ));
+            }
+            for (i, inner_hits) in collapse.inner_hits.iter().enumerate() {
+                let path = format!("collapse.inner_hits[{}]", i);
+                self.check_inner_hits(inner_hits, path, &mut checker);
+            }
+        }
+
+        self.finish("query", checker)
+    }
+
+    /// Checks a query against these limits, returning every violation.
+    pub fn check_query(&self, query: &CompoundQuery) -> Result<(), Vec<Violation>> {
+        let mut checker = Checker::default();
+        self.visit_compound(query, "query".to_string(), 0, &mut checker);
+        self.finish("query", checker)
+    }
+
+    /// Checks a list of (sibling) aggregations against these limits, returning
+    /// every violation.
+    pub fn check_aggregations(
+        &self,
+        aggregations: &[aggregation::Request],
+    ) -> Result<(), Vec<Violation>> {
+        let mut checker = Checker::default();
+
+        let mut total: u64 = 0;
+        for aggregation in aggregations {
+            let path = format!("aggs.{}", aggregation.name);
+            let estimated = self.visit_aggregation(aggregation, path.clone(), 1, &mut checker);
+            if let Some(max) = self.max_buckets.filter(|&max| estimated > max) {
+                checker.violations.push(Violation::new(
+                    path,
+                    ViolationKind::Buckets { max, estimated },
+                ));
+            }
+            total = total.saturating_add(estimated);
+        }
+
+        let reported = checker
+            .violations
+            .iter()
+            .any(|v| matches!(v.kind, ViolationKind::Buckets { .. }));
+        if let Some(max) = self.max_buckets.filter(|&max| !reported && total > max) {
+            checker.violations.push(Violation::new(
+                "aggs",
+                ViolationKind::Buckets {
+                    max,
+                    estimated: total,
+                },
+            ));
+        }
+
+        self.finish("aggs", checker)
+    }
+
+    /// Checks the total clause count and converts the violations into a
+    /// `Result`.
+    fn finish(&self, path: &str, mut checker: Checker) -> Result<(), Vec<Violation>> {
+        if checker.clauses > self.max_clause_count {
+            checker.violations.push(Violation::new(
+                path,
+                ViolationKind::ClauseCount {
+                    max: self.max_clause_count,
+                    actual: checker.clauses,
+                },
+            ));
+        }
+
+        if checker.violations.is_empty() {
+            Ok(())
+        } else {
+            Err(checker.violations)
+        }
+    }
+
+    /// Visits every clause of a compound query.
+    fn visit_compound(
+        &self,
+        query: &CompoundQuery,
+        path: String,
+        depth: usize,
+        checker: &mut Checker,
+    ) {
+        if let Some(boolean) = &query.boolean {
+            self.visit_boolean(boolean, format!("{}.bool", path), depth + 1, checker);
+        }
+    }
+
+    /// Visits every clause of a boolean query at the given `depth`.
+    fn visit_boolean(
+        &self,
+        boolean: &BooleanQuery,
+        path: String,
+        depth: usize,
+        checker: &mut Checker,
+    ) {
+        if depth > self.max_depth {
+            checker.violations.push(Violation::new(
+                path,
+                ViolationKind::Depth {
+                    max: self.max_depth,
+                },
+            ));
+            return;
+        }
+
+        let occurrences = [
+            ("must", &boolean.must),
+            ("filter", &boolean.filter),
+            ("should", &boolean.should),
+            ("must_not", &boolean.must_not),
+        ];
+
+        for (occurrence, queries) in occurrences.iter() {
+            for (i, query) in queries.iter().enumerate() {
+                let path = format!("{}.{}[{}]", path, occurrence, i);
+                self.visit_query(query, path, depth, checker);
+            }
+        }
+    }
+
+    /// Visits a single query (a clause of a boolean query at `depth`).
+    fn visit_query(&self, query: &Query, path: String, depth: usize, checker: &mut Checker) {
+        checker.clauses += 1;
+
+        if let Some(boolean) = &query.boolean {
+            self.visit_boolean(boolean, format!("{}.bool", path), depth + 1, checker);
+        }
+
+        if let Some(nested) = &query.nested {
+            if let Some(inner_hits) = &nested.inner_hits {
+                let path = format!("{}.nested.inner_hits", path);
+                self.check_inner_hits(inner_hits, path, checker);
+            }
+            let path = format!("{}.nested.query", path);
+            self.visit_compound(&nested.query, path, depth, checker);
+        }
+
+        if let Some(terms) = &query.terms {
+            if terms.values.len() > self.max_terms {
+                checker.violations.push(Violation::new(
+                    format!("{}.terms", path),
+                    ViolationKind::Terms {
+                        max: self.max_terms,
+                        actual: terms.values.len(),
+                    },
+                ));
+            }
+        }
+
+        if let Some(regexp) = &query.regexp {
+            let path = format!("{}.regexp", path);
+            if !self.allow_expensive_queries {
+                checker
+                    .violations
+                    .push(Violation::new(path, ViolationKind::ExpensiveQuery));
+            } else if !self.allow_leading_wildcards && has_leading_wildcard(regexp) {
+                checker
+                    .violations
+                    .push(Violation::new(path, ViolationKind::LeadingWildcard));
+            }
+        }
+
+        if query.prefix.is_some() && !self.allow_expensive_queries {
+            checker.violations.push(Violation::new(
+                format!("{}.prefix", path),
+                ViolationKind::ExpensiveQuery,
+            ));
+        }
+
+        if let Some(query_string) = &query.query_string {
+            let path = format!("{}.query_string", path);
+            if !self.allow_expensive_queries {
+                checker
+                    .violations
+                    .push(Violation::new(path, ViolationKind::ExpensiveQuery));
+            } else if !self.allow_leading_wildcards
+                && (query_string.allow_leading_wildcard || has_leading_wildcard_regex(query_string))
+            {
+                checker
+                    .violations
+                    .push(Violation::new(path, ViolationKind::LeadingWildcard));
+            }
+        }
+
+        if query.raw_query.is_some() && !self.allow_expensive_queries {
+            checker
+                .violations
+                .push(Violation::new(path, ViolationKind::ExpensiveQuery));
+        }
+    }
+
+    /// Checks the `from + size` of inner hits.
+    fn check_inner_hits(&self, inner_hits: &InnerHits, path: String, checker: &mut Checker) {
+        // https://www.elastic.co/guide/en/elasticsearch/reference/current/inner-hits.html#inner-hits-options
+        let window = inner_hits
+            .from
+            .unwrap_or(0)
+            .saturating_add(inner_hits.size.unwrap_or(3));
+        if window > self.max_inner_result_window {
+            checker.violations.push(Violation::new(
+                path,
+                ViolationKind::ResultWindow {
+                    max: self.max_inner_result_window,
+                    actual: window,
+                },
+            ));
+        }
+    }
+
+    /// Visits a single aggregation at the given `depth` and its
+    /// sub-aggregations, returning the estimated number of buckets it returns.
+    fn visit_aggregation(
+        &self,
+        aggregation: &aggregation::Request,
+        path: String,
+        depth: usize,
+        checker: &mut Checker,
+    ) -> u64 {
+        if depth > self.max_aggregation_depth {
+            checker.violations.push(Violation::new(
+                path,
+                ViolationKind::AggregationDepth {
+                    max: self.max_aggregation_depth,
+                },
+            ));
+            return 0;
+        }
+
+        if let Some(query) = &aggregation.filters {
+            self.visit_compound(query, format!("{}.filter", path), 0, checker);
+        }
+
+        let buckets = match estimate_buckets(aggregation) {
+            Some(buckets) => buckets,
+            None if self.max_buckets.is_some() => {
+                checker.violations.push(Violation::new(
+                    path.clone(),
+                    ViolationKind::UnboundedBuckets,
+                ));
+                1
+            }
+            None => 1,
+        };
+
+        let mut children: u64 = 0;
+        for sub_aggregation in aggregation.aggregations.iter().flatten() {
+            let path = format!("{}.aggs.{}", path, sub_aggregation.name);
+            let estimated = self.visit_aggregation(sub_aggregation, path, depth + 1, checker);
+            children = children.saturating_add(estimated);
+        }
+
+        // every bucket contains the buckets of each sub-aggregation
+        buckets.saturating_add(buckets.saturating_mul(children))
+    }
+}
+
+/// Returns the number of buckets a single aggregation (excluding its
+/// sub-aggregations) is estimated to return, or `None` if it depends on the
+/// data.
+fn estimate_buckets(aggregation: &aggregation::Request) -> Option<u64> {
+    if let Some(terms) = &aggregation.terms {
+        // https://www.elastic.co/guide/en/elasticsearch/reference/current/search-aggregations-bucket-terms-aggregation.html#search-aggregations-bucket-terms-aggregation-size
+        Some(terms.size.unwrap_or(10))
+    } else if let Some(range) = &aggregation.range {
+        Some(u64::try_from(range.ranges.len()).unwrap_or(u64::MAX))
+    } else if let Some(range) = &aggregation.date_range {
+        Some(u64::try_from(range.ranges.len()).unwrap_or(u64::MAX))
+    } else if let Some(histogram) = &aggregation.histogram {
+        // only `hard_bounds` limit the buckets, `extended_bounds` add to them
+        match (&histogram.hard_bounds, histogram.interval) {
+            (Some(bounds), Some(interval)) if interval > 0.0 && bounds.max >= bounds.min => {
+                let intervals = ((bounds.max - bounds.min) / interval).floor();
+                // saturates at `u64::MAX`
+                #[allow(clippy::as_conversions)]
+                let intervals = intervals as u64;
+                Some(intervals.saturating_add(1))
+            }
+            _ => None,
+        }
+    } else if let Some(histogram) = &aggregation.auto_date_histogram {
+        Some(histogram.buckets.unwrap_or(10))
+    } else if let Some(histogram) = &aggregation.variable_width_histogram {
+        Some(histogram.buckets)
+    } else if aggregation.date_histogram.is_some() {
+        None
+    } else if aggregation.filters.is_some()
+        || aggregation.nested.is_some()
+        || aggregation.reverse_nested.is_some()
+    {
+        Some(1)
+    } else {
+        // metric & pipeline aggregations don't return any buckets
+        Some(0)
+    }
+}
+
+/// Returns `true` if a `regexp` query does not have a literal prefix, e.g.
+/// `.*son` or `[a-z]+son`. An invalid regular expression is assumed to.
+#[inline]
+fn has_leading_wildcard(query: &RegexpQuery) -> bool {
+    match query.parse() {
+        Ok(regexp) => regexp.has_leading_wildcard(),
+        Err(_) => true,
+    }
+}
+
+/// Returns `true` if any regular expression in the text of a `query_string`
+/// query (e.g. `name:/.*son/`) doesn't have a literal prefix, which
+/// Elasticsearch runs regardless of `allow_leading_wildcard`. Text that can't
+/// be parsed is assumed to.
+fn has_leading_wildcard_regex(query: &QueryStringQuery) -> bool {
+    match query.regexes() {
+        Ok(regexes) => regexes
+            .into_iter()
+            .any(|regex| has_leading_wildcard(&RegexpQuery::new("", regex, None))),
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::aggregation::types::{
+        CalendarInterval, DateHistogramAggregation, HistogramAggregation, HistogramBounds,
+        TermsAggregation,
+    };
+    use crate::search::query::{NestedQuery, TermQuery, TermsQuery};
+
+    fn nested_bool(depth: usize) -> CompoundQuery {
+        let mut query: Query = TermQuery::new("status", "active").into();
+        for _ in 1..depth {
+            let boolean = BooleanQuery::builder().filter(vec![query]).build();
+            query = Query::builder().boolean(boolean).build();
+        }
+        let mut compound = CompoundQuery::default();
+        compound.push(query);
+        compound
+    }
+
+    fn terms(name: &str, size: u64) -> aggregation::Request {
+        aggregation::Request::builder()
+            .name(name)
+            .terms(Some(
+                TermsAggregation::builder()
+                    .field(Some(name.to_string()))
+                    .script(None)
+                    .size(size)
+                    .build(),
+            ))
+            .build()
+    }
+
+    #[test]
+    fn default_allows_simple_queries() {
+        let mut query = CompoundQuery::default();
+        query.push(TermQuery::new("status", "active"));
+        query.push(TermsQuery::new("tags", vec!["a", "b"]));
+        query.push(RegexpQuery::new("name", "jo.*", None));
+
+        assert_eq!(ComplexityLimits::default().check_query(&query), Ok(()));
+    }
+
+    #[test]
+    fn limits_depth() {
+        let limits = ComplexityLimits {
+            max_depth: 3,
+            ..ComplexityLimits::default()
+        };
+
+        assert_eq!(limits.check_query(&nested_bool(2)), Ok(()));
+        assert_eq!(limits.check_query(&nested_bool(3)), Ok(()));
+
+        let violations = limits.check_query(&nested_bool(4)).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "query.bool.filter[0].bool.filter[0].bool.filter[0].bool",
+                ViolationKind::Depth { max: 3 }
+            )]
+        );
+    }
+
+    #[test]
+    fn nested_queries_count_towards_depth() {
+        let limits = ComplexityLimits {
+            max_depth: 2,
+            ..ComplexityLimits::default()
+        };
+
+        let mut query = CompoundQuery::default();
+        query.push(NestedQuery::new(
+            "comments",
+            NestedQuery::new("comments.replies", TermQuery::new("a", "b"), false),
+            false,
+        ));
+
+        let violations = limits.check_query(&query).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "query.bool.filter[0].nested.query.bool.filter[0].nested.query.bool",
+                ViolationKind::Depth { max: 2 }
+            )]
+        );
+    }
+
+    #[test]
+    fn limits_clause_count() {
+        let mut query = CompoundQuery::default();
+        for i in 0..1025 {
+            query.push(TermQuery::new("id", i.to_string()));
+        }
+
+        let violations = ComplexityLimits::default().check_query(&query).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "query",
+                ViolationKind::ClauseCount {
+                    max: 1024,
+                    actual: 1025
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn limits_terms() {
+        let limits = ComplexityLimits {
+            max_terms: 2,
+            ..ComplexityLimits::default()
+        };
+
+        let mut query = CompoundQuery::default();
+        query.push(TermsQuery::new("tags", vec!["a", "b", "c"]));
+
+        let violations = limits.check_query(&query).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "query.bool.filter[0].terms",
+                ViolationKind::Terms { max: 2, actual: 3 }
+            )]
+        );
+    }
+
+    #[test]
+    fn bans_leading_wildcards() {
+        let mut query = CompoundQuery::default();
+        query.push(RegexpQuery::new("name", ".*son", None));
+        for pattern in &["(.*)son", "[a-z]*son", "~(a)", "a*.*"] {
+            query.push(RegexpQuery::new("name", *pattern, None));
+        }
+        query.push(
+            QueryStringQuery::builder()
+                .query("*son")
+                .fields(vec![])
+                .allow_leading_wildcard(true)
+                .build(),
+        );
+        for text in &["status:active AND name:/.*son/", "name:/jo.*/"] {
+            query.push(
+                QueryStringQuery::builder()
+                    .query(*text)
+                    .fields(vec![])
+                    .allow_leading_wildcard(false)
+                    .build(),
+            );
+        }
+
+        let violations = ComplexityLimits::default().check_query(&query).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                Violation::new(
+                    "query.bool.filter[0].regexp",
+                    ViolationKind::LeadingWildcard
+                ),
+                Violation::new(
+                    "query.bool.filter[1].regexp",
+                    ViolationKind::LeadingWildcard
+                ),
+                Violation::new(
+                    "query.bool.filter[2].regexp",
+                    ViolationKind::LeadingWildcard
+                ),
+                Violation::new(
+                    "query.bool.filter[3].regexp",
+                    ViolationKind::LeadingWildcard
+                ),
+                Violation::new(
+                    "query.bool.filter[4].regexp",
+                    ViolationKind::LeadingWildcard
+                ),
+                Violation::new(
+                    "query.bool.filter[5].query_string",
+                    ViolationKind::LeadingWildcard
+                ),
+                Violation::new(
+                    "query.bool.filter[6].query_string",
+                    ViolationKind::LeadingWildcard
+                ),
+            ]
+        );
+
+        let limits = ComplexityLimits {
+            allow_leading_wildcards: true,
+            ..ComplexityLimits::default()
+        };
+        assert_eq!(limits.check_query(&query), Ok(()));
+    }
+
+    #[test]
+    fn bans_expensive_queries() {
+        let limits = ComplexityLimits {
+            allow_expensive_queries: false,
+            ..ComplexityLimits::default()
+        };
+
+        let mut query = CompoundQuery::default();
+        query.push(TermQuery::new("status", "active"));
+        query.push(RegexpQuery::new("name", "jo.*", None));
+        query.push(Query::raw(json!({ "wildcard": { "name": "*son" } })));
+
+        let violations = limits.check_query(&query).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                Violation::new("query.bool.filter[1].regexp", ViolationKind::ExpensiveQuery),
+                Violation::new("query.bool.filter[2]", ViolationKind::ExpensiveQuery),
+            ]
+        );
+    }
+
+    #[test]
+    fn limits_aggregation_depth() {
+        let limits = ComplexityLimits {
+            max_aggregation_depth: 1,
+            ..ComplexityLimits::default()
+        };
+
+        let mut aggregation = terms("a", 1);
+        aggregation.aggregations = Some(vec![terms("b", 1)]);
+
+        let violations = limits.check_aggregations(&[aggregation]).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "aggs.a.aggs.b",
+                ViolationKind::AggregationDepth { max: 1 }
+            )]
+        );
+    }
+
+    #[test]
+    fn estimates_buckets() {
+        let limits = ComplexityLimits {
+            max_buckets: Some(1_000),
+            ..ComplexityLimits::default()
+        };
+
+        // 10 + 10 * 50
+        let mut aggregation = terms("a", 10);
+        aggregation.aggregations = Some(vec![terms("b", 50)]);
+        assert_eq!(limits.check_aggregations(&[aggregation.clone()]), Ok(()));
+
+        // 10 + 10 * 100
+        aggregation.aggregations = Some(vec![terms("b", 100)]);
+        let violations = limits.check_aggregations(&[aggregation]).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "aggs.a",
+                ViolationKind::Buckets {
+                    max: 1_000,
+                    estimated: 1_010
+                }
+            )]
+        );
+
+        let histogram = aggregation::Request::builder()
+            .name("prices")
+            .histogram(Some(
+                HistogramAggregation::builder()
+                    .field("price")
+                    .interval(0.5)
+                    .hard_bounds(HistogramBounds {
+                        min: 0.0,
+                        max: 1000.0,
+                    })
+                    .build(),
+            ))
+            .build();
+        let violations = limits.check_aggregations(&[histogram]).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "aggs.prices",
+                ViolationKind::Buckets {
+                    max: 1_000,
+                    estimated: 2_001
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn bans_unbounded_histograms() {
+        let histogram = aggregation::Request::builder()
+            .name("prices")
+            .histogram(Some(
+                HistogramAggregation::builder()
+                    .field("price")
+                    .interval(10.0)
+                    .extended_bounds(HistogramBounds {
+                        min: 0.0,
+                        max: 100.0,
+                    })
+                    .build(),
+            ))
+            .build();
+        let date_histogram = aggregation::Request::builder()
+            .name("per_day")
+            .date_histogram(Some(
+                DateHistogramAggregation::builder()
+                    .field("created_at")
+                    .calendar_interval(CalendarInterval::Day)
+                    .build(),
+            ))
+            .build();
+        let aggregations = [histogram, date_histogram];
+
+        let violations = ComplexityLimits::default()
+            .check_aggregations(&aggregations)
+            .unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                Violation::new("aggs.prices", ViolationKind::UnboundedBuckets),
+                Violation::new("aggs.per_day", ViolationKind::UnboundedBuckets),
+            ]
+        );
+
+        let limits = ComplexityLimits {
+            max_buckets: None,
+            ..ComplexityLimits::default()
+        };
+        assert_eq!(limits.check_aggregations(&aggregations), Ok(()));
+    }
+
+    #[test]
+    fn limits_result_windows() {
+        let mut nested = NestedQuery::new("comments", TermQuery::new("a", "b"), false);
+        nested.inner_hits = Some(InnerHits {
+            from: Some(90),
+            size: Some(20),
+            ..InnerHits::default()
+        });
+        let mut query = CompoundQuery::default();
+        query.push(nested);
+        let request = search::Request::builder().query(query).from(9_995).build();
+
+        let violations = ComplexityLimits::default()
+            .check_search(&request)
+            .unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                Violation::new(
+                    "query.bool.filter[0].nested.inner_hits",
+                    ViolationKind::ResultWindow {
+                        max: 100,
+                        actual: 110
+                    }
+                ),
+                Violation::new(
+                    "size",
+                    ViolationKind::ResultWindow {
+                        max: 10_000,
+                        actual: 10_005
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn limits_collapse_inner_hits() {
+        let request: search::Request = serde_json::from_value(json!({
+            "collapse": {
+                "field": "family",
+                "inner_hits": [
+                    { "name": "a", "size": 50 },
+                    { "name": "b", "from": 100 },
+                    { "name": "c" },
+                ],
+            },
+        }))
+        .unwrap();
+
+        let limits = ComplexityLimits {
+            max_collapse_inner_hits: 2,
+            ..ComplexityLimits::default()
+        };
+        let violations = limits.check_search(&request).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                Violation::new(
+                    "collapse.inner_hits",
+                    ViolationKind::InnerHits { max: 2, actual: 3 }
+                ),
+                Violation::new(
+                    "collapse.inner_hits[1]",
+                    ViolationKind::ResultWindow {
+                        max: 100,
+                        actual: 103
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn estimates_buckets_of_siblings() {
+        let limits = ComplexityLimits {
+            max_buckets: Some(100),
+            ..ComplexityLimits::default()
+        };
+
+        let violations = limits
+            .check_aggregations(&[terms("a", 60), terms("b", 60)])
+            .unwrap_err();
+        assert_eq!(
+            violations,
+            vec![Violation::new(
+                "aggs",
+                ViolationKind::Buckets {
+                    max: 100,
+                    estimated: 120
+                }
+            )]
+        );
+    }
+}
@@ -0,0 +1,1317 @@
+//! Computes aggregations over JSON documents.
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use serde_json::{json, Map, Value};
+
+use super::{
+    date::{self, Unit},
+    query::{as_str, eval_compound, Scope},
+    EvalError, EvalErrorKind,
+};
+use crate::aggregation::{
+    types::{
+        CalendarInterval, DateHistogramAggregation, DateRangeAggregation, HistogramAggregation,
+        InnerAggregation, NestedAggregation, Range, RangeAggregation, ReverseNestedAggregation,
+        TermsAggregation,
+    },
+    Request,
+};
+
+/// Computes `aggregations` over `documents`.
+///
+/// The result has the same shape as the body of a search response from
+/// Elasticsearch when the [`typed_keys`] parameter is set, so it can be
+/// deserialized into an [`aggregation::Response`]:
+///
+/// ```
+/// use elastiql::aggregation::{types::InnerAggregation, Request, Response};
+/// use elastiql::eval;
+/// use serde_json::json;
+///
+/// let aggregation = Request::builder()
+///     .name("avg_age")
+///     .avg(Some(InnerAggregation::builder().field(Some("age".to_string())).build()))
+///     .build();
+/// let documents = vec![json!({ "age": 20 }), json!({ "age": 40 })];
+///
+/// let body = eval::aggregate(&[aggregation], &documents).unwrap();
+/// assert_eq!(body, json!({ "aggregations": { "avg#avg_age": { "value": 30.0 } } }));
+///
+/// let response: Response = serde_json::from_value(body).unwrap();
+/// assert_eq!(response.aggregations[0].values, vec![30.0]);
+/// ```
+///
+/// Supported are the `avg`, `min`, `max`, `sum`, `stats`, `value_count` &
+/// (exact) `cardinality` metrics and the `terms`, `range`, `date_range`,
+/// `histogram`, `date_histogram`, `filter`, `nested` & `reverse_nested`
+/// bucket aggregations. Scripts are not supported and date aggregations only
+/// support UTC or fixed offsets (e.g. `+01:00`) as time zones.
+///
+/// [`typed_keys`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-aggregations.html#return-agg-type
+/// [`aggregation::Response`]: crate::aggregation::Response
+pub fn aggregate(aggregations: &[Request], documents: &[Value]) -> Result<Value, EvalError> {
+    let documents: Vec<Document> = documents
+        .iter()
+        .map(|document| Document {
+            scope: Scope::root(document),
+            root: document,
+        })
+        .collect();
+    let aggregator = Aggregator { now: date::now() };
+    let results = aggregator.aggregate_all(aggregations, &documents, "aggs")?;
+    Ok(json!({ "aggregations": results }))
+}
+
+/// A (possibly nested) document being aggregated.
+#[derive(Clone, Copy)]
+struct Document<'a> {
+    /// The (nested) document.
+    scope: Scope<'a>,
+
+    /// The root document the nested document is part of.
+    root: &'a Value,
+}
+
+/// The key of a `terms` bucket.
+#[derive(Clone, PartialEq, Debug)]
+enum TermKey {
+    Long(i64),
+    Double(f64),
+    Bool(bool),
+    String(String),
+}
+
+impl TermKey {
+    /// Converts a scalar value into a key.
+    fn from_value(value: &Value) -> Option<TermKey> {
+        Some(match value {
+            Value::Number(n) => match n.as_i64() {
+                Some(n) => TermKey::Long(n),
+                None => TermKey::Double(n.as_f64()?),
+            },
+            Value::Bool(b) => TermKey::Bool(*b),
+            Value::String(s) => TermKey::String(s.to_string()),
+            _ => return None,
+        })
+    }
+
+    /// Converts a number into a key.
+    fn from_f64(value: f64) -> TermKey {
+        if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+            TermKey::Long(value as i64)
+        } else {
+            TermKey::Double(value)
+        }
+    }
+
+    /// The number this key represents, if any.
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            TermKey::Long(n) => Some(*n as f64),
+            TermKey::Double(n) => Some(*n),
+            TermKey::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+            TermKey::String(_) => None,
+        }
+    }
+
+    /// The string representation of this key.
+    fn to_string_key(&self) -> String {
+        match self {
+            TermKey::Long(n) => n.to_string(),
+            TermKey::Double(n) => n.to_string(),
+            TermKey::Bool(b) => b.to_string(),
+            TermKey::String(s) => s.to_string(),
+        }
+    }
+}
+
+/// Converts a number to a JSON value, which is `null` if it's not finite.
+#[inline]
+fn number(value: f64) -> Value {
+    serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+/// Computes aggregations.
+struct Aggregator {
+    /// The current time, in milliseconds since the epoch, used for date math.
+    now: i64,
+}
+
+impl Aggregator {
+    /// Computes a list of (sibling) aggregations, keyed by their typed name
+    /// (e.g. `avg#avg_age`).
+    fn aggregate_all(
+        &self,
+        aggregations: &[Request],
+        documents: &[Document],
+        path: &str,
+    ) -> Result<Map<String, Value>, EvalError> {
+        let mut results = Map::new();
+        for aggregation in aggregations {
+            let path = if path == "aggs" {
+                format!("aggs.{}", aggregation.name)
+            } else {
+                format!("{}.aggs.{}", path, aggregation.name)
+            };
+            let (ty, mut result) = self.aggregate(aggregation, documents, &path)?;
+            if let Some(metadata) = &aggregation.metadata {
+                let metadata = serde_json::to_value(metadata).unwrap_or_default();
+                result.insert("meta".to_string(), metadata);
+            }
+            results.insert(
+                format!("{}#{}", ty, aggregation.name),
+                Value::Object(result),
+            );
+        }
+        Ok(results)
+    }
+
+    /// Computes a single aggregation, returning its type and result.
+    fn aggregate(
+        &self,
+        aggregation: &Request,
+        documents: &[Document],
+        path: &str,
+    ) -> Result<(&'static str, Map<String, Value>), EvalError> {
+        let unsupported = [
+            ("weighted_avg", aggregation.weighted_avg.is_some()),
+            (
+                "median_absolute_deviation",
+                aggregation.median_absolute_deviation.is_some(),
+            ),
+            ("percentiles", aggregation.percentiles.is_some()),
+            ("percentile_ranks", aggregation.percentile_ranks.is_some()),
+            ("extended_stats", aggregation.extended_stats.is_some()),
+            (
+                "auto_date_histogram",
+                aggregation.auto_date_histogram.is_some(),
+            ),
+            (
+                "variable_width_histogram",
+                aggregation.variable_width_histogram.is_some(),
+            ),
+            ("bucket_script", aggregation.bucket_script.is_some()),
+            ("bucket_selector", aggregation.bucket_selector.is_some()),
+            ("bucket_sort", aggregation.bucket_sort.is_some()),
+        ];
+        if let Some((ty, _)) = unsupported.iter().find(|(_, is_some)| *is_some) {
+            let path = format!("{}.{}", path, ty);
+            return Err(EvalError::new(path, EvalErrorKind::Unsupported));
+        }
+
+        let metrics = [
+            ("avg", &aggregation.avg),
+            ("min", &aggregation.min),
+            ("max", &aggregation.max),
+            ("sum", &aggregation.sum),
+            ("stats", &aggregation.stats),
+            ("value_count", &aggregation.value_count),
+            ("cardinality", &aggregation.cardinality),
+        ];
+        for (ty, metric) in metrics.iter() {
+            if let Some(metric) = metric {
+                let path = format!("{}.{}", path, ty);
+                return Ok((ty, self.metric(ty, metric, documents, &path)?));
+            }
+        }
+
+        if let Some(terms) = &aggregation.terms {
+            return self.terms(terms, aggregation, documents, path);
+        }
+        if let Some(range) = &aggregation.range {
+            let result = self.range(range, aggregation, documents, path)?;
+            return Ok(("range", result));
+        }
+        if let Some(date_range) = &aggregation.date_range {
+            let result = self.date_range(date_range, aggregation, documents, path)?;
+            return Ok(("date_range", result));
+        }
+        if let Some(histogram) = &aggregation.histogram {
+            let result = self.histogram(histogram, aggregation, documents, path)?;
+            return Ok(("histogram", result));
+        }
+        if let Some(date_histogram) = &aggregation.date_histogram {
+            let result = self.date_histogram(date_histogram, aggregation, documents, path)?;
+            return Ok(("date_histogram", result));
+        }
+        if let Some(query) = &aggregation.filters {
+            let mut matching = Vec::new();
+            for document in documents {
+                let path = format!("{}.filter", path);
+                if eval_compound(query, &document.scope, path)? {
+                    matching.push(*document);
+                }
+            }
+            return Ok(("filter", self.bucket(aggregation, &matching, path)?));
+        }
+        if let Some(nested) = &aggregation.nested {
+            let documents = nested_documents(nested, documents);
+            return Ok(("nested", self.bucket(aggregation, &documents, path)?));
+        }
+        if let Some(reverse_nested) = &aggregation.reverse_nested {
+            let documents = reverse_nested_documents(reverse_nested, documents, path)?;
+            return Ok((
+                "reverse_nested",
+                self.bucket(aggregation, &documents, path)?,
+            ));
+        }
+
+        Err(EvalError::new(path, EvalErrorKind::Unsupported))
+    }
+
+    /// Computes a bucket, i.e. its `doc_count` and sub-aggregations.
+    fn bucket(
+        &self,
+        aggregation: &Request,
+        documents: &[Document],
+        path: &str,
+    ) -> Result<Map<String, Value>, EvalError> {
+        let mut bucket = match &aggregation.aggregations {
+            Some(aggregations) => self.aggregate_all(aggregations, documents, path)?,
+            None => Map::new(),
+        };
+        bucket.insert("doc_count".to_string(), documents.len().into());
+        Ok(bucket)
+    }
+
+    /// Computes a metric aggregation.
+    fn metric(
+        &self,
+        ty: &str,
+        metric: &InnerAggregation,
+        documents: &[Document],
+        path: &str,
+    ) -> Result<Map<String, Value>, EvalError> {
+        let field = field(metric.field.as_deref(), metric.script.is_some(), path)?;
+        let mut result = Map::new();
+
+        if ty == "value_count" || ty == "cardinality" {
+            let mut values: Vec<String> = Vec::new();
+            for document in documents {
+                let before = values.len();
+                values.extend(
+                    document
+                        .scope
+                        .values(field)
+                        .into_iter()
+                        .filter_map(as_str)
+                        .map(Into::into),
+                );
+                if values.len() == before {
+                    values.extend(metric.missing.map(|missing| missing.to_string()));
+                }
+            }
+            if ty == "cardinality" {
+                values.sort();
+                values.dedup();
+            }
+            result.insert("value".to_string(), values.len().into());
+            return Ok(result);
+        }
+
+        let values = numbers(documents, field, metric.missing, date::parse);
+        let count = values.len();
+        let sum: f64 = values.iter().sum();
+        let min = values.iter().copied().fold(None, |min: Option<f64>, v| {
+            Some(min.map_or(v, |min| min.min(v)))
+        });
+        let max = values.iter().copied().fold(None, |max: Option<f64>, v| {
+            Some(max.map_or(v, |max| max.max(v)))
+        });
+        let avg = if count == 0 {
+            None
+        } else {
+            Some(sum / count as f64)
+        };
+        let optional = |value: Option<f64>| value.map_or(Value::Null, number);
+
+        match ty {
+            "avg" => result.insert("value".to_string(), optional(avg)),
+            "min" => result.insert("value".to_string(), optional(min)),
+            "max" => result.insert("value".to_string(), optional(max)),
+            "sum" => result.insert("value".to_string(), number(sum)),
+            _ => {
+                result.insert("count".to_string(), count.into());
+                result.insert("min".to_string(), optional(min));
+                result.insert("max".to_string(), optional(max));
+                result.insert("avg".to_string(), optional(avg));
+                result.insert("sum".to_string(), number(sum))
+            }
+        };
+        Ok(result)
+    }
+
+    /// Computes a `terms` aggregation, ordered by descending `doc_count`.
+    fn terms(
+        &self,
+        terms: &TermsAggregation,
+        aggregation: &Request,
+        documents: &[Document],
+        path: &str,
+    ) -> Result<(&'static str, Map<String, Value>), EvalError> {
+        let field = field(
+            terms.field.as_deref(),
+            terms.script.is_some(),
+            &format!("{}.terms", path),
+        )?;
+
+        let mut buckets: Vec<(TermKey, Vec<Document>)> = Vec::new();
+        let mut indices: HashMap<String, usize> = HashMap::new();
+        for document in documents {
+            let mut keys: Vec<TermKey> = document
+                .scope
+                .values(field)
+                .into_iter()
+                .filter_map(TermKey::from_value)
+                .collect();
+            if keys.is_empty() {
+                keys.extend(terms.missing.map(TermKey::from_f64));
+            }
+            let mut seen = Vec::new();
+            for key in keys {
+                let string = key.to_string_key();
+                if seen.contains(&string) {
+                    continue;
+                }
+                let i = *indices.entry(string.clone()).or_insert_with(|| {
+                    buckets.push((key, Vec::new()));
+                    buckets.len() - 1
+                });
+                buckets[i].1.push(*document);
+                seen.push(string);
+            }
+        }
+
+        let ty = if buckets
+            .iter()
+            .all(|(key, _)| matches!(key, TermKey::Long(_) | TermKey::Bool(_)))
+        {
+            "lterms"
+        } else if buckets.iter().all(|(key, _)| key.as_f64().is_some()) {
+            "dterms"
+        } else {
+            "sterms"
+        };
+        let ty = if buckets.is_empty() { "sterms" } else { ty };
+
+        buckets.sort_by(|(a, a_docs), (b, b_docs)| {
+            b_docs
+                .len()
+                .cmp(&a_docs.len())
+                .then_with(|| match (a.as_f64(), b.as_f64()) {
+                    (Some(a), Some(b)) if ty != "sterms" => {
+                        a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+                    }
+                    _ => a.to_string_key().cmp(&b.to_string_key()),
+                })
+        });
+        let size = terms.size.unwrap_or(10) as usize;
+        let other: usize = buckets.iter().skip(size).map(|(_, docs)| docs.len()).sum();
+        buckets.truncate(size);
+
+        let mut results = Vec::new();
+        for (key, documents) in buckets {
+            let mut bucket = self.bucket(aggregation, &documents, path)?;
+            match key {
+                TermKey::Bool(b) => {
+                    bucket.insert("key".to_string(), (b as i64).into());
+                    bucket.insert("key_as_string".to_string(), b.to_string().into());
+                }
+                key if ty == "sterms" => {
+                    bucket.insert("key".to_string(), key.to_string_key().into());
+                }
+                TermKey::Long(n) if ty == "dterms" => {
+                    bucket.insert("key".to_string(), number(n as f64));
+                }
+                TermKey::Long(n) => {
+                    bucket.insert("key".to_string(), n.into());
+                }
+                TermKey::Double(n) => {
+                    bucket.insert("key".to_string(), number(n));
+                }
+                TermKey::String(s) => {
+                    bucket.insert("key".to_string(), s.into());
+                }
+            }
+            results.push(Value::Object(bucket));
+        }
+
+        let mut result = Map::new();
+        result.insert("doc_count_error_upper_bound".to_string(), 0.into());
+        result.insert("sum_other_doc_count".to_string(), other.into());
+        result.insert("buckets".to_string(), results.into());
+        Ok((ty, result))
+    }
+
+    /// Computes a `range` aggregation.
+    fn range(
+        &self,
+        range: &RangeAggregation,
+        aggregation: &Request,
+        documents: &[Document],
+        path: &str,
+    ) -> Result<Map<String, Value>, EvalError> {
+        let field = field(
+            range.field.as_deref(),
+            range.script.is_some(),
+            &format!("{}.range", path),
+        )?;
+
+        let mut ranges: Vec<(Option<f64>, Option<f64>)> = range
+            .ranges
+            .iter()
+            .map(|range| (range.from, range.to))
+            .collect();
+        sort_ranges(&mut ranges);
+
+        let mut buckets = Vec::new();
+        for (from, to) in ranges {
+            let matching: Vec<Document> = documents
+                .iter()
+                .filter(|document| {
+                    numbers(&[**document], field, None, |_| None)
+                        .into_iter()
+                        .any(|value| in_range(value, from, to))
+                })
+                .copied()
+                .collect();
+            let mut bucket = self.bucket(aggregation, &matching, path)?;
+            bucket.insert("key".to_string(), Range { from, to }.key().into());
+            if let Some(from) = from {
+                bucket.insert("from".to_string(), number(from));
+            }
+            if let Some(to) = to {
+                bucket.insert("to".to_string(), number(to));
+            }
+            buckets.push(Value::Object(bucket));
+        }
+
+        let mut result = Map::new();
+        result.insert("buckets".to_string(), buckets.into());
+        Ok(result)
+    }
+
+    /// Computes a `date_range` aggregation.
+    fn date_range(
+        &self,
+        date_range: &DateRangeAggregation,
+        aggregation: &Request,
+        documents: &[Document],
+        path: &str,
+    ) -> Result<Map<String, Value>, EvalError> {
+        let path_of = |option: &str| format!("{}.date_range.{}", path, option);
+        let offset = time_zone(date_range.time_zone.as_deref(), || path_of("time_zone"))?;
+        let missing = match &date_range.missing {
+            Some(missing) => Some(
+                date::parse(missing)
+                    .ok_or_else(|| EvalError::new(path_of("missing"), EvalErrorKind::Unsupported))?
+                    as f64,
+            ),
+            None => None,
+        };
+        let parse = |expression: &Option<String>, option: &str| match expression {
+            Some(expression) => date::parse_math(expression, self.now + offset)
+                .map(|millis| Some((millis - offset) as f64))
+                .ok_or_else(|| EvalError::new(path_of(option), EvalErrorKind::Unsupported)),
+            None => Ok(None),
+        };
+        let format = |millis: f64| {
+            date::format(millis as i64, offset, date_range.format.as_deref())
+                .ok_or_else(|| EvalError::new(path_of("format"), EvalErrorKind::Unsupported))
+        };
+
+        let mut ranges = Vec::new();
+        for (i, range) in date_range.ranges.iter().enumerate() {
+            let from = parse(&range.from, &format!("ranges[{}].from", i))?;
+            let to = parse(&range.to, &format!("ranges[{}].to", i))?;
+            ranges.push((from, to));
+        }
+        sort_ranges(&mut ranges);
+
+        let mut buckets = Vec::new();
+        for (from, to) in ranges {
+            let matching: Vec<Document> = documents
+                .iter()
+                .filter(|document| {
+                    numbers(&[**document], &date_range.field, missing, date::parse)
+                        .into_iter()
+                        .any(|value| in_range(value, from, to))
+                })
+                .copied()
+                .collect();
+            let mut bucket = self.bucket(aggregation, &matching, path)?;
+            let from_as_string = from.map(format).transpose()?;
+            let to_as_string = to.map(format).transpose()?;
+            let key = format!(
+                "{}-{}",
+                from_as_string.as_deref().unwrap_or("*"),
+                to_as_string.as_deref().unwrap_or("*"),
+            );
+            bucket.insert("key".to_string(), key.into());
+            if let (Some(from), Some(from_as_string)) = (from, from_as_string) {
+                bucket.insert("from".to_string(), number(from));
+                bucket.insert("from_as_string".to_string(), from_as_string.into());
+            }
+            if let (Some(to), Some(to_as_string)) = (to, to_as_string) {
+                bucket.insert("to".to_string(), number(to));
+                bucket.insert("to_as_string".to_string(), to_as_string.into());
+            }
+            buckets.push(Value::Object(bucket));
+        }
+
+        let mut result = Map::new();
+        result.insert("buckets".to_string(), buckets.into());
+        Ok(result)
+    }
+
+    /// Computes a `histogram` aggregation.
+    fn histogram(
+        &self,
+        histogram: &HistogramAggregation,
+        aggregation: &Request,
+        documents: &[Document],
+        path: &str,
+    ) -> Result<Map<String, Value>, EvalError> {
+        let interval = histogram
+            .interval
+            .filter(|&interval| interval > 0.0)
+            .ok_or_else(|| {
+                EvalError::new(
+                    format!("{}.histogram.interval", path),
+                    EvalErrorKind::Unsupported,
+                )
+            })?;
+        let offset = histogram.offset.unwrap_or(0.0);
+        let index = |value: f64| ((value - offset) / interval).floor() as i64;
+        let key = |index: i64| index as f64 * interval + offset;
+
+        let mut buckets: Vec<(i64, Vec<Document>)> = Vec::new();
+        for document in documents {
+            let mut indices: Vec<i64> =
+                numbers(&[*document], &histogram.field, histogram.missing, |_| None)
+                    .into_iter()
+                    .map(index)
+                    .collect();
+            indices.sort_unstable();
+            indices.dedup();
+            for i in indices {
+                if let Some(bounds) = &histogram.hard_bounds {
+                    if key(i) < bounds.min || key(i) > bounds.max {
+                        continue;
+                    }
+                }
+                add_to_bucket(&mut buckets, i, *document);
+            }
+        }
+
+        let min_doc_count = histogram.min_doc_count.unwrap_or(0) as usize;
+        if min_doc_count == 0 {
+            let bounds = histogram
+                .extended_bounds
+                .as_ref()
+                .map(|bounds| (index(bounds.min), index(bounds.max)));
+            fill_gaps(&mut buckets, bounds, |i| i + 1);
+        }
+
+        let mut results = Vec::new();
+        for (i, documents) in buckets {
+            if documents.len() < min_doc_count {
+                continue;
+            }
+            let mut bucket = self.bucket(aggregation, &documents, path)?;
+            bucket.insert("key".to_string(), number(key(i)));
+            results.push(Value::Object(bucket));
+        }
+
+        let mut result = Map::new();
+        result.insert("buckets".to_string(), results.into());
+        Ok(result)
+    }
+
+    /// Computes a `date_histogram` aggregation.
+    fn date_histogram(
+        &self,
+        histogram: &DateHistogramAggregation,
+        aggregation: &Request,
+        documents: &[Document],
+        path: &str,
+    ) -> Result<Map<String, Value>, EvalError> {
+        let path_of = |option: &str| format!("{}.date_histogram.{}", path, option);
+        let unsupported =
+            |option: &str| EvalError::new(path_of(option), EvalErrorKind::Unsupported);
+
+        let time_zone = time_zone(histogram.time_zone.as_deref(), || path_of("time_zone"))?;
+        let offset = match histogram.offset.as_deref() {
+            Some(offset) => {
+                let (sign, interval) = match offset.strip_prefix('-') {
+                    Some(interval) => (-1, interval),
+                    None => (1, offset.trim_start_matches('+')),
+                };
+                sign * date::parse_fixed_interval(interval).ok_or_else(|| unsupported("offset"))?
+            }
+            None => 0,
+        };
+        let missing = match &histogram.missing {
+            Some(missing) => {
+                Some(date::parse(missing).ok_or_else(|| unsupported("missing"))? as f64)
+            }
+            None => None,
+        };
+
+        let interval = match (&histogram.calendar_interval, &histogram.fixed_interval) {
+            (Some(interval), None) => Interval::Calendar(match interval {
+                CalendarInterval::Minute => Unit::Minute,
+                CalendarInterval::Hour => Unit::Hour,
+                CalendarInterval::Day => Unit::Day,
+                CalendarInterval::Week => Unit::Week,
+                CalendarInterval::Month => Unit::Month,
+                CalendarInterval::Quarter => Unit::Quarter,
+                CalendarInterval::Year => Unit::Year,
+            }),
+            (None, Some(interval)) => Interval::Fixed(
+                date::parse_fixed_interval(interval)
+                    .ok_or_else(|| unsupported("fixed_interval"))?,
+            ),
+            _ => return Err(unsupported("calendar_interval")),
+        };
+
+        let mut buckets: Vec<(i64, Vec<Document>)> = Vec::new();
+        for document in documents {
+            let mut keys: Vec<i64> = numbers(&[*document], &histogram.field, missing, date::parse)
+                .into_iter()
+                .map(|millis| interval.start(millis as i64 + time_zone, offset))
+                .collect();
+            keys.sort_unstable();
+            keys.dedup();
+            for key in keys {
+                add_to_bucket(&mut buckets, key, *document);
+            }
+        }
+        fill_gaps(&mut buckets, None, |key| interval.next(key, offset));
+
+        let mut results = Vec::new();
+        for (key, documents) in buckets {
+            let key = key - time_zone;
+            let mut bucket = self.bucket(aggregation, &documents, path)?;
+            let key_as_string = date::format(key, time_zone, histogram.format.as_deref())
+                .ok_or_else(|| unsupported("format"))?;
+            bucket.insert("key_as_string".to_string(), key_as_string.into());
+            bucket.insert("key".to_string(), key.into());
+            results.push(Value::Object(bucket));
+        }
+
+        let mut result = Map::new();
+        result.insert("buckets".to_string(), results.into());
+        Ok(result)
+    }
+}
+
+/// The interval of a `date_histogram` aggregation.
+#[derive(Clone, Copy)]
+enum Interval {
+    Calendar(Unit),
+    /// A fixed interval in milliseconds.
+    Fixed(i64),
+}
+
+impl Interval {
+    /// Returns the start of the bucket containing `millis` (in local time),
+    /// with buckets shifted by `offset` milliseconds.
+    fn start(self, millis: i64, offset: i64) -> i64 {
+        match self {
+            Interval::Calendar(unit) => date::round_down(millis - offset, unit) + offset,
+            Interval::Fixed(interval) => (millis - offset).div_euclid(interval) * interval + offset,
+        }
+    }
+
+    /// Returns the start of the bucket following the one starting at `start`.
+    fn next(self, start: i64, offset: i64) -> i64 {
+        match self {
+            Interval::Calendar(unit) => date::add(start - offset, 1, unit) + offset,
+            Interval::Fixed(interval) => start + interval,
+        }
+    }
+}
+
+/// Returns the field of a metric or bucket aggregation, as scripts are not
+/// supported.
+fn field<'a>(field: Option<&'a str>, has_script: bool, path: &str) -> Result<&'a str, EvalError> {
+    match field {
+        Some(field) if !has_script => Ok(field),
+        _ => Err(EvalError::new(path, EvalErrorKind::Unsupported)),
+    }
+}
+
+/// Parses an optional time zone into its offset from UTC in milliseconds.
+fn time_zone(time_zone: Option<&str>, path: impl FnOnce() -> String) -> Result<i64, EvalError> {
+    match time_zone {
+        Some(time_zone) => date::parse_time_zone(time_zone)
+            .ok_or_else(|| EvalError::new(path(), EvalErrorKind::Unsupported)),
+        None => Ok(0),
+    }
+}
+
+/// Returns the numeric values of `field`, using `parse` for strings that are
+/// not numbers (e.g. dates) and `missing` for documents without any values.
+fn numbers(
+    documents: &[Document],
+    field: &str,
+    missing: Option<f64>,
+    parse: fn(&str) -> Option<i64>,
+) -> Vec<f64> {
+    let mut numbers = Vec::new();
+    for document in documents {
+        let before = numbers.len();
+        numbers.extend(
+            document
+                .scope
+                .values(field)
+                .into_iter()
+                .filter_map(|value| match value {
+                    Value::Number(n) => n.as_f64(),
+                    Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+                    Value::String(s) => s
+                        .parse()
+                        .ok()
+                        .or_else(|| parse(s).map(|millis| millis as f64)),
+                    _ => None,
+                }),
+        );
+        if numbers.len() == before {
+            numbers.extend(missing);
+        }
+    }
+    numbers
+}
+
+/// Returns `true` if `value` is in the range `[from, to)`.
+#[inline]
+fn in_range(value: f64, from: Option<f64>, to: Option<f64>) -> bool {
+    !matches!(from, Some(from) if value < from) && !matches!(to, Some(to) if value >= to)
+}
+
+/// Sorts ranges by their start, then their end, like Elasticsearch does.
+fn sort_ranges(ranges: &mut [(Option<f64>, Option<f64>)]) {
+    ranges.sort_by(|(a_from, a_to), (b_from, b_to)| {
+        let from = |from: &Option<f64>| from.unwrap_or(f64::NEG_INFINITY);
+        let to = |to: &Option<f64>| to.unwrap_or(f64::INFINITY);
+        from(a_from)
+            .partial_cmp(&from(b_from))
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| to(a_to).partial_cmp(&to(b_to)).unwrap_or(Ordering::Equal))
+    });
+}
+
+/// Adds `document` to the bucket with `key`, creating it if necessary.
+fn add_to_bucket<'a>(
+    buckets: &mut Vec<(i64, Vec<Document<'a>>)>,
+    key: i64,
+    document: Document<'a>,
+) {
+    match buckets.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, documents)) => documents.push(document),
+        None => buckets.push((key, vec![document])),
+    }
+}
+
+/// Sorts the buckets by key & inserts empty buckets between them (and within
+/// the optional `bounds`), using `next` to get the key of the following bucket.
+fn fill_gaps(
+    buckets: &mut Vec<(i64, Vec<Document>)>,
+    bounds: Option<(i64, i64)>,
+    next: impl Fn(i64) -> i64,
+) {
+    buckets.sort_by_key(|(key, _)| *key);
+    let first = buckets.first().map(|(key, _)| *key);
+    let last = buckets.last().map(|(key, _)| *key);
+    let (first, last) = match (bounds, first, last) {
+        (Some((min, max)), Some(first), Some(last)) => (min.min(first), max.max(last)),
+        (Some((min, max)), _, _) => (min, max),
+        (None, Some(first), Some(last)) => (first, last),
+        _ => return,
+    };
+
+    let mut filled = Vec::new();
+    let mut existing = std::mem::take(buckets).into_iter().peekable();
+    let mut key = first;
+    while key <= last {
+        match existing.peek() {
+            Some((k, _)) if *k == key => filled.extend(existing.next()),
+            _ => filled.push((key, Vec::new())),
+        }
+        key = next(key);
+    }
+    filled.extend(existing);
+    *buckets = filled;
+}
+
+/// Returns the nested documents at the `path` of a `nested` aggregation.
+fn nested_documents<'a>(
+    nested: &'a NestedAggregation,
+    documents: &[Document<'a>],
+) -> Vec<Document<'a>> {
+    let mut nested_documents = Vec::new();
+    for document in documents {
+        for value in document.scope.values(&nested.path) {
+            if value.is_object() {
+                nested_documents.push(Document {
+                    scope: Scope {
+                        path: &nested.path,
+                        document: value,
+                    },
+                    root: document.root,
+                });
+            }
+        }
+    }
+    nested_documents
+}
+
+/// Returns the (distinct) root documents of nested documents for a
+/// `reverse_nested` aggregation.
+fn reverse_nested_documents<'a>(
+    reverse_nested: &ReverseNestedAggregation,
+    documents: &[Document<'a>],
+    path: &str,
+) -> Result<Vec<Document<'a>>, EvalError> {
+    if reverse_nested.path.is_some() {
+        let path = format!("{}.reverse_nested.path", path);
+        return Err(EvalError::new(path, EvalErrorKind::Unsupported));
+    }
+    let mut roots: Vec<Document> = Vec::new();
+    for document in documents {
+        if !roots
+            .iter()
+            .any(|root| std::ptr::eq(root.root, document.root))
+        {
+            roots.push(Document {
+                scope: Scope::root(document.root),
+                root: document.root,
+            });
+        }
+    }
+    Ok(roots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::aggregation::{
+        types::{DateRange, HistogramBounds},
+        ComputedResult, Response, Ty,
+    };
+    use crate::search::query::{CompoundQuery, TermQuery};
+
+    fn documents() -> Vec<Value> {
+        vec![
+            json!({
+                "name": "a",
+                "tags": ["rust", "search"],
+                "price": 10,
+                "active": true,
+                "created_at": "2020-01-15T10:00:00Z",
+                "comments": [{ "stars": 5 }, { "stars": 3 }],
+            }),
+            json!({
+                "name": "b",
+                "tags": ["rust"],
+                "price": 25.5,
+                "active": false,
+                "created_at": "2020-03-02T00:00:00Z",
+                "comments": [{ "stars": 1 }],
+            }),
+            json!({
+                "name": "c",
+                "tags": ["go", "rust"],
+                "price": 40,
+                "active": true,
+                "created_at": "2020-03-31T23:59:59Z",
+            }),
+            json!({ "name": "d" }),
+        ]
+    }
+
+    fn inner(field: &str) -> Option<InnerAggregation> {
+        Some(
+            InnerAggregation::builder()
+                .field(Some(field.to_string()))
+                .build(),
+        )
+    }
+
+    fn aggregate(aggregation: Request) -> Value {
+        let result = super::aggregate(&[aggregation], &documents()).unwrap();
+        let (_, value) = result["aggregations"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .next()
+            .unwrap();
+        value.clone()
+    }
+
+    #[test]
+    fn metrics() {
+        let metric = |aggregation: Request| aggregate(aggregation)["value"].clone();
+        let builder = || Request::builder().name("metric");
+
+        assert_eq!(
+            metric(builder().avg(inner("price")).build()),
+            json!(25.166666666666668)
+        );
+        assert_eq!(metric(builder().min(inner("price")).build()), json!(10.0));
+        assert_eq!(metric(builder().max(inner("price")).build()), json!(40.0));
+        assert_eq!(metric(builder().sum(inner("price")).build()), json!(75.5));
+        assert_eq!(
+            metric(builder().value_count(inner("tags")).build()),
+            json!(5)
+        );
+        assert_eq!(
+            metric(builder().cardinality(inner("tags")).build()),
+            json!(3)
+        );
+        assert_eq!(metric(builder().avg(inner("missing")).build()), Value::Null);
+        assert_eq!(metric(builder().sum(inner("missing")).build()), json!(0.0));
+
+        assert_eq!(
+            aggregate(builder().stats(inner("price")).build()),
+            json!({ "count": 3, "min": 10.0, "max": 40.0, "avg": 25.166666666666668, "sum": 75.5 })
+        );
+
+        let mut with_missing = inner("price").unwrap();
+        with_missing.missing = Some(0.0);
+        assert_eq!(
+            metric(builder().min(Some(with_missing)).build()),
+            json!(0.0)
+        );
+    }
+
+    #[test]
+    fn terms() {
+        let terms = |field: &str, size: Option<u64>| {
+            let terms = TermsAggregation::builder()
+                .field(Some(field.to_string()))
+                .script(None)
+                .size(size)
+                .build();
+            let aggregation = Request::builder().name("terms").terms(Some(terms)).build();
+            super::aggregate(&[aggregation], &documents()).unwrap()["aggregations"].clone()
+        };
+
+        assert_eq!(
+            terms("tags", Some(2)),
+            json!({ "sterms#terms": {
+                "doc_count_error_upper_bound": 0,
+                "sum_other_doc_count": 1,
+                "buckets": [
+                    { "key": "rust", "doc_count": 3 },
+                    { "key": "go", "doc_count": 1 },
+                ],
+            } })
+        );
+        assert_eq!(
+            terms("active", None)["lterms#terms"]["buckets"],
+            json!([
+                { "key": 1, "key_as_string": "true", "doc_count": 2 },
+                { "key": 0, "key_as_string": "false", "doc_count": 1 },
+            ])
+        );
+        assert_eq!(
+            terms("price", None)["dterms#terms"]["buckets"][0],
+            json!({ "key": 10.0, "doc_count": 1 })
+        );
+    }
+
+    #[test]
+    fn range() {
+        let range = RangeAggregation::builder()
+            .field(Some("price".to_string()))
+            .ranges(vec![
+                Range {
+                    from: Some(20.0),
+                    to: None,
+                },
+                Range {
+                    from: None,
+                    to: Some(20.0),
+                },
+            ])
+            .build();
+        assert_eq!(
+            aggregate(Request::builder().name("range").range(Some(range)).build()),
+            json!({ "buckets": [
+                { "key": "*-20.0", "to": 20.0, "doc_count": 1 },
+                { "key": "20.0-*", "from": 20.0, "doc_count": 2 },
+            ] })
+        );
+    }
+
+    #[test]
+    fn date_range() {
+        let date_range = DateRangeAggregation::builder()
+            .field("created_at")
+            .format(Some("yyyy-MM-dd".to_string()))
+            .ranges(vec![
+                DateRange::new(None, Some("2020-03-01")),
+                DateRange::new(Some("2020-03-01"), Some("2020-03-01||+1M")),
+            ])
+            .build();
+        assert_eq!(
+            aggregate(
+                Request::builder()
+                    .name("range")
+                    .date_range(date_range)
+                    .build()
+            ),
+            json!({ "buckets": [
+                {
+                    "key": "*-2020-03-01",
+                    "to": 1_583_020_800_000.0,
+                    "to_as_string": "2020-03-01",
+                    "doc_count": 1,
+                },
+                {
+                    "key": "2020-03-01-2020-04-01",
+                    "from": 1_583_020_800_000.0,
+                    "from_as_string": "2020-03-01",
+                    "to": 1_585_699_200_000.0,
+                    "to_as_string": "2020-04-01",
+                    "doc_count": 2,
+                },
+            ] })
+        );
+    }
+
+    #[test]
+    fn histogram() {
+        let histogram = HistogramAggregation::builder()
+            .field("price")
+            .interval(Some(10.0))
+            .build();
+        let keys = |histogram: HistogramAggregation| {
+            let result = aggregate(
+                Request::builder()
+                    .name("h")
+                    .histogram(Some(histogram))
+                    .build(),
+            );
+            result["buckets"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|bucket| {
+                    (
+                        bucket["key"].as_f64().unwrap(),
+                        bucket["doc_count"].as_u64().unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            keys(histogram.clone()),
+            vec![(10.0, 1), (20.0, 1), (30.0, 0), (40.0, 1)]
+        );
+
+        let mut filtered = histogram.clone();
+        filtered.min_doc_count = Some(1);
+        filtered.offset = Some(5.0);
+        assert_eq!(keys(filtered), vec![(5.0, 1), (25.0, 1), (35.0, 1)]);
+
+        let mut bounded = histogram;
+        bounded.extended_bounds = Some(HistogramBounds {
+            min: 0.0,
+            max: 50.0,
+        });
+        bounded.hard_bounds = Some(HistogramBounds {
+            min: 0.0,
+            max: 30.0,
+        });
+        assert_eq!(
+            keys(bounded),
+            vec![
+                (0.0, 0),
+                (10.0, 1),
+                (20.0, 1),
+                (30.0, 0),
+                (40.0, 0),
+                (50.0, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn date_histogram() {
+        let histogram =
+            |interval: Option<CalendarInterval>, fixed: Option<&str>, tz: Option<&str>| {
+                let histogram = DateHistogramAggregation::builder()
+                    .field("created_at")
+                    .calendar_interval(interval)
+                    .fixed_interval(fixed.map(str::to_string))
+                    .time_zone(tz.map(str::to_string))
+                    .build();
+                let result = aggregate(
+                    Request::builder()
+                        .name("h")
+                        .date_histogram(Some(histogram))
+                        .build(),
+                );
+                result["buckets"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|bucket| {
+                        let key = bucket["key_as_string"].as_str().unwrap().to_string();
+                        (key, bucket["doc_count"].as_u64().unwrap())
+                    })
+                    .collect::<Vec<_>>()
+            };
+        let buckets = |buckets: &[(&str, u64)]| {
+            buckets
+                .iter()
+                .map(|(key, count)| (key.to_string(), *count))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            histogram(Some(CalendarInterval::Month), None, None),
+            buckets(&[
+                ("2020-01-01T00:00:00.000Z", 1),
+                ("2020-02-01T00:00:00.000Z", 0),
+                ("2020-03-01T00:00:00.000Z", 2),
+            ])
+        );
+        assert_eq!(
+            histogram(Some(CalendarInterval::Month), None, Some("+01:00")),
+            buckets(&[
+                ("2020-01-01T00:00:00.000+01:00", 1),
+                ("2020-02-01T00:00:00.000+01:00", 0),
+                ("2020-03-01T00:00:00.000+01:00", 1),
+                ("2020-04-01T00:00:00.000+01:00", 1),
+            ])
+        );
+        assert_eq!(
+            histogram(None, Some("30d"), None),
+            buckets(&[
+                ("2020-01-09T00:00:00.000Z", 1),
+                ("2020-02-08T00:00:00.000Z", 1),
+                ("2020-03-09T00:00:00.000Z", 1),
+            ])
+        );
+        let err = super::aggregate(
+            &[Request::builder()
+                .name("h")
+                .date_histogram(Some(
+                    DateHistogramAggregation::builder()
+                        .field("created_at")
+                        .time_zone(Some("Europe/Berlin".to_string()))
+                        .calendar_interval(Some(CalendarInterval::Day))
+                        .build(),
+                ))
+                .build()],
+            &documents(),
+        )
+        .unwrap_err();
+        assert_eq!(err.path, "aggs.h.date_histogram.time_zone");
+    }
+
+    #[test]
+    fn filter_nested_and_sub_aggregations() {
+        let mut query = CompoundQuery::default();
+        query.push(TermQuery::new("active", "true"));
+
+        let aggregation = Request::builder()
+            .name("active")
+            .filters(Some(query))
+            .aggregations(vec![Request::builder()
+                .name("comments")
+                .nested(Some(NestedAggregation::builder().path("comments").build()))
+                .aggregations(vec![
+                    Request::builder()
+                        .name("avg_stars")
+                        .avg(inner("comments.stars"))
+                        .build(),
+                    Request::builder()
+                        .name("products")
+                        .reverse_nested(Some(ReverseNestedAggregation::builder().build()))
+                        .build(),
+                ])
+                .build()])
+            .metadata(Some(json!({ "_skip": false }).into()))
+            .build();
+
+        assert_eq!(
+            aggregate(aggregation),
+            json!({
+                "doc_count": 2,
+                "meta": { "_skip": false },
+                "nested#comments": {
+                    "doc_count": 2,
+                    "avg#avg_stars": { "value": 4.0 },
+                    "reverse_nested#products": { "doc_count": 1 },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn can_be_flattened_into_a_response() {
+        let terms = TermsAggregation::builder()
+            .field(Some("tags".to_string()))
+            .script(None)
+            .build();
+        let aggregations = vec![
+            Request::builder()
+                .name("per_tag")
+                .terms(Some(terms))
+                .aggregations(vec![Request::builder()
+                    .name("avg_price")
+                    .avg(inner("price"))
+                    .build()])
+                .build(),
+            Request::builder()
+                .name("max_price")
+                .max(inner("price"))
+                .build(),
+        ];
+        let body = super::aggregate(&aggregations, &documents()).unwrap();
+        let mut response: Response = serde_json::from_value(body).unwrap();
+        response.aggregations.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let summary: Vec<_> = response
+            .aggregations
+            .iter()
+            .map(
+                |ComputedResult {
+                     name,
+                     type_,
+                     values,
+                     ..
+                 }| (name.as_str(), *type_, values.clone()),
+            )
+            .collect();
+        assert!(matches!(summary[0], ("avg_price", Ty::Avg, _)));
+        assert!(matches!(summary[1], ("max_price", Ty::Max, _)));
+        assert_eq!(summary[1].2, vec![40.0]);
+        let mut averages = summary[0].2.clone();
+        averages.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(averages, vec![10.0, 25.166666666666668, 40.0]);
+    }
+
+    #[test]
+    fn unsupported_aggregations_are_err() {
+        let aggregation = Request::builder()
+            .name("outer")
+            .filters(Some(CompoundQuery::default()))
+            .aggregations(vec![Request::builder()
+                .name("p")
+                .percentiles(inner("price"))
+                .build()])
+            .build();
+        let err = super::aggregate(&[aggregation], &documents()).unwrap_err();
+        assert_eq!(err.path, "aggs.outer.aggs.p.percentiles");
+        assert_eq!(err.kind, EvalErrorKind::Unsupported);
+    }
+}
@@ -0,0 +1,468 @@
+//! Just enough date handling to evaluate date aggregations: parsing ISO 8601
+//! dates & [date math], formatting dates and calendar arithmetic.
+//!
+//! Dates are represented as milliseconds since the epoch. Only UTC and fixed
+//! offsets are supported as time zones.
+//!
+//! [date math]: https://www.elastic.co/guide/en/elasticsearch/reference/current/common-options.html#date-math
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The number of milliseconds in a second.
+const SECOND: i64 = 1_000;
+
+/// The number of milliseconds in a minute.
+const MINUTE: i64 = 60 * SECOND;
+
+/// The number of milliseconds in an hour.
+const HOUR: i64 = 60 * MINUTE;
+
+/// The number of milliseconds in a day.
+const DAY: i64 = 24 * HOUR;
+
+/// A unit of time, as used by date math & calendar intervals.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(super) enum Unit {
+    Year,
+    Quarter,
+    Month,
+    Week,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+impl Unit {
+    /// Parses a date math unit, e.g. `M` for months.
+    fn from_char(c: char) -> Option<Unit> {
+        Some(match c {
+            'y' => Unit::Year,
+            'M' => Unit::Month,
+            'w' => Unit::Week,
+            'd' => Unit::Day,
+            'h' | 'H' => Unit::Hour,
+            'm' => Unit::Minute,
+            's' => Unit::Second,
+            _ => return None,
+        })
+    }
+}
+
+/// The broken down representation of a date.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct DateTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    millis_of_day: i64,
+}
+
+impl DateTime {
+    /// Converts milliseconds since the epoch to a `DateTime`.
+    fn from_millis(millis: i64) -> DateTime {
+        let (year, month, day) = civil_from_days(millis.div_euclid(DAY));
+        DateTime {
+            year,
+            month,
+            day,
+            millis_of_day: millis.rem_euclid(DAY),
+        }
+    }
+
+    /// Converts this `DateTime` to milliseconds since the epoch.
+    fn to_millis(self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) * DAY + self.millis_of_day
+    }
+}
+
+/// Returns the number of days since the epoch of a date in the proleptic
+/// Gregorian calendar, see <http://howardhinnant.github.io/date_algorithms.html>.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * i64::from((month + 9) % 12) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+/// Returns the number of days in the given month.
+fn days_in_month(year: i64, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    (days_from_civil(next_year, next_month, 1) - days_from_civil(year, month, 1)) as u32
+}
+
+/// Returns the current time.
+pub(super) fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64)
+}
+
+/// Rounds `millis` down to the start of the `unit` it's in, where weeks start
+/// on Monday.
+pub(super) fn round_down(millis: i64, unit: Unit) -> i64 {
+    let date = DateTime::from_millis(millis);
+    let start_of_month = |month| DateTime {
+        year: date.year,
+        month,
+        day: 1,
+        millis_of_day: 0,
+    };
+    match unit {
+        Unit::Year => start_of_month(1).to_millis(),
+        Unit::Quarter => start_of_month((date.month - 1) / 3 * 3 + 1).to_millis(),
+        Unit::Month => start_of_month(date.month).to_millis(),
+        Unit::Week => {
+            // the epoch was a Thursday
+            let days = millis.div_euclid(DAY);
+            (days - (days + 3).rem_euclid(7)) * DAY
+        }
+        Unit::Day => millis - millis.rem_euclid(DAY),
+        Unit::Hour => millis - millis.rem_euclid(HOUR),
+        Unit::Minute => millis - millis.rem_euclid(MINUTE),
+        Unit::Second => millis - millis.rem_euclid(SECOND),
+    }
+}
+
+/// Adds `amount` `unit`s to `millis`, clamping the day of the month if needed,
+/// e.g. adding a month to January 31st results in the last day of February.
+pub(super) fn add(millis: i64, amount: i64, unit: Unit) -> i64 {
+    let months = match unit {
+        Unit::Year => amount * 12,
+        Unit::Quarter => amount * 3,
+        Unit::Month => amount,
+        Unit::Week => return millis + amount * 7 * DAY,
+        Unit::Day => return millis + amount * DAY,
+        Unit::Hour => return millis + amount * HOUR,
+        Unit::Minute => return millis + amount * MINUTE,
+        Unit::Second => return millis + amount * SECOND,
+    };
+    let date = DateTime::from_millis(millis);
+    let month = date.year * 12 + i64::from(date.month - 1) + months;
+    let (year, month) = (month.div_euclid(12), month.rem_euclid(12) as u32 + 1);
+    DateTime {
+        year,
+        month,
+        day: date.day.min(days_in_month(year, month)),
+        ..date
+    }
+    .to_millis()
+}
+
+/// Parses a fixed interval, e.g. `90m`, into milliseconds.
+pub(super) fn parse_fixed_interval(interval: &str) -> Option<i64> {
+    let unit_start = interval.find(|c: char| !c.is_ascii_digit())?;
+    let amount: i64 = interval[..unit_start].parse().ok()?;
+    let unit = match &interval[unit_start..] {
+        "ms" => 1,
+        "s" => SECOND,
+        "m" => MINUTE,
+        "h" => HOUR,
+        "d" => DAY,
+        _ => return None,
+    };
+    Some(amount * unit).filter(|&interval| interval > 0)
+}
+
+/// Parses a time zone, which must be `UTC` or a fixed offset like `+01:00`,
+/// into its offset from UTC in milliseconds.
+pub(super) fn parse_time_zone(time_zone: &str) -> Option<i64> {
+    match time_zone {
+        "UTC" | "Z" | "GMT" | "Etc/UTC" => Some(0),
+        _ => parse_offset(time_zone),
+    }
+}
+
+/// Parses an offset like `+01:00`, `-0530` or `+01` into milliseconds.
+fn parse_offset(offset: &str) -> Option<i64> {
+    let sign = match offset.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits: String = offset[1..].chars().filter(|&c| c != ':').collect();
+    if !matches!(digits.len(), 2 | 4) || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i64 = digits[..2].parse().ok()?;
+    let minutes: i64 = digits
+        .get(2..)
+        .filter(|m| !m.is_empty())
+        .map_or(Some(0), |m| m.parse().ok())?;
+    Some(sign * (hours * HOUR + minutes * MINUTE))
+}
+
+/// Parses a date, i.e. milliseconds since the epoch or an ISO 8601 date like
+/// `2020-06-01`, `2020-06-01T12:00:00Z` or `2020-06-01T12:00:00.123+02:00`.
+///
+/// Dates without an offset are in UTC.
+pub(super) fn parse(date: &str) -> Option<i64> {
+    if !date.is_empty()
+        && date
+            .trim_start_matches('-')
+            .chars()
+            .all(|c| c.is_ascii_digit())
+    {
+        // years like `2020` are more likely than milliseconds after the epoch
+        if date.len() != 4 {
+            return date.parse().ok();
+        }
+    }
+
+    let number = |s: &str, len: usize| -> Option<i64> {
+        Some(s)
+            .filter(|s| s.len() == len && s.chars().all(|c| c.is_ascii_digit()))?
+            .parse()
+            .ok()
+    };
+
+    let (date, time) = match date.find(['T', ' ']) {
+        Some(i) => (&date[..i], Some(&date[i + 1..])),
+        None => (date, None),
+    };
+    let mut parts = date.split('-');
+    let year = number(parts.next()?, 4)?;
+    let month = parts.next().map_or(Some(1), |m| number(m, 2))? as u32;
+    let day = parts.next().map_or(Some(1), |d| number(d, 2))? as u32;
+    if parts.next().is_some()
+        || !(1..=12).contains(&month)
+        || day < 1
+        || day > days_in_month(year, month)
+    {
+        return None;
+    }
+
+    let mut millis = days_from_civil(year, month, day) * DAY;
+    if let Some(time) = time {
+        let (time, offset) = match time.find(['Z', '+', '-']) {
+            Some(i) if &time[i..] == "Z" => (&time[..i], 0),
+            Some(i) => (&time[..i], parse_offset(&time[i..])?),
+            None => (time, 0),
+        };
+        let (time, fraction) = match time.find('.') {
+            Some(i) => (&time[..i], Some(&time[i + 1..])),
+            None => (time, None),
+        };
+        let mut parts = time.split(':');
+        let hours = number(parts.next()?, 2)?;
+        let minutes = parts.next().map_or(Some(0), |m| number(m, 2))?;
+        let seconds = parts.next().map_or(Some(0), |s| number(s, 2))?;
+        if parts.next().is_some() || hours > 23 || minutes > 59 || seconds > 59 {
+            return None;
+        }
+        let fraction = match fraction {
+            Some(f) if !f.is_empty() && f.chars().all(|c| c.is_ascii_digit()) => {
+                // only milliseconds are kept
+                let f = format!("{:0<3}", &f[..f.len().min(3)]);
+                f.parse::<i64>().ok()?
+            }
+            Some(_) => return None,
+            None => 0,
+        };
+        millis += hours * HOUR + minutes * MINUTE + seconds * SECOND + fraction - offset;
+    }
+    Some(millis)
+}
+
+/// Parses a date math expression, e.g. `now-1d/d` or `2020-01-01||+1M`.
+pub(super) fn parse_math(expression: &str, now: i64) -> Option<i64> {
+    let (mut millis, mut math) = if let Some(math) = expression.strip_prefix("now") {
+        (now, math)
+    } else if let Some(i) = expression.find("||") {
+        (parse(&expression[..i])?, &expression[i + 2..])
+    } else {
+        return parse(expression);
+    };
+
+    while let Some(operator) = math.chars().next() {
+        math = &math[1..];
+        let digits = math
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(math.len());
+        let amount: i64 = if digits == 0 {
+            1
+        } else {
+            math[..digits].parse().ok()?
+        };
+        if operator == '/' && digits != 0 {
+            return None;
+        }
+        math = &math[digits..];
+        let unit = Unit::from_char(math.chars().next()?)?;
+        math = &math[1..];
+        millis = match operator {
+            '+' => add(millis, amount, unit),
+            '-' => add(millis, -amount, unit),
+            '/' => round_down(millis, unit),
+            _ => return None,
+        };
+    }
+    Some(millis)
+}
+
+/// Formats a date in the given time zone (i.e. offset in milliseconds) using
+/// a subset of the Java date format patterns, e.g. `yyyy-MM-dd'T'HH:mm:ssX`.
+///
+/// Returns `None` if the pattern is not supported.
+pub(super) fn format(millis: i64, offset: i64, pattern: Option<&str>) -> Option<String> {
+    // only the first of multiple formats is used for formatting
+    let pattern = pattern.map_or("", |pattern| pattern.split("||").next().unwrap_or_default());
+    let pattern = match pattern {
+        ""
+        | "strict_date_optional_time"
+        | "date_optional_time"
+        | "date_time"
+        | "strict_date_time" => "yyyy-MM-dd'T'HH:mm:ss.SSSXXX",
+        "strict_date" | "date" => "yyyy-MM-dd",
+        "epoch_millis" => return Some(millis.to_string()),
+        "epoch_second" => return Some(millis.div_euclid(SECOND).to_string()),
+        pattern => pattern,
+    };
+
+    let date = DateTime::from_millis(millis + offset);
+    let mut formatted = String::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            formatted.extend(chars.by_ref().take_while(|&c| c != '\''));
+            continue;
+        }
+        if !c.is_ascii_alphabetic() {
+            formatted.push(c);
+            continue;
+        }
+        let mut width = 1;
+        while chars.peek() == Some(&c) {
+            chars.next();
+            width += 1;
+        }
+        let value = match c {
+            'y' | 'u' if width == 2 => date.year.rem_euclid(100),
+            'y' | 'u' => date.year,
+            'M' => i64::from(date.month),
+            'd' => i64::from(date.day),
+            'H' => date.millis_of_day / HOUR,
+            'm' => date.millis_of_day % HOUR / MINUTE,
+            's' => date.millis_of_day % MINUTE / SECOND,
+            'S' => {
+                let millis = format!("{:03}", date.millis_of_day % SECOND);
+                formatted.push_str(&format!("{:0<width$}", millis, width = width)[..width]);
+                continue;
+            }
+            'X' | 'Z' | 'x' => {
+                if offset == 0 && c == 'X' {
+                    formatted.push('Z');
+                    continue;
+                }
+                let sign = if offset < 0 { '-' } else { '+' };
+                let (hours, minutes) = (offset.abs() / HOUR, offset.abs() % HOUR / MINUTE);
+                let separator = if width >= 3 || (c == 'Z' && width == 5) {
+                    ":"
+                } else {
+                    ""
+                };
+                formatted.push_str(&format!("{}{:02}{}{:02}", sign, hours, separator, minutes));
+                continue;
+            }
+            _ => return None,
+        };
+        formatted.push_str(&format!("{:0width$}", value, width = width));
+    }
+    Some(formatted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse() {
+        assert_eq!(parse("1970-01-01"), Some(0));
+        assert_eq!(parse("1591012800000"), Some(1_591_012_800_000));
+        assert_eq!(parse("2020-06-01T12:00:00Z"), Some(1_591_012_800_000));
+        assert_eq!(parse("2020-06-01T14:00:00+02:00"), Some(1_591_012_800_000));
+        assert_eq!(parse("2020-06-01 12:00:00.5"), Some(1_591_012_800_500));
+        assert_eq!(parse("2020"), Some(1_577_836_800_000));
+        assert_eq!(parse("2020-02-30"), None);
+        assert_eq!(parse("June 1st"), None);
+    }
+
+    #[test]
+    fn can_parse_math() {
+        let now = parse("2020-06-15T12:34:56Z").unwrap();
+        assert_eq!(parse_math("now", now), Some(now));
+        assert_eq!(parse_math("now-1d/d", now), parse("2020-06-14"));
+        assert_eq!(parse_math("now/M+1M", now), parse("2020-07-01"));
+        assert_eq!(parse_math("now/w", now), parse("2020-06-15"));
+        assert_eq!(parse_math("2020-01-31||+1M", now), parse("2020-02-29"));
+        assert_eq!(parse_math("2020-01-01||-1y/y", now), parse("2019-01-01"));
+        assert_eq!(parse_math("now+1x", now), None);
+    }
+
+    #[test]
+    fn can_round_down() {
+        let date = parse("2020-08-15T12:34:56.789Z").unwrap();
+        assert_eq!(
+            round_down(date, Unit::Quarter),
+            parse("2020-07-01").unwrap()
+        );
+        assert_eq!(
+            round_down(date, Unit::Hour),
+            parse("2020-08-15T12:00").unwrap()
+        );
+        let date = parse("1969-12-31T23:59:59Z").unwrap();
+        assert_eq!(round_down(date, Unit::Day), parse("1969-12-31").unwrap());
+        assert_eq!(round_down(date, Unit::Week), parse("1969-12-29").unwrap());
+    }
+
+    #[test]
+    fn can_format() {
+        let date = parse("2020-06-01T12:00:00.5Z").unwrap();
+        assert_eq!(
+            format(date, 0, None).as_deref(),
+            Some("2020-06-01T12:00:00.500Z")
+        );
+        assert_eq!(
+            format(date, 2 * HOUR, None).as_deref(),
+            Some("2020-06-01T14:00:00.500+02:00")
+        );
+        assert_eq!(
+            format(date, 0, Some("yyyy-MM-dd'T'HH:mm:ssX")).as_deref(),
+            Some("2020-06-01T12:00:00Z")
+        );
+        assert_eq!(
+            format(date, 0, Some("dd/MM/yy")).as_deref(),
+            Some("01/06/20")
+        );
+        assert_eq!(
+            format(date, 0, Some("epoch_millis")).as_deref(),
+            Some("1591012800500")
+        );
+        assert_eq!(format(date, 0, Some("EEE")), None);
+    }
+}
@@ -0,0 +1,104 @@
+//! An in-memory query evaluator and aggregation engine, e.g. for testing
+//! requests without running Elasticsearch.
+//!
+//! [`matches`] checks whether a JSON document matches a [`CompoundQuery`]:
+//!
+//! ```
+//! use elastiql::eval;
+//! use elastiql::search::query::{CompoundQuery, RangeQuery, TermQuery};
+//! use serde_json::json;
+//!
+//! let mut query = CompoundQuery::default();
+//! query.push(TermQuery::new("user.name", "kimchy"));
+//! query.push(
+//!     RangeQuery::builder()
+//!         .field("age".to_string())
+//!         .greater_than_or_equal_to(Some("18".to_string()))
+//!         .build(),
+//! );
+//!
+//! let document = json!({ "user": { "name": "kimchy" }, "age": 42 });
+//! assert!(eval::matches(&query, &document).unwrap());
+//! ```
+//!
+//! The evaluator is an *approximation* of Elasticsearch that doesn't know about
+//! the index's mapping:
+//!
+//! * `term`, `terms`, `prefix` & `regexp` queries compare against the values
+//!   of a field as is, i.e. as if it were a `keyword` field. A `.keyword`
+//!   multi-field falls back to its parent field.
+//! * `range` queries compare numerically if both the value and the bound are
+//!   numbers and as strings otherwise, so ISO 8601 dates work but [date math]
+//!   does not.
+//! * `match` & `simple_query_string` queries use a simple analyzer that
+//!   lowercases the text and splits it on anything but letters and digits.
+//! * `query_string` & raw queries are not supported and return an
+//!   [`EvalError`].
+//!
+//! [`aggregate`] computes aggregations over a list of documents, returning
+//! the same JSON Elasticsearch would, so it can be deserialized into an
+//! [`aggregation::Response`].
+//!
+//! [`CompoundQuery`]: crate::search::query::CompoundQuery
+//! [`aggregation::Response`]: crate::aggregation::Response
+//! [date math]: https://www.elastic.co/guide/en/elasticsearch/reference/current/common-options.html#date-math
+
+use std::fmt;
+
+pub use self::{aggregation::*, query::*};
+use crate::search::query::RegexpError;
+
+mod aggregation;
+mod date;
+mod query;
+
+/// The reason a query or aggregation can't be evaluated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EvalErrorKind {
+    /// The query or aggregation is not supported by the evaluator, e.g. a
+    /// `query_string` query or a `percentiles` aggregation.
+    Unsupported,
+
+    /// The regular expression of a `regexp` query is invalid.
+    InvalidRegexp(RegexpError),
+
+    /// The `minimum_should_match` parameter is invalid.
+    InvalidMinimumShouldMatch(String),
+}
+
+/// A query clause or aggregation that can't be evaluated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvalError {
+    /// The path to the offending clause, e.g. `query.bool.filter[0].query_string`
+    /// or `aggs.by_owner.percentiles`.
+    pub path: String,
+
+    /// The reason the clause can't be evaluated.
+    pub kind: EvalErrorKind,
+}
+
+impl EvalError {
+    /// Creates a new `EvalError`.
+    #[inline]
+    fn new(path: impl Into<String>, kind: EvalErrorKind) -> Self {
+        EvalError {
+            path: path.into(),
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.path)?;
+        match &self.kind {
+            EvalErrorKind::Unsupported => f.write_str("can't be evaluated in memory"),
+            EvalErrorKind::InvalidRegexp(err) => write!(f, "invalid regular expression: {}", err),
+            EvalErrorKind::InvalidMinimumShouldMatch(value) => {
+                write!(f, "invalid minimum_should_match `{}`", value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
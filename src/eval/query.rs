@@ -0,0 +1,851 @@
+//! Evaluates queries against JSON documents.
+
+use std::{borrow::Cow, cmp::Ordering};
+
+use serde_json::Value;
+
+use super::{EvalError, EvalErrorKind};
+use crate::search::query::{
+    BooleanQuery, CompoundQuery, MatchQuery, NestedQuery, PrefixQuery, Query,
+    QueryStringBooleanOperator, RangeQuery, RegexpQuery, SimpleQueryStringQuery, TermQuery,
+    TermsQuery,
+};
+
+/// Returns `true` if `document` matches `query`.
+pub fn matches(query: &CompoundQuery, document: &Value) -> Result<bool, EvalError> {
+    eval_compound(query, &Scope::root(document), "query".to_string())
+}
+
+/// Returns `true` if `document` matches a single `query`.
+pub fn matches_query(query: &Query, document: &Value) -> Result<bool, EvalError> {
+    eval_query(query, &Scope::root(document), "query".to_string())
+}
+
+/// Returns the (flattened) values of `field` in `document`, skipping nulls.
+#[cfg(feature = "mock")]
+pub(crate) fn field_values<'a>(document: &'a Value, field: &str) -> Vec<&'a Value> {
+    Scope::root(document).values(field)
+}
+
+/// The (nested) document a query is evaluated against.
+#[derive(Clone, Copy)]
+pub(super) struct Scope<'a> {
+    /// The full path of the nested document, empty for the root document.
+    pub(super) path: &'a str,
+
+    /// The (nested) document itself.
+    pub(super) document: &'a Value,
+}
+
+impl<'a> Scope<'a> {
+    /// The scope of a root document.
+    #[inline]
+    pub(super) fn root(document: &'a Value) -> Self {
+        Scope { path: "", document }
+    }
+
+    /// Returns the (flattened) values of the field with the full path `field`.
+    ///
+    /// Null values are skipped, as Elasticsearch does not index them.
+    pub(super) fn values(&self, field: &str) -> Vec<&'a Value> {
+        let mut values = Vec::new();
+        collect(self.document, self.relative(field), &mut values);
+        if values.is_empty() {
+            if let Some(parent) = field.strip_suffix(".keyword") {
+                collect(self.document, self.relative(parent), &mut values);
+            }
+        }
+        values.retain(|value| !value.is_null());
+        values
+    }
+
+    /// Strips the path of the nested document from `field`.
+    #[inline]
+    fn relative<'f>(&self, field: &'f str) -> &'f str {
+        if self.path.is_empty() {
+            return field;
+        }
+        field
+            .strip_prefix(self.path)
+            .and_then(|field| field.strip_prefix('.'))
+            .unwrap_or(field)
+    }
+}
+
+/// Collects every value at `path` in `value`, flattening arrays.
+fn collect<'a>(value: &'a Value, path: &str, values: &mut Vec<&'a Value>) {
+    match value {
+        Value::Array(array) => array.iter().for_each(|value| collect(value, path, values)),
+        _ if path.is_empty() => values.push(value),
+        Value::Object(object) => {
+            // a key may contain dots itself, e.g. `{ "user.name": "kimchy" }`
+            let ends = path.match_indices('.').map(|(i, _)| i);
+            for end in ends.chain(std::iter::once(path.len())) {
+                if let Some(value) = object.get(&path[..end]) {
+                    collect(value, path.get(end + 1..).unwrap_or(""), values);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Returns the string representation of a scalar value.
+pub(super) fn as_str(value: &Value) -> Option<Cow<'_, str>> {
+    match value {
+        Value::String(s) => Some(Cow::Borrowed(s)),
+        Value::Number(n) => Some(Cow::Owned(n.to_string())),
+        Value::Bool(b) => Some(Cow::Owned(b.to_string())),
+        _ => None,
+    }
+}
+
+/// Returns `true` if `value` is equal to `term`, comparing numbers numerically.
+fn term_eq(value: &Value, term: &str) -> bool {
+    match value {
+        Value::String(s) => s == term,
+        Value::Number(n) => matches!((n.as_f64(), term.parse::<f64>()), (Some(a), Ok(b)) if a == b),
+        Value::Bool(b) => term == if *b { "true" } else { "false" },
+        _ => false,
+    }
+}
+
+/// Compares `value` to `bound`, numerically if both are numbers.
+fn compare(value: &Value, bound: &str) -> Option<Ordering> {
+    match value {
+        Value::Number(n) => n.as_f64()?.partial_cmp(&bound.parse::<f64>().ok()?),
+        Value::String(s) => match (s.parse::<f64>(), bound.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a.partial_cmp(&b),
+            _ => Some(s.as_str().cmp(bound)),
+        },
+        _ => None,
+    }
+}
+
+/// Splits `text` into lowercase tokens of letters and digits.
+fn analyze(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Returns the number of `optional` clauses that must match according to the
+/// [`minimum_should_match`] parameter `value`.
+///
+/// [`minimum_should_match`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-minimum-should-match.html
+fn minimum_should_match(value: &str, optional: usize) -> Option<usize> {
+    let value = value.trim();
+    if value.contains('<') {
+        // e.g. `2<-25% 9<-3`: the spec with the largest threshold below the
+        // number of optional clauses applies, otherwise they're all required
+        let mut applicable = None;
+        for combination in value.split_whitespace() {
+            let mut parts = combination.splitn(2, '<');
+            let threshold: usize = parts.next()?.parse().ok()?;
+            let spec = parts.next()?;
+            minimum_should_match(spec, optional)?;
+            if optional > threshold && !matches!(applicable, Some((t, _)) if t >= threshold) {
+                applicable = Some((threshold, spec));
+            }
+        }
+        return match applicable {
+            Some((_, spec)) => minimum_should_match(spec, optional),
+            None => Some(optional),
+        };
+    }
+
+    let (number, percent) = match value.strip_suffix('%') {
+        Some(number) => (number, true),
+        None => (value, false),
+    };
+    let number: i64 = number.parse().ok()?;
+    let count = if percent {
+        optional * number.unsigned_abs() as usize / 100
+    } else {
+        number.unsigned_abs() as usize
+    };
+    Some(if number < 0 {
+        optional.saturating_sub(count)
+    } else {
+        count
+    })
+}
+
+/// Evaluates a compound query, which matches every document if it's empty.
+pub(super) fn eval_compound(
+    query: &CompoundQuery,
+    scope: &Scope,
+    path: String,
+) -> Result<bool, EvalError> {
+    match &query.boolean {
+        Some(boolean) => eval_boolean(boolean, scope, format!("{}.bool", path)),
+        None => Ok(true),
+    }
+}
+
+/// Evaluates a boolean query.
+fn eval_boolean(boolean: &BooleanQuery, scope: &Scope, path: String) -> Result<bool, EvalError> {
+    let all = |occurrence: &str, queries: &[Query]| -> Result<bool, EvalError> {
+        for (i, query) in queries.iter().enumerate() {
+            if !eval_query(query, scope, format!("{}.{}[{}]", path, occurrence, i))? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    };
+    if !all("must", &boolean.must)? || !all("filter", &boolean.filter)? {
+        return Ok(false);
+    }
+    for (i, query) in boolean.must_not.iter().enumerate() {
+        if eval_query(query, scope, format!("{}.must_not[{}]", path, i))? {
+            return Ok(false);
+        }
+    }
+
+    let required = match &boolean.minimum_should_match {
+        Some(value) => minimum_should_match(value, boolean.should.len()).ok_or_else(|| {
+            EvalError::new(
+                format!("{}.minimum_should_match", path),
+                EvalErrorKind::InvalidMinimumShouldMatch(value.to_string()),
+            )
+        })?,
+        None if boolean.must.is_empty() && boolean.filter.is_empty() => boolean.should.len().min(1),
+        None => 0,
+    };
+    let mut matched = 0;
+    for (i, query) in boolean.should.iter().enumerate() {
+        if matched >= required {
+            break;
+        }
+        if eval_query(query, scope, format!("{}.should[{}]", path, i))? {
+            matched += 1;
+        }
+    }
+    Ok(matched >= required)
+}
+
+/// Evaluates a single query, which must match *every* query type that is set.
+fn eval_query(query: &Query, scope: &Scope, path: String) -> Result<bool, EvalError> {
+    if let Some(q) = &query.exists {
+        if scope.values(&q.field).is_empty() {
+            return Ok(false);
+        }
+    }
+    if let Some(q) = &query.term {
+        if !eval_term(q, scope) {
+            return Ok(false);
+        }
+    }
+    if let Some(q) = &query.terms {
+        if !eval_terms(q, scope) {
+            return Ok(false);
+        }
+    }
+    if let Some(q) = &query.range {
+        if !eval_range(q, scope) {
+            return Ok(false);
+        }
+    }
+    if let Some(q) = &query.prefix {
+        if !eval_prefix(q, scope) {
+            return Ok(false);
+        }
+    }
+    if let Some(q) = &query.regexp {
+        if !eval_regexp(q, scope, format!("{}.regexp", path))? {
+            return Ok(false);
+        }
+    }
+    if let Some(q) = &query.match_ {
+        if !eval_match(q, scope) {
+            return Ok(false);
+        }
+    }
+    if let Some(q) = &query.simple_query_string {
+        let path = format!("{}.simple_query_string", path);
+        if !eval_simple_query_string(q, scope, path)? {
+            return Ok(false);
+        }
+    }
+    if query.query_string.is_some() {
+        let path = format!("{}.query_string", path);
+        return Err(EvalError::new(path, EvalErrorKind::Unsupported));
+    }
+    if let Some(q) = &query.nested {
+        if !eval_nested(q, scope, format!("{}.nested", path))? {
+            return Ok(false);
+        }
+    }
+    if let Some(q) = &query.boolean {
+        if !eval_boolean(q, scope, format!("{}.bool", path))? {
+            return Ok(false);
+        }
+    }
+    if query.raw_query.is_some() {
+        return Err(EvalError::new(path, EvalErrorKind::Unsupported));
+    }
+    Ok(true)
+}
+
+/// Evaluates a term query.
+fn eval_term(query: &TermQuery, scope: &Scope) -> bool {
+    scope
+        .values(&query.field)
+        .into_iter()
+        .any(|value| term_eq(value, &query.value))
+}
+
+/// Evaluates a terms query.
+fn eval_terms(query: &TermsQuery, scope: &Scope) -> bool {
+    scope
+        .values(&query.field)
+        .into_iter()
+        .any(|value| query.values.iter().any(|term| term_eq(value, term)))
+}
+
+/// Evaluates a range query.
+fn eval_range(query: &RangeQuery, scope: &Scope) -> bool {
+    let within = |value: &Value| {
+        let bounds = [
+            (&query.greater_than, &[Ordering::Greater][..]),
+            (
+                &query.greater_than_or_equal_to,
+                &[Ordering::Greater, Ordering::Equal],
+            ),
+            (&query.less_than, &[Ordering::Less]),
+            (
+                &query.less_than_or_equal_to,
+                &[Ordering::Less, Ordering::Equal],
+            ),
+        ];
+        bounds.iter().all(|(bound, allowed)| match bound {
+            Some(bound) => matches!(compare(value, bound), Some(o) if allowed.contains(&o)),
+            None => true,
+        })
+    };
+    scope.values(&query.field).into_iter().any(within)
+}
+
+/// Evaluates a prefix query.
+fn eval_prefix(query: &PrefixQuery, scope: &Scope) -> bool {
+    let prefix = if query.case_insensitive {
+        Cow::Owned(query.value.to_lowercase())
+    } else {
+        Cow::Borrowed(query.value.as_str())
+    };
+    scope
+        .values(&query.field)
+        .into_iter()
+        .filter_map(as_str)
+        .any(|value| {
+            if query.case_insensitive {
+                value.to_lowercase().starts_with(prefix.as_ref())
+            } else {
+                value.starts_with(prefix.as_ref())
+            }
+        })
+}
+
+/// Evaluates a regexp query, which must match a *whole* value.
+fn eval_regexp(query: &RegexpQuery, scope: &Scope, path: String) -> Result<bool, EvalError> {
+    let regexp = query
+        .parse()
+        .map_err(|err| EvalError::new(path, EvalErrorKind::InvalidRegexp(err)))?;
+    Ok(scope
+        .values(&query.field)
+        .into_iter()
+        .filter_map(as_str)
+        .any(|value| regexp.is_match(&value, query.case_insensitive)))
+}
+
+/// Evaluates a match query, which matches if *any* (or, with the `AND`
+/// operator, *all*) of its tokens match.
+fn eval_match(query: &MatchQuery, scope: &Scope) -> bool {
+    let tokens: Vec<String> = scope
+        .values(&query.field)
+        .into_iter()
+        .filter_map(as_str)
+        .flat_map(|value| analyze(&value))
+        .collect();
+    let query_tokens = analyze(&query.query);
+    let mut matching = query_tokens.iter().filter(|&token| tokens.contains(token));
+    match query.operator {
+        Some(QueryStringBooleanOperator::And) => {
+            !query_tokens.is_empty() && matching.count() == query_tokens.len()
+        }
+        _ => matching.next().is_some(),
+    }
+}
+
+/// Evaluates a nested query, which matches if *any* nested document matches.
+fn eval_nested(query: &NestedQuery, scope: &Scope, path: String) -> Result<bool, EvalError> {
+    let path = format!("{}.query", path);
+    for document in scope.values(&query.path) {
+        if !document.is_object() {
+            continue;
+        }
+        let nested = Scope {
+            path: &query.path,
+            document,
+        };
+        if eval_compound(&query.query, &nested, path.clone())? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// How a term of a simple query string must occur.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Occur {
+    Must,
+    Should,
+    MustNot,
+}
+
+/// A term of a simple query string.
+#[derive(PartialEq, Eq, Debug)]
+enum Term {
+    /// A single token, e.g. `quick`.
+    Token(String),
+
+    /// A prefix of a token, e.g. `qui*`.
+    Prefix(String),
+
+    /// Consecutive tokens, e.g. `"quick fox"`.
+    Phrase(Vec<String>),
+}
+
+impl Term {
+    /// Returns `true` if this term occurs in any of the (analyzed) `values`.
+    fn occurs_in(&self, values: &[Vec<String>]) -> bool {
+        values.iter().any(|tokens| match self {
+            Term::Token(token) => tokens.contains(token),
+            Term::Prefix(prefix) => tokens
+                .iter()
+                .any(|token| token.starts_with(prefix.as_str())),
+            Term::Phrase(phrase) => tokens
+                .windows(phrase.len())
+                .any(|window| window == &phrase[..]),
+        })
+    }
+}
+
+/// A clause of a simple query string; negated clauses match if the term does
+/// *not* occur.
+type Clause = (Occur, bool, Term);
+
+/// Parses a simple query string into groups of clauses separated by `|`, only
+/// one of which has to match.
+///
+/// Precedence (i.e. parentheses), fuzziness & slop are ignored.
+fn parse_simple_query_string(
+    query: &str,
+    default_operator: &QueryStringBooleanOperator,
+) -> Vec<Vec<Clause>> {
+    let mut groups = vec![Vec::new()];
+    let mut chars = query.chars().peekable();
+    loop {
+        while matches!(chars.peek(), Some(c) if c.is_whitespace() || *c == '(' || *c == ')') {
+            chars.next();
+        }
+        let mut negated = false;
+        let mut occur = match default_operator {
+            QueryStringBooleanOperator::Or => Occur::Should,
+            QueryStringBooleanOperator::And => Occur::Must,
+        };
+        match chars.peek() {
+            None => break,
+            Some('|') => {
+                chars.next();
+                groups.push(Vec::new());
+                continue;
+            }
+            Some('+') => {
+                chars.next();
+                occur = Occur::Must;
+            }
+            Some('-') => {
+                chars.next();
+                // with the `OR` operator, `-` means "or not"
+                if occur == Occur::Must {
+                    occur = Occur::MustNot;
+                } else {
+                    negated = true;
+                }
+            }
+            _ => {}
+        }
+
+        let term = if chars.peek() == Some(&'"') {
+            chars.next();
+            let phrase: String = chars.by_ref().take_while(|&c| c != '"').collect();
+            Term::Phrase(analyze(&phrase))
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '|' || c == '(' || c == ')' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            let word = match word.find('~') {
+                Some(i) => &word[..i],
+                None => &word,
+            };
+            let mut tokens = analyze(word);
+            if word.ends_with('*') && tokens.len() == 1 {
+                Term::Prefix(tokens.remove(0))
+            } else if tokens.len() == 1 {
+                Term::Token(tokens.remove(0))
+            } else {
+                Term::Phrase(tokens)
+            }
+        };
+        if !matches!(&term, Term::Phrase(tokens) if tokens.is_empty()) {
+            groups.last_mut().unwrap().push((occur, negated, term));
+        }
+    }
+    groups
+}
+
+/// Evaluates a simple query string query.
+fn eval_simple_query_string(
+    query: &SimpleQueryStringQuery,
+    scope: &Scope,
+    path: String,
+) -> Result<bool, EvalError> {
+    let mut values = Vec::new();
+    for field in &query.fields {
+        // strip the boost, e.g. `title^3`
+        let field = field.split('^').next().unwrap_or_default();
+        values.extend(scope.values(field));
+    }
+    if query.fields.is_empty() {
+        collect_all(scope.document, &mut values);
+    }
+    let values: Vec<Vec<String>> = values
+        .into_iter()
+        .filter_map(as_str)
+        .map(|value| analyze(&value))
+        .collect();
+
+    for clauses in parse_simple_query_string(&query.query, &query.default_operator) {
+        let occurs = |(_, negated, term): &&Clause| term.occurs_in(&values) != *negated;
+        let count = |occur: Occur| clauses.iter().filter(|c| c.0 == occur).count();
+
+        let (must, should) = (count(Occur::Must), count(Occur::Should));
+        if clauses.is_empty()
+            || clauses
+                .iter()
+                .filter(|c| c.0 == Occur::Must)
+                .filter(occurs)
+                .count()
+                < must
+            || clauses
+                .iter()
+                .filter(|c| c.0 == Occur::MustNot)
+                .any(|c| occurs(&c))
+        {
+            continue;
+        }
+        let required = match &query.minimum_should_match {
+            Some(value) => minimum_should_match(value, should).ok_or_else(|| {
+                EvalError::new(
+                    format!("{}.minimum_should_match", path),
+                    EvalErrorKind::InvalidMinimumShouldMatch(value.to_string()),
+                )
+            })?,
+            None if must == 0 => should.min(1),
+            None => 0,
+        };
+        let matched = clauses
+            .iter()
+            .filter(|c| c.0 == Occur::Should)
+            .filter(occurs)
+            .count();
+        if matched >= required {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Collects every scalar value in `value`, i.e. the values of all its fields.
+fn collect_all<'a>(value: &'a Value, values: &mut Vec<&'a Value>) {
+    match value {
+        Value::Array(array) => array.iter().for_each(|value| collect_all(value, values)),
+        Value::Object(object) => object.values().for_each(|value| collect_all(value, values)),
+        Value::Null => {}
+        _ => values.push(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::search::query::{ExistsQuery, QueryStringQuery, RawQuery};
+
+    fn document() -> Value {
+        json!({
+            "user": { "name": "kimchy", "id": 7 },
+            "tags": ["rust", "search"],
+            "age": 42,
+            "active": true,
+            "deleted_at": null,
+            "created_at": "2020-06-01T12:00:00Z",
+            "title": "The Quick Brown Fox",
+            "comments": [
+                { "author": "alice", "stars": 5, "text": "Great read" },
+                { "author": "bob", "stars": 1, "text": "Too slow" },
+            ],
+        })
+    }
+
+    fn query(query: impl Into<Query>) -> bool {
+        matches_query(&query.into(), &document()).unwrap()
+    }
+
+    fn range_query(field: &str, gte: Option<&str>, lt: Option<&str>) -> RangeQuery {
+        RangeQuery::builder()
+            .field(field.to_string())
+            .greater_than_or_equal_to(gte.map(str::to_string))
+            .less_than(lt.map(str::to_string))
+            .build()
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert!(matches(&CompoundQuery::default(), &document()).unwrap());
+    }
+
+    #[test]
+    fn term_and_terms() {
+        assert!(query(TermQuery::new("user.name", "kimchy")));
+        assert!(query(TermQuery::new("user.name.keyword", "kimchy")));
+        assert!(!query(TermQuery::new("user.name", "Kimchy")));
+        assert!(query(TermQuery::new("user.id", "7.0")));
+        assert!(query(TermQuery::new("active", "true")));
+        assert!(query(TermQuery::new("tags", "search")));
+        assert!(query(TermsQuery::new("tags", vec!["go", "rust"])));
+        assert!(!query(TermsQuery::new("tags", vec!["go", "java"])));
+        assert!(!query(TermQuery::new("missing", "kimchy")));
+    }
+
+    #[test]
+    fn keys_may_contain_dots() {
+        let document = json!({ "user.name": "kimchy" });
+        let query = TermQuery::new("user.name", "kimchy").into();
+        assert!(matches_query(&query, &document).unwrap());
+    }
+
+    #[test]
+    fn range() {
+        assert!(query(range_query("age", Some("18"), None)));
+        assert!(query(range_query("age", Some("42"), Some("43"))));
+        assert!(!query(range_query("age", None, Some("42"))));
+        assert!(query(range_query(
+            "created_at",
+            Some("2020-01-01"),
+            Some("2021-01-01")
+        )));
+        assert!(!query(range_query("created_at", Some("2021-01-01"), None)));
+        assert!(!query(range_query("missing", None, None)));
+    }
+
+    #[test]
+    fn exists() {
+        assert!(query(ExistsQuery::new("user.name")));
+        assert!(query(ExistsQuery::new("user")));
+        assert!(!query(ExistsQuery::new("deleted_at")));
+        assert!(!query(ExistsQuery::new("missing")));
+        let document = json!({ "tags": [] });
+        let query = ExistsQuery::new("tags").into();
+        assert!(!matches_query(&query, &document).unwrap());
+    }
+
+    #[test]
+    fn prefix() {
+        assert!(query(PrefixQuery::new("user.name", "kim")));
+        assert!(!query(PrefixQuery::new("user.name", "KIM")));
+        let mut prefix = PrefixQuery::new("user.name", "KIM");
+        prefix.case_insensitive = true;
+        assert!(query(prefix));
+    }
+
+    #[test]
+    fn regexp() {
+        assert!(query(RegexpQuery::new("user.name", "k.*y", None)));
+        assert!(query(RegexpQuery::new("user.name", "k[a-z]{3}h?y", None)));
+        assert!(!query(RegexpQuery::new("user.name", "k.*z", None)));
+        // the whole value must match
+        assert!(!query(RegexpQuery::new("user.name", "kim", None)));
+        assert!(query(RegexpQuery::new("user.name", "kim@", None)));
+        assert!(query(RegexpQuery::new("user.name", "~(bob)", None)));
+        assert!(query(RegexpQuery::new("user.name", "k.*&.*y", None)));
+        assert!(query(RegexpQuery::new("tags", "r(u|a)st", None)));
+        assert!(query(RegexpQuery::new("user.id", "<1-10>", None)));
+        assert!(!query(RegexpQuery::new("user.name", "\"k.\".*", None)));
+        assert!(!query(RegexpQuery::new("user.name", "KIMCHY", None)));
+        let mut regexp = RegexpQuery::new("user.name", "KIM[A-C]HY", None);
+        regexp.case_insensitive = true;
+        assert!(query(regexp));
+        assert!(query(RegexpQuery::new("user.name", "k.{5}", None)));
+        assert!(!query(RegexpQuery::new("user.name", "k.{6}", None)));
+        assert!(query(RegexpQuery::new("user.name", "k.{4,}y", None)));
+        assert!(!query(RegexpQuery::new("user.name", "k.{0,3}y", None)));
+        assert!(query(RegexpQuery::new(
+            "user.name",
+            "(k|i|m|c|h|y){2,}",
+            None
+        )));
+    }
+
+    #[test]
+    fn regexp_with_nested_repetitions_is_fast() {
+        let document = json!({ "name": "a".repeat(100) });
+        let started = std::time::Instant::now();
+        let patterns = [
+            ("((((a*)*)*)*)*b", false),
+            ("((((a*)*)*)*)*", true),
+            ("(a|aa)*(a+)+c", false),
+        ];
+        for &(pattern, expected) in &patterns {
+            let query = RegexpQuery::new("name", pattern, None).into();
+            assert_eq!(matches_query(&query, &document).unwrap(), expected);
+        }
+        // without memoization, these take exponentially long
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn match_() {
+        assert!(query(MatchQuery::new("title", "quick")));
+        assert!(query(MatchQuery::new("title", "slow FOX!")));
+        assert!(!query(MatchQuery::new("title", "slow dog")));
+        assert!(query(MatchQuery::new("comments.text", "read")));
+        let mut match_ = MatchQuery::new("title", "slow FOX!");
+        match_.operator = Some(QueryStringBooleanOperator::And);
+        assert!(!query(match_.clone()));
+        match_.query = "quick fox".to_string();
+        assert!(query(match_));
+    }
+
+    #[test]
+    fn simple_query_string() {
+        let query = |fields: Vec<&str>, q: &str, op: QueryStringBooleanOperator| {
+            let mut query = SimpleQueryStringQuery::new(fields, q);
+            query.default_operator = op;
+            self::query(query)
+        };
+        let (or, and) = (
+            QueryStringBooleanOperator::Or,
+            QueryStringBooleanOperator::And,
+        );
+
+        assert!(query(vec!["title"], "quick dog", or));
+        assert!(!query(vec!["title"], "quick dog", and));
+        assert!(query(vec!["title^2"], "quick +fox", or));
+        assert!(!query(vec!["title"], "quick +dog", or));
+        assert!(!query(vec!["title"], "quick -fox", and));
+        assert!(query(vec!["title"], "dog -cat", or));
+        assert!(query(vec!["title"], "dog | (quick fox)", and));
+        assert!(query(vec!["title"], "\"brown fox\"", or));
+        assert!(!query(vec!["title"], "\"fox brown\"", or));
+        assert!(query(vec!["title"], "bro*", and));
+        assert!(query(vec![], "kimchy", or));
+        assert!(!query(vec![], "", or));
+
+        let mut q = SimpleQueryStringQuery::new(vec!["title"], "quick brown dog");
+        q.minimum_should_match = Some("2".to_string());
+        assert!(self::query(q.clone()));
+        q.minimum_should_match = Some("100%".to_string());
+        assert!(!self::query(q));
+    }
+
+    #[test]
+    fn boolean() {
+        let mut boolean = BooleanQuery::default();
+        boolean
+            .must
+            .push(TermQuery::new("user.name", "kimchy").into());
+        boolean.must_not.push(TermQuery::new("tags", "java").into());
+        assert!(query(Query::builder().boolean(boolean.clone()).build()));
+
+        boolean.filter.push(TermQuery::new("tags", "go").into());
+        assert!(!query(Query::builder().boolean(boolean).build()));
+
+        let mut boolean = BooleanQuery::default();
+        boolean.should.push(TermQuery::new("tags", "go").into());
+        assert!(!query(Query::builder().boolean(boolean.clone()).build()));
+        boolean.should.push(TermQuery::new("tags", "rust").into());
+        assert!(query(Query::builder().boolean(boolean.clone()).build()));
+        boolean.minimum_should_match = Some("2".to_string());
+        assert!(!query(Query::builder().boolean(boolean.clone()).build()));
+        boolean.minimum_should_match = Some("50%".to_string());
+        assert!(query(Query::builder().boolean(boolean.clone()).build()));
+
+        // `should` clauses are optional next to `must` clauses
+        let mut boolean = BooleanQuery::default();
+        boolean
+            .must
+            .push(TermQuery::new("user.name", "kimchy").into());
+        boolean.should.push(TermQuery::new("tags", "go").into());
+        assert!(query(Query::builder().boolean(boolean).build()));
+    }
+
+    #[test]
+    fn minimum_should_match() {
+        assert_eq!(super::minimum_should_match("2", 4), Some(2));
+        assert_eq!(super::minimum_should_match("-1", 4), Some(3));
+        assert_eq!(super::minimum_should_match("75%", 3), Some(2));
+        assert_eq!(super::minimum_should_match("-25%", 3), Some(3));
+        assert_eq!(super::minimum_should_match("3<90%", 3), Some(3));
+        assert_eq!(super::minimum_should_match("3<90%", 10), Some(9));
+        assert_eq!(super::minimum_should_match("2<-25% 9<-3", 5), Some(4));
+        assert_eq!(super::minimum_should_match("2<-25% 9<-3", 12), Some(9));
+        assert_eq!(super::minimum_should_match("a lot", 3), None);
+    }
+
+    #[test]
+    fn nested() {
+        let nested = |author: &str, stars: &str| {
+            let mut query = CompoundQuery::default();
+            query.push(TermQuery::new("comments.author", author));
+            query.push(range_query("comments.stars", Some(stars), None));
+            NestedQuery::new("comments", query, false)
+        };
+        assert!(query(nested("alice", "4")));
+        // both clauses must match the *same* nested document
+        assert!(!query(nested("bob", "4")));
+    }
+
+    #[test]
+    fn unsupported_queries_are_err() {
+        let mut query = CompoundQuery::default();
+        query.push(TermQuery::new("user.name", "kimchy"));
+        query.push(
+            QueryStringQuery::builder()
+                .query("kimchy")
+                .fields(vec![])
+                .build(),
+        );
+        let err = matches(&query, &document()).unwrap_err();
+        assert_eq!(err.path, "query.bool.filter[1].query_string");
+        assert_eq!(err.kind, EvalErrorKind::Unsupported);
+
+        let query = Query::raw(RawQuery::new(json!({ "match_all": {} })));
+        let err = matches_query(&query, &document()).unwrap_err();
+        assert_eq!(err.path, "query");
+
+        let query = RegexpQuery::new("user.name", "k(", None).into();
+        let err = matches_query(&query, &document()).unwrap_err();
+        assert_eq!(err.path, "query.regexp");
+        assert!(matches!(err.kind, EvalErrorKind::InvalidRegexp(_)));
+    }
+}
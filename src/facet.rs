@@ -0,0 +1,660 @@
+//! A helper for [faceted navigation], where the hits are filtered by the
+//! values the user selected in each facet while every facet still counts the
+//! values it would match *without* its own selection (so that e.g. selecting
+//! one brand doesn't hide the other brands).
+//!
+//! [`FacetedSearch`] applies the selections as the `post_filter` of the search
+//! request, and wraps the aggregation of each facet in a `filter` aggregation
+//! of the selections of all the *other* facets.
+//!
+//! ```
+//! use elastiql::facet::{Facet, FacetedSearch};
+//! use elastiql::search;
+//! use serde_json::json;
+//!
+//! let search = FacetedSearch::new(search::Request::builder().build())
+//!     .facet(Facet::terms("brand", "brand"))
+//!     .facet(Facet::terms("color", "color"))
+//!     .select("brand", "acme")
+//!     .select("color", "red")
+//!     .select("color", "blue");
+//!
+//! assert_eq!(
+//!     search.body()?,
+//!     json!({
+//!         "post_filter": {
+//!             "bool": {
+//!                 "filter": [
+//!                     { "terms": { "brand": ["acme"] } },
+//!                     { "terms": { "color": ["blue", "red"] } },
+//!                 ],
+//!             },
+//!         },
+//!         "terminate_after": 0,
+//!         "version": false,
+//!         "seq_no_primary_term": false,
+//!         "aggs": {
+//!             "brand": {
+//!                 "filter": { "bool": { "filter": [{ "terms": { "color": ["blue", "red"] } }] } },
+//!                 "meta": { "_skip": true },
+//!                 "aggs": { "brand": { "terms": { "field": "brand" } } },
+//!             },
+//!             "color": {
+//!                 "filter": { "bool": { "filter": [{ "terms": { "brand": ["acme"] } }] } },
+//!                 "meta": { "_skip": true },
+//!                 "aggs": { "color": { "terms": { "field": "color" } } },
+//!             },
+//!         },
+//!     })
+//! );
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+//!
+//! [faceted navigation]: https://www.elastic.co/guide/en/elasticsearch/reference/current/filter-search-results.html#post-filter
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::ser;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    aggregation::{
+        self,
+        types::{
+            CalendarInterval, DateHistogramAggregation, Range, RangeAggregation, TermsAggregation,
+        },
+    },
+    search::{
+        self,
+        query::{BooleanQuery, CompoundQuery, Query, RangeQuery, TermsQuery},
+    },
+};
+
+/// A facet, i.e. an aggregation on a field whose buckets the user can select
+/// to filter the hits by.
+#[derive(Clone, Debug)]
+pub struct Facet {
+    /// The name of the facet, which is also the name of its aggregation.
+    pub name: String,
+
+    /// The field to aggregate and filter on.
+    pub field: String,
+
+    /// How the values of `field` are bucketed.
+    pub kind: FacetKind,
+}
+
+/// How the values of the field of a [`Facet`] are bucketed.
+#[derive(Clone, Debug)]
+pub enum FacetKind {
+    /// One bucket per unique value, using a `terms` aggregation. The values
+    /// of the facet are the terms.
+    ///
+    /// **Note**: only keyword (or other string-keyed) fields are supported,
+    /// as the [`aggregation::Response`] doesn't keep numeric keys.
+    Terms {
+        /// The maximum number of buckets to return. Defaults to `10`.
+        size: Option<u64>,
+    },
+
+    /// One bucket per range, using a `range` aggregation. The values of the
+    /// facet are the [keys](Range::key) of the ranges, e.g. `100.0-200.0`.
+    Range {
+        /// The ranges to count, each including its `from` and excluding its
+        /// `to` value.
+        ranges: Vec<Range>,
+    },
+
+    /// One bucket per interval, using a `date_histogram` aggregation. The
+    /// values of the facet are the start of each interval, formatted as the
+    /// default format of the field (e.g. `2020-01-01T00:00:00.000Z`).
+    ///
+    /// **Note**: exactly one of `calendar_interval` and `fixed_interval` must
+    /// be specified, and the `fixed_interval` may not be in milliseconds.
+    DateHistogram {
+        /// The calendar-aware interval of each bucket, e.g. one month.
+        calendar_interval: Option<CalendarInterval>,
+
+        /// The fixed interval of each bucket, e.g. `12h`.
+        fixed_interval: Option<String>,
+    },
+}
+
+impl Facet {
+    /// Constructs a new `terms` facet on `field`.
+    #[inline]
+    pub fn terms(name: impl Into<String>, field: impl Into<String>) -> Facet {
+        Facet {
+            name: name.into(),
+            field: field.into(),
+            kind: FacetKind::Terms { size: None },
+        }
+    }
+
+    /// Constructs a new `range` facet on `field` with one bucket per range.
+    #[inline]
+    pub fn range(name: impl Into<String>, field: impl Into<String>, ranges: Vec<Range>) -> Facet {
+        Facet {
+            name: name.into(),
+            field: field.into(),
+            kind: FacetKind::Range { ranges },
+        }
+    }
+
+    /// Constructs a new `date_histogram` facet on `field` with one bucket per
+    /// calendar `interval`.
+    #[inline]
+    pub fn date_histogram(
+        name: impl Into<String>,
+        field: impl Into<String>,
+        interval: CalendarInterval,
+    ) -> Facet {
+        Facet {
+            name: name.into(),
+            field: field.into(),
+            kind: FacetKind::DateHistogram {
+                calendar_interval: Some(interval),
+                fixed_interval: None,
+            },
+        }
+    }
+
+    /// Returns the aggregation counting the values of this facet.
+    fn aggregation(&self) -> aggregation::Request {
+        let field = Some(self.field.clone());
+        let request = aggregation::Request {
+            name: self.name.clone(),
+            ..aggregation::Request::default()
+        };
+
+        match &self.kind {
+            FacetKind::Terms { size } => aggregation::Request {
+                terms: Some(TermsAggregation {
+                    field,
+                    script: None,
+                    size: *size,
+                    missing: None,
+                }),
+                ..request
+            },
+            FacetKind::Range { ranges } => aggregation::Request {
+                range: Some(RangeAggregation {
+                    field,
+                    script: None,
+                    ranges: ranges.clone(),
+                }),
+                ..request
+            },
+            FacetKind::DateHistogram {
+                calendar_interval,
+                fixed_interval,
+            } => aggregation::Request {
+                date_histogram: Some(DateHistogramAggregation {
+                    field: self.field.clone(),
+                    calendar_interval: *calendar_interval,
+                    fixed_interval: fixed_interval.clone(),
+                    time_zone: None,
+                    offset: None,
+                    format: None,
+                    missing: None,
+                }),
+                ..request
+            },
+        }
+    }
+
+    /// Returns the query matching any of the `selected` values of this facet,
+    /// if any of them are known.
+    fn query(&self, selected: &BTreeSet<String>) -> Option<Query> {
+        let mut queries: Vec<Query> = match &self.kind {
+            FacetKind::Terms { .. } => {
+                return Some(TermsQuery::new(self.field.as_str(), selected).into());
+            }
+            FacetKind::Range { ranges } => ranges
+                .iter()
+                .filter(|range| selected.contains(&range.key()))
+                .map(|range| {
+                    RangeQuery {
+                        field: self.field.clone(),
+                        greater_than: None,
+                        greater_than_or_equal_to: range.from.map(|from| from.to_string()),
+                        less_than: range.to.map(|to| to.to_string()),
+                        less_than_or_equal_to: None,
+                        boost: None,
+                        name: None,
+                    }
+                    .into()
+                })
+                .collect(),
+            FacetKind::DateHistogram {
+                calendar_interval,
+                fixed_interval,
+            } => {
+                let interval = match (calendar_interval, fixed_interval) {
+                    (Some(interval), _) => date_math(interval).to_string(),
+                    (None, Some(interval)) => interval.clone(),
+                    (None, None) => return None,
+                };
+                selected
+                    .iter()
+                    .map(|start| {
+                        RangeQuery {
+                            field: self.field.clone(),
+                            greater_than: None,
+                            greater_than_or_equal_to: Some(start.clone()),
+                            less_than: Some(format!("{}||+{}", start, interval)),
+                            less_than_or_equal_to: None,
+                            boost: None,
+                            name: None,
+                        }
+                        .into()
+                    })
+                    .collect()
+            }
+        };
+
+        match queries.len() {
+            0 => None,
+            1 => queries.pop(),
+            _ => Some(Query {
+                boolean: Some(BooleanQuery {
+                    should: queries,
+                    ..BooleanQuery::default()
+                }),
+                ..Query::default()
+            }),
+        }
+    }
+}
+
+/// The date math of one calendar `interval`, e.g. `1M` for a month.
+fn date_math(interval: &CalendarInterval) -> &'static str {
+    match interval {
+        CalendarInterval::Minute => "1m",
+        CalendarInterval::Hour => "1h",
+        CalendarInterval::Day => "1d",
+        CalendarInterval::Week => "1w",
+        CalendarInterval::Month => "1M",
+        CalendarInterval::Quarter => "3M",
+        CalendarInterval::Year => "1y",
+    }
+}
+
+/// The document `count` of a bucket as an integer, which saturates at `0` (e.g.
+/// for negative counts or `NaN`) and `u64::MAX`.
+#[inline]
+fn doc_count(count: f64) -> u64 {
+    #[allow(clippy::as_conversions)]
+    let count = count.round() as u64;
+    count
+}
+
+/// A search request with facets and the values the user selected in them.
+///
+/// Within a facet, a hit has to match *any* of the selected values, and it has
+/// to match *every* facet with a selection.
+#[derive(Clone, Debug)]
+pub struct FacetedSearch {
+    /// The request to filter the hits of.
+    request: search::Request,
+
+    /// The facets, in order.
+    facets: Vec<Facet>,
+
+    /// The selected values, by facet name.
+    selections: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl FacetedSearch {
+    /// Constructs a new `FacetedSearch` without facets, which filters the hits
+    /// of `request`.
+    #[inline]
+    pub fn new(request: search::Request) -> FacetedSearch {
+        FacetedSearch {
+            request,
+            facets: vec![],
+            selections: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a facet.
+    #[inline]
+    pub fn facet(mut self, facet: Facet) -> Self {
+        self.facets.push(facet);
+        self
+    }
+
+    /// Selects a `value` of the facet named `facet`.
+    #[inline]
+    pub fn select(mut self, facet: impl Into<String>, value: impl Into<String>) -> Self {
+        self.selections
+            .entry(facet.into())
+            .or_default()
+            .insert(value.into());
+        self
+    }
+
+    /// The facets, in order.
+    #[inline]
+    pub fn facets(&self) -> &[Facet] {
+        &self.facets
+    }
+
+    /// Returns the search request, with the selections appended to its
+    /// `post_filter`.
+    pub fn request(&self) -> search::Request {
+        let mut request = self.request.clone();
+        for (_, query) in self.queries() {
+            request.post_filter.push(query);
+        }
+        request
+    }
+
+    /// Returns the aggregation of each facet, wrapped in a `filter`
+    /// aggregation (of the same name) of the selections of all the other
+    /// facets if there are any.
+    ///
+    /// The `filter` aggregations have a `_skip` metadata, so they are left out
+    /// of the [`aggregation::Response`].
+    pub fn aggregations(&self) -> Vec<aggregation::Request> {
+        let queries = self.queries();
+
+        self.facets
+            .iter()
+            .map(|facet| {
+                let mut filter = CompoundQuery::default();
+                for (name, query) in &queries {
+                    if *name != facet.name {
+                        filter.push(query.clone());
+                    }
+                }
+
+                if filter.is_empty() {
+                    facet.aggregation()
+                } else {
+                    aggregation::Request {
+                        name: facet.name.clone(),
+                        filters: Some(filter),
+                        metadata: Some(json!({ "_skip": true }).into()),
+                        aggregations: Some(vec![facet.aggregation()]),
+                        ..aggregation::Request::default()
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the body of the search request, i.e. the
+    /// [`request`](FacetedSearch::request) with the
+    /// [`aggregations`](FacetedSearch::aggregations) as its `aggs`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request or any aggregation can't be serialized
+    /// as a JSON object.
+    pub fn body(&self) -> Result<Value, serde_json::Error> {
+        let mut body = match serde_json::to_value(self.request())? {
+            Value::Object(body) => body,
+            _ => return Err(ser::Error::custom("the request must be a JSON object")),
+        };
+
+        let mut aggs = Map::new();
+        for aggregation in self.aggregations() {
+            match serde_json::to_value(&aggregation)? {
+                Value::Object(aggregation) => aggs.extend(aggregation),
+                _ => return Err(ser::Error::custom("an aggregation must be a JSON object")),
+            }
+        }
+        body.insert("aggs".to_string(), Value::Object(aggs));
+
+        Ok(Value::Object(body))
+    }
+
+    /// Returns the values of each facet, in order, from the `response` to the
+    /// [`request`](FacetedSearch::request).
+    ///
+    /// Selected values that aren't in the response (e.g. as nothing matches
+    /// them anymore) are appended with a `count` of `0`.
+    pub fn facet_values(&self, response: &aggregation::Response) -> Vec<FacetValues> {
+        let no_selections = BTreeSet::new();
+
+        self.facets
+            .iter()
+            .map(|facet| {
+                let selected = self.selections.get(&facet.name).unwrap_or(&no_selections);
+
+                let mut values: Vec<FacetValue> = response
+                    .aggregations
+                    .iter()
+                    .find(|result| result.parent.is_none() && result.name == facet.name)
+                    .map(|result| {
+                        result
+                            .fields
+                            .iter()
+                            .zip(&result.values)
+                            .map(|(value, &count)| FacetValue {
+                                value: value.clone(),
+                                count: doc_count(count),
+                                selected: selected.contains(value),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                for value in selected {
+                    if !values.iter().any(|v| v.value == *value) {
+                        values.push(FacetValue {
+                            value: value.clone(),
+                            count: 0,
+                            selected: true,
+                        });
+                    }
+                }
+
+                FacetValues {
+                    name: facet.name.clone(),
+                    values,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the query of the selections of each facet with any, by facet
+    /// name.
+    fn queries(&self) -> Vec<(&str, Query)> {
+        self.facets
+            .iter()
+            .filter_map(|facet| {
+                let selected = self.selections.get(&facet.name)?;
+                Some((facet.name.as_str(), facet.query(selected)?))
+            })
+            .collect()
+    }
+}
+
+/// The values of a facet in a search response.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Clone, Debug)]
+pub struct FacetValues {
+    /// The name of the facet.
+    pub name: String,
+
+    /// The values, in the order of the buckets of the aggregation.
+    pub values: Vec<FacetValue>,
+}
+
+/// A value of a facet.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Clone, Debug)]
+pub struct FacetValue {
+    /// The value, i.e. the key of its bucket.
+    pub value: String,
+
+    /// The number of documents with this value which match the selections
+    /// of all the *other* facets.
+    pub count: u64,
+
+    /// Whether the user selected this value.
+    pub selected: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::Deserialize;
+
+    fn search() -> FacetedSearch {
+        FacetedSearch::new(search::Request::builder().build())
+            .facet(Facet::terms("brand", "brand"))
+            .facet(Facet::range(
+                "price",
+                "price",
+                vec![
+                    Range {
+                        from: None,
+                        to: Some(100.0),
+                    },
+                    Range {
+                        from: Some(100.0),
+                        to: None,
+                    },
+                ],
+            ))
+            .facet(Facet::date_histogram(
+                "released",
+                "released_at",
+                CalendarInterval::Year,
+            ))
+    }
+
+    #[test]
+    fn doc_counts_saturate() {
+        assert_eq!(doc_count(3.0), 3);
+        assert_eq!(doc_count(2.6), 3);
+        assert_eq!(doc_count(-1.0), 0);
+        assert_eq!(doc_count(f64::NAN), 0);
+        assert_eq!(doc_count(f64::INFINITY), u64::MAX);
+    }
+
+    #[test]
+    fn can_build_faceted_requests() {
+        let search = search()
+            .select("price", "*-100.0")
+            .select("price", "100.0-*")
+            .select("released", "2020-01-01T00:00:00.000Z")
+            .select("unknown", "value");
+
+        let body = search.body().unwrap();
+        assert_eq!(
+            body["post_filter"],
+            json!({
+                "bool": {
+                    "filter": [
+                        {
+                            "bool": {
+                                "should": [
+                                    { "range": { "price": { "lt": "100" } } },
+                                    { "range": { "price": { "gte": "100" } } },
+                                ],
+                            },
+                        },
+                        {
+                            "range": {
+                                "released_at": {
+                                    "gte": "2020-01-01T00:00:00.000Z",
+                                    "lt": "2020-01-01T00:00:00.000Z||+1y",
+                                },
+                            },
+                        },
+                    ],
+                },
+            })
+        );
+        assert_eq!(
+            body["aggs"]["brand"]["filter"]["bool"]["filter"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(
+            body["aggs"]["price"]["filter"]["bool"]["filter"][0]["range"]["released_at"]["gte"],
+            "2020-01-01T00:00:00.000Z"
+        );
+        assert_eq!(
+            body["aggs"]["price"]["aggs"]["price"]["range"]["ranges"],
+            json!([{ "to": 100.0 }, { "from": 100.0 }])
+        );
+        assert_eq!(
+            body["aggs"]["released"]["aggs"]["released"]["date_histogram"],
+            json!({ "field": "released_at", "calendar_interval": "year" })
+        );
+    }
+
+    #[test]
+    fn unselected_facets_are_not_wrapped() {
+        let aggregations = search().select("brand", "acme").aggregations();
+        assert!(aggregations[0].terms.is_some());
+        assert!(aggregations[1].filters.is_some());
+        assert!(aggregations[2].filters.is_some());
+
+        let aggregations = search().aggregations();
+        assert!(aggregations.iter().all(|agg| agg.filters.is_none()));
+    }
+
+    #[test]
+    fn can_read_facet_values() {
+        let search = search().select("brand", "acme").select("brand", "globex");
+        let response = aggregation::Response::deserialize(json!({
+            "aggregations": {
+                "sterms#brand": {
+                    "doc_count_error_upper_bound": 0,
+                    "sum_other_doc_count": 0,
+                    "buckets": [
+                        { "key": "acme", "doc_count": 3 },
+                        { "key": "initech", "doc_count": 1 },
+                    ],
+                },
+                "filter#price": {
+                    "doc_count": 3,
+                    "meta": { "_skip": true },
+                    "range#price": {
+                        "buckets": [
+                            { "key": "*-100.0", "to": 100.0, "doc_count": 2 },
+                            { "key": "100.0-*", "from": 100.0, "doc_count": 1 },
+                        ],
+                    },
+                },
+            },
+        }))
+        .unwrap();
+
+        let value = |value: &str, count, selected| FacetValue {
+            value: value.to_string(),
+            count,
+            selected,
+        };
+        assert_eq!(
+            search.facet_values(&response),
+            vec![
+                FacetValues {
+                    name: "brand".to_string(),
+                    values: vec![
+                        value("acme", 3, true),
+                        value("initech", 1, false),
+                        value("globex", 0, true),
+                    ],
+                },
+                FacetValues {
+                    name: "price".to_string(),
+                    values: vec![value("*-100.0", 2, false), value("100.0-*", 1, false)],
+                },
+                FacetValues {
+                    name: "released".to_string(),
+                    values: vec![],
+                },
+            ]
+        );
+    }
+}
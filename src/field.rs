@@ -0,0 +1,257 @@
+//! Typed paths to the fields of documents.
+//!
+//! Instead of stringly-typed field names (e.g. `TermQuery::new("id", ...)`),
+//! `#[derive(Fields)]` (with the `derive` feature) generates a [`Field`]
+//! constant for each field of a struct, and [`Fields::fields`] returns the
+//! paths to all of its fields, including those of nested structs:
+//!
+//! ```
+//! # #[cfg(feature = "derive")]
+//! # fn main() {
+//! use elastiql::{field::Fields, search::query::TermQuery};
+//! use serde::Serialize;
+//!
+//! #[derive(Fields, Serialize)]
+//! struct User {
+//!     id: String,
+//!     #[serde(rename = "homeAddress")]
+//!     #[elastiql(object)]
+//!     address: Address,
+//! }
+//!
+//! #[derive(Fields, Serialize)]
+//! struct Address {
+//!     city: String,
+//! }
+//!
+//! let query = TermQuery::new(User::ID, "test_user_id");
+//! assert_eq!(query.field, "id");
+//! assert_eq!(User::fields().address.city.as_str(), "homeAddress.city");
+//! # }
+//! # #[cfg(not(feature = "derive"))]
+//! # fn main() {}
+//! ```
+//!
+//! A [`Field`] can be used anywhere a field name is accepted as an
+//! `impl Into<String>` (or an `impl Into<Option<String>>`).
+
+use std::{borrow::Cow, fmt};
+
+use serde::{Serialize, Serializer};
+
+#[cfg(feature = "derive")]
+pub use elastiql_derive::Fields;
+
+/// The (dotted) path to a field of a document, e.g. `address.city`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Field(Cow<'static, str>);
+
+impl Field {
+    /// Creates the path to a top-level field.
+    #[inline]
+    pub const fn new(path: &'static str) -> Field {
+        Field(Cow::Borrowed(path))
+    }
+
+    /// Creates the path to the field `name` of the object at `prefix`, if any.
+    #[inline]
+    pub fn with_prefix(prefix: Option<&Field>, name: &'static str) -> Field {
+        match prefix {
+            Some(prefix) => prefix.join(name),
+            None => Field::new(name),
+        }
+    }
+
+    /// Returns the path to the (sub-)field `name` of this field, e.g. a
+    /// `keyword` [multi-field].
+    ///
+    /// [multi-field]: https://www.elastic.co/guide/en/elasticsearch/reference/current/multi-fields.html
+    #[inline]
+    pub fn join(&self, name: &str) -> Field {
+        Field(Cow::Owned(format!("{}.{}", self.0, name)))
+    }
+
+    /// The path to the field.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Field {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Field {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<Field> for String {
+    #[inline]
+    fn from(field: Field) -> Self {
+        field.0.into_owned()
+    }
+}
+
+impl From<&Field> for String {
+    #[inline]
+    fn from(field: &Field) -> Self {
+        field.0.to_string()
+    }
+}
+
+impl From<Field> for Option<String> {
+    #[inline]
+    fn from(field: Field) -> Self {
+        Some(field.into())
+    }
+}
+
+impl From<&Field> for Option<String> {
+    #[inline]
+    fn from(field: &Field) -> Self {
+        Some(field.into())
+    }
+}
+
+impl Serialize for Field {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+/// A document whose fields have typed paths; usually derived with
+/// `#[derive(Fields)]`.
+pub trait Fields {
+    /// The paths to the fields of the document.
+    type Paths;
+
+    /// Returns the paths to the fields of the document, as an object at
+    /// `prefix` (if any).
+    fn fields_at(prefix: Option<&Field>) -> Self::Paths;
+
+    /// Returns the paths to the fields of the document.
+    #[inline]
+    fn fields() -> Self::Paths {
+        Self::fields_at(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_join_paths() {
+        let address = Field::new("address");
+        assert_eq!(address.join("city").as_str(), "address.city");
+        assert_eq!(
+            Field::with_prefix(Some(&address), "city"),
+            Field::new("address.city")
+        );
+        assert_eq!(Field::with_prefix(None, "city").to_string(), "city");
+        assert_eq!(String::from(address.join("city")), "address.city");
+        assert_eq!(
+            serde_json::to_value(&address).unwrap(),
+            serde_json::json!("address")
+        );
+    }
+
+    #[cfg(feature = "derive")]
+    mod derive {
+        use serde::Serialize;
+        use serde_json::json;
+
+        use crate::{
+            aggregation::types::TermsAggregation,
+            field::{Field, Fields},
+            search::{query::RangeQuery, query::TermQuery, Sort, SortOrder},
+        };
+
+        #[derive(Fields, Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct User {
+            id: String,
+            created_at: String,
+            #[serde(rename = "homeAddress")]
+            #[elastiql(object)]
+            address: Option<Address>,
+            #[elastiql(nested)]
+            posts: Vec<Post>,
+            #[serde(flatten)]
+            audit: Audit,
+            #[serde(skip)]
+            #[allow(dead_code)]
+            cache: (),
+            r#type: String,
+        }
+
+        #[derive(Fields, Serialize)]
+        struct Address {
+            city: String,
+        }
+
+        #[derive(Fields, Serialize)]
+        struct Post {
+            #[elastiql(rename = "text")]
+            body: String,
+            #[elastiql(object)]
+            address: Address,
+        }
+
+        #[derive(Fields, Serialize)]
+        struct Audit {
+            updated_by: String,
+        }
+
+        #[test]
+        fn derives_constants() {
+            assert_eq!(User::ID, Field::new("id"));
+            assert_eq!(User::CREATED_AT.as_str(), "createdAt");
+            assert_eq!(User::ADDRESS.as_str(), "homeAddress");
+            assert_eq!(User::TYPE.as_str(), "type");
+            assert_eq!(Post::BODY.as_str(), "text");
+        }
+
+        #[test]
+        fn derives_nested_paths() {
+            let fields = User::fields();
+            assert_eq!(fields.id.as_str(), "id");
+            assert_eq!(fields.address.city.as_str(), "homeAddress.city");
+            assert_eq!(fields.posts.body.as_str(), "posts.text");
+            assert_eq!(fields.posts.address.city.as_str(), "posts.address.city");
+            assert_eq!(fields.audit.updated_by.as_str(), "updated_by");
+        }
+
+        #[test]
+        fn fields_can_be_used_in_requests() {
+            let query = TermQuery::new(User::ID, "test_user_id");
+            assert_eq!(query.field, "id");
+
+            let query = RangeQuery::builder()
+                .field(User::fields().posts.body)
+                .greater_than("a".to_string())
+                .build();
+            assert_eq!(query.field, "posts.text");
+
+            let sort = Sort::new(User::CREATED_AT).with_order(SortOrder::Desc);
+            assert_eq!(
+                serde_json::to_value(&sort).unwrap(),
+                json!({ "createdAt": { "order": "desc", "unmapped_type": "keyword" } })
+            );
+
+            let terms = TermsAggregation::builder()
+                .field(User::fields().address.city)
+                .script(None)
+                .build();
+            assert_eq!(terms.field.as_deref(), Some("homeAddress.city"));
+        }
+    }
+}
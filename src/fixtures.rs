@@ -0,0 +1,405 @@
+//! Record/replay fixtures of Elasticsearch requests & their responses, e.g.
+//! for deterministic regression tests of parsing responses.
+//!
+//! In [`Mode::Record`] every request body (e.g. a serialized
+//! [`search::Request`] or [`aggregation::Request`]) is stored in a file
+//! together with the raw response Elasticsearch returned for it. In
+//! [`Mode::Replay`] the stored response is looked up by the request:
+//!
+//! ```
+//! use elastiql::{
+//!     aggregation::{self, types::InnerAggregation},
+//!     fixtures::Fixtures,
+//! };
+//! use serde_json::json;
+//!
+//! # let dir = std::env::temp_dir().join(format!("elastiql-doc-{}", std::process::id()));
+//! let aggregation = aggregation::Request::builder()
+//!     .name("avg_age")
+//!     .avg(Some(InnerAggregation::builder().field(Some("age".to_string())).build()))
+//!     .build();
+//! let request = json!({ "size": 0, "aggs": aggregation });
+//!
+//! // e.g. with the response from a real Elasticsearch cluster
+//! let response = json!({ "aggregations": { "avg#avg_age": { "value": 30.0 } } });
+//! Fixtures::record(&dir).save(&request, 200, &response)?;
+//!
+//! let fixture = Fixtures::replay(&dir).load(&request)?;
+//! let response: aggregation::Response = serde_json::from_value(fixture.response)?;
+//! assert_eq!(response.aggregations[0].values, vec![30.0]);
+//! # std::fs::remove_dir_all(&dir)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Requests are matched by their *normalized* JSON, i.e. regardless of the
+//! order of keys, `null` members of objects (which are usually skipped when
+//! serializing anyway) or whether a number is written as `1` or `1.0`.
+//!
+//! [`search::Request`]: crate::search::Request
+//! [`aggregation::Request`]: crate::aggregation::Request
+
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The environment variable [`Fixtures::from_env`] reads the [`Mode`] from.
+pub const MODE_VAR: &str = "ELASTIQL_FIXTURES";
+
+/// Whether [`Fixtures`] are recorded or replayed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+    /// Responses are stored, replacing existing fixtures for the same request.
+    Record,
+
+    /// Stored responses are looked up.
+    Replay,
+}
+
+/// A directory of fixtures.
+#[derive(Clone, Debug)]
+pub struct Fixtures {
+    /// The directory the fixtures are stored in.
+    dir: PathBuf,
+
+    /// Whether fixtures are recorded or replayed.
+    mode: Mode,
+}
+
+/// A request & the response Elasticsearch returned for it.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Fixture {
+    /// The (normalized) request body.
+    pub request: Value,
+
+    /// The [HTTP status code](https://developer.mozilla.org/en-US/docs/Web/HTTP/Status)
+    /// of the response.
+    pub status: u16,
+
+    /// The raw response body.
+    pub response: Value,
+}
+
+impl Fixtures {
+    /// Creates fixtures in `dir` with the given `mode`.
+    #[inline]
+    pub fn new(dir: impl Into<PathBuf>, mode: Mode) -> Self {
+        Fixtures {
+            dir: dir.into(),
+            mode,
+        }
+    }
+
+    /// Creates fixtures in `dir` that are recorded.
+    #[inline]
+    pub fn record(dir: impl Into<PathBuf>) -> Self {
+        Fixtures::new(dir, Mode::Record)
+    }
+
+    /// Creates fixtures in `dir` that are replayed.
+    #[inline]
+    pub fn replay(dir: impl Into<PathBuf>) -> Self {
+        Fixtures::new(dir, Mode::Replay)
+    }
+
+    /// Creates fixtures in `dir` that are recorded if the [`MODE_VAR`]
+    /// environment variable is set to `record` and replayed otherwise.
+    #[inline]
+    pub fn from_env(dir: impl Into<PathBuf>) -> Self {
+        let mode = match env::var(MODE_VAR) {
+            Ok(mode) if mode.eq_ignore_ascii_case("record") => Mode::Record,
+            _ => Mode::Replay,
+        };
+        Fixtures::new(dir, mode)
+    }
+
+    /// The directory the fixtures are stored in.
+    #[inline]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Whether fixtures are recorded or replayed.
+    #[inline]
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Stores the `response` for `request`, replacing any existing fixture
+    /// for the same request.
+    ///
+    /// This works in either [`Mode`], e.g. to write fixtures by hand.
+    pub fn save(
+        &self,
+        request: &impl Serialize,
+        status: u16,
+        response: &Value,
+    ) -> Result<(), FixtureError> {
+        let request = normalize(serde_json::to_value(request)?);
+        let path = self.path(&request);
+        let fixture = Fixture {
+            request,
+            status,
+            response: response.clone(),
+        };
+        fs::create_dir_all(&self.dir)?;
+        let mut json = serde_json::to_string_pretty(&fixture)?;
+        json.push('\n');
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Loads the fixture for `request`.
+    pub fn load(&self, request: &impl Serialize) -> Result<Fixture, FixtureError> {
+        let request = normalize(serde_json::to_value(request)?);
+        let path = self.path(&request);
+        let json = match fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(FixtureError::NotFound(request))
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let mut fixture: Fixture = serde_json::from_str(&json)?;
+        fixture.request = normalize(fixture.request);
+        if fixture.request != request {
+            // the file name is just a hash of the request
+            return Err(FixtureError::NotFound(request));
+        }
+        Ok(fixture)
+    }
+
+    /// Returns the response for `request`, by calling `fetch` (and storing
+    /// its result) when recording and by loading the fixture when replaying.
+    ///
+    /// `fetch` returns the status code & body of the response, e.g. from a
+    /// real Elasticsearch cluster.
+    pub fn fetch<E>(
+        &self,
+        request: &impl Serialize,
+        fetch: impl FnOnce() -> Result<(u16, Value), E>,
+    ) -> Result<Fixture, E>
+    where
+        E: From<FixtureError>,
+    {
+        match self.mode {
+            Mode::Record => {
+                let (status, response) = fetch()?;
+                self.save(request, status, &response)?;
+                Ok(Fixture {
+                    request: normalize(serde_json::to_value(request).map_err(FixtureError::from)?),
+                    status,
+                    response,
+                })
+            }
+            Mode::Replay => Ok(self.load(request)?),
+        }
+    }
+
+    /// The file of the fixture for the (normalized) `request`.
+    fn path(&self, request: &Value) -> PathBuf {
+        self.dir.join(format!(
+            "{:016x}.json",
+            fnv1a(request.to_string().as_bytes())
+        ))
+    }
+}
+
+/// Normalizes a JSON value, so that equivalent requests are equal: `null`
+/// members of objects are removed, keys are sorted and floats without a
+/// fractional part become integers.
+pub fn normalize(value: Value) -> Value {
+    match value {
+        Value::Object(object) => {
+            let mut entries: Vec<(String, Value)> = object
+                .into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key, normalize(value)))
+                .collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(entries.into_iter().collect::<Map<_, _>>())
+        }
+        Value::Array(array) => Value::Array(array.into_iter().map(normalize).collect()),
+        Value::Number(n) => {
+            // an integral float is printed without a fraction, e.g. `1` for
+            // `1.0`, as long as it's exactly representable as an `i64`
+            match n.as_f64() {
+                Some(f) if n.is_f64() && f.fract() == 0.0 && f.abs() < 2f64.powi(53) => {
+                    #[allow(clippy::as_conversions)]
+                    let integer = f as i64;
+                    Value::from(integer)
+                }
+                _ => Value::Number(n),
+            }
+        }
+        value => value,
+    }
+}
+
+/// The 64-bit [FNV-1a] hash, which (unlike `std`'s hashers) is stable, so
+/// it can be used for file names.
+///
+/// [FNV-1a]: https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// An error loading or storing a [`Fixture`].
+#[derive(Debug)]
+pub enum FixtureError {
+    /// There is no fixture for the (normalized) request.
+    NotFound(Value),
+
+    /// The fixture could not be read or written.
+    Io(io::Error),
+
+    /// The request could not be serialized or the fixture is not valid JSON.
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for FixtureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FixtureError::NotFound(request) => write!(
+                f,
+                "no fixture for request {} (set {}=record to record it)",
+                request, MODE_VAR
+            ),
+            FixtureError::Io(err) => write!(f, "fixture I/O error: {}", err),
+            FixtureError::Json(err) => write!(f, "invalid fixture: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for FixtureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FixtureError::NotFound(_) => None,
+            FixtureError::Io(err) => Some(err),
+            FixtureError::Json(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for FixtureError {
+    #[inline]
+    fn from(err: io::Error) -> Self {
+        FixtureError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for FixtureError {
+    #[inline]
+    fn from(err: serde_json::Error) -> Self {
+        FixtureError::Json(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::aggregation::{self, types::TermsAggregation, ComputedResult, Ty};
+
+    /// A fresh (empty) directory for a test.
+    fn dir(name: &str) -> PathBuf {
+        let dir =
+            env::temp_dir().join(format!("elastiql-fixtures-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        dir
+    }
+
+    #[test]
+    fn normalizes_requests() {
+        assert_eq!(
+            normalize(
+                json!({ "size": 10.0, "query": { "term": { "a": { "value": "b", "boost": null } } } })
+            ),
+            normalize(json!({ "query": { "term": { "a": { "value": "b" } } }, "size": 10 }))
+        );
+        assert_ne!(
+            normalize(json!({ "size": 1.5 })),
+            normalize(json!({ "size": 1 }))
+        );
+        assert_ne!(normalize(json!([1, 2])), normalize(json!([2, 1])));
+    }
+
+    #[test]
+    fn can_record_and_replay() {
+        let dir = dir("round-trip");
+        let request = json!({ "query": { "match_all": {} }, "size": 1 });
+        let response = json!({ "took": 1, "timed_out": false, "hits": { "hits": [] } });
+
+        let recorder = Fixtures::record(&dir);
+        let fixture = recorder
+            .fetch(&request, || Ok::<_, FixtureError>((200, response.clone())))
+            .unwrap();
+        assert_eq!(fixture.response, response);
+
+        let replayer = Fixtures::replay(&dir);
+        let equivalent = json!({ "size": 1.0, "query": { "match_all": {} }, "sort": null });
+        let fixture = replayer
+            .fetch(&equivalent, || -> Result<_, FixtureError> {
+                panic!("not replayed")
+            })
+            .unwrap();
+        assert_eq!((fixture.status, fixture.response), (200, response));
+
+        let err = replayer.load(&json!({ "size": 2 })).unwrap_err();
+        assert!(matches!(err, FixtureError::NotFound(_)), "{:?}", err);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// A regression test for flattening a (recorded) response.
+    #[test]
+    fn can_flatten_a_replayed_response() {
+        let fixtures = Fixtures::replay(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"));
+        let request = json!({
+            "size": 0,
+            "aggs": aggregation::Request::builder()
+                .name("per_tag")
+                .terms(Some(
+                    TermsAggregation::builder()
+                        .field(Some("tags".to_string()))
+                        .script(None)
+                        .build()
+                ))
+                .aggregations(vec![aggregation::Request::builder()
+                    .name("avg_price")
+                    .avg(Some(aggregation::types::InnerAggregation::builder().field(Some("price".to_string())).build()))
+                    .build()])
+                .build(),
+        });
+
+        let fixture = fixtures.load(&request).unwrap();
+        assert_eq!(fixture.status, 200);
+        let response: aggregation::Response = serde_json::from_value(fixture.response).unwrap();
+
+        assert_eq!(response.aggregations.len(), 1);
+        let ComputedResult {
+            parent,
+            name,
+            type_,
+            fields,
+            values,
+            ..
+        } = &response.aggregations[0];
+        assert_eq!((parent, name.as_str()), (&None, "avg_price"));
+        assert!(matches!(type_, Ty::Avg));
+        let mut per_tag: Vec<_> = fields
+            .iter()
+            .map(String::as_str)
+            .zip(values.iter().copied())
+            .collect();
+        per_tag.sort_by(|a, b| a.0.cmp(b.0));
+        assert_eq!(per_tag, vec![("go", 40.0), ("rust", 12.5)]);
+    }
+}
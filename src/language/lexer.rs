@@ -0,0 +1,332 @@
+//! Splits a filter expression into [`Token`]s.
+
+use super::{ParseError, ParseErrorKind, Span};
+
+/// The kind of a single token of a filter expression.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum TokenKind {
+    /// A bare word, i.e. a field name or an unquoted value.
+    Word(String),
+
+    /// A double quoted string, with escapes already resolved.
+    Str(String),
+
+    /// A backtick delimited raw JSON query.
+    Raw(String),
+
+    /// The `AND` keyword.
+    And,
+
+    /// The `OR` keyword.
+    Or,
+
+    /// The `NOT` keyword.
+    Not,
+
+    /// `:`
+    Colon,
+
+    /// `~`
+    Tilde,
+
+    /// `*`
+    Star,
+
+    /// `,`
+    Comma,
+
+    /// `(`
+    LeftParen,
+
+    /// `)`
+    RightParen,
+
+    /// `[`
+    LeftBracket,
+
+    /// `]`
+    RightBracket,
+
+    /// `{`
+    LeftBrace,
+
+    /// `}`
+    RightBrace,
+
+    /// `>`
+    GreaterThan,
+
+    /// `>=`
+    GreaterThanOrEqualTo,
+
+    /// `<`
+    LessThan,
+
+    /// `<=`
+    LessThanOrEqualTo,
+}
+
+impl TokenKind {
+    /// Describes this token in an error message.
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            TokenKind::Word(word) => format!("`{}`", word),
+            TokenKind::Str(s) => format!("\"{}\"", s),
+            TokenKind::Raw(_) => "a raw JSON query".to_string(),
+            TokenKind::And => "`AND`".to_string(),
+            TokenKind::Or => "`OR`".to_string(),
+            TokenKind::Not => "`NOT`".to_string(),
+            TokenKind::Colon => "`:`".to_string(),
+            TokenKind::Tilde => "`~`".to_string(),
+            TokenKind::Star => "`*`".to_string(),
+            TokenKind::Comma => "`,`".to_string(),
+            TokenKind::LeftParen => "`(`".to_string(),
+            TokenKind::RightParen => "`)`".to_string(),
+            TokenKind::LeftBracket => "`[`".to_string(),
+            TokenKind::RightBracket => "`]`".to_string(),
+            TokenKind::LeftBrace => "`{`".to_string(),
+            TokenKind::RightBrace => "`}`".to_string(),
+            TokenKind::GreaterThan => "`>`".to_string(),
+            TokenKind::GreaterThanOrEqualTo => "`>=`".to_string(),
+            TokenKind::LessThan => "`<`".to_string(),
+            TokenKind::LessThanOrEqualTo => "`<=`".to_string(),
+        }
+    }
+}
+
+/// A single token of a filter expression and where it is in the input.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Token {
+    /// The kind of token.
+    pub(crate) kind: TokenKind,
+
+    /// Where the token is in the input.
+    pub(crate) span: Span,
+}
+
+/// Returns `true` if `c` is a character that may be part of a bare word.
+#[inline]
+pub(crate) fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !"():[]{},\"<>=~*`".contains(c)
+}
+
+/// Splits `input` into tokens.
+pub(crate) fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let single = |kind| Token {
+            kind,
+            span: Span::new(start, start + 1),
+        };
+
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            ':' => single(TokenKind::Colon),
+            '~' => single(TokenKind::Tilde),
+            '*' => single(TokenKind::Star),
+            ',' => single(TokenKind::Comma),
+            '(' => single(TokenKind::LeftParen),
+            ')' => single(TokenKind::RightParen),
+            '[' => single(TokenKind::LeftBracket),
+            ']' => single(TokenKind::RightBracket),
+            '{' => single(TokenKind::LeftBrace),
+            '}' => single(TokenKind::RightBrace),
+            '>' | '<' => {
+                let or_equal = matches!(chars.peek(), Some(&(_, '=')));
+                if or_equal {
+                    chars.next();
+                }
+                let kind = match (c, or_equal) {
+                    ('>', false) => TokenKind::GreaterThan,
+                    ('>', true) => TokenKind::GreaterThanOrEqualTo,
+                    ('<', false) => TokenKind::LessThan,
+                    _ => TokenKind::LessThanOrEqualTo,
+                };
+                Token {
+                    kind,
+                    span: Span::new(start, start + if or_equal { 2 } else { 1 }),
+                }
+            }
+            '=' => {
+                return Err(ParseError::new(
+                    ParseErrorKind::UnexpectedCharacter('='),
+                    Span::new(start, start + 1),
+                ))
+            }
+            '`' => match input[start + 1..].find('`') {
+                Some(len) => {
+                    let end = start + 1 + len;
+                    // skip over the raw query & the closing backtick
+                    while matches!(chars.next(), Some((i, _)) if i < end) {}
+                    Token {
+                        kind: TokenKind::Raw(input[start + 1..end].to_string()),
+                        span: Span::new(start, end + 1),
+                    }
+                }
+                None => {
+                    return Err(ParseError::new(
+                        ParseErrorKind::UnterminatedString,
+                        Span::new(start, input.len()),
+                    ))
+                }
+            },
+            '"' => {
+                let mut value = String::new();
+                let end = loop {
+                    match chars.next() {
+                        Some((i, '"')) => break i + 1,
+                        Some((i, '\\')) => match chars.next() {
+                            Some((_, c @ '"')) | Some((_, c @ '\\')) => value.push(c),
+                            Some((j, c)) => {
+                                return Err(ParseError::new(
+                                    ParseErrorKind::InvalidEscape(c),
+                                    Span::new(i, j + c.len_utf8()),
+                                ))
+                            }
+                            None => {
+                                return Err(ParseError::new(
+                                    ParseErrorKind::UnterminatedString,
+                                    Span::new(start, input.len()),
+                                ))
+                            }
+                        },
+                        Some((_, c)) => value.push(c),
+                        None => {
+                            return Err(ParseError::new(
+                                ParseErrorKind::UnterminatedString,
+                                Span::new(start, input.len()),
+                            ))
+                        }
+                    }
+                };
+                Token {
+                    kind: TokenKind::Str(value),
+                    span: Span::new(start, end),
+                }
+            }
+            _ => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, next)) = chars.peek() {
+                    if !is_word_char(next) {
+                        break;
+                    }
+                    end = i + next.len_utf8();
+                    chars.next();
+                }
+
+                let word = &input[start..end];
+                let kind = if word.eq_ignore_ascii_case("and") {
+                    TokenKind::And
+                } else if word.eq_ignore_ascii_case("or") {
+                    TokenKind::Or
+                } else if word.eq_ignore_ascii_case("not") {
+                    TokenKind::Not
+                } else {
+                    TokenKind::Word(word.to_string())
+                };
+                Token {
+                    kind,
+                    span: Span::new(start, end),
+                }
+            }
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<TokenKind> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    #[test]
+    fn can_tokenize() {
+        use TokenKind::*;
+
+        assert_eq!(
+            kinds(r#"status:active AND (age >= 30 or tags:in[a,b]) not name:~"jo*""#),
+            vec![
+                Word("status".to_string()),
+                Colon,
+                Word("active".to_string()),
+                And,
+                LeftParen,
+                Word("age".to_string()),
+                GreaterThanOrEqualTo,
+                Word("30".to_string()),
+                Or,
+                Word("tags".to_string()),
+                Colon,
+                Word("in".to_string()),
+                LeftBracket,
+                Word("a".to_string()),
+                Comma,
+                Word("b".to_string()),
+                RightBracket,
+                RightParen,
+                Not,
+                Word("name".to_string()),
+                Colon,
+                Tilde,
+                Str("jo*".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn tracks_spans() {
+        let tokens = tokenize("héllo <= \"wörld\"").unwrap();
+        let spans: Vec<Span> = tokens.into_iter().map(|token| token.span).collect();
+        assert_eq!(
+            spans,
+            vec![Span::new(0, 6), Span::new(7, 9), Span::new(10, 18)]
+        );
+    }
+
+    #[test]
+    fn can_tokenize_escapes() {
+        assert_eq!(
+            kinds(r#""a \"quoted\" \\ value""#),
+            vec![TokenKind::Str(r#"a "quoted" \ value"#.to_string())]
+        );
+    }
+
+    #[test]
+    fn can_tokenize_raw() {
+        let tokens = tokenize(r#"a:1 `{"match":{"b":{"query":"c"}}}` d:2"#).unwrap();
+        assert_eq!(
+            tokens[3],
+            Token {
+                kind: TokenKind::Raw(r#"{"match":{"b":{"query":"c"}}}"#.to_string()),
+                span: Span::new(4, 35),
+            }
+        );
+        assert_eq!(tokens[4].kind, TokenKind::Word("d".to_string()));
+    }
+
+    #[test]
+    fn unterminated_string_is_err() {
+        let err = tokenize(r#"name:"jo"#).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnterminatedString);
+        assert_eq!(err.span, Span::new(5, 8));
+    }
+
+    #[test]
+    fn invalid_escape_is_err() {
+        let err = tokenize(r#"name:"j\o""#).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::InvalidEscape('o'));
+        assert_eq!(err.span, Span::new(7, 9));
+    }
+}
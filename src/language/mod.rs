@@ -0,0 +1,208 @@
+//! A small, safe textual filter language that compiles to a [`CompoundQuery`].
+//!
+//! The language is meant to be exposed to end users, e.g. in a search box, and
+//! can only express a limited set of [leaf queries] combined using boolean
+//! logic:
+//!
+//! | Expression               | Query                                             |
+//! |--------------------------|---------------------------------------------------|
+//! | `status:active`          | [`TermQuery`]                                     |
+//! | `name:"Jane Doe"`        | [`TermQuery`] (quoted value)                      |
+//! | `tags:in[a, "b c"]`      | [`TermsQuery`]                                    |
+//! | `age >= 30`              | [`RangeQuery`] (also `>`, `<` and `<=`)           |
+//! | `name:~jo*`              | [`PrefixQuery`] (the trailing `*` is optional)    |
+//! | `email:*`                | [`ExistsQuery`]                                   |
+//! | `comments:{author:jane}` | [`NestedQuery`] (fields are relative to the path) |
+//! | `a AND b`, `a b`         | `bool` query with `filter` clauses                |
+//! | `a OR b`                 | `bool` query with `should` clauses                |
+//! | `NOT a`                  | `bool` query with a `must_not` clause             |
+//! | `(a OR b) AND c`         | grouping                                          |
+//!
+//! `NOT` binds tighter than `AND`, which binds tighter than `OR`. The keywords
+//! are case-insensitive and must be quoted when used as a value, e.g.
+//! `operator:"or"`. Values containing whitespace or any of
+//! `():[]{},"<>=~*` must be quoted as well; within quotes `\"` and `\\` are the
+//! only escapes.
+//!
+//! ```
+//! use elastiql::language::parse;
+//!
+//! let query = parse(r#"status:active AND (age >= 30 OR tags:in[a,b]) AND NOT name:~"jo*""#)?;
+//! # Ok::<(), elastiql::language::ParseError>(())
+//! ```
+//!
+//! Queries can be rendered back into a filter expression using their
+//! [`Display`] implementation, which round-trips with `parse`. Queries without
+//! a textual equivalent are rendered as raw JSON between backticks, which is
+//! only accepted by [`parse_trusted`]:
+//!
+//! ```
+//! use elastiql::language::{parse, parse_trusted};
+//! use elastiql::search::query::{CompoundQuery, MatchQuery};
+//!
+//! let mut query = parse("status:active AND (age >= 30 OR tags:in[a,b])")?;
+//! assert_eq!(query.to_string(), "status:active AND (age >= 30 OR tags:in[a, b])");
+//!
+//! query.push(MatchQuery::new("title", "rust"));
+//! let rendered = query.to_string();
+//! assert!(rendered.ends_with(r#"AND `{"match":{"title":{"query":"rust"}}}`"#));
+//! assert!(parse(&rendered).is_err());
+//! assert_eq!(parse_trusted(&rendered)?.to_string(), rendered);
+//! # Ok::<(), elastiql::language::ParseError>(())
+//! ```
+//!
+//! Errors point at the offending part of the input, so they can be shown to the
+//! user as-is:
+//!
+//! ```
+//! use elastiql::language::{parse, Span};
+//!
+//! let err = parse("status:active AND (age >= 30").unwrap_err();
+//! assert_eq!(err.span, Span::new(28, 28));
+//! assert_eq!(err.to_string(), "expected `)` but reached the end of the input at 28..28");
+//! ```
+//!
+//! [`Display`]: std::fmt::Display
+//! [leaf queries]: https://www.elastic.co/guide/en/elasticsearch/reference/current/term-level-queries.html
+//! [`TermQuery`]: crate::search::query::TermQuery
+//! [`TermsQuery`]: crate::search::query::TermsQuery
+//! [`RangeQuery`]: crate::search::query::RangeQuery
+//! [`PrefixQuery`]: crate::search::query::PrefixQuery
+//! [`ExistsQuery`]: crate::search::query::ExistsQuery
+//! [`NestedQuery`]: crate::search::query::NestedQuery
+
+use std::{fmt, str::FromStr};
+
+pub use self::parser::{parse, parse_trusted};
+use crate::search::query::CompoundQuery;
+
+mod lexer;
+mod parser;
+mod printer;
+
+/// The byte offsets of a part of the input, i.e. `input[start..end]`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    /// The (inclusive) byte offset the span starts at.
+    pub start: usize,
+
+    /// The (exclusive) byte offset the span ends at.
+    pub end: usize,
+}
+
+impl Span {
+    /// Constructs a new `Span`.
+    #[inline]
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+}
+
+impl fmt::Display for Span {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// The reason a filter expression could not be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A character that is not valid anywhere in an expression.
+    UnexpectedCharacter(char),
+
+    /// A quoted string is missing its closing `"`.
+    UnterminatedString,
+
+    /// An unsupported escape sequence within a quoted string, e.g. `\n`.
+    InvalidEscape(char),
+
+    /// A token that is not valid at this position.
+    UnexpectedToken {
+        /// The token that was found.
+        found: String,
+
+        /// What was expected instead.
+        expected: &'static str,
+    },
+
+    /// The input ended while more was expected.
+    UnexpectedEnd {
+        /// What was expected.
+        expected: &'static str,
+    },
+
+    /// A wildcard was used anywhere other than at the end of a prefix (`~`)
+    /// value.
+    UnsupportedWildcard,
+
+    /// The expression is nested deeper than the given maximum.
+    TooDeep(usize),
+
+    /// A raw JSON query was used, which is only allowed by [`parse_trusted`].
+    RawQueryNotAllowed,
+
+    /// A raw JSON query could not be deserialized.
+    InvalidRawQuery(String),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character `{}`", c),
+            ParseErrorKind::UnterminatedString => f.write_str("unterminated string"),
+            ParseErrorKind::InvalidEscape(c) => write!(f, "invalid escape `\\{}`", c),
+            ParseErrorKind::UnexpectedToken { found, expected } => {
+                write!(f, "expected {} but found {}", expected, found)
+            }
+            ParseErrorKind::UnexpectedEnd { expected } => {
+                write!(f, "expected {} but reached the end of the input", expected)
+            }
+            ParseErrorKind::UnsupportedWildcard => {
+                f.write_str("wildcards are only supported at the end of a prefix, e.g. `name:~jo*`")
+            }
+            ParseErrorKind::TooDeep(max) => {
+                write!(f, "expression is nested deeper than {} levels", max)
+            }
+            ParseErrorKind::RawQueryNotAllowed => f.write_str("raw JSON queries are not allowed"),
+            ParseErrorKind::InvalidRawQuery(err) => write!(f, "invalid raw JSON query: {}", err),
+        }
+    }
+}
+
+/// An error encountered while parsing a filter expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The reason the expression could not be parsed.
+    pub kind: ParseErrorKind,
+
+    /// The part of the input the error refers to.
+    pub span: Span,
+}
+
+impl ParseError {
+    /// Constructs a new `ParseError`.
+    #[inline]
+    pub(crate) fn new(kind: ParseErrorKind, span: Span) -> Self {
+        ParseError { kind, span }
+    }
+}
+
+impl fmt::Display for ParseError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.span)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl FromStr for CompoundQuery {
+    type Err = ParseError;
+
+    /// Parses a filter expression, see [`parse`].
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s)
+    }
+}
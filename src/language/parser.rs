@@ -0,0 +1,694 @@
+//! Parses a filter expression into a [`CompoundQuery`].
+
+use super::{
+    lexer::{tokenize, Token, TokenKind},
+    ParseError, ParseErrorKind, Span,
+};
+use crate::search::query::{
+    BooleanQuery, CompoundQuery, ExistsQuery, NestedQuery, PrefixQuery, Query, RangeQuery,
+    TermQuery, TermsQuery,
+};
+
+/// The maximum depth of groups, `NOT`s and nested queries.
+const MAX_DEPTH: usize = 32;
+
+/// Parses a filter expression into a [`CompoundQuery`].
+///
+/// An empty (or whitespace-only) expression results in an empty query, which
+/// matches every document. See the [module documentation](super) for the
+/// syntax.
+pub fn parse(input: &str) -> Result<CompoundQuery, ParseError> {
+    parse_with(input, false)
+}
+
+/// Parses a filter expression into a [`CompoundQuery`], allowing raw JSON
+/// queries (e.g. `` `{"match":{"title":{"query":"rust"}}}` ``) as rendered by
+/// the [`Display`] implementation of queries without a textual equivalent.
+///
+/// **Note**: raw queries can express *any* query, so this should only be used
+/// with trusted input, e.g. to read back audit logs.
+///
+/// [`Display`]: std::fmt::Display
+pub fn parse_trusted(input: &str) -> Result<CompoundQuery, ParseError> {
+    parse_with(input, true)
+}
+
+/// Parses a filter expression, optionally allowing raw JSON queries.
+fn parse_with(input: &str, allow_raw: bool) -> Result<CompoundQuery, ParseError> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Ok(CompoundQuery::default());
+    }
+
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        len: input.len(),
+        depth: 0,
+        allow_raw,
+    };
+
+    let expression = parser.or("")?;
+    if let Some(token) = parser.peek() {
+        return Err(ParseError::new(
+            ParseErrorKind::UnexpectedToken {
+                found: token.kind.describe(),
+                expected: "`AND`, `OR` or the end of the input",
+            },
+            token.span,
+        ));
+    }
+
+    Ok(CompoundQuery {
+        boolean: Some(expression.into_boolean()),
+    })
+}
+
+/// A parsed (sub-)expression.
+#[derive(Debug)]
+enum Expression {
+    /// A single leaf (or nested) query.
+    Leaf(Box<Query>),
+
+    /// All of the expressions must match.
+    And(Vec<Expression>),
+
+    /// At least one of the expressions must match.
+    Or(Vec<Expression>),
+
+    /// The expression must not match.
+    Not(Box<Expression>),
+}
+
+impl Expression {
+    /// Converts this expression into a `bool` query.
+    fn into_boolean(self) -> BooleanQuery {
+        match self {
+            Expression::Leaf(query) => BooleanQuery {
+                filter: vec![*query],
+                ..BooleanQuery::default()
+            },
+            Expression::And(expressions) => {
+                let mut boolean = BooleanQuery::default();
+                for expression in expressions {
+                    match expression {
+                        Expression::Not(expression) => {
+                            boolean.must_not.push(expression.into_query())
+                        }
+                        expression => boolean.filter.push(expression.into_query()),
+                    }
+                }
+                boolean
+            }
+            Expression::Or(expressions) => BooleanQuery {
+                should: expressions
+                    .into_iter()
+                    .map(Expression::into_query)
+                    .collect(),
+                minimum_should_match: Some("1".to_string()),
+                ..BooleanQuery::default()
+            },
+            Expression::Not(expression) => BooleanQuery {
+                must_not: vec![expression.into_query()],
+                ..BooleanQuery::default()
+            },
+        }
+    }
+
+    /// Converts this expression into a single query.
+    fn into_query(self) -> Query {
+        match self {
+            Expression::Leaf(query) => *query,
+            expression => Query {
+                boolean: Some(expression.into_boolean()),
+                ..Query::default()
+            },
+        }
+    }
+}
+
+/// A recursive descent parser over the tokens of a filter expression.
+struct Parser {
+    /// The tokens of the expression.
+    tokens: Vec<Token>,
+
+    /// The index of the next token.
+    position: usize,
+
+    /// The length of the input, used for errors at the end of the input.
+    len: usize,
+
+    /// The current depth of groups, `NOT`s and nested queries.
+    depth: usize,
+
+    /// Whether or not raw JSON queries are allowed.
+    allow_raw: bool,
+}
+
+impl Parser {
+    /// Returns the next token without consuming it.
+    #[inline]
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    /// Returns `true` if the next token is of the given `kind`.
+    #[inline]
+    fn peek_is(&self, kind: &TokenKind) -> bool {
+        matches!(self.peek(), Some(token) if &token.kind == kind)
+    }
+
+    /// Consumes and returns the next token.
+    #[inline]
+    fn next(&mut self, expected: &'static str) -> Result<Token, ParseError> {
+        match self.tokens.get(self.position) {
+            Some(token) => {
+                self.position += 1;
+                Ok(token.clone())
+            }
+            None => Err(ParseError::new(
+                ParseErrorKind::UnexpectedEnd { expected },
+                Span::new(self.len, self.len),
+            )),
+        }
+    }
+
+    /// Consumes the next token, which must be of the given `kind`.
+    fn expect(&mut self, kind: TokenKind, expected: &'static str) -> Result<Token, ParseError> {
+        let token = self.next(expected)?;
+        if token.kind == kind {
+            Ok(token)
+        } else {
+            Err(unexpected(&token, expected))
+        }
+    }
+
+    /// Increases the depth, failing if it exceeds the maximum.
+    #[inline]
+    fn descend(&mut self, span: Span) -> Result<(), ParseError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            Err(ParseError::new(ParseErrorKind::TooDeep(MAX_DEPTH), span))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Parses `and (OR and)*`.
+    fn or(&mut self, prefix: &str) -> Result<Expression, ParseError> {
+        let mut expressions = vec![self.and(prefix)?];
+        while self.peek_is(&TokenKind::Or) {
+            self.position += 1;
+            expressions.push(self.and(prefix)?);
+        }
+
+        Ok(match expressions.len() {
+            1 => expressions.remove(0),
+            _ => Expression::Or(expressions),
+        })
+    }
+
+    /// Parses `unary (AND? unary)*`.
+    fn and(&mut self, prefix: &str) -> Result<Expression, ParseError> {
+        let mut expressions = vec![self.unary(prefix)?];
+        loop {
+            match self.peek().map(|token| &token.kind) {
+                Some(TokenKind::And) => self.position += 1,
+                // juxtaposition is an implicit `AND`
+                Some(TokenKind::Word(_))
+                | Some(TokenKind::Raw(_))
+                | Some(TokenKind::Not)
+                | Some(TokenKind::LeftParen) => {}
+                _ => break,
+            }
+            expressions.push(self.unary(prefix)?);
+        }
+
+        Ok(match expressions.len() {
+            1 => expressions.remove(0),
+            _ => Expression::And(expressions),
+        })
+    }
+
+    /// Parses `NOT unary | primary`.
+    fn unary(&mut self, prefix: &str) -> Result<Expression, ParseError> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Not => {
+                let span = token.span;
+                self.position += 1;
+                self.descend(span)?;
+                let expression = self.unary(prefix)?;
+                self.depth -= 1;
+                Ok(Expression::Not(Box::new(expression)))
+            }
+            _ => self.primary(prefix),
+        }
+    }
+
+    /// Parses `( or ) | clause`.
+    fn primary(&mut self, prefix: &str) -> Result<Expression, ParseError> {
+        const EXPECTED: &str = "a field, `NOT` or `(`";
+
+        let token = self.next(EXPECTED)?;
+        match token.kind {
+            TokenKind::LeftParen => {
+                self.descend(token.span)?;
+                let expression = self.or(prefix)?;
+                self.expect(TokenKind::RightParen, "`)`")?;
+                self.depth -= 1;
+                Ok(expression)
+            }
+            TokenKind::Word(field) => self.clause(format!("{}{}", prefix, field)),
+            TokenKind::Raw(_) if !self.allow_raw => Err(ParseError::new(
+                ParseErrorKind::RawQueryNotAllowed,
+                token.span,
+            )),
+            TokenKind::Raw(json) => match serde_json::from_str::<Query>(&json) {
+                Ok(query) => Ok(Expression::Leaf(Box::new(query))),
+                Err(err) => Err(ParseError::new(
+                    ParseErrorKind::InvalidRawQuery(err.to_string()),
+                    token.span,
+                )),
+            },
+            _ => Err(unexpected(&token, EXPECTED)),
+        }
+    }
+
+    /// Parses everything after the field name of a clause.
+    fn clause(&mut self, field: String) -> Result<Expression, ParseError> {
+        const EXPECTED: &str = "`:`, `>`, `>=`, `<` or `<=`";
+
+        let token = self.next(EXPECTED)?;
+        let mut range = RangeQuery {
+            field,
+            greater_than: None,
+            greater_than_or_equal_to: None,
+            less_than: None,
+            less_than_or_equal_to: None,
+            boost: None,
+            name: None,
+        };
+        let bound = match token.kind {
+            TokenKind::Colon => return self.value_clause(range.field),
+            TokenKind::GreaterThan => &mut range.greater_than,
+            TokenKind::GreaterThanOrEqualTo => &mut range.greater_than_or_equal_to,
+            TokenKind::LessThan => &mut range.less_than,
+            TokenKind::LessThanOrEqualTo => &mut range.less_than_or_equal_to,
+            _ => return Err(unexpected(&token, EXPECTED)),
+        };
+        *bound = Some(self.value()?);
+
+        Ok(Expression::Leaf(Box::new(range.into())))
+    }
+
+    /// Parses everything after the `:` of a clause.
+    fn value_clause(&mut self, field: String) -> Result<Expression, ParseError> {
+        const EXPECTED: &str = "a value, `*`, `~`, `{` or `in[`";
+
+        let token = self.next(EXPECTED)?;
+        let query: Query = match token.kind {
+            TokenKind::Star => ExistsQuery::new(field).into(),
+            TokenKind::Tilde => {
+                let token = self.next("a value")?;
+                let value = match token.kind {
+                    TokenKind::Word(value) => {
+                        // the trailing `*` is optional
+                        if self.peek_is(&TokenKind::Star) {
+                            self.position += 1;
+                        }
+                        value
+                    }
+                    TokenKind::Str(value) => {
+                        let value = value.strip_suffix('*').unwrap_or(&value);
+                        if value.contains(&['*', '?'][..]) {
+                            return Err(ParseError::new(
+                                ParseErrorKind::UnsupportedWildcard,
+                                token.span,
+                            ));
+                        }
+                        value.to_string()
+                    }
+                    _ => return Err(unexpected(&token, "a value")),
+                };
+                PrefixQuery::new(field, value).into()
+            }
+            TokenKind::LeftBrace => {
+                self.descend(token.span)?;
+                let expression = self.or(&format!("{}.", field))?;
+                self.expect(TokenKind::RightBrace, "`}`")?;
+                self.depth -= 1;
+                let query = CompoundQuery {
+                    boolean: Some(expression.into_boolean()),
+                };
+                NestedQuery::new(field, query, false).into()
+            }
+            TokenKind::Word(ref word) if word == "in" && self.peek_is(&TokenKind::LeftBracket) => {
+                self.position += 1;
+                let mut values = vec![self.value()?];
+                while !self.peek_is(&TokenKind::RightBracket) {
+                    self.expect(TokenKind::Comma, "`,` or `]`")?;
+                    values.push(self.value()?);
+                }
+                self.position += 1;
+                TermsQuery::new(field, values).into()
+            }
+            TokenKind::Word(value) => {
+                self.reject_wildcard()?;
+                TermQuery::new(field, value).into()
+            }
+            TokenKind::Str(value) => TermQuery::new(field, value).into(),
+            _ => return Err(unexpected(&token, EXPECTED)),
+        };
+
+        Ok(Expression::Leaf(Box::new(query)))
+    }
+
+    /// Parses a (quoted or bare) value.
+    fn value(&mut self) -> Result<String, ParseError> {
+        let token = self.next("a value")?;
+        match token.kind {
+            TokenKind::Word(value) => {
+                self.reject_wildcard()?;
+                Ok(value)
+            }
+            TokenKind::Str(value) => Ok(value),
+            _ => Err(unexpected(&token, "a value")),
+        }
+    }
+
+    /// Fails if a bare value is directly followed by a `*`, e.g. `jo*`.
+    #[inline]
+    fn reject_wildcard(&self) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Star => Err(ParseError::new(
+                ParseErrorKind::UnsupportedWildcard,
+                token.span,
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Constructs an error for an unexpected token.
+#[inline]
+fn unexpected(token: &Token, expected: &'static str) -> ParseError {
+    ParseError::new(
+        ParseErrorKind::UnexpectedToken {
+            found: token.kind.describe(),
+            expected,
+        },
+        token.span,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    macro_rules! can_parse {
+        ($name:ident, $input:expr, $expected:tt) => {
+            #[test]
+            fn $name() {
+                let query = parse($input).unwrap();
+                let expected = json!($expected);
+                assert_eq!(
+                    serde_json::to_value(&query).unwrap(),
+                    expected,
+                    "{}",
+                    &expected
+                );
+            }
+        };
+    }
+
+    macro_rules! cannot_parse {
+        ($name:ident, $input:expr, $kind:expr, $span:expr) => {
+            #[test]
+            fn $name() {
+                let err = parse($input).unwrap_err();
+                assert_eq!(err.kind, $kind, "{}", err);
+                assert_eq!(err.span, Span::new($span.0, $span.1), "{}", err);
+            }
+        };
+    }
+
+    #[test]
+    fn empty_is_empty_query() {
+        assert!(parse("  ").unwrap().is_empty());
+    }
+
+    can_parse!(
+        term,
+        "status:active",
+        { "bool": { "filter": [{ "term": { "status": { "value": "active" } } }] } }
+    );
+
+    can_parse!(
+        quoted_term,
+        r#"name:"Jane \"JD\" Doe""#,
+        { "bool": { "filter": [{ "term": { "name": { "value": "Jane \"JD\" Doe" } } }] } }
+    );
+
+    can_parse!(
+        keyword_as_value,
+        r#"operator:"or""#,
+        { "bool": { "filter": [{ "term": { "operator": { "value": "or" } } }] } }
+    );
+
+    can_parse!(
+        terms,
+        r#"tags:in[a, "b c",d]"#,
+        { "bool": { "filter": [{ "terms": { "tags": ["a", "b c", "d"] } }] } }
+    );
+
+    can_parse!(
+        in_is_a_value_without_brackets,
+        "word:in",
+        { "bool": { "filter": [{ "term": { "word": { "value": "in" } } }] } }
+    );
+
+    can_parse!(
+        ranges,
+        "age >= 30 AND age < 40 AND price > -1.5 AND created <= \"2020-01-01T00:00:00\"",
+        { "bool": { "filter": [
+            { "range": { "age": { "gte": "30" } } },
+            { "range": { "age": { "lt": "40" } } },
+            { "range": { "price": { "gt": "-1.5" } } },
+            { "range": { "created": { "lte": "2020-01-01T00:00:00" } } },
+        ] } }
+    );
+
+    can_parse!(
+        prefix,
+        r#"name:~"jo*" AND city:~ams AND street:~main*"#,
+        { "bool": { "filter": [
+            { "prefix": { "name": { "value": "jo", "case_insensitive": false } } },
+            { "prefix": { "city": { "value": "ams", "case_insensitive": false } } },
+            { "prefix": { "street": { "value": "main", "case_insensitive": false } } },
+        ] } }
+    );
+
+    can_parse!(
+        exists,
+        "email:*",
+        { "bool": { "filter": [{ "exists": { "field": "email" } }] } }
+    );
+
+    can_parse!(
+        nested,
+        "comments:{author:jane AND replies:{stars >= 3}}",
+        { "bool": { "filter": [{ "nested": {
+            "path": "comments",
+            "ignore_unmapped": false,
+            "query": { "bool": { "filter": [
+                { "term": { "comments.author": { "value": "jane" } } },
+                { "nested": {
+                    "path": "comments.replies",
+                    "ignore_unmapped": false,
+                    "query": { "bool": { "filter": [
+                        { "range": { "comments.replies.stars": { "gte": "3" } } },
+                    ] } },
+                } },
+            ] } },
+        } }] } }
+    );
+
+    can_parse!(
+        or,
+        "status:active OR status:pending",
+        { "bool": {
+            "should": [
+                { "term": { "status": { "value": "active" } } },
+                { "term": { "status": { "value": "pending" } } },
+            ],
+            "minimum_should_match": "1",
+        } }
+    );
+
+    can_parse!(
+        not,
+        "NOT status:deleted",
+        { "bool": { "must_not": [{ "term": { "status": { "value": "deleted" } } }] } }
+    );
+
+    can_parse!(
+        implicit_and,
+        "a:1 b:2 not c:3",
+        { "bool": {
+            "filter": [
+                { "term": { "a": { "value": "1" } } },
+                { "term": { "b": { "value": "2" } } },
+            ],
+            "must_not": [{ "term": { "c": { "value": "3" } } }],
+        } }
+    );
+
+    can_parse!(
+        precedence,
+        "a:1 OR b:2 AND NOT c:3",
+        { "bool": {
+            "should": [
+                { "term": { "a": { "value": "1" } } },
+                { "bool": {
+                    "filter": [{ "term": { "b": { "value": "2" } } }],
+                    "must_not": [{ "term": { "c": { "value": "3" } } }],
+                } },
+            ],
+            "minimum_should_match": "1",
+        } }
+    );
+
+    can_parse!(
+        example,
+        r#"status:active AND (age >= 30 OR tags:in[a,b]) AND NOT name:~"jo*""#,
+        { "bool": {
+            "filter": [
+                { "term": { "status": { "value": "active" } } },
+                { "bool": {
+                    "should": [
+                        { "range": { "age": { "gte": "30" } } },
+                        { "terms": { "tags": ["a", "b"] } },
+                    ],
+                    "minimum_should_match": "1",
+                } },
+            ],
+            "must_not": [{ "prefix": { "name": { "value": "jo", "case_insensitive": false } } }],
+        } }
+    );
+
+    cannot_parse!(
+        missing_close_paren,
+        "(a:1",
+        ParseErrorKind::UnexpectedEnd { expected: "`)`" },
+        (4, 4)
+    );
+
+    cannot_parse!(
+        trailing_tokens,
+        "a:1)",
+        ParseErrorKind::UnexpectedToken {
+            found: "`)`".to_string(),
+            expected: "`AND`, `OR` or the end of the input",
+        },
+        (3, 4)
+    );
+
+    cannot_parse!(
+        missing_value,
+        "a: AND b:2",
+        ParseErrorKind::UnexpectedToken {
+            found: "`AND`".to_string(),
+            expected: "a value, `*`, `~`, `{` or `in[`",
+        },
+        (3, 6)
+    );
+
+    cannot_parse!(
+        missing_operator,
+        "status active",
+        ParseErrorKind::UnexpectedToken {
+            found: "`active`".to_string(),
+            expected: "`:`, `>`, `>=`, `<` or `<=`",
+        },
+        (7, 13)
+    );
+
+    cannot_parse!(
+        bare_value,
+        "\"active\"",
+        ParseErrorKind::UnexpectedToken {
+            found: "\"active\"".to_string(),
+            expected: "a field, `NOT` or `(`",
+        },
+        (0, 8)
+    );
+
+    cannot_parse!(
+        wildcard_in_term,
+        "name:jo*",
+        ParseErrorKind::UnsupportedWildcard,
+        (7, 8)
+    );
+
+    cannot_parse!(
+        wildcard_in_prefix,
+        r#"name:~"j*o*""#,
+        ParseErrorKind::UnsupportedWildcard,
+        (6, 12)
+    );
+
+    cannot_parse!(
+        empty_terms,
+        "tags:in[]",
+        ParseErrorKind::UnexpectedToken {
+            found: "`]`".to_string(),
+            expected: "a value",
+        },
+        (8, 9)
+    );
+
+    cannot_parse!(
+        equals,
+        "a = 1",
+        ParseErrorKind::UnexpectedCharacter('='),
+        (2, 3)
+    );
+
+    #[test]
+    fn too_deep_is_err() {
+        let input = format!("{}a:1{}", "(".repeat(40), ")".repeat(40));
+        let err = parse(&input).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::TooDeep(MAX_DEPTH));
+        assert_eq!(err.span, Span::new(32, 33));
+
+        let input = format!("{}a:1", "NOT ".repeat(40));
+        let err = parse(&input).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::TooDeep(MAX_DEPTH));
+    }
+
+    #[test]
+    fn raw_query_is_err() {
+        let input = r#"a:1 AND `{"match":{"b":{"query":"c"}}}`"#;
+        let err = parse(input).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::RawQueryNotAllowed);
+        assert_eq!(err.span, Span::new(8, 39));
+
+        let query = parse_trusted(input).unwrap();
+        let expected = json!({ "bool": { "filter": [
+            { "term": { "a": { "value": "1" } } },
+            { "match": { "b": { "query": "c" } } },
+        ] } });
+        assert_eq!(serde_json::to_value(&query).unwrap(), expected);
+
+        let err = parse_trusted("`{\"match\":1}`").unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::InvalidRawQuery(_)));
+    }
+
+    #[test]
+    fn can_parse_from_str() {
+        let query: CompoundQuery = "status:active".parse().unwrap();
+        assert_eq!(query, parse("status:active").unwrap());
+    }
+}
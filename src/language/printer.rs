@@ -0,0 +1,417 @@
+//! Renders queries as filter expressions.
+//!
+//! The output of a query produced by [`parse`] parses back into an identical
+//! query. Queries (or parts of a query) without a textual equivalent, e.g. a
+//! `match` query or a `term` query with a `boost`, are rendered as raw JSON
+//! between backticks, which can only be parsed back by [`parse_trusted`].
+//!
+//! [`parse`]: super::parse
+//! [`parse_trusted`]: super::parse_trusted
+
+use std::fmt;
+
+use super::lexer::is_word_char;
+use crate::search::query::{BooleanQuery, CompoundQuery, NestedQuery, Query};
+
+impl fmt::Display for CompoundQuery {
+    /// Renders this query as a filter expression, e.g.
+    /// `status:active AND (age >= 30 OR tags:in[a, b])`.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.boolean {
+            Some(boolean) => f.write_str(&boolean_or_raw(boolean, "")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Query {
+    /// Renders this query as a filter expression, e.g. `status:active`.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&operand(self, Context::Or, ""))
+    }
+}
+
+/// Where a query is rendered, which determines whether it needs parentheses.
+#[derive(Copy, Clone, PartialEq)]
+enum Context {
+    /// An operand of `AND`.
+    And,
+
+    /// An operand of `OR`.
+    Or,
+
+    /// The operand of `NOT`.
+    Not,
+}
+
+/// Renders a top-level `bool` query (i.e. of a `CompoundQuery`), falling back
+/// to raw JSON.
+fn boolean_or_raw(boolean: &BooleanQuery, prefix: &str) -> String {
+    boolean_expression(boolean, prefix, 1).unwrap_or_else(|| {
+        raw(&Query {
+            boolean: Some(boolean.clone()),
+            ..Query::default()
+        })
+    })
+}
+
+/// Renders a `bool` query as an `AND` or `OR` expression, if it has the same
+/// shape as one produced by the parser. `AND` expressions must have at least
+/// `min_and` operands.
+fn boolean_expression(boolean: &BooleanQuery, prefix: &str, min_and: usize) -> Option<String> {
+    if !boolean.must.is_empty() || boolean.boost.is_some() || boolean.name.is_some() {
+        return None;
+    }
+
+    let operands = boolean.filter.len() + boolean.must_not.len();
+    if boolean.should.is_empty() && boolean.minimum_should_match.is_none() && operands >= min_and {
+        let filter = boolean
+            .filter
+            .iter()
+            .map(|query| operand(query, Context::And, prefix));
+        let must_not = boolean
+            .must_not
+            .iter()
+            .map(|query| format!("NOT {}", operand(query, Context::Not, prefix)));
+        return Some(filter.chain(must_not).collect::<Vec<_>>().join(" AND "));
+    }
+
+    if operands == 0
+        && boolean.should.len() >= 2
+        && boolean.minimum_should_match.as_deref() == Some("1")
+    {
+        let should = boolean
+            .should
+            .iter()
+            .map(|query| operand(query, Context::Or, prefix));
+        return Some(should.collect::<Vec<_>>().join(" OR "));
+    }
+
+    None
+}
+
+/// Renders a query that is an operand of an operator (or the whole expression).
+fn operand(query: &Query, context: Context, prefix: &str) -> String {
+    let set = [
+        query.exists.is_some(),
+        query.term.is_some(),
+        query.terms.is_some(),
+        query.range.is_some(),
+        query.prefix.is_some(),
+        query.regexp.is_some(),
+        query.match_.is_some(),
+        query.simple_query_string.is_some(),
+        query.query_string.is_some(),
+        query.nested.is_some(),
+        query.boolean.is_some(),
+        query.raw_query.is_some(),
+    ];
+    if set.iter().filter(|set| **set).count() != 1 {
+        return raw(query);
+    }
+
+    let rendered = match &query.boolean {
+        Some(boolean) => boolean_operand(boolean, context, prefix),
+        None => leaf(query, prefix),
+    };
+    rendered.unwrap_or_else(|| raw(query))
+}
+
+/// Renders a `bool` query that is an operand of an operator.
+fn boolean_operand(boolean: &BooleanQuery, context: Context, prefix: &str) -> Option<String> {
+    let is_not = boolean.filter.is_empty()
+        && boolean.must_not.len() == 1
+        && boolean.should.is_empty()
+        && boolean.must.is_empty()
+        && boolean.minimum_should_match.is_none()
+        && boolean.boost.is_none()
+        && boolean.name.is_none();
+
+    if is_not {
+        // within `AND`, a `NOT` would end up in the parent's `must_not`
+        return match context {
+            Context::And => None,
+            _ => Some(format!(
+                "NOT {}",
+                operand(&boolean.must_not[0], Context::Not, prefix)
+            )),
+        };
+    }
+
+    let is_and = boolean.should.is_empty();
+    let expression = boolean_expression(boolean, prefix, 2)?;
+    Some(match context {
+        Context::Or if is_and => expression,
+        _ => format!("({})", expression),
+    })
+}
+
+/// Renders a leaf (or nested) query, if it has a textual equivalent.
+fn leaf(query: &Query, prefix: &str) -> Option<String> {
+    if let Some(q) = &query.exists {
+        if q.name.is_some() {
+            return None;
+        }
+        return Some(format!("{}:*", field(&q.field, prefix)?));
+    }
+
+    if let Some(q) = &query.term {
+        if q.boost.is_some() || q.name.is_some() {
+            return None;
+        }
+        return Some(format!("{}:{}", field(&q.field, prefix)?, value(&q.value)));
+    }
+
+    if let Some(q) = &query.terms {
+        if q.boost.is_some() || q.name.is_some() || q.values.is_empty() {
+            return None;
+        }
+        let values: Vec<String> = q.values.iter().map(|v| value(v)).collect();
+        return Some(format!(
+            "{}:in[{}]",
+            field(&q.field, prefix)?,
+            values.join(", ")
+        ));
+    }
+
+    if let Some(q) = &query.range {
+        let bounds = [
+            (">", &q.greater_than),
+            (">=", &q.greater_than_or_equal_to),
+            ("<", &q.less_than),
+            ("<=", &q.less_than_or_equal_to),
+        ];
+        let mut bounds = bounds.iter().filter(|(_, bound)| bound.is_some());
+        return match (bounds.next(), bounds.next(), q.boost, &q.name) {
+            (Some((operator, Some(bound))), None, None, None) => Some(format!(
+                "{} {} {}",
+                field(&q.field, prefix)?,
+                operator,
+                value(bound)
+            )),
+            _ => None,
+        };
+    }
+
+    if let Some(q) = &query.prefix {
+        if q.case_insensitive || q.name.is_some() || q.value.contains(&['*', '?'][..]) {
+            return None;
+        }
+        let value = value(&q.value);
+        let value = match value.strip_suffix('"') {
+            Some(quoted) => format!("{}*\"", quoted),
+            None => format!("{}*", value),
+        };
+        return Some(format!("{}:~{}", field(&q.field, prefix)?, value));
+    }
+
+    if let Some(q) = &query.nested {
+        return nested(q, prefix);
+    }
+
+    None
+}
+
+/// Renders a nested query, with the fields of its query relative to its path.
+fn nested(query: &NestedQuery, prefix: &str) -> Option<String> {
+    if query.ignore_unmapped
+        || query.score_mode.is_some()
+        || query.inner_hits.is_some()
+        || query.name.is_some()
+    {
+        return None;
+    }
+
+    let path = field(&query.path, prefix)?;
+    let boolean = query.query.boolean.as_ref()?;
+    let expression = boolean_expression(boolean, &format!("{}.", query.path), 1)?;
+    Some(format!("{}:{{{}}}", path, expression))
+}
+
+/// Renders a field name relative to the path of the enclosing nested query.
+#[inline]
+fn field<'a>(field: &'a str, prefix: &str) -> Option<&'a str> {
+    field.strip_prefix(prefix).filter(|field| is_word(field))
+}
+
+/// Renders a value, quoting it if necessary.
+fn value(value: &str) -> String {
+    if is_word(value) {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// Returns `true` if `word` can be rendered as is, i.e. without quotes.
+#[inline]
+fn is_word(word: &str) -> bool {
+    !word.is_empty()
+        && word.chars().all(is_word_char)
+        && !["and", "or", "not"]
+            .iter()
+            .any(|keyword| word.eq_ignore_ascii_case(keyword))
+}
+
+/// Renders a query as raw JSON between backticks.
+fn raw(query: &Query) -> String {
+    let json = serde_json::to_string(query).expect("queries can always be serialized");
+    // backticks can only occur within JSON strings, where they can be escaped
+    format!("`{}`", json.replace('`', "\\u0060"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::language::{parse, parse_trusted};
+    use crate::search::query::{ExistsQuery, MatchQuery, RangeQuery, TermQuery};
+
+    macro_rules! round_trips {
+        ($name:ident, $input:expr) => {
+            round_trips!($name, $input, $input);
+        };
+        ($name:ident, $input:expr, $rendered:expr) => {
+            #[test]
+            fn $name() {
+                let query = parse($input).unwrap();
+                let rendered = query.to_string();
+                assert_eq!(rendered, $rendered);
+                assert_eq!(parse(&rendered).unwrap(), query, "{}", rendered);
+            }
+        };
+    }
+
+    round_trips!(empty, "");
+    round_trips!(term, "status:active");
+    round_trips!(quoted, r#"name:"Jane \"JD\" Doe""#);
+    round_trips!(keyword_value, r#"operator:"OR""#);
+    round_trips!(terms, r#"tags:in[a,"b c"]"#, r#"tags:in[a, "b c"]"#);
+    round_trips!(range, "age >= 30 AND age < 40 AND x > -1 AND y <= 2");
+    round_trips!(
+        prefix,
+        r#"name:~jo AND city:~"new y""#,
+        r#"name:~jo* AND city:~"new y*""#
+    );
+    round_trips!(exists, "email:*");
+    round_trips!(or, "a:1 OR b:2 OR c:3");
+    round_trips!(not, "NOT a:1");
+    round_trips!(not_not, "NOT NOT a:1");
+    round_trips!(and_not, "a:1 b:2 NOT c:3", "a:1 AND b:2 AND NOT c:3");
+    round_trips!(not_group, "NOT (a:1 OR b:2) AND NOT (c:3 AND d:4)");
+    round_trips!(or_of_not, "a:1 OR NOT b:2");
+    round_trips!(groups, "a:1 AND (b:2 OR c:3) AND (d:4 AND e:5)");
+    round_trips!(
+        or_of_and,
+        "(a:1 AND b:2) OR (c:3 OR d:4)",
+        "a:1 AND b:2 OR (c:3 OR d:4)"
+    );
+    round_trips!(
+        nested,
+        "comments:{author:jane AND replies:{stars >= 3}} AND NOT comments:{spam:true}"
+    );
+    round_trips!(
+        example,
+        r#"status:active AND (age >= 30 OR tags:in[a,b]) AND NOT name:~"jo*""#,
+        "status:active AND (age >= 30 OR tags:in[a, b]) AND NOT name:~jo*"
+    );
+
+    #[test]
+    fn unsupported_queries_are_raw() {
+        let mut query = CompoundQuery::default();
+        query.push(TermQuery::new("status", "active"));
+        query.push(MatchQuery::new("title", "rust `lang`"));
+        query.push(TermQuery {
+            boost: Some(2.0),
+            ..TermQuery::new("featured", "true")
+        });
+
+        let rendered = query.to_string();
+        assert_eq!(
+            rendered,
+            "status:active AND `{\"match\":{\"title\":{\"query\":\"rust \\u0060lang\\u0060\"}}}` \
+             AND `{\"term\":{\"featured\":{\"value\":\"true\",\"boost\":2.0}}}`"
+        );
+        assert!(parse(&rendered).is_err());
+        assert_eq!(parse_trusted(&rendered).unwrap(), query);
+    }
+
+    #[test]
+    fn unsupported_bool_queries_are_raw() {
+        let range: Query = RangeQuery {
+            field: "age".to_string(),
+            greater_than: Some("1".to_string()),
+            greater_than_or_equal_to: None,
+            less_than: Some("5".to_string()),
+            less_than_or_equal_to: None,
+            boost: None,
+            name: None,
+        }
+        .into();
+        let must = Query {
+            boolean: Some(BooleanQuery {
+                must: vec![TermQuery::new("a", "1").into()],
+                ..BooleanQuery::default()
+            }),
+            ..Query::default()
+        };
+        let mut query = CompoundQuery::default();
+        query.push(range);
+        query.push(must);
+
+        let rendered = query.to_string();
+        assert_eq!(
+            rendered,
+            "`{\"range\":{\"age\":{\"gt\":\"1\",\"lt\":\"5\"}}}` \
+             AND `{\"bool\":{\"must\":[{\"term\":{\"a\":{\"value\":\"1\"}}}]}}`"
+        );
+        assert_eq!(parse_trusted(&rendered).unwrap(), query);
+    }
+
+    #[test]
+    fn named_queries_are_raw() {
+        let mut query = CompoundQuery::default();
+        query.push(TermQuery::new("status", "active"));
+        query.push(ExistsQuery {
+            name: Some("has_tags".to_string()),
+            ..ExistsQuery::new("tags")
+        });
+
+        let rendered = query.to_string();
+        assert_eq!(
+            rendered,
+            "status:active AND `{\"exists\":{\"field\":\"tags\",\"_name\":\"has_tags\"}}`"
+        );
+        assert_eq!(parse_trusted(&rendered).unwrap(), query);
+    }
+
+    #[test]
+    fn nested_fields_outside_of_the_path_are_raw() {
+        let json = json!({ "bool": { "filter": [{ "nested": {
+            "path": "comments",
+            "ignore_unmapped": false,
+            "query": { "bool": { "filter": [
+                { "term": { "comments.author": { "value": "jane" } } },
+                { "term": { "status": { "value": "active" } } },
+            ] } },
+        } }] } });
+        let query: CompoundQuery = serde_json::from_value(json).unwrap();
+
+        let rendered = query.to_string();
+        assert_eq!(
+            rendered,
+            "comments:{author:jane AND `{\"term\":{\"status\":{\"value\":\"active\"}}}`}"
+        );
+        assert_eq!(parse_trusted(&rendered).unwrap(), query);
+    }
+
+    #[test]
+    fn can_render_query() {
+        let query: Query = TermQuery::new("status", "active").into();
+        assert_eq!(query.to_string(), "status:active");
+    }
+}
@@ -5,7 +5,27 @@
 //!
 //! [Elasticsearch]: https://www.elastic.co/guide/en/elasticsearch/reference/current/index.html
 
+// allows `#[derive(Fields)]` to be used within this crate (i.e. in tests)
+#[cfg(feature = "derive")]
+extern crate self as elastiql;
+
 pub mod aggregation;
 pub mod bulk;
+pub mod complexity;
+#[cfg(feature = "eval")]
+pub mod eval;
+pub mod facet;
+pub mod field;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+pub mod language;
+pub mod lint;
+pub mod mapping;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod policy;
+pub mod rewrite;
 pub mod scalars;
 pub mod search;
+pub mod settings;
+pub mod template;
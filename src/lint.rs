@@ -0,0 +1,535 @@
+//! Mapping-aware linting of requests, to catch mistakes that Elasticsearch
+//! silently accepts but that match (or aggregate) the wrong documents.
+//!
+//! A [`Linter`] checks the *converted* [`search::Request`] and
+//! [`aggregation::Request`] types against the [`Mapping`] of the index (e.g.
+//! from a [`MappingResponse`]) and reports every suspicious clause along with
+//! its path in the request body, like an [`AccessPolicy`] does.
+//!
+//! ```
+//! use elastiql::lint::{LintKind, Linter};
+//! use elastiql::mapping::MappingResponse;
+//! use elastiql::search::query::{CompoundQuery, TermQuery};
+//! use serde_json::json;
+//!
+//! let response: MappingResponse = serde_json::from_value(json!({
+//!     "users": { "mappings": { "properties": { "name": { "type": "text" } } } }
+//! }))
+//! .unwrap();
+//! let linter = Linter::new(response.into_mapping());
+//!
+//! let mut query = CompoundQuery::default();
+//! query.push(TermQuery::new("name", "Bob"));
+//!
+//! let lints = linter.lint_query(&query);
+//! assert_eq!(lints[0].path, "query.bool.filter[0].term");
+//! assert_eq!(lints[0].kind, LintKind::TermOnText);
+//! ```
+//!
+//! [`search::Request`]: crate::search::Request
+//! [`aggregation::Request`]: crate::aggregation::Request
+//! [`MappingResponse`]: crate::mapping::MappingResponse
+//! [`AccessPolicy`]: crate::policy::AccessPolicy
+
+use std::fmt;
+
+use crate::{
+    aggregation,
+    mapping::{FieldMapping, Mapping},
+    search::{
+        self,
+        query::{BooleanQuery, CompoundQuery, Query},
+    },
+};
+
+/// Checks requests against the [`Mapping`] of an index.
+#[derive(Clone, Debug)]
+pub struct Linter {
+    mapping: Mapping,
+}
+
+/// The kind of mistake a [`Lint`] is about.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LintKind {
+    /// A `term` or `terms` query on a `text` field, which only matches the
+    /// *analyzed* terms (e.g. `bob` but not `Bob`); use a `match` query or a
+    /// `keyword` (multi-)field instead.
+    TermOnText,
+
+    /// A `terms` aggregation on a `text` field without [`fielddata`], which
+    /// fails.
+    ///
+    /// [`fielddata`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/text.html#fielddata-mapping-param
+    TermsAggregationOnText,
+
+    /// A `range` query on a `keyword` field, which compares strings (e.g.
+    /// `"10" < "9"`) rather than numbers or dates.
+    RangeOnKeyword,
+
+    /// A query on a field of a [`nested`] object that is not wrapped in a
+    /// `nested` query with its path, which never matches.
+    ///
+    /// [`nested`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/nested.html
+    NotNested {
+        /// The path of the (innermost) `nested` field that contains the field.
+        nested_path: String,
+    },
+
+    /// Sorting on a field that isn't mapped.
+    UnmappedSort,
+}
+
+/// A single suspicious clause of a request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lint {
+    /// The path to the clause in the request body, e.g.
+    /// `query.bool.filter[0].term` or `aggs.by_owner.terms`.
+    pub path: String,
+
+    /// The field the clause is on.
+    pub field: String,
+
+    /// The kind of mistake.
+    pub kind: LintKind,
+}
+
+impl Lint {
+    /// Creates a new `Lint`.
+    #[inline]
+    fn new(path: String, field: &str, kind: LintKind) -> Self {
+        Lint {
+            path,
+            field: field.to_string(),
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.path)?;
+        match &self.kind {
+            LintKind::TermOnText => write!(f, "term-level query on text field `{}`", self.field),
+            LintKind::TermsAggregationOnText => write!(
+                f,
+                "terms aggregation on text field `{}` without fielddata",
+                self.field
+            ),
+            LintKind::RangeOnKeyword => write!(f, "range query on keyword field `{}`", self.field),
+            LintKind::NotNested { nested_path } => write!(
+                f,
+                "query on field `{}` is not wrapped in a nested query on `{}`",
+                self.field, nested_path
+            ),
+            LintKind::UnmappedSort => write!(f, "sort on unmapped field `{}`", self.field),
+        }
+    }
+}
+
+impl std::error::Error for Lint {}
+
+impl From<Mapping> for Linter {
+    #[inline]
+    fn from(mapping: Mapping) -> Self {
+        Linter::new(mapping)
+    }
+}
+
+impl Linter {
+    /// Creates a new `Linter` for an index with the given `mapping`.
+    #[inline]
+    pub fn new(mapping: Mapping) -> Self {
+        Linter { mapping }
+    }
+
+    /// The mapping requests are checked against.
+    #[inline]
+    pub fn mapping(&self) -> &Mapping {
+        &self.mapping
+    }
+
+    /// Lints a search request, returning every lint.
+    pub fn lint_search(&self, request: &search::Request) -> Vec<Lint> {
+        let mut lints = vec![];
+
+        self.visit_compound(&request.query, "query".to_string(), None, &mut lints);
+        let path = "post_filter".to_string();
+        self.visit_compound(&request.post_filter, path, None, &mut lints);
+
+        for (i, sort) in request.sort.iter().enumerate() {
+            let field = sort.field_name();
+            let special = field.starts_with('_') || matches!(field, "score" | "key" | "count");
+            if !special && self.mapping.field(field).is_none() {
+                lints.push(Lint::new(
+                    format!("sort[{}]", i),
+                    field,
+                    LintKind::UnmappedSort,
+                ));
+            }
+        }
+
+        lints
+    }
+
+    /// Lints a query, returning every lint.
+    pub fn lint_query(&self, query: &CompoundQuery) -> Vec<Lint> {
+        let mut lints = vec![];
+        self.visit_compound(query, "query".to_string(), None, &mut lints);
+        lints
+    }
+
+    /// Lints a list of (sibling) aggregations, returning every lint.
+    pub fn lint_aggregations(&self, aggregations: &[aggregation::Request]) -> Vec<Lint> {
+        let mut lints = vec![];
+        for aggregation in aggregations {
+            self.visit_aggregation(aggregation, "aggs", None, &mut lints);
+        }
+        lints
+    }
+
+    /// Checks the query on `field` is within its nested path, if any.
+    fn check_nested(
+        &self,
+        field: &str,
+        nested: Option<&str>,
+        path: impl FnOnce() -> String,
+        lints: &mut Vec<Lint>,
+    ) {
+        match self.mapping.nested_path(field) {
+            Some(nested_path) if Some(nested_path) != nested => {
+                let nested_path = nested_path.to_string();
+                lints.push(Lint::new(
+                    path(),
+                    field,
+                    LintKind::NotNested { nested_path },
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    /// Visits every clause of a compound query within the `nested` path.
+    fn visit_compound(
+        &self,
+        query: &CompoundQuery,
+        path: String,
+        nested: Option<&str>,
+        lints: &mut Vec<Lint>,
+    ) {
+        if let Some(boolean) = &query.boolean {
+            self.visit_boolean(boolean, format!("{}.bool", path), nested, lints);
+        }
+    }
+
+    /// Visits every clause of a boolean query within the `nested` path.
+    fn visit_boolean(
+        &self,
+        boolean: &BooleanQuery,
+        path: String,
+        nested: Option<&str>,
+        lints: &mut Vec<Lint>,
+    ) {
+        let occurrences = [
+            ("must", &boolean.must),
+            ("filter", &boolean.filter),
+            ("should", &boolean.should),
+            ("must_not", &boolean.must_not),
+        ];
+
+        for (occurrence, queries) in occurrences.iter() {
+            for (i, query) in queries.iter().enumerate() {
+                let path = format!("{}.{}[{}]", path, occurrence, i);
+                self.visit_query(query, path, nested, lints);
+            }
+        }
+    }
+
+    /// Visits a single query within the `nested` path, recursing into nested &
+    /// boolean queries.
+    fn visit_query(
+        &self,
+        query: &Query,
+        path: String,
+        nested: Option<&str>,
+        lints: &mut Vec<Lint>,
+    ) {
+        let fields = [
+            ("exists", query.exists.as_ref().map(|q| &q.field)),
+            ("term", query.term.as_ref().map(|q| &q.field)),
+            ("terms", query.terms.as_ref().map(|q| &q.field)),
+            ("range", query.range.as_ref().map(|q| &q.field)),
+            ("prefix", query.prefix.as_ref().map(|q| &q.field)),
+            ("regexp", query.regexp.as_ref().map(|q| &q.field)),
+            ("match", query.match_.as_ref().map(|q| &q.field)),
+        ];
+
+        for (ty, field) in fields.iter() {
+            let field = match field {
+                Some(field) => field.as_str(),
+                None => continue,
+            };
+            let path = || format!("{}.{}", path, ty);
+
+            match (*ty, self.mapping.field(field)) {
+                ("term", Some(FieldMapping::Text(_))) | ("terms", Some(FieldMapping::Text(_))) => {
+                    lints.push(Lint::new(path(), field, LintKind::TermOnText))
+                }
+                ("range", Some(FieldMapping::Keyword(_))) => {
+                    lints.push(Lint::new(path(), field, LintKind::RangeOnKeyword))
+                }
+                _ => {}
+            }
+            self.check_nested(field, nested, path, lints);
+        }
+
+        if let Some(q) = &query.nested {
+            let path = format!("{}.nested.query", path);
+            self.visit_compound(&q.query, path, Some(&q.path), lints);
+        }
+        if let Some(q) = &query.boolean {
+            self.visit_boolean(q, format!("{}.bool", path), nested, lints);
+        }
+    }
+
+    /// Visits a single aggregation within the `nested` path and its
+    /// sub-aggregations.
+    fn visit_aggregation(
+        &self,
+        aggregation: &aggregation::Request,
+        parent: &str,
+        nested: Option<&str>,
+        lints: &mut Vec<Lint>,
+    ) {
+        let path = format!("{}.{}", parent, aggregation.name);
+
+        if let Some(query) = &aggregation.filters {
+            self.visit_compound(query, format!("{}.filter", path), nested, lints);
+        }
+        if let Some(field) = aggregation.terms.as_ref().and_then(|a| a.field.as_deref()) {
+            if let Some(FieldMapping::Text(text)) = self.mapping.field(field) {
+                if text.fielddata != Some(true) {
+                    lints.push(Lint::new(
+                        format!("{}.terms", path),
+                        field,
+                        LintKind::TermsAggregationOnText,
+                    ));
+                }
+            }
+        }
+
+        // sub-aggregations of `nested`/`reverse_nested` aggregations are on
+        // other documents
+        let nested = match (&aggregation.nested, &aggregation.reverse_nested) {
+            (Some(agg), _) => Some(agg.path.as_str()),
+            (None, Some(agg)) => agg.path.as_deref(),
+            (None, None) => nested,
+        };
+        if let Some(aggregations) = &aggregation.aggregations {
+            let parent = format!("{}.aggs", path);
+            for aggregation in aggregations {
+                self.visit_aggregation(aggregation, &parent, nested, lints);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::aggregation::types::{NestedAggregation, TermsAggregation};
+    use crate::search::query::{NestedQuery, RangeQuery, TermQuery, TermsQuery};
+    use crate::search::{Request, Sort};
+
+    fn linter() -> Linter {
+        let mapping = serde_json::from_value(json!({
+            "properties": {
+                "name": {
+                    "type": "text",
+                    "fields": { "keyword": { "type": "keyword" } },
+                },
+                "bio": { "type": "text", "fielddata": true },
+                "zip": { "type": "keyword" },
+                "age": { "type": "integer" },
+                "comments": {
+                    "type": "nested",
+                    "properties": {
+                        "author": { "type": "keyword" },
+                        "votes": {
+                            "type": "nested",
+                            "properties": { "user": { "type": "keyword" } },
+                        },
+                    },
+                },
+            }
+        }))
+        .unwrap();
+        Linter::new(mapping)
+    }
+
+    #[test]
+    fn well_formed_query_has_no_lints() {
+        let mut query = CompoundQuery::default();
+        query.push(TermQuery::new("name.keyword", "Bob"));
+        query.push(
+            RangeQuery::builder()
+                .field("age")
+                .greater_than_or_equal_to(Some("30".to_string()))
+                .build(),
+        );
+        query.push(NestedQuery::new(
+            "comments",
+            TermQuery::new("comments.author", "bob"),
+            false,
+        ));
+
+        assert_eq!(linter().lint_query(&query), vec![]);
+    }
+
+    #[test]
+    fn flags_term_level_queries_on_the_wrong_types() {
+        let mut query = CompoundQuery::default();
+        query.push(TermQuery::new("name", "Bob"));
+        query.push(TermsQuery::new("name", vec!["Bob".to_string()]));
+        query.push(
+            RangeQuery::builder()
+                .field("zip")
+                .greater_than_or_equal_to(Some("10000".to_string()))
+                .build(),
+        );
+
+        let lints = linter().lint_query(&query);
+        assert_eq!(
+            lints,
+            vec![
+                Lint::new(
+                    "query.bool.filter[0].term".to_string(),
+                    "name",
+                    LintKind::TermOnText
+                ),
+                Lint::new(
+                    "query.bool.filter[1].terms".to_string(),
+                    "name",
+                    LintKind::TermOnText
+                ),
+                Lint::new(
+                    "query.bool.filter[2].range".to_string(),
+                    "zip",
+                    LintKind::RangeOnKeyword
+                ),
+            ]
+        );
+        assert_eq!(
+            lints[2].to_string(),
+            "query.bool.filter[2].range: range query on keyword field `zip`"
+        );
+    }
+
+    #[test]
+    fn flags_queries_outside_of_their_nested_path() {
+        let mut query = CompoundQuery::default();
+        query.push(TermQuery::new("comments.author", "bob"));
+        query.push(NestedQuery::new(
+            "comments",
+            TermQuery::new("comments.votes.user", "alice"),
+            false,
+        ));
+        query.push(NestedQuery::new(
+            "comments",
+            NestedQuery::new(
+                "comments.votes",
+                TermQuery::new("comments.votes.user", "alice"),
+                false,
+            ),
+            false,
+        ));
+
+        let lints = linter().lint_query(&query);
+        assert_eq!(
+            lints,
+            vec![
+                Lint::new(
+                    "query.bool.filter[0].term".to_string(),
+                    "comments.author",
+                    LintKind::NotNested {
+                        nested_path: "comments".to_string()
+                    }
+                ),
+                Lint::new(
+                    "query.bool.filter[1].nested.query.bool.filter[0].term".to_string(),
+                    "comments.votes.user",
+                    LintKind::NotNested {
+                        nested_path: "comments.votes".to_string()
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_sorting_on_unmapped_fields() {
+        let request = Request::builder()
+            .sort(vec![
+                Sort::new("age"),
+                Sort::new("_score"),
+                Sort::new("missing"),
+            ])
+            .build();
+
+        assert_eq!(
+            linter().lint_search(&request),
+            vec![Lint::new(
+                "sort[2]".to_string(),
+                "missing",
+                LintKind::UnmappedSort
+            )]
+        );
+    }
+
+    #[test]
+    fn flags_terms_aggregations_on_text_without_fielddata() {
+        let terms = |field: &str| {
+            Some(
+                TermsAggregation::builder()
+                    .field(Some(field.to_string()))
+                    .script(None)
+                    .build(),
+            )
+        };
+
+        let mut recent = CompoundQuery::default();
+        recent.push(TermQuery::new("comments.author", "bob"));
+
+        let aggregations = vec![
+            aggregation::Request::builder()
+                .name("by_name")
+                .terms(terms("name"))
+                .build(),
+            aggregation::Request::builder()
+                .name("by_bio")
+                .terms(terms("bio"))
+                .build(),
+            aggregation::Request::builder()
+                .name("comments")
+                .nested(Some(NestedAggregation {
+                    path: "comments".to_string(),
+                }))
+                .aggregations(Some(vec![aggregation::Request::builder()
+                    .name("recent")
+                    .filters(Some(recent))
+                    .build()]))
+                .build(),
+        ];
+
+        assert_eq!(
+            linter().lint_aggregations(&aggregations),
+            vec![Lint::new(
+                "aggs.by_name.terms".to_string(),
+                "name",
+                LintKind::TermsAggregationOnText
+            )]
+        );
+    }
+}
@@ -0,0 +1,531 @@
+//! [Mapping] types, which define how documents and their fields are stored
+//! and indexed.
+//!
+//! With the `derive` feature, `#[derive(Mapping)]` builds the [`Mapping`] of
+//! a document from the struct used to (de)serialize it, e.g. as the source of
+//! a `Hit<T>`, so the two can't drift apart:
+//!
+//! ```
+//! # #[cfg(feature = "derive")]
+//! # fn main() {
+//! use elastiql::mapping::{Document, Mapping};
+//! use serde::{Deserialize, Serialize};
+//! use serde_json::json;
+//!
+//! #[derive(Mapping, Serialize, Deserialize)]
+//! struct User {
+//!     id: String,
+//!     #[elastiql(type = "text", analyzer = "english")]
+//!     bio: String,
+//!     age: Option<u8>,
+//!     #[elastiql(nested)]
+//!     posts: Vec<Post>,
+//! }
+//!
+//! #[derive(Mapping, Serialize, Deserialize)]
+//! struct Post {
+//!     #[serde(rename = "createdAt")]
+//!     #[elastiql(type = "date", format = "epoch_millis")]
+//!     created_at: u64,
+//! }
+//!
+//! assert_eq!(
+//!     serde_json::to_value(User::mapping()).unwrap(),
+//!     json!({
+//!         "properties": {
+//!             "id": { "type": "keyword" },
+//!             "bio": { "type": "text", "analyzer": "english" },
+//!             "age": { "type": "short" },
+//!             "posts": {
+//!                 "type": "nested",
+//!                 "properties": {
+//!                     "createdAt": { "type": "date", "format": "epoch_millis" },
+//!                 },
+//!             },
+//!         }
+//!     })
+//! );
+//! # }
+//! # #[cfg(not(feature = "derive"))]
+//! # fn main() {}
+//! ```
+//!
+//! [Mapping]: https://www.elastic.co/guide/en/elasticsearch/reference/current/mapping.html
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashSet},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub use self::types::*;
+
+mod types;
+
+#[cfg(feature = "derive")]
+pub use elastiql_derive::Mapping;
+
+/// The [mapping] of an index, i.e. the mappings of the fields of its
+/// documents.
+///
+/// [mapping]: https://www.elastic.co/guide/en/elasticsearch/reference/current/mapping.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct Mapping {
+    /// Whether new fields are added to the mapping dynamically. Defaults to
+    /// `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dynamic: Option<Dynamic>,
+
+    /// The mappings of the (top-level) fields of the documents.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, FieldMapping>,
+
+    /// Any other parameters, e.g. `_source` or `dynamic_templates`.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Mapping {
+    /// Returns the mapping of the field at the (dotted) `path`, e.g.
+    /// `address.city`, including [multi-fields] such as `name.keyword`.
+    ///
+    /// [multi-fields]: https://www.elastic.co/guide/en/elasticsearch/reference/current/multi-fields.html
+    pub fn field(&self, path: &str) -> Option<&FieldMapping> {
+        let mut properties = &self.properties;
+        let mut field: Option<&FieldMapping> = None;
+        for segment in path.split('.') {
+            field = match field {
+                None => properties.get(segment),
+                Some(parent) => match parent.properties() {
+                    Some(children) => {
+                        properties = children;
+                        properties.get(segment)
+                    }
+                    None => parent.fields().and_then(|fields| fields.get(segment)),
+                },
+            };
+            field?;
+        }
+        field
+    }
+
+    /// Returns the path of the innermost [`nested`] field that contains the
+    /// field at the (dotted) `path`, if any, e.g. `comments` for
+    /// `comments.author`.
+    ///
+    /// [`nested`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/nested.html
+    pub fn nested_path<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let mut nested = None;
+        let mut properties = &self.properties;
+        let segments = path.match_indices('.').map(|(i, _)| i);
+        let mut start = 0;
+        for end in segments {
+            let field = properties.get(&path[start..end])?;
+            if let FieldMapping::Nested(_) = field {
+                nested = Some(&path[..end]);
+            }
+            properties = match field.properties() {
+                Some(properties) => properties,
+                // e.g. a multi-field such as `name.keyword`
+                None => break,
+            };
+            start = end + 1;
+        }
+        nested
+    }
+}
+
+/// The response of a [`GET <index>/_mapping`] request, i.e. the mapping of
+/// each index by name.
+///
+/// [`GET <index>/_mapping`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/indices-get-mapping.html
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(transparent)]
+pub struct MappingResponse {
+    /// The mapping of each index, by name.
+    pub indices: BTreeMap<String, IndexMapping>,
+}
+
+impl MappingResponse {
+    /// Merges the mappings of every index (e.g. behind an alias) into a single
+    /// mapping. When indices map the same top-level field differently, the
+    /// mapping of the first index (by name) wins.
+    pub fn into_mapping(self) -> Mapping {
+        let mut indices = self.indices.into_values().map(|index| index.mappings);
+        let mut mapping = indices.next().unwrap_or_default();
+        for other in indices {
+            for (name, field) in other.properties {
+                mapping.properties.entry(name).or_insert(field);
+            }
+        }
+        mapping
+    }
+}
+
+/// The mapping of a single index in a [`MappingResponse`].
+#[derive(Deserialize, Default, Clone, Debug)]
+pub struct IndexMapping {
+    /// The mapping of the index.
+    #[serde(default)]
+    pub mappings: Mapping,
+}
+
+/// A document (i.e. a struct) with a mapping; usually derived with
+/// `#[derive(Mapping)]`.
+pub trait Document {
+    /// Returns the mappings of the fields of the document.
+    fn properties() -> BTreeMap<String, FieldMapping>;
+
+    /// Returns the mapping of the document.
+    #[inline]
+    fn mapping() -> Mapping {
+        Mapping {
+            properties: Self::properties(),
+            ..Mapping::default()
+        }
+    }
+}
+
+/// A type that is stored as a certain data type, e.g. a `String` is a
+/// `keyword`.
+///
+/// `Option`s, `Vec`s etc. have the mapping of their values, since any field
+/// may be missing or contain multiple values.
+pub trait MappingType {
+    /// Returns the mapping of a field of this type.
+    fn field_mapping() -> FieldMapping;
+}
+
+/// Implements `MappingType` for types that are stored as `$mapping`.
+macro_rules! mapping_type {
+    ($mapping:ident: $($ty:ty),*) => {
+        $(
+            impl MappingType for $ty {
+                #[inline]
+                fn field_mapping() -> FieldMapping {
+                    FieldMapping::$mapping(Default::default())
+                }
+            }
+        )*
+    };
+}
+
+mapping_type!(Keyword: String, str, char, Cow<'_, str>);
+// `unsigned_long` is only supported by Elasticsearch 7.10+
+mapping_type!(Long: i64, isize, u32, u64, usize);
+mapping_type!(Integer: i32, u16);
+mapping_type!(Short: i16, u8);
+mapping_type!(Byte: i8);
+mapping_type!(Double: f64);
+mapping_type!(Float: f32);
+mapping_type!(Boolean: bool);
+
+/// Implements `MappingType` for containers of values of type `T`.
+macro_rules! container_mapping_type {
+    ($($ty:ty),*) => {
+        $(
+            impl<T: MappingType + ?Sized> MappingType for $ty {
+                #[inline]
+                fn field_mapping() -> FieldMapping {
+                    T::field_mapping()
+                }
+            }
+        )*
+    };
+}
+
+container_mapping_type!(&T, Box<T>);
+
+/// Implements `MappingType` for containers of values of type `T`.
+macro_rules! sized_container_mapping_type {
+    ($($ty:ty),*) => {
+        $(
+            impl<T: MappingType> MappingType for $ty {
+                #[inline]
+                fn field_mapping() -> FieldMapping {
+                    T::field_mapping()
+                }
+            }
+        )*
+    };
+}
+
+sized_container_mapping_type!(Option<T>, Vec<T>, [T], BTreeSet<T>, HashSet<T>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn can_round_trip_mappings() {
+        let j = json!({
+            "dynamic": "strict",
+            "properties": {
+                "id": { "type": "keyword", "ignore_above": 256 },
+                "name": {
+                    "type": "text",
+                    "analyzer": "english",
+                    "fields": { "keyword": { "type": "keyword" } },
+                },
+                "age": { "type": "short", "index": false },
+                "price": { "type": "scaled_float", "scaling_factor": 100.0 },
+                "created_at": { "type": "date", "format": "epoch_millis" },
+                "active": { "type": "boolean", "doc_values": false },
+                "address": {
+                    "properties": {
+                        "city": { "type": "keyword" },
+                        "location": { "type": "geo_point" },
+                    },
+                },
+                "comments": {
+                    "type": "nested",
+                    "dynamic": false,
+                    "properties": { "author": { "type": "keyword" } },
+                },
+                "relation": {
+                    "type": "join",
+                    "relations": { "question": ["answer", "comment"], "answer": "vote" },
+                },
+                "embedding": { "type": "dense_vector", "dims": 3 },
+                "ip": { "type": "ip", "ignore_malformed": true },
+            }
+        });
+
+        let mapping: Mapping = serde_json::from_value(j.clone()).unwrap();
+        assert_eq!(mapping.dynamic, Some(Dynamic::Strict));
+        assert_eq!(mapping.properties["address"].ty(), "object");
+        assert_eq!(mapping.properties["ip"].ty(), "ip");
+        match &mapping.properties["relation"] {
+            FieldMapping::Join(join) => assert_eq!(join.relations["answer"], vec!["vote"]),
+            mapping => panic!("unexpected mapping: {:?}", mapping),
+        }
+        assert_eq!(serde_json::to_value(&mapping).unwrap(), j);
+    }
+
+    #[test]
+    fn can_round_trip_unknown_parameters() {
+        let j = json!({
+            "_source": { "excludes": ["secret"] },
+            "properties": {
+                "id": { "type": "keyword", "eager_global_ordinals": true },
+                "name": { "type": "text", "copy_to": "all", "norms": false },
+                "age": { "type": "short", "ignore_malformed": true },
+                "created_at": { "type": "date", "locale": "de" },
+                "active": { "type": "boolean", "meta": { "unit": "flag" } },
+                "comments": {
+                    "type": "nested",
+                    "include_in_parent": true,
+                    "properties": { "author": { "type": "keyword" } },
+                },
+                "location": { "type": "geo_point", "null_value": [0.0, 0.0] },
+                "relation": {
+                    "type": "join",
+                    "relations": { "question": "answer" },
+                    "meta": { "a": "b" },
+                },
+                "embedding": { "type": "dense_vector", "dims": 3, "index": true },
+            }
+        });
+
+        let mapping: Mapping = serde_json::from_value(j.clone()).unwrap();
+        match &mapping.properties["name"] {
+            FieldMapping::Text(text) => assert_eq!(text.other["copy_to"], "all"),
+            mapping => panic!("unexpected mapping: {:?}", mapping),
+        }
+        assert_eq!(serde_json::to_value(&mapping).unwrap(), j);
+    }
+
+    #[test]
+    fn dynamic_may_be_a_string() {
+        let mapping: Mapping = serde_json::from_value(json!({ "dynamic": "false" })).unwrap();
+        assert_eq!(mapping.dynamic, Some(Dynamic::False));
+        assert!(serde_json::from_value::<Mapping>(json!({ "dynamic": "nope" })).is_err());
+    }
+
+    #[test]
+    fn can_find_fields() {
+        let mapping: Mapping = serde_json::from_value(json!({
+            "properties": {
+                "name": {
+                    "type": "text",
+                    "fields": { "keyword": { "type": "keyword" } },
+                },
+                "comments": {
+                    "type": "nested",
+                    "properties": { "author": { "type": "keyword" } },
+                },
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(mapping.field("name").map(FieldMapping::ty), Some("text"));
+        assert_eq!(
+            mapping.field("name.keyword").map(FieldMapping::ty),
+            Some("keyword")
+        );
+        assert_eq!(
+            mapping.field("comments").map(FieldMapping::ty),
+            Some("nested")
+        );
+        assert_eq!(
+            mapping.field("comments.author").map(FieldMapping::ty),
+            Some("keyword")
+        );
+        assert_eq!(mapping.field("comments.missing"), None);
+        assert_eq!(mapping.field("name.keyword.nope"), None);
+    }
+
+    #[test]
+    fn can_find_nested_paths() {
+        let mapping: Mapping = serde_json::from_value(json!({
+            "properties": {
+                "name": {
+                    "type": "text",
+                    "fields": { "keyword": { "type": "keyword" } },
+                },
+                "comments": {
+                    "type": "nested",
+                    "properties": {
+                        "author": {
+                            "properties": { "name": { "type": "keyword" } },
+                        },
+                        "votes": {
+                            "type": "nested",
+                            "properties": { "user": { "type": "keyword" } },
+                        },
+                    },
+                },
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(mapping.nested_path("name.keyword"), None);
+        assert_eq!(mapping.nested_path("comments"), None);
+        assert_eq!(
+            mapping.nested_path("comments.author.name"),
+            Some("comments")
+        );
+        assert_eq!(
+            mapping.nested_path("comments.votes.user"),
+            Some("comments.votes")
+        );
+    }
+
+    #[test]
+    fn can_merge_mapping_responses() {
+        let response: MappingResponse = serde_json::from_value(json!({
+            "logs-2": { "mappings": { "properties": { "level": { "type": "keyword" } } } },
+            "logs-1": {
+                "mappings": {
+                    "dynamic": "strict",
+                    "properties": { "level": { "type": "text" }, "message": { "type": "text" } },
+                },
+            },
+        }))
+        .unwrap();
+
+        let mapping = response.into_mapping();
+        assert_eq!(mapping.dynamic, Some(Dynamic::Strict));
+        assert_eq!(mapping.field("level").map(FieldMapping::ty), Some("text"));
+        assert_eq!(mapping.field("message").map(FieldMapping::ty), Some("text"));
+    }
+
+    #[cfg(feature = "derive")]
+    mod derive {
+        use std::collections::BTreeMap;
+
+        use serde::{Deserialize, Serialize};
+        use serde_json::json;
+
+        use crate::mapping::{Document, FieldMapping, Mapping, TextMapping};
+
+        #[derive(Mapping, Serialize, Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct User {
+            id: String,
+            created_at: u64,
+            #[elastiql(mapping = "name_mapping")]
+            name: String,
+            #[elastiql(type = "keyword", ignore_above = 64, doc_values = false)]
+            email: Option<String>,
+            tags: Vec<String>,
+            score: f32,
+            address: Option<Address>,
+            #[elastiql(nested)]
+            comments: Vec<Comment>,
+            #[serde(flatten)]
+            audit: Audit,
+            #[serde(skip)]
+            #[allow(dead_code)]
+            cache: (),
+        }
+
+        #[derive(Mapping, Serialize, Deserialize)]
+        struct Address {
+            city: String,
+            #[elastiql(type = "geo_point")]
+            location: (f64, f64),
+        }
+
+        #[derive(Mapping, Serialize, Deserialize)]
+        struct Comment {
+            author: String,
+            #[elastiql(type = "dense_vector", dims = 3)]
+            embedding: Vec<f32>,
+        }
+
+        #[derive(Mapping, Serialize, Deserialize)]
+        struct Audit {
+            updated_by: String,
+        }
+
+        fn name_mapping() -> FieldMapping {
+            let mut fields = BTreeMap::new();
+            fields.insert("keyword".to_string(), FieldMapping::keyword());
+            FieldMapping::Text(TextMapping {
+                fields,
+                ..TextMapping::default()
+            })
+        }
+
+        #[test]
+        fn derives_mappings() {
+            assert_eq!(
+                serde_json::to_value(User::mapping()).unwrap(),
+                json!({
+                    "properties": {
+                        "id": { "type": "keyword" },
+                        "createdAt": { "type": "long" },
+                        "name": {
+                            "type": "text",
+                            "fields": { "keyword": { "type": "keyword" } },
+                        },
+                        "email": { "type": "keyword", "ignore_above": 64, "doc_values": false },
+                        "tags": { "type": "keyword" },
+                        "score": { "type": "float" },
+                        "address": {
+                            "properties": {
+                                "city": { "type": "keyword" },
+                                "location": { "type": "geo_point" },
+                            },
+                        },
+                        "comments": {
+                            "type": "nested",
+                            "properties": {
+                                "author": { "type": "keyword" },
+                                "embedding": { "type": "dense_vector", "dims": 3 },
+                            },
+                        },
+                        "updated_by": { "type": "keyword" },
+                    }
+                })
+            );
+        }
+    }
+}
@@ -0,0 +1,717 @@
+//! [Field data types] and their mapping parameters.
+//!
+//! [Field data types]: https://www.elastic.co/guide/en/elasticsearch/reference/current/mapping-types.html
+
+use std::{collections::BTreeMap, fmt};
+
+use serde::{
+    de::{self, DeserializeOwned, Deserializer},
+    ser::{self, Serializer},
+    Deserialize, Serialize,
+};
+use serde_json::{Map, Value};
+
+/// Whether new fields are added to an object [dynamically].
+///
+/// [dynamically]: https://www.elastic.co/guide/en/elasticsearch/reference/current/dynamic.html
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Dynamic {
+    /// New fields are added to the mapping.
+    True,
+
+    /// New fields are ignored; they are not indexed or searchable, but are
+    /// still part of the `_source`.
+    False,
+
+    /// Documents with new fields are rejected.
+    Strict,
+}
+
+impl Serialize for Dynamic {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Dynamic::True => serializer.serialize_bool(true),
+            Dynamic::False => serializer.serialize_bool(false),
+            Dynamic::Strict => serializer.serialize_str("strict"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Dynamic {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        /// Elasticsearch returns all of the values as strings.
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum BoolOrString {
+            Bool(bool),
+            String(String),
+        }
+
+        match BoolOrString::deserialize(deserializer)? {
+            BoolOrString::Bool(true) => Ok(Dynamic::True),
+            BoolOrString::Bool(false) => Ok(Dynamic::False),
+            BoolOrString::String(value) => match value.as_str() {
+                "true" => Ok(Dynamic::True),
+                "false" => Ok(Dynamic::False),
+                "strict" => Ok(Dynamic::Strict),
+                _ => Err(de::Error::unknown_variant(
+                    &value,
+                    &["true", "false", "strict"],
+                )),
+            },
+        }
+    }
+}
+
+/// The [mapping] of a field, i.e. its data type and how it's indexed.
+///
+/// [mapping]: https://www.elastic.co/guide/en/elasticsearch/reference/current/mapping-types.html
+#[derive(PartialEq, Clone, Debug)]
+pub enum FieldMapping {
+    /// A [`keyword`](https://www.elastic.co/guide/en/elasticsearch/reference/current/keyword.html)
+    /// field, for structured content such as IDs, tags or statuses.
+    Keyword(KeywordMapping),
+
+    /// A [`text`](https://www.elastic.co/guide/en/elasticsearch/reference/current/text.html)
+    /// field, for analyzed full-text content.
+    Text(TextMapping),
+
+    /// A signed 64-bit integer [numeric](https://www.elastic.co/guide/en/elasticsearch/reference/current/number.html) field.
+    Long(NumericMapping),
+
+    /// A signed 32-bit integer [numeric](https://www.elastic.co/guide/en/elasticsearch/reference/current/number.html) field.
+    Integer(NumericMapping),
+
+    /// A signed 16-bit integer [numeric](https://www.elastic.co/guide/en/elasticsearch/reference/current/number.html) field.
+    Short(NumericMapping),
+
+    /// A signed 8-bit integer [numeric](https://www.elastic.co/guide/en/elasticsearch/reference/current/number.html) field.
+    Byte(NumericMapping),
+
+    /// A double-precision 64-bit floating point [numeric](https://www.elastic.co/guide/en/elasticsearch/reference/current/number.html) field.
+    Double(NumericMapping),
+
+    /// A single-precision 32-bit floating point [numeric](https://www.elastic.co/guide/en/elasticsearch/reference/current/number.html) field.
+    Float(NumericMapping),
+
+    /// A half-precision 16-bit floating point [numeric](https://www.elastic.co/guide/en/elasticsearch/reference/current/number.html) field.
+    HalfFloat(NumericMapping),
+
+    /// A floating point [numeric](https://www.elastic.co/guide/en/elasticsearch/reference/current/number.html)
+    /// field backed by a `long`, scaled by a fixed `scaling_factor`.
+    ScaledFloat(NumericMapping),
+
+    /// An unsigned 64-bit integer [numeric](https://www.elastic.co/guide/en/elasticsearch/reference/current/number.html) field.
+    UnsignedLong(NumericMapping),
+
+    /// A [`date`](https://www.elastic.co/guide/en/elasticsearch/reference/current/date.html) field.
+    Date(DateMapping),
+
+    /// A [`boolean`](https://www.elastic.co/guide/en/elasticsearch/reference/current/boolean.html) field.
+    Boolean(BooleanMapping),
+
+    /// An [`object`](https://www.elastic.co/guide/en/elasticsearch/reference/current/object.html)
+    /// field, whose fields are flattened into the parent document.
+    Object(ObjectMapping),
+
+    /// A [`nested`](https://www.elastic.co/guide/en/elasticsearch/reference/current/nested.html)
+    /// field, whose objects are indexed as separate (hidden) documents so
+    /// they can be queried independently of each other.
+    Nested(ObjectMapping),
+
+    /// A [`geo_point`](https://www.elastic.co/guide/en/elasticsearch/reference/current/geo-point.html) field.
+    GeoPoint(GeoPointMapping),
+
+    /// A [`join`](https://www.elastic.co/guide/en/elasticsearch/reference/current/parent-join.html)
+    /// field, which creates parent/child relations between documents.
+    Join(JoinMapping),
+
+    /// A [`dense_vector`](https://www.elastic.co/guide/en/elasticsearch/reference/current/dense-vector.html) field.
+    DenseVector(DenseVectorMapping),
+
+    /// A field of any other data type (e.g. `ip`), with its raw mapping
+    /// parameters.
+    Other {
+        /// The data type, e.g. `ip`.
+        ty: String,
+
+        /// The mapping parameters, excluding `type`.
+        parameters: Map<String, Value>,
+    },
+}
+
+impl FieldMapping {
+    /// A `keyword` field with the default parameters.
+    #[inline]
+    pub fn keyword() -> Self {
+        FieldMapping::Keyword(KeywordMapping::default())
+    }
+
+    /// A `text` field with the default parameters.
+    #[inline]
+    pub fn text() -> Self {
+        FieldMapping::Text(TextMapping::default())
+    }
+
+    /// A `long` field with the default parameters.
+    #[inline]
+    pub fn long() -> Self {
+        FieldMapping::Long(NumericMapping::default())
+    }
+
+    /// An `integer` field with the default parameters.
+    #[inline]
+    pub fn integer() -> Self {
+        FieldMapping::Integer(NumericMapping::default())
+    }
+
+    /// A `double` field with the default parameters.
+    #[inline]
+    pub fn double() -> Self {
+        FieldMapping::Double(NumericMapping::default())
+    }
+
+    /// A `float` field with the default parameters.
+    #[inline]
+    pub fn float() -> Self {
+        FieldMapping::Float(NumericMapping::default())
+    }
+
+    /// A `date` field with the default parameters.
+    #[inline]
+    pub fn date() -> Self {
+        FieldMapping::Date(DateMapping::default())
+    }
+
+    /// A `boolean` field with the default parameters.
+    #[inline]
+    pub fn boolean() -> Self {
+        FieldMapping::Boolean(BooleanMapping::default())
+    }
+
+    /// An `object` field with the `properties`.
+    #[inline]
+    pub fn object(properties: BTreeMap<String, FieldMapping>) -> Self {
+        FieldMapping::Object(ObjectMapping {
+            properties,
+            ..ObjectMapping::default()
+        })
+    }
+
+    /// A `nested` field with the `properties`.
+    #[inline]
+    pub fn nested(properties: BTreeMap<String, FieldMapping>) -> Self {
+        FieldMapping::Nested(ObjectMapping {
+            properties,
+            ..ObjectMapping::default()
+        })
+    }
+
+    /// A `geo_point` field with the default parameters.
+    #[inline]
+    pub fn geo_point() -> Self {
+        FieldMapping::GeoPoint(GeoPointMapping::default())
+    }
+
+    /// A `dense_vector` field with `dims` dimensions.
+    #[inline]
+    pub fn dense_vector(dims: u32) -> Self {
+        FieldMapping::DenseVector(DenseVectorMapping {
+            dims: Some(dims),
+            ..DenseVectorMapping::default()
+        })
+    }
+
+    /// The name of the data type, e.g. `keyword`.
+    pub fn ty(&self) -> &str {
+        match self {
+            FieldMapping::Keyword(_) => "keyword",
+            FieldMapping::Text(_) => "text",
+            FieldMapping::Long(_) => "long",
+            FieldMapping::Integer(_) => "integer",
+            FieldMapping::Short(_) => "short",
+            FieldMapping::Byte(_) => "byte",
+            FieldMapping::Double(_) => "double",
+            FieldMapping::Float(_) => "float",
+            FieldMapping::HalfFloat(_) => "half_float",
+            FieldMapping::ScaledFloat(_) => "scaled_float",
+            FieldMapping::UnsignedLong(_) => "unsigned_long",
+            FieldMapping::Date(_) => "date",
+            FieldMapping::Boolean(_) => "boolean",
+            FieldMapping::Object(_) => "object",
+            FieldMapping::Nested(_) => "nested",
+            FieldMapping::GeoPoint(_) => "geo_point",
+            FieldMapping::Join(_) => "join",
+            FieldMapping::DenseVector(_) => "dense_vector",
+            FieldMapping::Other { ty, .. } => ty,
+        }
+    }
+
+    /// The mappings of the fields of an `object` or `nested` field.
+    #[inline]
+    pub fn properties(&self) -> Option<&BTreeMap<String, FieldMapping>> {
+        match self {
+            FieldMapping::Object(object) | FieldMapping::Nested(object) => Some(&object.properties),
+            _ => None,
+        }
+    }
+
+    /// The mappings of the [multi-fields] of this field, e.g. `keyword` for
+    /// `text` fields.
+    ///
+    /// [multi-fields]: https://www.elastic.co/guide/en/elasticsearch/reference/current/multi-fields.html
+    #[inline]
+    pub fn fields(&self) -> Option<&BTreeMap<String, FieldMapping>> {
+        match self {
+            FieldMapping::Keyword(keyword) => Some(&keyword.fields),
+            FieldMapping::Text(text) => Some(&text.fields),
+            FieldMapping::Long(numeric)
+            | FieldMapping::Integer(numeric)
+            | FieldMapping::Short(numeric)
+            | FieldMapping::Byte(numeric)
+            | FieldMapping::Double(numeric)
+            | FieldMapping::Float(numeric)
+            | FieldMapping::HalfFloat(numeric)
+            | FieldMapping::ScaledFloat(numeric)
+            | FieldMapping::UnsignedLong(numeric) => Some(&numeric.fields),
+            FieldMapping::Date(date) => Some(&date.fields),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for FieldMapping {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let parameters = match self {
+            FieldMapping::Keyword(keyword) => serde_json::to_value(keyword),
+            FieldMapping::Text(text) => serde_json::to_value(text),
+            FieldMapping::Long(numeric)
+            | FieldMapping::Integer(numeric)
+            | FieldMapping::Short(numeric)
+            | FieldMapping::Byte(numeric)
+            | FieldMapping::Double(numeric)
+            | FieldMapping::Float(numeric)
+            | FieldMapping::HalfFloat(numeric)
+            | FieldMapping::ScaledFloat(numeric)
+            | FieldMapping::UnsignedLong(numeric) => serde_json::to_value(numeric),
+            FieldMapping::Date(date) => serde_json::to_value(date),
+            FieldMapping::Boolean(boolean) => serde_json::to_value(boolean),
+            FieldMapping::Object(object) | FieldMapping::Nested(object) => {
+                serde_json::to_value(object)
+            }
+            FieldMapping::GeoPoint(geo_point) => serde_json::to_value(geo_point),
+            FieldMapping::Join(join) => serde_json::to_value(join),
+            FieldMapping::DenseVector(dense_vector) => serde_json::to_value(dense_vector),
+            FieldMapping::Other { parameters, .. } => Ok(Value::Object(parameters.clone())),
+        };
+        let mut parameters = match parameters.map_err(ser::Error::custom)? {
+            Value::Object(parameters) => parameters,
+            _ => unreachable!("mapping parameters are always objects"),
+        };
+
+        // `object` is the default (and Elasticsearch omits it too)
+        if !matches!(self, FieldMapping::Object(_)) {
+            parameters.insert("type".to_string(), self.ty().into());
+        }
+
+        parameters.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FieldMapping {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut parameters = Map::deserialize(deserializer)?;
+        let ty = match parameters.remove("type") {
+            Some(Value::String(ty)) => ty,
+            Some(ty) => {
+                return Err(de::Error::invalid_type(
+                    de::Unexpected::Other(&ty.to_string()),
+                    &"a string",
+                ))
+            }
+            None => "object".to_string(),
+        };
+
+        Ok(match ty.as_str() {
+            "keyword" => FieldMapping::Keyword(from_parameters(parameters)?),
+            "text" => FieldMapping::Text(from_parameters(parameters)?),
+            "long" => FieldMapping::Long(from_parameters(parameters)?),
+            "integer" => FieldMapping::Integer(from_parameters(parameters)?),
+            "short" => FieldMapping::Short(from_parameters(parameters)?),
+            "byte" => FieldMapping::Byte(from_parameters(parameters)?),
+            "double" => FieldMapping::Double(from_parameters(parameters)?),
+            "float" => FieldMapping::Float(from_parameters(parameters)?),
+            "half_float" => FieldMapping::HalfFloat(from_parameters(parameters)?),
+            "scaled_float" => FieldMapping::ScaledFloat(from_parameters(parameters)?),
+            "unsigned_long" => FieldMapping::UnsignedLong(from_parameters(parameters)?),
+            "date" => FieldMapping::Date(from_parameters(parameters)?),
+            "boolean" => FieldMapping::Boolean(from_parameters(parameters)?),
+            "object" => FieldMapping::Object(from_parameters(parameters)?),
+            "nested" => FieldMapping::Nested(from_parameters(parameters)?),
+            "geo_point" => FieldMapping::GeoPoint(from_parameters(parameters)?),
+            "join" => FieldMapping::Join(from_parameters(parameters)?),
+            "dense_vector" => FieldMapping::DenseVector(from_parameters(parameters)?),
+            _ => FieldMapping::Other { ty, parameters },
+        })
+    }
+}
+
+/// Deserializes the mapping `parameters` of a known data type.
+#[inline]
+fn from_parameters<T: DeserializeOwned, E: de::Error>(
+    parameters: Map<String, Value>,
+) -> Result<T, E> {
+    serde_json::from_value(Value::Object(parameters)).map_err(E::custom)
+}
+
+impl fmt::Display for FieldMapping {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.ty())
+    }
+}
+
+/// The parameters of a [`keyword`] field.
+///
+/// [`keyword`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/keyword.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct KeywordMapping {
+    /// Whether the field is searchable. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<bool>,
+
+    /// Whether the field is stored on disk in a column-stride fashion, so it
+    /// can be used for sorting, aggregations or scripting. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc_values: Option<bool>,
+
+    /// Whether the field value is stored separately from the `_source`.
+    /// Defaults to `false`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub store: Option<bool>,
+
+    /// Strings longer than this are not indexed (or stored).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ignore_above: Option<u32>,
+
+    /// The [normalizer] to apply before indexing, e.g. `lowercase`.
+    ///
+    /// [normalizer]: https://www.elastic.co/guide/en/elasticsearch/reference/current/normalizer.html
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalizer: Option<String>,
+
+    /// The value to index instead of an explicit `null`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub null_value: Option<String>,
+
+    /// The [multi-fields] of this field.
+    ///
+    /// [multi-fields]: https://www.elastic.co/guide/en/elasticsearch/reference/current/multi-fields.html
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, FieldMapping>,
+
+    /// Any other parameters, e.g. `copy_to` or `eager_global_ordinals`.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [`text`] field.
+///
+/// [`text`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/text.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct TextMapping {
+    /// Whether the field is searchable. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<bool>,
+
+    /// Whether the field value is stored separately from the `_source`.
+    /// Defaults to `false`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub store: Option<bool>,
+
+    /// The [analyzer] used when indexing (and searching, unless a
+    /// `search_analyzer` is given). Defaults to the index's default analyzer.
+    ///
+    /// [analyzer]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analyzer.html
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analyzer: Option<String>,
+
+    /// The [analyzer] used when searching.
+    ///
+    /// [analyzer]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-analyzer.html
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_analyzer: Option<String>,
+
+    /// Whether to load [fielddata] into memory so the field can be used for
+    /// sorting, aggregations or scripting. Defaults to `false`.
+    ///
+    /// [fielddata]: https://www.elastic.co/guide/en/elasticsearch/reference/current/fielddata.html
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fielddata: Option<bool>,
+
+    /// The [multi-fields] of this field, e.g. a `keyword` to sort on.
+    ///
+    /// [multi-fields]: https://www.elastic.co/guide/en/elasticsearch/reference/current/multi-fields.html
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, FieldMapping>,
+
+    /// Any other parameters, e.g. `copy_to` or `norms`.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [numeric] field.
+///
+/// [numeric]: https://www.elastic.co/guide/en/elasticsearch/reference/current/number.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct NumericMapping {
+    /// Whether the field is searchable. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<bool>,
+
+    /// Whether the field is stored on disk in a column-stride fashion, so it
+    /// can be used for sorting, aggregations or scripting. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc_values: Option<bool>,
+
+    /// Whether the field value is stored separately from the `_source`.
+    /// Defaults to `false`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub store: Option<bool>,
+
+    /// Whether to convert strings to numbers and truncate fractions for
+    /// integers. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coerce: Option<bool>,
+
+    /// The value to index instead of an explicit `null`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub null_value: Option<f64>,
+
+    /// The factor to scale values of a `scaled_float` field by; required for
+    /// (and only applicable to) `scaled_float` fields.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scaling_factor: Option<f64>,
+
+    /// The [multi-fields] of this field.
+    ///
+    /// [multi-fields]: https://www.elastic.co/guide/en/elasticsearch/reference/current/multi-fields.html
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, FieldMapping>,
+
+    /// Any other parameters, e.g. `ignore_malformed` or `meta`.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [`date`] field.
+///
+/// [`date`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/date.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct DateMapping {
+    /// Whether the field is searchable. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<bool>,
+
+    /// Whether the field is stored on disk in a column-stride fashion, so it
+    /// can be used for sorting, aggregations or scripting. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc_values: Option<bool>,
+
+    /// Whether the field value is stored separately from the `_source`.
+    /// Defaults to `false`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub store: Option<bool>,
+
+    /// The [date format(s)] that can be parsed, e.g. `epoch_millis`. Defaults
+    /// to `strict_date_optional_time||epoch_millis`.
+    ///
+    /// [date format(s)]: https://www.elastic.co/guide/en/elasticsearch/reference/current/mapping-date-format.html
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+
+    /// The value to index instead of an explicit `null`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub null_value: Option<String>,
+
+    /// The [multi-fields] of this field.
+    ///
+    /// [multi-fields]: https://www.elastic.co/guide/en/elasticsearch/reference/current/multi-fields.html
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, FieldMapping>,
+
+    /// Any other parameters, e.g. `locale` or `ignore_malformed`.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [`boolean`] field.
+///
+/// [`boolean`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/boolean.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct BooleanMapping {
+    /// Whether the field is searchable. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<bool>,
+
+    /// Whether the field is stored on disk in a column-stride fashion, so it
+    /// can be used for sorting, aggregations or scripting. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc_values: Option<bool>,
+
+    /// Whether the field value is stored separately from the `_source`.
+    /// Defaults to `false`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub store: Option<bool>,
+
+    /// The value to index instead of an explicit `null`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub null_value: Option<bool>,
+
+    /// Any other parameters, e.g. `meta`.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of an [`object`] or [`nested`] field.
+///
+/// [`object`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/object.html
+/// [`nested`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/nested.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct ObjectMapping {
+    /// Whether new fields are added to the object dynamically. Defaults to
+    /// the parent's setting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dynamic: Option<Dynamic>,
+
+    /// Whether the object is parsed and indexed at all. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    /// The mappings of the fields of the object.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, FieldMapping>,
+
+    /// Any other parameters, e.g. `include_in_parent` of `nested` fields.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [`geo_point`] field.
+///
+/// [`geo_point`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/geo-point.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct GeoPointMapping {
+    /// Whether the field is searchable. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<bool>,
+
+    /// Whether the field is stored on disk in a column-stride fashion, so it
+    /// can be used for sorting, aggregations or scripting. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc_values: Option<bool>,
+
+    /// Whether to ignore malformed points instead of rejecting the whole
+    /// document. Defaults to `false`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ignore_malformed: Option<bool>,
+
+    /// Any other parameters, e.g. `null_value`.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [`join`] field.
+///
+/// [`join`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/parent-join.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct JoinMapping {
+    /// The names of the children of each parent relation, e.g.
+    /// `question -> [answer]`.
+    #[serde(with = "relations")]
+    pub relations: BTreeMap<String, Vec<String>>,
+
+    /// Whether to eagerly load global ordinals on refresh. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eager_global_ordinals: Option<bool>,
+
+    /// Any other parameters, e.g. `meta`.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// (De)serializes `join` relations, whose children may be a single name.
+mod relations {
+    use std::collections::BTreeMap;
+
+    use serde::{ser::SerializeMap, Deserialize, Deserializer, Serializer};
+
+    /// Either a single child or a list of children.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    pub(super) fn serialize<S: Serializer>(
+        relations: &BTreeMap<String, Vec<String>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(relations.len()))?;
+        for (parent, children) in relations {
+            match children.as_slice() {
+                [child] => map.serialize_entry(parent, child)?,
+                children => map.serialize_entry(parent, children)?,
+            }
+        }
+        map.end()
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<String, Vec<String>>, D::Error> {
+        let relations = BTreeMap::<String, OneOrMany>::deserialize(deserializer)?;
+        Ok(relations
+            .into_iter()
+            .map(|(parent, children)| match children {
+                OneOrMany::One(child) => (parent, vec![child]),
+                OneOrMany::Many(children) => (parent, children),
+            })
+            .collect())
+    }
+}
+
+/// The parameters of a [`dense_vector`] field.
+///
+/// [`dense_vector`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/dense-vector.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct DenseVectorMapping {
+    /// The number of dimensions of the vectors; required.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dims: Option<u32>,
+
+    /// Any other parameters, e.g. `index` or `similarity`.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
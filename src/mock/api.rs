@@ -0,0 +1,905 @@
+//! The Elasticsearch APIs served by the mock server.
+
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, VecDeque},
+};
+
+use serde_json::{json, Map, Value};
+
+use super::{http, Endpoint, MockError};
+use crate::{
+    aggregation, eval,
+    search::{self, SortOrder},
+};
+
+/// An HTTP status code & JSON body.
+type Response = (u16, Value);
+
+/// The indices & injected errors of a mock server.
+#[derive(Default, Debug)]
+pub(super) struct State {
+    /// The indices, by name.
+    pub(super) indices: BTreeMap<String, Index>,
+
+    /// The errors to return for the next requests to an endpoint.
+    pub(super) failures: Vec<(Endpoint, MockError)>,
+
+    /// The errors to return for the next `_bulk` operations.
+    pub(super) bulk_item_failures: VecDeque<MockError>,
+
+    /// The recorded responses to replay for `_search` requests.
+    #[cfg(feature = "fixtures")]
+    pub(super) fixtures: Option<crate::fixtures::Fixtures>,
+}
+
+/// An in-memory index.
+#[derive(Default, Debug)]
+pub(super) struct Index {
+    /// The documents, by ID.
+    documents: BTreeMap<String, Document>,
+
+    /// The sequence number of the next write.
+    sequence_number: u64,
+
+    /// Used to generate the IDs of documents indexed without one.
+    next_id: u64,
+}
+
+/// A document stored in an [`Index`].
+#[derive(Debug)]
+pub(super) struct Document {
+    /// The document itself.
+    pub(super) source: Value,
+
+    /// The version, incremented by every write.
+    version: u64,
+
+    /// The sequence number of the last write.
+    sequence_number: u64,
+}
+
+/// The outcome of writing a document.
+pub(super) struct Write {
+    /// The `result` of the write, e.g. `created`.
+    result: &'static str,
+
+    /// The HTTP status code.
+    status: u16,
+
+    /// The version of the document after the write.
+    version: u64,
+
+    /// The sequence number of the write.
+    sequence_number: u64,
+}
+
+impl Index {
+    /// Gets a document.
+    #[inline]
+    pub(super) fn get(&self, id: &str) -> Option<&Document> {
+        self.documents.get(id)
+    }
+
+    /// Creates or replaces a document.
+    pub(super) fn put(&mut self, id: &str, source: Value) -> Write {
+        let sequence_number = self.next_sequence_number();
+        match self.documents.get_mut(id) {
+            Some(document) => {
+                document.source = source;
+                document.version += 1;
+                document.sequence_number = sequence_number;
+                Write {
+                    result: "updated",
+                    status: 200,
+                    version: document.version,
+                    sequence_number,
+                }
+            }
+            None => {
+                let document = Document {
+                    source,
+                    version: 1,
+                    sequence_number,
+                };
+                self.documents.insert(id.to_string(), document);
+                Write {
+                    result: "created",
+                    status: 201,
+                    version: 1,
+                    sequence_number,
+                }
+            }
+        }
+    }
+
+    /// Deletes a document.
+    fn delete(&mut self, id: &str) -> Write {
+        let sequence_number = self.next_sequence_number();
+        match self.documents.remove(id) {
+            Some(document) => Write {
+                result: "deleted",
+                status: 200,
+                version: document.version + 1,
+                sequence_number,
+            },
+            None => Write {
+                result: "not_found",
+                status: 404,
+                version: 1,
+                sequence_number,
+            },
+        }
+    }
+
+    /// Generates an ID for a document indexed without one.
+    fn generate_id(&mut self) -> String {
+        loop {
+            self.next_id += 1;
+            let id = format!("mock-{}", self.next_id);
+            if !self.documents.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+
+    /// Returns the next sequence number.
+    #[inline]
+    fn next_sequence_number(&mut self) -> u64 {
+        self.sequence_number += 1;
+        self.sequence_number - 1
+    }
+}
+
+/// Handles a request, returning the response.
+pub(super) fn handle(state: &mut State, request: &http::Request) -> Response {
+    let segments: Vec<&str> = request.segments.iter().map(String::as_str).collect();
+    let method = request.method.as_str();
+
+    let (endpoint, index) = match segments.as_slice() {
+        [] if method == "GET" || method == "HEAD" => return (200, info()),
+        ["_search"] | ["_count"] | ["_bulk"] | ["_mget"] | ["_refresh"] => (segments[0], None),
+        [index, api] => (*api, Some(*index)),
+        [index, api @ "_doc", _] | [index, api @ "_create", _] => (*api, Some(*index)),
+        _ => ("", None),
+    };
+    let endpoint = match endpoint {
+        "_search" => Endpoint::Search,
+        "_count" => Endpoint::Count,
+        "_bulk" => Endpoint::Bulk,
+        "_doc" | "_create" => Endpoint::Doc,
+        "_mget" => Endpoint::Mget,
+        "_refresh" => return (200, json!({ "_shards": shards() })),
+        _ => {
+            let reason = format!(
+                "no handler found for uri [/{}] and method [{}]",
+                segments.join("/"),
+                method
+            );
+            return (400, json!({ "error": reason, "status": 400 }));
+        }
+    };
+
+    if let Some(i) = state.failures.iter().position(|(e, _)| *e == endpoint) {
+        let (_, error) = state.failures.remove(i);
+        return error_response(&error, index.unwrap_or("_na_"));
+    }
+
+    #[cfg(feature = "fixtures")]
+    {
+        if let (Endpoint::Search, Some(fixtures)) = (endpoint, &state.fixtures) {
+            if let Some(response) = replay(fixtures, request) {
+                return response;
+            }
+        }
+    }
+
+    let result = match (endpoint, method, segments.as_slice()) {
+        (Endpoint::Search, _, _) => search(state, index, request),
+        (Endpoint::Count, _, _) => count(state, index, request),
+        (Endpoint::Bulk, "POST", _) | (Endpoint::Bulk, "PUT", _) => bulk(state, index, request),
+        (Endpoint::Mget, _, _) => mget(state, index, request),
+        (Endpoint::Doc, "GET", [index, "_doc", id])
+        | (Endpoint::Doc, "HEAD", [index, "_doc", id]) => get(state, index, id),
+        (Endpoint::Doc, "DELETE", [index, "_doc", id]) => delete(state, index, id),
+        (Endpoint::Doc, "POST", [index, "_doc"]) => {
+            let id = state
+                .indices
+                .entry(index.to_string())
+                .or_default()
+                .generate_id();
+            put(state, request, index, &id, false)
+        }
+        (Endpoint::Doc, "PUT", [index, api, id]) | (Endpoint::Doc, "POST", [index, api, id]) => {
+            let create = *api == "_create" || request.parameter("op_type") == Some("create");
+            put(state, request, index, id, create)
+        }
+        _ => Err(MockError::new(
+            405,
+            "method_not_allowed",
+            format!(
+                "Incorrect HTTP method for uri [/{}] and method [{}]",
+                segments.join("/"),
+                method
+            ),
+        )
+        .into_response(index.unwrap_or("_na_"))),
+    };
+    result.unwrap_or_else(|response| response)
+}
+
+/// Returns the recorded response for a request, if any.
+#[cfg(feature = "fixtures")]
+fn replay(fixtures: &crate::fixtures::Fixtures, request: &http::Request) -> Option<Response> {
+    use crate::fixtures::FixtureError;
+
+    let body = match body(request) {
+        Ok(body) => body,
+        Err(response) => return Some(response),
+    };
+    match fixtures.load(&body) {
+        Ok(fixture) => Some((fixture.status, fixture.response)),
+        Err(FixtureError::NotFound(_)) => None,
+        Err(err) => {
+            let error = MockError::new(500, "fixture_exception", err.to_string());
+            Some(error_response(&error, "_na_"))
+        }
+    }
+}
+
+/// The response of `GET /`.
+fn info() -> Value {
+    json!({
+        "name": "elastiql-mock",
+        "cluster_name": "elastiql-mock",
+        "version": { "number": "7.9.0" },
+        "tagline": "You Know, for Search",
+    })
+}
+
+/// The `_shards` of a response, as if there was only a single shard.
+#[inline]
+fn shards() -> Value {
+    json!({ "total": 1, "successful": 1, "skipped": 0, "failed": 0 })
+}
+
+/// The response of a failed request.
+fn error_response(error: &MockError, index: &str) -> Response {
+    (
+        error.status,
+        json!({ "error": error.details(index), "status": error.status }),
+    )
+}
+
+impl MockError {
+    /// Converts this error into the response of a failed request.
+    #[inline]
+    fn into_response(self, index: &str) -> Response {
+        error_response(&self, index)
+    }
+}
+
+/// The error returned for a request Elasticsearch would reject.
+#[inline]
+fn bad_request(reason: impl Into<String>) -> MockError {
+    MockError::new(400, "illegal_argument_exception", reason)
+}
+
+/// Parses the JSON body of a request, which defaults to `{}`.
+fn body(request: &http::Request) -> Result<Value, Response> {
+    if request.body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(json!({}));
+    }
+    serde_json::from_slice(&request.body).map_err(|err| {
+        MockError::new(400, "parsing_exception", err.to_string()).into_response("_na_")
+    })
+}
+
+/// Resolves an index expression (e.g. `users,posts*`) into the names of
+/// existing indices.
+fn resolve<'s>(
+    state: &'s State,
+    expression: Option<&str>,
+    request: &http::Request,
+) -> Result<Vec<&'s str>, Response> {
+    let expression = expression.unwrap_or("_all");
+    if expression == "_all" {
+        return Ok(state.indices.keys().map(String::as_str).collect());
+    }
+
+    let mut names = Vec::new();
+    for pattern in expression.split(',') {
+        if pattern.contains('*') {
+            names.extend(
+                state
+                    .indices
+                    .keys()
+                    .filter(|name| wildcard_matches(pattern, name))
+                    .map(String::as_str),
+            );
+        } else if let Some((name, _)) = state.indices.get_key_value(pattern) {
+            names.push(name.as_str());
+        } else if request.parameter("ignore_unavailable") != Some("true") {
+            return Err(error_response(
+                &MockError::index_not_found(pattern),
+                pattern,
+            ));
+        }
+    }
+    names.sort_unstable();
+    names.dedup();
+    Ok(names)
+}
+
+/// Returns `true` if `name` matches `pattern`, in which `*` matches anything.
+fn wildcard_matches(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        Some((prefix, rest)) => {
+            name.starts_with(prefix)
+                && (0..=name.len() - prefix.len())
+                    .filter(|&i| name.is_char_boundary(prefix.len() + i))
+                    .any(|i| wildcard_matches(rest, &name[prefix.len() + i..]))
+        }
+        None => pattern == name,
+    }
+}
+
+/// Parses the `search::Request` & aggregations of a `_search` or `_count`
+/// request.
+fn search_request(
+    request: &http::Request,
+) -> Result<(search::Request, Vec<aggregation::Request>), Response> {
+    let body = body(request)?;
+    let parse_error = |err: serde_json::Error| {
+        MockError::new(400, "parsing_exception", err.to_string()).into_response("_na_")
+    };
+    let mut search = body;
+    let aggregations = search.as_object_mut().and_then(|search| {
+        search
+            .remove("aggs")
+            .or_else(|| search.remove("aggregations"))
+    });
+    let search = serde_json::from_value(search).map_err(parse_error)?;
+    let aggregations = match aggregations {
+        Some(Value::Object(aggregations)) => aggregations
+            .iter()
+            .map(|(name, aggregation)| serde_json::from_value(json!({ name: aggregation })))
+            .collect::<Result<_, _>>()
+            .map_err(parse_error)?,
+        _ => Vec::new(),
+    };
+    Ok((search, aggregations))
+}
+
+/// A document matching a search request.
+struct Match<'s> {
+    index: &'s str,
+    id: &'s str,
+    document: &'s Document,
+    /// The values the document is sorted by.
+    sort: Vec<Value>,
+}
+
+/// Returns the documents in `indices` matching `query`.
+fn find<'s>(
+    state: &'s State,
+    indices: &[&'s str],
+    query: &search::query::CompoundQuery,
+) -> Result<Vec<Match<'s>>, Response> {
+    let mut matches = Vec::new();
+    for &index in indices {
+        for (id, document) in &state.indices[index].documents {
+            let is_match = eval::matches(query, &document.source)
+                .map_err(|err| bad_request(err.to_string()).into_response(index))?;
+            if is_match {
+                matches.push(Match {
+                    index,
+                    id,
+                    document,
+                    sort: Vec::new(),
+                });
+            }
+        }
+    }
+    Ok(matches)
+}
+
+/// Handles a `_search` request.
+fn search(
+    state: &State,
+    index: Option<&str>,
+    request: &http::Request,
+) -> Result<Response, Response> {
+    let indices = resolve(state, index, request)?;
+    let (search, aggregations) = search_request(request)?;
+    let mut matches = find(state, &indices, &search.query)?;
+
+    let aggregations = if aggregations.is_empty() {
+        None
+    } else {
+        let documents: Vec<Value> = matches.iter().map(|m| m.document.source.clone()).collect();
+        let body = eval::aggregate(&aggregations, &documents)
+            .map_err(|err| bad_request(err.to_string()).into_response(index.unwrap_or("_all")))?;
+        let mut aggregations = body["aggregations"].clone();
+        if request.parameter("typed_keys") != Some("true") {
+            strip_types(&mut aggregations);
+        }
+        Some(aggregations)
+    };
+
+    // the `post_filter` only applies to the hits, not the aggregations
+    if !search.post_filter.is_empty() {
+        let mut filtered = Vec::with_capacity(matches.len());
+        for m in matches {
+            let is_match = eval::matches(&search.post_filter, &m.document.source)
+                .map_err(|err| bad_request(err.to_string()).into_response(m.index))?;
+            if is_match {
+                filtered.push(m);
+            }
+        }
+        matches = filtered;
+    }
+    let total = matches.len();
+
+    let sorted = !search.sort.is_empty();
+    if sorted {
+        let orders: Vec<SortOrder> = search.sort.iter().map(order).collect();
+        for m in &mut matches {
+            m.sort = search
+                .sort
+                .iter()
+                .zip(&orders)
+                .map(|(sort, order)| sort_value(m, sort.field_name(), order))
+                .collect();
+        }
+        matches.sort_by(|a, b| compare_sort_values(&a.sort, &b.sort, &orders));
+        if !search.after.is_empty() {
+            let after: Vec<Value> = search
+                .after
+                .iter()
+                .map(|value| serde_json::to_value(value).unwrap_or_default())
+                .collect();
+            matches.retain(|m| compare_sort_values(&m.sort, &after, &orders) == Ordering::Greater);
+        }
+    }
+
+    let from = search.from.unwrap_or(0) as usize;
+    let size = search.size.unwrap_or(10) as usize;
+    let with_source = !matches!(&search.source, Some(source) if !source.enabled);
+    let hits: Vec<Value> = matches
+        .iter()
+        .skip(from)
+        .take(size)
+        .map(|m| {
+            let mut hit = json!({
+                "_index": m.index,
+                "_type": "_doc",
+                "_id": m.id,
+                "_score": if sorted { Value::Null } else { json!(1.0) },
+                "_source": m.document.source,
+            });
+            if let (false, Some(hit)) = (with_source, hit.as_object_mut()) {
+                hit.remove("_source");
+            }
+            if search.version {
+                hit["_version"] = m.document.version.into();
+            }
+            if search.seq_no_primary_term {
+                hit["_seq_no"] = m.document.sequence_number.into();
+                hit["_primary_term"] = 1.into();
+            }
+            if sorted {
+                hit["sort"] = m.sort.clone().into();
+            }
+            hit
+        })
+        .collect();
+
+    let max_score = if sorted || hits.is_empty() {
+        Value::Null
+    } else {
+        json!(1.0)
+    };
+    let mut response = json!({
+        "took": 1,
+        "timed_out": false,
+        "_shards": shards(),
+        "hits": {
+            "total": { "value": total, "relation": "eq" },
+            "max_score": max_score,
+            "hits": hits,
+        },
+    });
+    if let Some(aggregations) = aggregations {
+        response["aggregations"] = aggregations;
+    }
+    Ok((200, response))
+}
+
+/// Removes the types from the (typed) names of aggregations, e.g. `avg#age`
+/// becomes `age`, as Elasticsearch only includes them with `typed_keys`.
+fn strip_types(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            let entries: Map<String, Value> = std::mem::take(object);
+            for (key, mut value) in entries {
+                strip_types(&mut value);
+                let key = match key.split_once('#') {
+                    Some((_, name)) => name.to_string(),
+                    None => key,
+                };
+                object.insert(key, value);
+            }
+        }
+        Value::Array(array) => array.iter_mut().for_each(strip_types),
+        _ => {}
+    }
+}
+
+/// The order of a sort, which is descending for `_score` by default.
+fn order(sort: &search::Sort) -> SortOrder {
+    match (sort.sort_order(), sort.field_name()) {
+        (Some(order), _) => *order,
+        (None, "_score") | (None, "score") => SortOrder::Desc,
+        (None, _) => SortOrder::Asc,
+    }
+}
+
+/// The value a document is sorted by, i.e. the lowest value of `field` when
+/// sorting in ascending order & the highest otherwise.
+fn sort_value(m: &Match, field: &str, order: &SortOrder) -> Value {
+    match field {
+        "_score" | "score" => return json!(1.0),
+        "_id" => return m.id.into(),
+        "_doc" => return m.document.sequence_number.into(),
+        _ => {}
+    }
+    let values = eval::field_values(&m.document.source, field);
+    let value = match order {
+        SortOrder::Asc => values.into_iter().min_by(|a, b| compare_values(a, b)),
+        SortOrder::Desc => values.into_iter().max_by(|a, b| compare_values(a, b)),
+    };
+    value.cloned().unwrap_or(Value::Null)
+}
+
+/// Compares two sort values, numerically if both are numbers.
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+/// Compares the sort values of two documents, where missing (i.e. `null`)
+/// values are always sorted last.
+fn compare_sort_values(a: &[Value], b: &[Value], orders: &[SortOrder]) -> Ordering {
+    for ((a, b), order) in a.iter().zip(b).zip(orders) {
+        let ordering = match (a.is_null(), b.is_null()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => match order {
+                SortOrder::Asc => compare_values(a, b),
+                SortOrder::Desc => compare_values(b, a),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Handles a `_count` request.
+fn count(
+    state: &State,
+    index: Option<&str>,
+    request: &http::Request,
+) -> Result<Response, Response> {
+    let indices = resolve(state, index, request)?;
+    let (search, _) = search_request(request)?;
+    let count = find(state, &indices, &search.query)?.len();
+    Ok((200, json!({ "count": count, "_shards": shards() })))
+}
+
+/// The response of a document write.
+fn write_response(index: &str, id: &str, write: &Write) -> Value {
+    json!({
+        "_index": index,
+        "_type": "_doc",
+        "_id": id,
+        "_version": write.version,
+        "result": write.result,
+        "_shards": { "total": 1, "successful": 1, "failed": 0 },
+        "_seq_no": write.sequence_number,
+        "_primary_term": 1,
+    })
+}
+
+/// The error returned when creating a document that already exists.
+fn version_conflict(id: &str, document: &Document) -> MockError {
+    MockError::new(
+        409,
+        "version_conflict_engine_exception",
+        format!(
+            "[{}]: version conflict, document already exists (current version [{}])",
+            id, document.version
+        ),
+    )
+}
+
+/// Handles a `_doc` or `_create` request that indexes a document.
+fn put(
+    state: &mut State,
+    request: &http::Request,
+    index: &str,
+    id: &str,
+    create: bool,
+) -> Result<Response, Response> {
+    let source = body(request)?;
+    let documents = state.indices.entry(index.to_string()).or_default();
+    if create {
+        if let Some(document) = documents.get(id) {
+            return Err(version_conflict(id, document).into_response(index));
+        }
+    }
+    let write = documents.put(id, source);
+    Ok((write.status, write_response(index, id, &write)))
+}
+
+/// The response of getting a document.
+fn get_response(state: &State, index: &str, id: &str) -> Result<Value, MockError> {
+    let documents = state
+        .indices
+        .get(index)
+        .ok_or_else(|| MockError::index_not_found(index))?;
+    Ok(match documents.get(id) {
+        Some(document) => json!({
+            "_index": index,
+            "_type": "_doc",
+            "_id": id,
+            "_version": document.version,
+            "_seq_no": document.sequence_number,
+            "_primary_term": 1,
+            "found": true,
+            "_source": document.source,
+        }),
+        None => json!({ "_index": index, "_type": "_doc", "_id": id, "found": false }),
+    })
+}
+
+/// Handles a `_doc` request that gets a document.
+fn get(state: &State, index: &str, id: &str) -> Result<Response, Response> {
+    let document = get_response(state, index, id).map_err(|err| err.into_response(index))?;
+    let status = if document["found"] == true { 200 } else { 404 };
+    Ok((status, document))
+}
+
+/// Handles a `_doc` request that deletes a document.
+fn delete(state: &mut State, index: &str, id: &str) -> Result<Response, Response> {
+    let documents = state
+        .indices
+        .get_mut(index)
+        .ok_or_else(|| MockError::index_not_found(index).into_response(index))?;
+    let write = documents.delete(id);
+    Ok((write.status, write_response(index, id, &write)))
+}
+
+/// Handles a `_mget` request.
+fn mget(state: &State, index: Option<&str>, request: &http::Request) -> Result<Response, Response> {
+    let body = body(request)?;
+    let mut ids: Vec<(Option<&str>, &str)> = Vec::new();
+    if let Some(Value::Array(docs)) = body.get("docs") {
+        for doc in docs {
+            let id = doc["_id"].as_str().ok_or_else(|| {
+                bad_request("Validation Failed: 1: id is missing;").into_response("_na_")
+            })?;
+            ids.push((doc["_index"].as_str().or(index), id));
+        }
+    }
+    if let Some(Value::Array(doc_ids)) = body.get("ids") {
+        ids.extend(
+            doc_ids
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|id| (index, id)),
+        );
+    }
+
+    let mut docs = Vec::new();
+    for (doc_index, id) in ids {
+        let doc_index = doc_index.ok_or_else(|| {
+            bad_request("Validation Failed: 1: index is missing;").into_response("_na_")
+        })?;
+        docs.push(get_response(state, doc_index, id).unwrap_or_else(|err| {
+            json!({ "_index": doc_index, "_type": "_doc", "_id": id, "error": err.details(doc_index) })
+        }));
+    }
+    Ok((200, json!({ "docs": docs })))
+}
+
+/// Handles a `_bulk` request.
+fn bulk(
+    state: &mut State,
+    index: Option<&str>,
+    request: &http::Request,
+) -> Result<Response, Response> {
+    let body = String::from_utf8_lossy(&request.body);
+    let mut lines = body.lines().filter(|line| !line.trim().is_empty());
+    let parse = |line: &str| {
+        serde_json::from_str::<Value>(line).map_err(|err| {
+            MockError::new(400, "parsing_exception", err.to_string()).into_response("_na_")
+        })
+    };
+
+    let mut items = Vec::new();
+    let mut errors = false;
+    while let Some(line) = lines.next() {
+        let action = parse(line)?;
+        let (op, metadata) = match action.as_object().and_then(|action| action.iter().next()) {
+            Some((op, metadata))
+                if ["index", "create", "update", "delete"].contains(&op.as_str()) =>
+            {
+                (op.clone(), metadata.clone())
+            }
+            _ => {
+                let reason = format!("Malformed action/metadata line [{}]", items.len() + 1);
+                return Err(bad_request(reason).into_response("_na_"));
+            }
+        };
+        let source = if op == "delete" {
+            Value::Null
+        } else {
+            let line = lines.next().ok_or_else(|| {
+                bad_request("The bulk request must be terminated by a newline [\\n]")
+                    .into_response("_na_")
+            })?;
+            parse(line)?
+        };
+
+        let doc_index = metadata["_index"]
+            .as_str()
+            .or(index)
+            .unwrap_or("_na_")
+            .to_string();
+        let id = metadata["_id"].as_str().map(str::to_string);
+        let result = bulk_item(state, &op, &doc_index, id, source);
+        let item = match result {
+            Ok((id, write)) => {
+                let mut item = write_response(&doc_index, &id, &write);
+                item["status"] = write.status.into();
+                item
+            }
+            Err((id, error)) => {
+                errors = true;
+                json!({
+                "_index": doc_index,
+                "_type": "_doc",
+                "_id": id,
+                "status": error.status,
+                "error": error.details(&doc_index),
+                })
+            }
+        };
+        items.push(json!({ op: item }));
+    }
+
+    Ok((200, json!({ "took": 1, "errors": errors, "items": items })))
+}
+
+/// Performs a single `_bulk` operation, returning the ID of the document.
+fn bulk_item(
+    state: &mut State,
+    op: &str,
+    index: &str,
+    id: Option<String>,
+    source: Value,
+) -> Result<(String, Write), (Option<String>, MockError)> {
+    if let Some(error) = state.bulk_item_failures.pop_front() {
+        return Err((id, error));
+    }
+    if index == "_na_" {
+        return Err((id, bad_request("Validation Failed: 1: index is missing;")));
+    }
+
+    let documents = state.indices.entry(index.to_string()).or_default();
+    let id = match (id, op) {
+        (Some(id), _) => id,
+        (None, "index") | (None, "create") => documents.generate_id(),
+        (None, _) => return Err((None, bad_request("Validation Failed: 1: id is missing;"))),
+    };
+
+    match op {
+        "create" => match documents.get(&id) {
+            Some(document) => {
+                let error = version_conflict(&id, document);
+                Err((Some(id), error))
+            }
+            None => Ok((id.clone(), documents.put(&id, source))),
+        },
+        "update" => {
+            let updated = match (documents.get(&id), &source["doc"]) {
+                (Some(document), Value::Object(doc)) => {
+                    let mut updated = document.source.clone();
+                    merge(&mut updated, doc);
+                    if updated == document.source {
+                        let write = Write {
+                            result: "noop",
+                            status: 200,
+                            version: document.version,
+                            sequence_number: document.sequence_number,
+                        };
+                        return Ok((id, write));
+                    }
+                    updated
+                }
+                (None, doc) if source["doc_as_upsert"] == true => doc.clone(),
+                (None, _) if source["upsert"].is_object() => source["upsert"].clone(),
+                (Some(_), _) => {
+                    return Err((
+                        Some(id),
+                        bad_request("Validation Failed: 1: script or doc is missing;"),
+                    ))
+                }
+                (None, _) => {
+                    let reason = format!("[_doc][{}]: document missing", id);
+                    let error = MockError::new(404, "document_missing_exception", reason);
+                    return Err((Some(id), error));
+                }
+            };
+            Ok((id.clone(), documents.put(&id, updated)))
+        }
+        "delete" => Ok((id.clone(), documents.delete(&id))),
+        _ => Ok((id.clone(), documents.put(&id, source))),
+    }
+}
+
+/// Merges `doc` into `document` (recursively), like partial updates do.
+fn merge(document: &mut Value, doc: &Map<String, Value>) {
+    let document = match document {
+        Value::Object(document) => document,
+        document => {
+            *document = Value::Object(doc.clone());
+            return;
+        }
+    };
+    for (key, value) in doc {
+        match (document.get_mut(key), value) {
+            (Some(existing @ Value::Object(_)), Value::Object(value)) => merge(existing, value),
+            _ => {
+                document.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_match_wildcards() {
+        assert!(wildcard_matches("logs-*", "logs-2020"));
+        assert!(wildcard_matches("*-2020", "logs-2020"));
+        assert!(wildcard_matches("l*s-*0", "logs-2020"));
+        assert!(!wildcard_matches("logs-*", "metrics-2020"));
+        assert!(!wildcard_matches("logs", "logs-2020"));
+    }
+
+    #[test]
+    fn can_merge_partial_documents() {
+        let mut document =
+            json!({ "name": "jane", "address": { "city": "Paris", "zip": "75001" } });
+        let doc = json!({ "address": { "city": "Lyon" }, "age": 28 });
+        merge(&mut document, doc.as_object().unwrap());
+        assert_eq!(
+            document,
+            json!({ "name": "jane", "age": 28, "address": { "city": "Lyon", "zip": "75001" } })
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! Just enough HTTP/1.1 to serve requests from Elasticsearch clients.
+
+use std::io::{self, BufRead, Write};
+
+/// An HTTP request.
+#[derive(Debug)]
+pub(super) struct Request {
+    /// The (uppercase) method, e.g. `GET`.
+    pub(super) method: String,
+
+    /// The (percent-decoded) segments of the path, e.g. `["users", "_search"]`.
+    pub(super) segments: Vec<String>,
+
+    /// The (percent-decoded) query string parameters.
+    pub(super) parameters: Vec<(String, String)>,
+
+    /// The request body.
+    pub(super) body: Vec<u8>,
+
+    /// Whether the client asked to close the connection after this request.
+    pub(super) close: bool,
+}
+
+impl Request {
+    /// Returns the value of the query string parameter `name`, if any.
+    pub(super) fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Reads the next request from `reader`, returning `None` once the client
+/// closed the connection.
+pub(super) fn read_request(reader: &mut impl BufRead) -> io::Result<Option<Request>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method.to_uppercase(), target.to_string()),
+        _ => return Err(invalid_data("invalid request line")),
+    };
+
+    let mut content_length = 0;
+    let mut chunked = false;
+    let mut close = false;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+            match name.trim().to_lowercase().as_str() {
+                "content-length" => {
+                    content_length = value
+                        .parse()
+                        .map_err(|_| invalid_data("invalid content-length"))?
+                }
+                "transfer-encoding" => chunked = value.eq_ignore_ascii_case("chunked"),
+                "connection" => close = value.eq_ignore_ascii_case("close"),
+                _ => {}
+            }
+        }
+    }
+
+    let body = if chunked {
+        read_chunked(reader)?
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        body
+    };
+
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    let segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| decode(segment, false))
+        .collect();
+    let parameters = query
+        .split('&')
+        .filter(|parameter| !parameter.is_empty())
+        .map(|parameter| {
+            let (key, value) = parameter.split_once('=').unwrap_or((parameter, ""));
+            (decode(key, true), decode(value, true))
+        })
+        .collect();
+
+    Ok(Some(Request {
+        method,
+        segments,
+        parameters,
+        body,
+        close,
+    }))
+}
+
+/// Reads a body sent with the `chunked` transfer encoding.
+fn read_chunked(reader: &mut impl BufRead) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let size = line.trim().split(';').next().unwrap_or("");
+        let size = usize::from_str_radix(size, 16).map_err(|_| invalid_data("invalid chunk"))?;
+        if size == 0 {
+            // skip any trailers
+            loop {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+                    return Ok(body);
+                }
+            }
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        line.clear();
+        reader.read_line(&mut line)?;
+    }
+}
+
+/// Writes a JSON response with the `status` code.
+pub(super) fn write_response(writer: &mut impl Write, status: u16, body: &[u8]) -> io::Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\ncontent-type: application/json; charset=UTF-8\r\ncontent-length: {}\r\n\r\n",
+        status,
+        reason(status),
+        body.len()
+    )?;
+    writer.write_all(body)?;
+    writer.flush()
+}
+
+/// The reason phrase of a status code.
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+/// Decodes a percent-encoded URL component, in which `+` is a space if it's
+/// part of the query string.
+fn decode(component: &str, is_query: bool) -> String {
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => match component
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 3;
+                    continue;
+                }
+                None => decoded.push(b'%'),
+            },
+            b'+' if is_query => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Creates an `InvalidData` error.
+#[inline]
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_read_requests() {
+        let raw = "POST /users%2Cposts/_search?allow_no_indices=true&q=a+b HTTP/1.1\r\n\
+                   Host: localhost\r\nContent-Length: 2\r\n\r\n{}\
+                   GET / HTTP/1.1\r\nConnection: close\r\nTransfer-Encoding: chunked\r\n\r\n\
+                   3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n";
+        let mut reader = io::BufReader::new(raw.as_bytes());
+
+        let request = read_request(&mut reader).unwrap().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.segments, vec!["users,posts", "_search"]);
+        assert_eq!(request.parameter("allow_no_indices"), Some("true"));
+        assert_eq!(request.parameter("q"), Some("a b"));
+        assert_eq!(request.body, b"{}");
+        assert!(!request.close);
+
+        let request = read_request(&mut reader).unwrap().unwrap();
+        assert!(request.segments.is_empty());
+        assert_eq!(request.body, b"abcde");
+        assert!(request.close);
+
+        assert!(read_request(&mut reader).unwrap().is_none());
+    }
+}
@@ -0,0 +1,714 @@
+//! A mock Elasticsearch server for integration tests.
+//!
+//! A [`MockServer`] listens on a local TCP port and serves the `_search`,
+//! `_count`, `_bulk`, `_doc` & `_mget` APIs from in-memory indices, so code
+//! using an Elasticsearch client can be tested without running Elasticsearch:
+//!
+//! ```
+//! use elasticsearch::{
+//!     http::transport::Transport, params::Refresh, BulkOperation, BulkParts, Elasticsearch,
+//!     SearchParts,
+//! };
+//! use elastiql::{
+//!     mock::MockServer,
+//!     search::{query::TermQuery, Request, Response},
+//! };
+//! use serde_json::{json, Value};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let server = MockServer::start()?;
+//! let client = Elasticsearch::new(Transport::single_node(&server.url())?);
+//!
+//! let users: Vec<BulkOperation<Value>> = vec![
+//!     BulkOperation::index(json!({ "name": "kimchy" })).id("1").into(),
+//!     BulkOperation::index(json!({ "name": "jane" })).id("2").into(),
+//! ];
+//! client
+//!     .bulk(BulkParts::Index("users"))
+//!     .body(users)
+//!     .refresh(Refresh::True)
+//!     .send()
+//!     .await?
+//!     .error_for_status_code()?;
+//!
+//! let request = Request::builder().query(TermQuery::new("name", "jane")).build();
+//! let response: Response<Value> = client
+//!     .search(SearchParts::Index(&["users"]))
+//!     .body(request)
+//!     .send()
+//!     .await?
+//!     .json()
+//!     .await?;
+//!
+//! match response {
+//!     Response::Ok(results) => assert_eq!(results.hits.hits[0].id, "2"),
+//!     Response::Err { error, .. } => panic!("{}", error.reason),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Queries & aggregations are evaluated by the [`eval`](crate::eval) module,
+//! with the same limitations. Every hit has a `_score` of `1.0`, so hits are
+//! ordered by `_id` unless the request has a `sort`, and every write is
+//! visible immediately, as if it used `refresh=true`.
+//!
+//! Error responses can be injected with [`MockServer::fail_next`] and
+//! [`MockServer::fail_next_bulk_item`], e.g. to test how a client handles a
+//! [`search::Response::Err`] or a [`bulk::Item::Err`].
+//!
+//! [`search::Response::Err`]: crate::search::Response::Err
+//! [`bulk::Item::Err`]: crate::bulk::Item::Err
+
+use std::{
+    io::{self, BufReader},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread,
+};
+
+use serde_json::{json, Value};
+
+use self::api::State;
+
+mod api;
+mod http;
+
+/// A mock Elasticsearch server, which stops once it's dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    /// The local address the server listens on.
+    address: SocketAddr,
+
+    /// The indices & injected errors.
+    state: Arc<Mutex<State>>,
+
+    /// Set once the server is dropped, to stop accepting connections.
+    shutdown: Arc<AtomicBool>,
+
+    /// The thread accepting connections.
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Starts a new server, without any indices, on a random local port.
+    pub fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0))?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let state = Arc::clone(&state);
+            let shutdown = Arc::clone(&shutdown);
+            thread::Builder::new()
+                .name("elastiql-mock-server".to_string())
+                .spawn(move || {
+                    for stream in listener.incoming() {
+                        if shutdown.load(Ordering::SeqCst) {
+                            break;
+                        }
+                        if let Ok(stream) = stream {
+                            let state = Arc::clone(&state);
+                            // the client is gone if this fails, so there is no one to tell
+                            thread::spawn(move || serve(stream, &state).ok());
+                        }
+                    }
+                })?
+        };
+
+        Ok(MockServer {
+            address,
+            state,
+            shutdown,
+            thread: Some(thread),
+        })
+    }
+
+    /// The local address the server listens on.
+    #[inline]
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// The URL of the server, e.g. `http://127.0.0.1:9200`.
+    #[inline]
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Creates an empty index, if it doesn't exist yet.
+    pub fn create_index(&self, index: &str) {
+        self.state().indices.entry(index.to_string()).or_default();
+    }
+
+    /// Indexes (i.e. creates or replaces) a document, creating the index if
+    /// it doesn't exist yet.
+    pub fn index(&self, index: &str, id: &str, document: Value) {
+        self.state()
+            .indices
+            .entry(index.to_string())
+            .or_default()
+            .put(id, document);
+    }
+
+    /// Gets the source of a document, if it exists.
+    pub fn document(&self, index: &str, id: &str) -> Option<Value> {
+        self.state()
+            .indices
+            .get(index)?
+            .get(id)
+            .map(|document| document.source.clone())
+    }
+
+    /// Makes the next request to `endpoint` fail with `error`.
+    ///
+    /// Errors are returned in the order they were injected in.
+    pub fn fail_next(&self, endpoint: Endpoint, error: MockError) {
+        self.state().failures.push((endpoint, error));
+    }
+
+    /// Makes the next operation of a `_bulk` request fail with `error`,
+    /// without failing the other operations.
+    ///
+    /// Errors are returned in the order they were injected in.
+    pub fn fail_next_bulk_item(&self, error: MockError) {
+        self.state().bulk_item_failures.push_back(error);
+    }
+
+    /// Responds to `_search` requests with the recorded response of a
+    /// matching fixture (if there is one) instead of searching the indices.
+    #[cfg(feature = "fixtures")]
+    pub fn replay(&self, fixtures: crate::fixtures::Fixtures) {
+        self.state().fixtures = Some(fixtures);
+    }
+
+    /// Locks the state, ignoring panics of other threads holding the lock.
+    #[inline]
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // wakes up the thread blocked on accepting the next connection
+        if TcpStream::connect(self.address).is_ok() {
+            if let Some(thread) = self.thread.take() {
+                thread.join().ok();
+            }
+        }
+    }
+}
+
+/// Serves the requests of a single connection.
+fn serve(stream: TcpStream, state: &Mutex<State>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    while let Some(request) = http::read_request(&mut reader)? {
+        let (status, body) = {
+            let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+            api::handle(&mut state, &request)
+        };
+        let body = if request.method == "HEAD" {
+            Vec::new()
+        } else {
+            serde_json::to_vec(&body)?
+        };
+        http::write_response(&mut writer, status, &body)?;
+        if request.close {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// An API of the [`MockServer`] which errors can be injected into.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Endpoint {
+    /// The [`_search`] API.
+    ///
+    /// [`_search`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-search.html
+    Search,
+
+    /// The [`_count`] API.
+    ///
+    /// [`_count`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-count.html
+    Count,
+
+    /// The [`_bulk`] API, failing the request as a whole.
+    ///
+    /// [`_bulk`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/docs-bulk.html
+    Bulk,
+
+    /// The single document APIs, i.e. [`_doc`] & `_create`.
+    ///
+    /// [`_doc`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/docs-index_.html
+    Doc,
+
+    /// The [`_mget`] API.
+    ///
+    /// [`_mget`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/docs-multi-get.html
+    Mget,
+}
+
+/// An error returned by the [`MockServer`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MockError {
+    /// The [HTTP status code](https://developer.mozilla.org/en-US/docs/Web/HTTP/Status).
+    pub status: u16,
+
+    /// The error type, e.g. `index_not_found_exception`.
+    pub ty: String,
+
+    /// The reason/message for this error.
+    pub reason: String,
+
+    /// The name of the relevant index, the index of the request by default.
+    pub index: Option<String>,
+}
+
+impl MockError {
+    /// Creates a new `MockError`.
+    #[inline]
+    pub fn new(status: u16, ty: impl Into<String>, reason: impl Into<String>) -> Self {
+        MockError {
+            status,
+            ty: ty.into(),
+            reason: reason.into(),
+            index: None,
+        }
+    }
+
+    /// The error Elasticsearch returns for an `index` that doesn't exist.
+    #[inline]
+    pub fn index_not_found(index: impl Into<String>) -> Self {
+        let index = index.into();
+        MockError {
+            reason: format!("no such index [{}]", index),
+            index: Some(index),
+            ..MockError::new(404, "index_not_found_exception", "")
+        }
+    }
+
+    /// The error Elasticsearch returns when it's overloaded, i.e. `429 Too
+    /// Many Requests`.
+    #[inline]
+    pub fn too_many_requests() -> Self {
+        MockError::new(
+            429,
+            "es_rejected_execution_exception",
+            "rejected execution of coordinating operation",
+        )
+    }
+
+    /// The details of this error, using `index` if it has none itself.
+    fn details(&self, index: &str) -> Value {
+        let index = self.index.as_deref().unwrap_or(index);
+        let cause = json!({
+            "type": self.ty,
+            "reason": self.reason,
+            "index": index,
+            "index_uuid": "_na_",
+        });
+        let mut details = cause.clone();
+        details["root_cause"] = json!([cause]);
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use elasticsearch::{
+        http::transport::Transport, BulkOperation, BulkParts, CountParts, Elasticsearch, GetParts,
+        IndexParts, MgetParts, SearchParts,
+    };
+    use serde::Deserialize;
+
+    use crate::{
+        aggregation::{self, types::InnerAggregation},
+        bulk,
+        search::{self, Sort},
+    };
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct User {
+        name: String,
+        age: u64,
+    }
+
+    fn client(server: &MockServer) -> Elasticsearch {
+        Elasticsearch::new(Transport::single_node(&server.url()).unwrap())
+    }
+
+    fn users() -> MockServer {
+        let server = MockServer::start().unwrap();
+        server.index("users", "1", json!({ "name": "kimchy", "age": 42 }));
+        server.index("users", "2", json!({ "name": "jane", "age": 27 }));
+        server.index("users", "3", json!({ "name": "john", "age": 35 }));
+        server
+    }
+
+    #[tokio::test]
+    async fn can_search() {
+        let server = users();
+        let request = json!({
+            "query": { "range": { "age": { "gte": "30" } } },
+            "sort": [{ "age": "desc" }],
+            "size": 1,
+            "version": true,
+            "aggs": { "avg_age": { "avg": { "field": "age" } } },
+        });
+        let response = client(&server)
+            .search(SearchParts::Index(&["users"]))
+            .typed_keys(true)
+            .body(request)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status_code().as_u16(), 200);
+        let body: Value = response.json().await.unwrap();
+
+        let results = match search::Response::<User>::deserialize(&body).unwrap() {
+            search::Response::Ok(results) => results,
+            search::Response::Err { error, .. } => panic!("{:?}", error),
+        };
+        assert_eq!(results.hits.total_count.value, 2);
+        let hit = &results.hits.hits[0];
+        assert_eq!((hit.id.as_str(), hit.version), ("1", Some(1)));
+        assert_eq!(hit.sort, vec![json!(42)]);
+        assert_eq!(results.hits.hits.len(), 1);
+
+        let aggregations = aggregation::Response::deserialize(&body).unwrap();
+        assert_eq!(aggregations.aggregations[0].values, vec![38.5]);
+    }
+
+    #[tokio::test]
+    async fn post_filter_only_applies_to_hits() {
+        let server = users();
+        let request = json!({
+            "post_filter": { "term": { "name": "jane" } },
+            "aggs": { "avg_age": { "avg": { "field": "age" } } },
+        });
+        let body: Value = client(&server)
+            .search(SearchParts::Index(&["users"]))
+            .typed_keys(true)
+            .body(request)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+
+        let results = match search::Response::<User>::deserialize(&body).unwrap() {
+            search::Response::Ok(results) => results,
+            search::Response::Err { error, .. } => panic!("{:?}", error),
+        };
+        assert_eq!(results.hits.total_count.value, 1);
+        assert_eq!(results.hits.hits[0].id, "2");
+
+        let aggregations = aggregation::Response::deserialize(&body).unwrap();
+        assert_eq!(aggregations.aggregations[0].values, vec![104.0 / 3.0]);
+    }
+
+    #[tokio::test]
+    async fn can_search_after() {
+        let server = users();
+        let request = search::Request::builder()
+            .sort(vec![serde_json::from_value::<Sort>(json!("age")).unwrap()])
+            .after(vec![serde_json::from_value(json!(27)).unwrap()])
+            .build();
+        let results: search::Response<User> = client(&server)
+            .search(SearchParts::None)
+            .body(request)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let ids: Vec<_> = match &results {
+            search::Response::Ok(results) => results
+                .hits
+                .hits
+                .iter()
+                .map(|hit| hit.id.as_str())
+                .collect(),
+            search::Response::Err { error, .. } => panic!("{:?}", error),
+        };
+        assert_eq!(ids, vec!["3", "1"]);
+    }
+
+    #[tokio::test]
+    async fn can_page_without_source() {
+        let server = users();
+        let request = search::Request::builder()
+            .sort(vec![Sort::new("age")])
+            .from(Some(1))
+            .source(Some(search::SourceFilter::disabled()))
+            .build();
+        let results: search::Response<User> = client(&server)
+            .search(SearchParts::None)
+            .body(request)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let hits: Vec<_> = match &results {
+            search::Response::Ok(results) => results
+                .hits
+                .hits
+                .iter()
+                .map(|hit| (hit.id.as_str(), hit.source.is_none()))
+                .collect(),
+            search::Response::Err { error, .. } => panic!("{:?}", error),
+        };
+        assert_eq!(hits, vec![("3", true), ("1", true)]);
+    }
+
+    #[tokio::test]
+    async fn can_count_get_and_index() {
+        let server = users();
+        let client = client(&server);
+
+        let count: Value = client
+            .count(CountParts::Index(&["users"]))
+            .body(json!({ "query": { "term": { "name": "jane" } } }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(count["count"], 1);
+
+        let response = client
+            .index(IndexParts::IndexId("users", "4"))
+            .body(json!({ "name": "anna", "age": 19 }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status_code().as_u16(), 201);
+
+        let document: Value = client
+            .get(GetParts::IndexId("users", "4"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(document["_source"], json!({ "name": "anna", "age": 19 }));
+
+        let response = client
+            .get(GetParts::IndexId("users", "5"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status_code().as_u16(), 404);
+
+        let documents: Value = client
+            .mget(MgetParts::Index("users"))
+            .body(json!({ "ids": ["4", "5"] }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(documents["docs"][0]["found"], true);
+        assert_eq!(documents["docs"][1]["found"], false);
+    }
+
+    #[tokio::test]
+    async fn can_bulk() {
+        let server = users();
+        let operations: Vec<BulkOperation<Value>> = vec![
+            BulkOperation::create("2", json!({ "name": "jane", "age": 28 })).into(),
+            BulkOperation::update("2", json!({ "doc": { "age": 28 } })).into(),
+            BulkOperation::delete("3").into(),
+            BulkOperation::index(json!({ "name": "anna", "age": 19 })).into(),
+        ];
+        let response: bulk::Response<Value> = client(&server)
+            .bulk(BulkParts::Index("users"))
+            .body(operations)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+
+        assert!(response.errors);
+        assert!(matches!(
+            &response.items[0],
+            bulk::Action::Create(bulk::Item::Err { status: 409, .. })
+        ));
+        assert!(matches!(
+            &response.items[1],
+            bulk::Action::Update(bulk::Item::Ok(_))
+        ));
+        assert!(matches!(
+            &response.items[2],
+            bulk::Action::Delete(bulk::Item::Ok(_))
+        ));
+        let id = match &response.items[3] {
+            bulk::Action::Index(bulk::Item::Ok(item)) => item.id.clone(),
+            item => panic!("{:?}", item),
+        };
+
+        assert_eq!(
+            server.document("users", "2"),
+            Some(json!({ "name": "jane", "age": 28 }))
+        );
+        assert_eq!(server.document("users", "3"), None);
+        assert_eq!(
+            server.document("users", &id),
+            Some(json!({ "name": "anna", "age": 19 }))
+        );
+    }
+
+    #[tokio::test]
+    async fn can_inject_errors() {
+        let server = users();
+        let client = client(&server);
+
+        let search = |indices: &'static [&'static str]| {
+            client
+                .search(SearchParts::Index(indices))
+                .body(json!({}))
+                .send()
+        };
+
+        let response = search(&["posts"]).await.unwrap();
+        assert_eq!(response.status_code().as_u16(), 404);
+        match response.json::<search::Response<User>>().await.unwrap() {
+            search::Response::Err { error, status } => {
+                assert_eq!(status, 404);
+                assert_eq!(error.ty, "index_not_found_exception");
+                assert_eq!(error.index, "posts");
+            }
+            search::Response::Ok(_) => panic!("expected an error"),
+        }
+
+        server.fail_next(Endpoint::Search, MockError::too_many_requests());
+        let response = search(&["users"]).await.unwrap();
+        assert_eq!(response.status_code().as_u16(), 429);
+        assert!(matches!(
+            response.json::<search::Response<User>>().await.unwrap(),
+            search::Response::Err { status: 429, .. }
+        ));
+        // only the next request fails
+        assert_eq!(
+            search(&["users"]).await.unwrap().status_code().as_u16(),
+            200
+        );
+
+        server.fail_next_bulk_item(MockError::too_many_requests());
+        let operations: Vec<BulkOperation<Value>> = vec![
+            BulkOperation::index(json!({ "name": "anna", "age": 19 }))
+                .id("4")
+                .into(),
+            BulkOperation::index(json!({ "name": "otto", "age": 52 }))
+                .id("5")
+                .into(),
+        ];
+        let response: bulk::Response<User> = client
+            .bulk(BulkParts::Index("users"))
+            .body(operations)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert!(response.errors);
+        match &response.items[0] {
+            bulk::Action::Index(bulk::Item::Err { error, status }) => {
+                assert_eq!(*status, 429);
+                assert_eq!(error.ty, "es_rejected_execution_exception");
+                assert_eq!(error.index, "users");
+            }
+            item => panic!("{:?}", item),
+        }
+        assert!(matches!(
+            &response.items[1],
+            bulk::Action::Index(bulk::Item::Ok(_))
+        ));
+        assert_eq!(server.document("users", "4"), None);
+    }
+
+    #[cfg(feature = "fixtures")]
+    #[tokio::test]
+    async fn can_replay_fixtures() {
+        let server = users();
+        server.replay(crate::fixtures::Fixtures::replay(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures"
+        )));
+        let client = client(&server);
+
+        // the recorded response, even though there are no `tags`
+        let request = json!({
+            "aggs": { "per_tag": {
+                "terms": { "field": "tags" },
+                "aggs": { "avg_price": { "avg": { "field": "price" } } },
+            } },
+            "size": 0.0,
+        });
+        let body: Value = client
+            .search(SearchParts::Index(&["users"]))
+            .body(request)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["hits"]["total"]["value"], 3);
+
+        // searches the index without a fixture
+        let body: Value = client
+            .search(SearchParts::Index(&["users"]))
+            .body(json!({ "size": 0 }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["hits"]["total"]["value"], 3);
+        assert!(body.get("aggregations").is_none());
+    }
+
+    #[test]
+    fn metrics_need_a_field() {
+        let server = MockServer::start().unwrap();
+        server.create_index("users");
+        let aggregation = aggregation::Request::builder()
+            .name("avg")
+            .avg(Some(InnerAggregation::builder().build()))
+            .build();
+        let mut state = server.state();
+        let request = http::Request {
+            method: "POST".to_string(),
+            segments: vec!["users".to_string(), "_search".to_string()],
+            parameters: Vec::new(),
+            body: serde_json::to_vec(&json!({ "aggs": aggregation })).unwrap(),
+            close: false,
+        };
+        let (status, body) = api::handle(&mut state, &request);
+        assert_eq!(status, 400);
+        assert_eq!(body["error"]["type"], "illegal_argument_exception");
+    }
+}
@@ -0,0 +1,859 @@
+//! Field-level access control for requests built from untrusted input.
+//!
+//! GraphQL inputs such as [`RequestInput`] let clients reference *any* field
+//! in the index. An [`AccessPolicy`] restricts which fields may be used for
+//! each kind of operation, and whether [scripts] may be used at all, so that
+//! sensitive fields cannot be probed by filtering, sorting, aggregating or
+//! highlighting on them.
+//!
+//! Policies are checked against the *converted* [`search::Request`] and
+//! [`aggregation::Request`] types, i.e. after any `*Input` has been turned into
+//! its output type, and report *every* offending clause along with its path in
+//! the request body.
+//!
+//! ```
+//! use elastiql::policy::{AccessPolicy, Operation};
+//! use elastiql::search::query::{CompoundQuery, TermQuery};
+//!
+//! let policy = AccessPolicy {
+//!     filter: Some(vec!["status".to_string(), "comments.*".to_string()]),
+//!     ..AccessPolicy::default()
+//! };
+//!
+//! let mut query = CompoundQuery::default();
+//! query.push(TermQuery::new("ssn", "123-45-6789"));
+//!
+//! let violations = policy.check_query(&query).unwrap_err();
+//! assert_eq!(violations[0].path, "query.bool.filter[0].term");
+//! assert_eq!(violations[0].field.as_deref(), Some("ssn"));
+//! assert_eq!(violations[0].operation, Some(Operation::Filter));
+//! ```
+//!
+//! [`RequestInput`]: crate::search::RequestInput
+//! [`search::Request`]: crate::search::Request
+//! [`aggregation::Request`]: crate::aggregation::Request
+//! [scripts]: https://www.elastic.co/guide/en/elasticsearch/reference/current/modules-scripting.html
+
+use std::fmt;
+
+use crate::{
+    aggregation,
+    search::{
+        self,
+        query::{BooleanQuery, CompoundQuery, Query},
+        HighlightOptions, InnerHits, Sort,
+    },
+};
+
+/// Restricts which fields may be used by which operations, and whether scripts
+/// are allowed.
+///
+/// Each list of allowed fields is either `None`, which allows *any* field, or a
+/// list of patterns. A pattern is either an exact field name (e.g. `status`),
+/// a prefix ending with `*` (e.g. `comments.*`) or just `*` to match every
+/// field.
+///
+/// The fields referenced *inside* the text of a [`query_string`] query (e.g.
+/// `ssn:123`) are checked along with its `fields`/`default_field`. If neither
+/// are set, or the text can't be parsed, the query targets every field and is
+/// only allowed if `*` is allowed. The same goes for a [`RawQuery`], which
+/// can't be inspected at all.
+///
+/// The sorts and highlighted fields of `inner_hits` are checked like those of
+/// the request. The field to [collapse] the hits by is checked as an
+/// aggregated one, as the collapsed hits reveal its distinct values. Which
+/// fields are *returned* (e.g. by `_source`) isn't restricted at all, use
+/// Elasticsearch's [field level security] for that.
+///
+/// [`query_string`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-query-string-query.html
+/// [`RawQuery`]: crate::search::query::RawQuery
+/// [collapse]: https://www.elastic.co/guide/en/elasticsearch/reference/current/collapse-search-results.html
+/// [field level security]: https://www.elastic.co/guide/en/elasticsearch/reference/current/field-level-security.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[derive(Default, Clone, Debug)]
+pub struct AccessPolicy {
+    /// The fields that may be queried/filtered on.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub filter: Option<Vec<String>>,
+
+    /// The fields that may be sorted on.
+    ///
+    /// The special `_score`, `_key` and `_count` fields are always allowed.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub sort: Option<Vec<String>>,
+
+    /// The fields (and nested paths) that may be aggregated on.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub aggregate: Option<Vec<String>>,
+
+    /// The fields that may be highlighted.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub highlight: Option<Vec<String>>,
+
+    /// Whether or not [scripts] may be used.
+    ///
+    /// [scripts]: https://www.elastic.co/guide/en/elasticsearch/reference/current/modules-scripting.html
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub scripts: ScriptPolicy,
+}
+
+/// Whether or not [scripts] may be used in a request.
+///
+/// [scripts]: https://www.elastic.co/guide/en/elasticsearch/reference/current/modules-scripting.html
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScriptPolicy {
+    /// Scripts may be used.
+    Allow,
+
+    /// Scripts may *not* be used. This is the default.
+    Deny,
+}
+
+impl Default for ScriptPolicy {
+    #[inline]
+    fn default() -> Self {
+        ScriptPolicy::Deny
+    }
+}
+
+/// The kind of operation a field is used for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// The field is queried/filtered on.
+    Filter,
+
+    /// The field is sorted on.
+    Sort,
+
+    /// The field is aggregated on.
+    Aggregate,
+
+    /// The field is highlighted.
+    Highlight,
+}
+
+impl fmt::Display for Operation {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Operation::Filter => "filter",
+            Operation::Sort => "sort",
+            Operation::Aggregate => "aggregate",
+            Operation::Highlight => "highlight",
+        })
+    }
+}
+
+/// A single clause of a request that is not allowed by an [`AccessPolicy`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// The path to the offending clause in the request body, e.g.
+    /// `query.bool.filter[0].term` or `aggs.by_owner.terms`.
+    pub path: String,
+
+    /// The operation the field is used for, or `None` if this is a script.
+    pub operation: Option<Operation>,
+
+    /// The field that is not allowed, or `None` if this is a script.
+    pub field: Option<String>,
+}
+
+impl Violation {
+    /// Creates a new `Violation` for a disallowed `field`.
+    #[inline]
+    fn field(path: String, operation: Operation, field: &str) -> Self {
+        Violation {
+            path,
+            operation: Some(operation),
+            field: Some(field.to_string()),
+        }
+    }
+
+    /// Creates a new `Violation` for a disallowed script.
+    #[inline]
+    fn script(path: String) -> Self {
+        Violation {
+            path,
+            operation: None,
+            field: None,
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.operation, &self.field) {
+            (Some(operation), Some(field)) => write!(
+                f,
+                "{}: not allowed to {} on field `{}`",
+                self.path, operation, field
+            ),
+            _ => write!(f, "{}: scripts are not allowed", self.path),
+        }
+    }
+}
+
+impl std::error::Error for Violation {}
+
+impl AccessPolicy {
+    /// Returns `true` if `field` may be used for `operation`.
+    pub fn allows(&self, operation: Operation, field: &str) -> bool {
+        let patterns = match operation {
+            Operation::Filter => &self.filter,
+            Operation::Sort => &self.sort,
+            Operation::Aggregate => &self.aggregate,
+            Operation::Highlight => &self.highlight,
+        };
+
+        match patterns {
+            None => true,
+            Some(patterns) => patterns.iter().any(|pattern| matches(pattern, field)),
+        }
+    }
+
+    /// Checks a search request against this policy, returning every violation.
+    pub fn check_search(&self, request: &search::Request) -> Result<(), Vec<Violation>> {
+        let mut violations = vec![];
+
+        self.visit_compound(&request.query, "query".to_string(), &mut violations);
+        let path = "post_filter".to_string();
+        self.visit_compound(&request.post_filter, path, &mut violations);
+
+        self.check_sort(&request.sort, "sort", &mut violations);
+        if let Some(highlight) = &request.highlight {
+            self.check_highlight(highlight, "highlight", &mut violations);
+        }
+
+        if let Some(collapse) = &request.collapse {
+            let path = || "collapse".to_string();
+            self.check_field(Operation::Aggregate, &collapse.field, path, &mut violations);
+            for (i, inner_hits) in collapse.inner_hits.iter().enumerate() {
+                let path = format!("collapse.inner_hits[{}]", i);
+                self.check_inner_hits(inner_hits, &path, &mut violations);
+            }
+        }
+
+        into_result(violations)
+    }
+
+    /// Checks a query against this policy, returning every violation.
+    pub fn check_query(&self, query: &CompoundQuery) -> Result<(), Vec<Violation>> {
+        let mut violations = vec![];
+        self.visit_compound(query, "query".to_string(), &mut violations);
+        into_result(violations)
+    }
+
+    /// Checks a list of (sibling) aggregations against this policy, returning
+    /// every violation.
+    pub fn check_aggregations(
+        &self,
+        aggregations: &[aggregation::Request],
+    ) -> Result<(), Vec<Violation>> {
+        let mut violations = vec![];
+        for aggregation in aggregations {
+            self.visit_aggregation(aggregation, "aggs", &mut violations);
+        }
+        into_result(violations)
+    }
+
+    /// Checks `field` is allowed for `operation`.
+    #[inline]
+    fn check_field(
+        &self,
+        operation: Operation,
+        field: &str,
+        path: impl FnOnce() -> String,
+        violations: &mut Vec<Violation>,
+    ) {
+        if !self.allows(operation, field) {
+            violations.push(Violation::field(path(), operation, field));
+        }
+    }
+
+    /// Checks the fields of a list of sorts at `path`.
+    fn check_sort(&self, sort: &[Sort], path: &str, violations: &mut Vec<Violation>) {
+        for (i, sort) in sort.iter().enumerate() {
+            let field = sort.field_name();
+            let special = matches!(
+                field,
+                "score" | "key" | "count" | "_score" | "_key" | "_count"
+            );
+            if !special {
+                let path = || format!("{}[{}]", path, i);
+                self.check_field(Operation::Sort, field, path, violations);
+            }
+        }
+    }
+
+    /// Checks the highlighted fields of the highlight options at `path`.
+    fn check_highlight(
+        &self,
+        highlight: &HighlightOptions,
+        path: &str,
+        violations: &mut Vec<Violation>,
+    ) {
+        for field in highlight.fields.keys() {
+            let path = || format!("{}.fields.{}", path, field);
+            self.check_field(Operation::Highlight, field, path, violations);
+        }
+    }
+
+    /// Checks the sorts and highlighted fields of inner hits at `path`.
+    fn check_inner_hits(
+        &self,
+        inner_hits: &InnerHits,
+        path: &str,
+        violations: &mut Vec<Violation>,
+    ) {
+        self.check_sort(&inner_hits.sort, &format!("{}.sort", path), violations);
+        if let Some(highlight) = &inner_hits.highlight {
+            self.check_highlight(highlight, &format!("{}.highlight", path), violations);
+        }
+    }
+
+    /// Checks a script is allowed.
+    #[inline]
+    fn check_script(&self, path: impl FnOnce() -> String, violations: &mut Vec<Violation>) {
+        if self.scripts == ScriptPolicy::Deny {
+            violations.push(Violation::script(path()));
+        }
+    }
+
+    /// Visits every clause of a compound query.
+    fn visit_compound(&self, query: &CompoundQuery, path: String, violations: &mut Vec<Violation>) {
+        if let Some(boolean) = &query.boolean {
+            self.visit_boolean(boolean, format!("{}.bool", path), violations);
+        }
+    }
+
+    /// Visits every clause of a boolean query.
+    fn visit_boolean(&self, boolean: &BooleanQuery, path: String, violations: &mut Vec<Violation>) {
+        let occurrences = [
+            ("must", &boolean.must),
+            ("filter", &boolean.filter),
+            ("should", &boolean.should),
+            ("must_not", &boolean.must_not),
+        ];
+
+        for (occurrence, queries) in occurrences.iter() {
+            for (i, query) in queries.iter().enumerate() {
+                self.visit_query(query, format!("{}.{}[{}]", path, occurrence, i), violations);
+            }
+        }
+    }
+
+    /// Visits a single query, recursing into nested & boolean queries.
+    fn visit_query(&self, query: &Query, path: String, violations: &mut Vec<Violation>) {
+        let filter = Operation::Filter;
+
+        if let Some(q) = &query.exists {
+            self.check_field(filter, &q.field, || format!("{}.exists", path), violations);
+        }
+        if let Some(q) = &query.term {
+            self.check_field(filter, &q.field, || format!("{}.term", path), violations);
+        }
+        if let Some(q) = &query.terms {
+            self.check_field(filter, &q.field, || format!("{}.terms", path), violations);
+        }
+        if let Some(q) = &query.range {
+            self.check_field(filter, &q.field, || format!("{}.range", path), violations);
+        }
+        if let Some(q) = &query.prefix {
+            self.check_field(filter, &q.field, || format!("{}.prefix", path), violations);
+        }
+        if let Some(q) = &query.regexp {
+            self.check_field(filter, &q.field, || format!("{}.regexp", path), violations);
+        }
+        if let Some(q) = &query.match_ {
+            self.check_field(filter, &q.field, || format!("{}.match", path), violations);
+        }
+        if let Some(q) = &query.simple_query_string {
+            let path = format!("{}.simple_query_string", path);
+            self.check_query_fields(&q.fields, None, &path, violations);
+        }
+        if let Some(q) = &query.query_string {
+            let path = format!("{}.query_string", path);
+            self.check_query_fields(&q.fields, q.default_field.as_deref(), &path, violations);
+            // `field:value` in the text searches `field` regardless of `fields`
+            let referenced = q.query_fields().unwrap_or_else(|_| vec!["*".to_string()]);
+            for field in &referenced {
+                self.check_field(filter, field, || path.clone(), violations);
+            }
+        }
+        if let Some(q) = &query.nested {
+            self.visit_compound(&q.query, format!("{}.nested.query", path), violations);
+            if let Some(inner_hits) = &q.inner_hits {
+                let path = format!("{}.nested.inner_hits", path);
+                self.check_inner_hits(inner_hits, &path, violations);
+            }
+        }
+        if let Some(q) = &query.boolean {
+            self.visit_boolean(q, format!("{}.bool", path), violations);
+        }
+        if query.raw_query.is_some() {
+            // a raw query can't be inspected, so it may reference any field
+            self.check_field(filter, "*", || path.to_string(), violations);
+        }
+    }
+
+    /// Checks the `fields` of a full text query, which may contain a boost
+    /// (e.g. `title^3`).
+    fn check_query_fields(
+        &self,
+        fields: &[String],
+        default_field: Option<&str>,
+        path: &str,
+        violations: &mut Vec<Violation>,
+    ) {
+        let fields: Vec<&str> = if fields.is_empty() {
+            // without any fields, *every* field is searched
+            vec![default_field.unwrap_or("*")]
+        } else {
+            fields.iter().map(String::as_str).collect()
+        };
+
+        for field in fields {
+            let field = field.split('^').next().unwrap_or(field);
+            self.check_field(Operation::Filter, field, || path.to_string(), violations);
+        }
+    }
+
+    /// Visits a single aggregation and its sub-aggregations.
+    fn visit_aggregation(
+        &self,
+        aggregation: &aggregation::Request,
+        parent: &str,
+        violations: &mut Vec<Violation>,
+    ) {
+        use crate::aggregation::types::InnerAggregation;
+
+        let path = format!("{}.{}", parent, aggregation.name);
+        let aggregate = Operation::Aggregate;
+
+        let inner = [
+            ("avg", &aggregation.avg),
+            ("cardinality", &aggregation.cardinality),
+            ("max", &aggregation.max),
+            ("min", &aggregation.min),
+            (
+                "median_absolute_deviation",
+                &aggregation.median_absolute_deviation,
+            ),
+            ("percentiles", &aggregation.percentiles),
+            ("percentile_ranks", &aggregation.percentile_ranks),
+            ("stats", &aggregation.stats),
+            ("extended_stats", &aggregation.extended_stats),
+            ("sum", &aggregation.sum),
+            ("value_count", &aggregation.value_count),
+        ];
+
+        let visit_inner = |ty: &str, inner: &InnerAggregation, violations: &mut Vec<Violation>| {
+            if let Some(field) = &inner.field {
+                self.check_field(aggregate, field, || format!("{}.{}", path, ty), violations);
+            }
+            if inner.script.is_some() {
+                self.check_script(|| format!("{}.{}.script", path, ty), violations);
+            }
+        };
+
+        for (ty, inner) in inner.iter() {
+            if let Some(inner) = inner {
+                visit_inner(ty, inner, violations);
+            }
+        }
+
+        if let Some(agg) = &aggregation.weighted_avg {
+            visit_inner("weighted_avg.value", &agg.value, violations);
+            visit_inner("weighted_avg.weight", &agg.weight, violations);
+        }
+        if let Some(query) = &aggregation.filters {
+            self.visit_compound(query, format!("{}.filter", path), violations);
+        }
+        if let Some(agg) = &aggregation.terms {
+            if let Some(field) = &agg.field {
+                self.check_field(aggregate, field, || format!("{}.terms", path), violations);
+            }
+            if agg.script.is_some() {
+                self.check_script(|| format!("{}.terms.script", path), violations);
+            }
+        }
+        if let Some(agg) = &aggregation.range {
+            if let Some(field) = &agg.field {
+                self.check_field(aggregate, field, || format!("{}.range", path), violations);
+            }
+            if agg.script.is_some() {
+                self.check_script(|| format!("{}.range.script", path), violations);
+            }
+        }
+
+        let fields = [
+            (
+                "date_range",
+                aggregation.date_range.as_ref().map(|a| &a.field),
+            ),
+            (
+                "date_histogram",
+                aggregation.date_histogram.as_ref().map(|a| &a.field),
+            ),
+            (
+                "auto_date_histogram",
+                aggregation.auto_date_histogram.as_ref().map(|a| &a.field),
+            ),
+            (
+                "histogram",
+                aggregation.histogram.as_ref().map(|a| &a.field),
+            ),
+            (
+                "variable_width_histogram",
+                aggregation
+                    .variable_width_histogram
+                    .as_ref()
+                    .map(|a| &a.field),
+            ),
+            ("nested", aggregation.nested.as_ref().map(|a| &a.path)),
+            (
+                "reverse_nested",
+                aggregation
+                    .reverse_nested
+                    .as_ref()
+                    .and_then(|a| a.path.as_ref()),
+            ),
+        ];
+
+        for (ty, field) in fields.iter() {
+            if let Some(field) = field {
+                self.check_field(aggregate, field, || format!("{}.{}", path, ty), violations);
+            }
+        }
+
+        if aggregation.bucket_script.is_some() {
+            self.check_script(|| format!("{}.bucket_script.script", path), violations);
+        }
+        if aggregation.bucket_selector.is_some() {
+            self.check_script(|| format!("{}.bucket_selector.script", path), violations);
+        }
+
+        if let Some(aggregations) = &aggregation.aggregations {
+            let parent = format!("{}.aggs", path);
+            for aggregation in aggregations {
+                self.visit_aggregation(aggregation, &parent, violations);
+            }
+        }
+    }
+}
+
+/// Returns `true` if `field` matches the allowed field `pattern`.
+#[inline]
+fn matches(pattern: &str, field: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => field.starts_with(prefix),
+        None => pattern == field,
+    }
+}
+
+/// Converts a list of violations into a `Result`.
+#[inline]
+fn into_result(violations: Vec<Violation>) -> Result<(), Vec<Violation>> {
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::aggregation::types::{InnerAggregation, TermsAggregation};
+    use crate::search::query::{NestedQuery, RangeQuery, TermQuery};
+    use crate::search::Request;
+
+    fn policy() -> AccessPolicy {
+        AccessPolicy {
+            filter: Some(vec!["status".to_string(), "comments.*".to_string()]),
+            sort: Some(vec!["created_at".to_string()]),
+            aggregate: Some(vec!["status".to_string()]),
+            highlight: Some(vec!["title".to_string()]),
+            scripts: ScriptPolicy::Deny,
+        }
+    }
+
+    #[test]
+    fn default_allows_everything_but_scripts() {
+        let policy = AccessPolicy::default();
+        assert!(policy.allows(Operation::Filter, "ssn"));
+        assert!(policy.allows(Operation::Highlight, "*"));
+        assert_eq!(policy.scripts, ScriptPolicy::Deny);
+    }
+
+    #[test]
+    fn matches_patterns() {
+        assert!(matches("*", "anything"));
+        assert!(matches("comments.*", "comments.author"));
+        assert!(!matches("comments.*", "comments"));
+        assert!(matches("status", "status"));
+        assert!(!matches("status", "status.keyword"));
+    }
+
+    #[test]
+    fn allowed_query_is_ok() {
+        let mut query = CompoundQuery::default();
+        query.push(TermQuery::new("status", "active"));
+        query.push(NestedQuery::new(
+            "comments",
+            TermQuery::new("comments.author", "bob"),
+            false,
+        ));
+
+        assert_eq!(policy().check_query(&query), Ok(()));
+    }
+
+    #[test]
+    fn reports_every_disallowed_field_with_its_path() {
+        let mut query = CompoundQuery::default();
+        query.push(TermQuery::new("status", "active"));
+        query.push(TermQuery::new("ssn", "123"));
+        query.push(NestedQuery::new(
+            "owner",
+            RangeQuery::builder()
+                .field("owner.salary".to_string())
+                .greater_than(Some("100".to_string()))
+                .build(),
+            false,
+        ));
+
+        let violations = policy().check_query(&query).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                Violation::field(
+                    "query.bool.filter[1].term".to_string(),
+                    Operation::Filter,
+                    "ssn"
+                ),
+                Violation::field(
+                    "query.bool.filter[2].nested.query.bool.filter[0].range".to_string(),
+                    Operation::Filter,
+                    "owner.salary"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_post_filter_sort_and_highlight() {
+        let sort: Vec<Sort> = serde_json::from_value(json!([
+            { "_score": {} },
+            { "created_at": { "order": "desc" } },
+            { "salary": {} },
+        ]))
+        .unwrap();
+
+        let mut post_filter = CompoundQuery::default();
+        post_filter.push(TermQuery::new("ssn", "123-45-6789"));
+
+        let request = Request::builder()
+            .post_filter(post_filter)
+            .sort(sort)
+            .highlight(HighlightOptions::default())
+            .build();
+
+        let violations = policy().check_search(&request).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                Violation::field(
+                    "post_filter.bool.filter[0].term".to_string(),
+                    Operation::Filter,
+                    "ssn"
+                ),
+                Violation::field("sort[2]".to_string(), Operation::Sort, "salary"),
+                Violation::field("highlight.fields.*".to_string(), Operation::Highlight, "*"),
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_inner_hits_of_nested_queries() {
+        let query: Query = serde_json::from_value(json!({
+            "nested": {
+                "path": "comments",
+                "query": { "term": { "comments.author": "jane" } },
+                "inner_hits": {
+                    "sort": [{ "comments.created_at": {} }, { "salary": {} }],
+                    "highlight": { "fields": { "title": {}, "comments.ssn": {} } },
+                    "_source": ["comments.ssn"],
+                },
+            }
+        }))
+        .unwrap();
+        let mut compound = CompoundQuery::default();
+        compound.push(query);
+
+        let policy = AccessPolicy {
+            sort: Some(vec!["comments.*".to_string()]),
+            ..policy()
+        };
+        let violations = policy.check_query(&compound).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                Violation::field(
+                    "query.bool.filter[0].nested.inner_hits.sort[1]".to_string(),
+                    Operation::Sort,
+                    "salary"
+                ),
+                Violation::field(
+                    "query.bool.filter[0].nested.inner_hits.highlight.fields.comments.ssn"
+                        .to_string(),
+                    Operation::Highlight,
+                    "comments.ssn"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_collapse_and_its_inner_hits() {
+        let request: Request = serde_json::from_value(json!({
+            "collapse": {
+                "field": "salary",
+                "inner_hits": [
+                    { "name": "newest", "sort": [{ "created_at": {} }] },
+                    { "name": "best", "sort": [{ "ssn": {} }], "highlight": { "fields": { "ssn": {} } } },
+                ],
+            },
+        }))
+        .unwrap();
+
+        let violations = policy().check_search(&request).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                Violation::field("collapse".to_string(), Operation::Aggregate, "salary"),
+                Violation::field(
+                    "collapse.inner_hits[1].sort[0]".to_string(),
+                    Operation::Sort,
+                    "ssn"
+                ),
+                Violation::field(
+                    "collapse.inner_hits[1].highlight.fields.ssn".to_string(),
+                    Operation::Highlight,
+                    "ssn"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_aggregations_and_scripts() {
+        let script: crate::search::Script =
+            serde_json::from_value(json!({ "source": "doc['salary'].value * 2" })).unwrap();
+
+        let aggregations = vec![aggregation::Request::builder()
+            .name("by_status")
+            .terms(Some(
+                TermsAggregation::builder()
+                    .field(Some("status".to_string()))
+                    .script(None)
+                    .build(),
+            ))
+            .aggregations(Some(vec![
+                aggregation::Request::builder()
+                    .name("salary")
+                    .avg(Some(InnerAggregation::from("salary")))
+                    .build(),
+                aggregation::Request::builder()
+                    .name("doubled")
+                    .sum(Some(
+                        InnerAggregation::builder().script(Some(script)).build(),
+                    ))
+                    .build(),
+            ]))
+            .build()];
+
+        let violations = policy().check_aggregations(&aggregations).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                Violation::field(
+                    "aggs.by_status.aggs.salary.avg".to_string(),
+                    Operation::Aggregate,
+                    "salary"
+                ),
+                Violation::script("aggs.by_status.aggs.doubled.sum.script".to_string()),
+            ]
+        );
+
+        let policy = AccessPolicy {
+            aggregate: Some(vec!["*".to_string()]),
+            scripts: ScriptPolicy::Allow,
+            ..policy()
+        };
+        assert_eq!(policy.check_aggregations(&aggregations), Ok(()));
+    }
+
+    #[test]
+    fn query_string_without_fields_targets_everything() {
+        let query: Query = serde_json::from_value(json!({
+            "query_string": { "query": "ssn:123" }
+        }))
+        .unwrap();
+        let mut compound = CompoundQuery::default();
+        compound.push(query);
+
+        let violations = policy().check_query(&compound).unwrap_err();
+        assert_eq!(violations[0].field.as_deref(), Some("*"));
+    }
+
+    #[test]
+    fn checks_fields_inside_query_strings() {
+        let query: Query = serde_json::from_value(json!({
+            "query_string": {
+                "query": r"status:open AND (comments.body:rust OR ssn:123 OR _exists_:sal\ary)",
+                "default_field": "status",
+            }
+        }))
+        .unwrap();
+        let mut compound = CompoundQuery::default();
+        compound.push(query);
+
+        let violations = policy().check_query(&compound).unwrap_err();
+        let fields: Vec<_> = violations.iter().map(|v| v.field.as_deref()).collect();
+        assert_eq!(fields, vec![Some("ssn"), Some("salary")]);
+        assert_eq!(violations[0].path, "query.bool.filter[0].query_string");
+
+        // a query that can't be parsed may reference any field
+        let query: Query = serde_json::from_value(json!({
+            "query_string": { "query": "ssn:\"123", "default_field": "status" }
+        }))
+        .unwrap();
+        let mut compound = CompoundQuery::default();
+        compound.push(query);
+
+        let violations = policy().check_query(&compound).unwrap_err();
+        assert_eq!(violations[0].field.as_deref(), Some("*"));
+    }
+
+    #[test]
+    fn raw_queries_target_everything() {
+        let mut query = CompoundQuery::default();
+        query.push(Query::raw(
+            json!({ "geo_distance": { "distance": "1km", "home": [0, 0] } }),
+        ));
+
+        let violations = policy().check_query(&query).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![Violation::field(
+                "query.bool.filter[0]".to_string(),
+                Operation::Filter,
+                "*"
+            )]
+        );
+    }
+}
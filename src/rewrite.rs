@@ -0,0 +1,633 @@
+//! Rewrite passes over requests.
+//!
+//! A query on a field of a [`nested`] object (e.g. `comments.author`) only
+//! matches if it's wrapped in a `nested` query with the path of that object
+//! (e.g. `comments`), and an aggregation on such a field needs a `nested`
+//! aggregation. [`NestedPaths`] inserts these automatically, so requests can
+//! be written (or built from untrusted input) without knowing which fields are
+//! nested.
+//!
+//! ```
+//! use elastiql::rewrite::NestedPaths;
+//! use elastiql::search::query::{CompoundQuery, TermQuery};
+//! use serde_json::json;
+//!
+//! let mut query = CompoundQuery::default();
+//! query.push(TermQuery::new("status", "published"));
+//! query.push(TermQuery::new("comments.author", "bob"));
+//! query.push(TermQuery::new("comments.approved", "true"));
+//!
+//! let query = NestedPaths::new(vec!["comments"]).nest_query(query);
+//! assert_eq!(
+//!     serde_json::to_value(&query).unwrap(),
+//!     json!({
+//!         "bool": {
+//!             "filter": [
+//!                 { "term": { "status": { "value": "published" } } },
+//!                 {
+//!                     "nested": {
+//!                         "path": "comments",
+//!                         "ignore_unmapped": false,
+//!                         "query": {
+//!                             "bool": {
+//!                                 "filter": [
+//!                                     { "term": { "comments.author": { "value": "bob" } } },
+//!                                     { "term": { "comments.approved": { "value": "true" } } },
+//!                                 ],
+//!                             },
+//!                         },
+//!                     },
+//!                 },
+//!             ],
+//!         },
+//!     })
+//! );
+//! ```
+//!
+//! [`nested`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/nested.html
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    aggregation::{
+        self,
+        types::{NestedAggregation, ReverseNestedAggregation},
+    },
+    mapping::{FieldMapping, Mapping},
+    search::{
+        self,
+        query::{BooleanQuery, CompoundQuery, NestedQuery, Query},
+    },
+};
+
+/// The paths of the [`nested`] fields of an index, used to wrap queries and
+/// aggregations on their fields in `nested` queries and aggregations.
+///
+/// [`nested`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/nested.html
+#[derive(Default, Clone, Debug)]
+pub struct NestedPaths {
+    paths: BTreeSet<String>,
+}
+
+/// Which clauses of a boolean query a list of queries are.
+#[derive(Copy, Clone)]
+enum Occurrence {
+    Must,
+    Filter,
+    Should,
+    MustNot,
+}
+
+/// A rewritten clause: either a query as is, or a group of queries to wrap in
+/// a `nested` query on `path`.
+enum Clause {
+    Query(Box<Query>),
+    Nested { path: String, queries: Vec<Query> },
+}
+
+impl NestedPaths {
+    /// Creates a new `NestedPaths` from the (full, dotted) `paths` of the
+    /// nested fields, e.g. `comments` and `comments.votes`.
+    pub fn new<T: Into<String>>(paths: impl IntoIterator<Item = T>) -> Self {
+        NestedPaths {
+            paths: paths.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Creates a new `NestedPaths` from the nested fields of a `mapping`.
+    pub fn from_mapping(mapping: &Mapping) -> Self {
+        /// Collects the paths of the nested fields within `properties`.
+        fn collect(
+            properties: &BTreeMap<String, FieldMapping>,
+            prefix: Option<&str>,
+            paths: &mut BTreeSet<String>,
+        ) {
+            for (name, field) in properties {
+                let path = match prefix {
+                    Some(prefix) => format!("{}.{}", prefix, name),
+                    None => name.clone(),
+                };
+                if let Some(properties) = field.properties() {
+                    collect(properties, Some(&path), paths);
+                }
+                if let FieldMapping::Nested(_) = field {
+                    paths.insert(path);
+                }
+            }
+        }
+
+        let mut paths = BTreeSet::new();
+        collect(&mapping.properties, None, &mut paths);
+        NestedPaths { paths }
+    }
+
+    /// Returns the path of the innermost nested field that contains `field`,
+    /// if any.
+    pub fn path_of(&self, field: &str) -> Option<&str> {
+        self.paths
+            .iter()
+            .map(String::as_str)
+            .filter(|path| is_within(field, Some(path)))
+            .max_by_key(|path| path.len())
+    }
+
+    /// Wraps the queries of a search request on nested fields in `nested`
+    /// queries; see [`nest_query`](NestedPaths::nest_query).
+    pub fn nest_search(&self, mut request: search::Request) -> search::Request {
+        request.query = self.nest_query(request.query);
+        request.post_filter = self.nest_query(request.post_filter);
+        request
+    }
+
+    /// Wraps the leaf queries on nested fields that aren't already within
+    /// their nested path in (possibly multiple levels of) `nested` queries.
+    ///
+    /// Sibling clauses on the same path are wrapped in a single `nested` query,
+    /// so that e.g. two `must` clauses have to match the *same* nested object:
+    /// `must`, `filter` and `should` clauses are grouped as the same clauses of
+    /// the inner `bool` query, and `must_not` clauses as `should` clauses (i.e.
+    /// *no* nested object may match any of them). `should` clauses are not
+    /// grouped when the query has a `minimum_should_match`, as that would
+    /// change the number of clauses.
+    ///
+    /// Queries on a field outside of the `nested` query they are already in
+    /// can't be fixed (there is no reverse nested query) and are left as is.
+    pub fn nest_query(&self, query: CompoundQuery) -> CompoundQuery {
+        self.nest_compound(query, None)
+    }
+
+    /// Wraps (sibling) aggregations on nested fields in `nested` aggregations,
+    /// and aggregations on fields outside of the nested path of their parent
+    /// in `reverse_nested` aggregations.
+    ///
+    /// A wrapper aggregation has the same name as the aggregation it wraps,
+    /// e.g. the buckets of a `by_author` terms aggregation on
+    /// `comments.author` are in `by_author.by_author` of the response.
+    pub fn nest_aggregations(
+        &self,
+        aggregations: Vec<aggregation::Request>,
+    ) -> Vec<aggregation::Request> {
+        aggregations
+            .into_iter()
+            .map(|aggregation| self.nest_aggregation(aggregation, None))
+            .collect()
+    }
+
+    /// Rewrites a compound query within the `nested` path.
+    fn nest_compound(&self, query: CompoundQuery, nested: Option<&str>) -> CompoundQuery {
+        CompoundQuery {
+            boolean: query
+                .boolean
+                .map(|boolean| self.nest_boolean(boolean, nested)),
+        }
+    }
+
+    /// Rewrites every clause of a boolean query within the `nested` path.
+    fn nest_boolean(&self, mut boolean: BooleanQuery, nested: Option<&str>) -> BooleanQuery {
+        let group_should = boolean.minimum_should_match.is_none();
+
+        boolean.must = self.nest_clauses(boolean.must, Occurrence::Must, nested, true);
+        boolean.filter = self.nest_clauses(boolean.filter, Occurrence::Filter, nested, true);
+        boolean.should =
+            self.nest_clauses(boolean.should, Occurrence::Should, nested, group_should);
+        boolean.must_not = self.nest_clauses(boolean.must_not, Occurrence::MustNot, nested, true);
+
+        boolean
+    }
+
+    /// Rewrites the `queries` of one occurrence of a boolean query within the
+    /// `nested` path, grouping those on the same path if `group` is set.
+    fn nest_clauses(
+        &self,
+        queries: Vec<Query>,
+        occurrence: Occurrence,
+        nested: Option<&str>,
+        group: bool,
+    ) -> Vec<Query> {
+        let mut clauses: Vec<Clause> = vec![];
+
+        for query in queries {
+            let query = self.nest_children(query, nested);
+            let path = match self.next_path(&query, nested) {
+                Some(path) => path,
+                None => {
+                    clauses.push(Clause::Query(Box::new(query)));
+                    continue;
+                }
+            };
+
+            let existing = clauses.iter_mut().find_map(|clause| match clause {
+                Clause::Nested { path: p, queries } if group && p == path => Some(queries),
+                _ => None,
+            });
+            match existing {
+                Some(queries) => queries.push(query),
+                None => clauses.push(Clause::Nested {
+                    path: path.to_string(),
+                    queries: vec![query],
+                }),
+            }
+        }
+
+        clauses
+            .into_iter()
+            .map(|clause| match clause {
+                Clause::Query(query) => *query,
+                Clause::Nested { path, queries } => {
+                    let mut inner = BooleanQuery::default();
+                    match occurrence {
+                        Occurrence::Must => inner.must = queries,
+                        Occurrence::Filter => inner.filter = queries,
+                        Occurrence::Should | Occurrence::MustNot => inner.should = queries,
+                    }
+                    // the queries may be nested deeper still
+                    let inner = self.nest_boolean(inner, Some(&path));
+                    NestedQuery::new(path, inner, false).into()
+                }
+            })
+            .collect()
+    }
+
+    /// Rewrites the queries within a (`nested` or `bool`) query.
+    fn nest_children(&self, mut query: Query, nested: Option<&str>) -> Query {
+        if let Some(q) = query.nested.as_mut() {
+            let inner = std::mem::take(&mut q.query);
+            q.query = self.nest_compound(inner, Some(&q.path));
+        }
+        if let Some(boolean) = query.boolean.take() {
+            query.boolean = Some(self.nest_boolean(boolean, nested));
+        }
+        query
+    }
+
+    /// Returns the path of the next (i.e. outermost) `nested` query that a
+    /// leaf query within the `nested` path needs to be wrapped in, if any.
+    fn next_path(&self, query: &Query, nested: Option<&str>) -> Option<&str> {
+        let field = leaf_field(query)?;
+        let required = self.path_of(field)?;
+        if Some(required) == nested || !is_within(required, nested) {
+            return None;
+        }
+        self.paths
+            .iter()
+            .map(String::as_str)
+            // shorter paths sort first
+            .find(|path| is_within(path, nested) && is_within_or_at(required, path))
+    }
+
+    /// Rewrites an aggregation, and its sub-aggregations, within the `nested`
+    /// path.
+    fn nest_aggregation(
+        &self,
+        mut aggregation: aggregation::Request,
+        nested: Option<&str>,
+    ) -> aggregation::Request {
+        if let Some(query) = aggregation.filters.take() {
+            aggregation.filters = Some(self.nest_compound(query, nested));
+        }
+
+        // the path the aggregation itself has to be within
+        let target = match aggregation_field(&aggregation) {
+            Some(field) => self.path_of(field).map(ToString::to_string),
+            None => nested.map(ToString::to_string),
+        };
+        // the path its sub-aggregations are within
+        let children = match (&aggregation.nested, &aggregation.reverse_nested) {
+            (Some(agg), _) => Some(agg.path.clone()),
+            (None, Some(agg)) => agg.path.clone(),
+            (None, None) => target.clone(),
+        };
+
+        if let Some(aggregations) = aggregation.aggregations.take() {
+            aggregation.aggregations = Some(
+                aggregations
+                    .into_iter()
+                    .map(|child| self.nest_aggregation(child, children.as_deref()))
+                    .collect(),
+            );
+        }
+
+        self.wrap_aggregation(aggregation, nested, target.as_deref())
+    }
+
+    /// Wraps an aggregation within the `from` path in the `reverse_nested`
+    /// and/or `nested` aggregations needed to move it to the `to` path.
+    fn wrap_aggregation(
+        &self,
+        mut aggregation: aggregation::Request,
+        from: Option<&str>,
+        to: Option<&str>,
+    ) -> aggregation::Request {
+        if from == to {
+            return aggregation;
+        }
+
+        // the innermost path that contains both (or is either)
+        let common = self
+            .paths
+            .iter()
+            .map(String::as_str)
+            .filter(|path| is_within_or_at(from.unwrap_or(""), path))
+            .filter(|path| is_within_or_at(to.unwrap_or(""), path))
+            .max_by_key(|path| path.len());
+
+        let steps: Vec<&str> = self
+            .paths
+            .iter()
+            .map(String::as_str)
+            .filter(|path| is_within(path, common) && is_within_or_at(to.unwrap_or(""), path))
+            .collect();
+        for path in steps.into_iter().rev() {
+            aggregation = aggregation::Request {
+                name: aggregation.name.clone(),
+                nested: Some(NestedAggregation {
+                    path: path.to_string(),
+                }),
+                aggregations: Some(vec![aggregation]),
+                ..aggregation::Request::default()
+            };
+        }
+
+        if common != from {
+            aggregation = aggregation::Request {
+                name: aggregation.name.clone(),
+                reverse_nested: Some(ReverseNestedAggregation {
+                    path: common.map(ToString::to_string),
+                }),
+                aggregations: Some(vec![aggregation]),
+                ..aggregation::Request::default()
+            };
+        }
+
+        aggregation
+    }
+}
+
+/// Returns `true` if `field` is within (i.e. below) the `nested` path, or if
+/// there is no nested path.
+#[inline]
+fn is_within(field: &str, nested: Option<&str>) -> bool {
+    match nested {
+        Some(nested) => matches!(field.strip_prefix(nested), Some(rest) if rest.starts_with('.')),
+        None => true,
+    }
+}
+
+/// Returns `true` if `field` is within or *is* the `nested` path.
+#[inline]
+fn is_within_or_at(field: &str, nested: &str) -> bool {
+    field == nested || is_within(field, Some(nested))
+}
+
+/// Returns the field of a leaf query, if any.
+fn leaf_field(query: &Query) -> Option<&str> {
+    let fields = [
+        query.exists.as_ref().map(|q| &q.field),
+        query.term.as_ref().map(|q| &q.field),
+        query.terms.as_ref().map(|q| &q.field),
+        query.range.as_ref().map(|q| &q.field),
+        query.prefix.as_ref().map(|q| &q.field),
+        query.regexp.as_ref().map(|q| &q.field),
+        query.match_.as_ref().map(|q| &q.field),
+    ];
+    fields.iter().flatten().next().map(|field| field.as_str())
+}
+
+/// Returns the field an aggregation is on, if any.
+fn aggregation_field(aggregation: &aggregation::Request) -> Option<&str> {
+    let inner = [
+        &aggregation.avg,
+        &aggregation.cardinality,
+        &aggregation.max,
+        &aggregation.min,
+        &aggregation.median_absolute_deviation,
+        &aggregation.percentiles,
+        &aggregation.percentile_ranks,
+        &aggregation.stats,
+        &aggregation.extended_stats,
+        &aggregation.sum,
+        &aggregation.value_count,
+    ];
+    let fields = [
+        aggregation.terms.as_ref().and_then(|a| a.field.as_ref()),
+        aggregation.range.as_ref().and_then(|a| a.field.as_ref()),
+        aggregation.date_range.as_ref().map(|a| &a.field),
+        aggregation.date_histogram.as_ref().map(|a| &a.field),
+        aggregation.auto_date_histogram.as_ref().map(|a| &a.field),
+        aggregation.histogram.as_ref().map(|a| &a.field),
+        aggregation
+            .variable_width_histogram
+            .as_ref()
+            .map(|a| &a.field),
+        aggregation
+            .weighted_avg
+            .as_ref()
+            .and_then(|a| a.value.field.as_ref()),
+    ];
+
+    inner
+        .iter()
+        .filter_map(|inner| inner.as_ref().and_then(|a| a.field.as_ref()))
+        .chain(fields.iter().flatten().copied())
+        .next()
+        .map(String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::aggregation::types::{InnerAggregation, TermsAggregation};
+    use crate::search::query::TermQuery;
+
+    fn paths() -> NestedPaths {
+        NestedPaths::new(vec!["comments", "comments.votes"])
+    }
+
+    fn terms(name: &str, field: &str) -> aggregation::Request {
+        aggregation::Request {
+            name: name.to_string(),
+            terms: Some(
+                TermsAggregation::builder()
+                    .field(Some(field.to_string()))
+                    .script(None)
+                    .build(),
+            ),
+            ..aggregation::Request::default()
+        }
+    }
+
+    #[test]
+    fn can_find_nested_paths() {
+        let mapping: Mapping = serde_json::from_value(json!({
+            "properties": {
+                "comments": {
+                    "type": "nested",
+                    "properties": {
+                        "votes": { "type": "nested", "properties": {} },
+                        "meta": { "properties": { "tags": { "type": "nested" } } },
+                    },
+                },
+                "name": { "type": "text" },
+            }
+        }))
+        .unwrap();
+
+        let paths = NestedPaths::from_mapping(&mapping);
+        assert_eq!(
+            paths.paths.iter().collect::<Vec<_>>(),
+            vec!["comments", "comments.meta.tags", "comments.votes"]
+        );
+        assert_eq!(paths.path_of("comments.votes.user"), Some("comments.votes"));
+        assert_eq!(paths.path_of("comments.author"), Some("comments"));
+        assert_eq!(paths.path_of("commentsx.author"), None);
+        assert_eq!(paths.path_of("comments"), None);
+    }
+
+    #[test]
+    fn wraps_queries_in_multiple_levels() {
+        let mut query = CompoundQuery::default();
+        query.push(TermQuery::new("comments.votes.user", "alice"));
+        query.push(TermQuery::new("comments.author", "bob"));
+
+        assert_eq!(
+            serde_json::to_value(paths().nest_query(query)).unwrap(),
+            json!({
+                "bool": {
+                    "filter": [{
+                        "nested": {
+                            "path": "comments",
+                            "ignore_unmapped": false,
+                            "query": {
+                                "bool": {
+                                    "filter": [
+                                        {
+                                            "nested": {
+                                                "path": "comments.votes",
+                                                "ignore_unmapped": false,
+                                                "query": {
+                                                    "bool": {
+                                                        "filter": [
+                                                            { "term": { "comments.votes.user": { "value": "alice" } } },
+                                                        ],
+                                                    },
+                                                },
+                                            },
+                                        },
+                                        { "term": { "comments.author": { "value": "bob" } } },
+                                    ],
+                                },
+                            },
+                        },
+                    }],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn must_not_clauses_are_grouped_as_should() {
+        let query: CompoundQuery = serde_json::from_value(json!({
+            "bool": {
+                "must_not": [
+                    { "term": { "comments.author": "bob" } },
+                    { "term": { "comments.author": "alice" } },
+                ],
+                "should": [
+                    { "term": { "comments.author": "bob" } },
+                    { "term": { "comments.author": "alice" } },
+                ],
+                "minimum_should_match": "1",
+            }
+        }))
+        .unwrap();
+
+        let query = serde_json::to_value(paths().nest_query(query)).unwrap();
+        let must_not = &query["bool"]["must_not"];
+        assert_eq!(must_not.as_array().unwrap().len(), 1);
+        assert_eq!(must_not[0]["nested"]["path"], "comments");
+        assert_eq!(
+            must_not[0]["nested"]["query"]["bool"]["should"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        // grouping would change the meaning of `minimum_should_match`
+        assert_eq!(query["bool"]["should"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn leaves_correctly_nested_queries_alone() {
+        let query: CompoundQuery = serde_json::from_value(json!({
+            "nested": {
+                "path": "comments",
+                "ignore_unmapped": false,
+                "query": { "term": { "comments.author": "bob" } },
+            }
+        }))
+        .unwrap();
+
+        let expected = serde_json::to_value(&query).unwrap();
+        assert_eq!(
+            serde_json::to_value(paths().nest_query(query)).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn wraps_aggregations() {
+        let mut by_status = terms("by_status", "status");
+        by_status.aggregations = Some(vec![terms("by_voter", "comments.votes.user")]);
+
+        let mut by_author = terms("by_author", "comments.author");
+        by_author.aggregations = Some(vec![aggregation::Request {
+            name: "avg_age".to_string(),
+            avg: Some(InnerAggregation::from("age")),
+            ..aggregation::Request::default()
+        }]);
+
+        let aggregations = paths().nest_aggregations(vec![by_status, by_author]);
+        let j = |agg: &aggregation::Request| serde_json::to_value(agg).unwrap()[&agg.name].take();
+        let terms = |field: &str| json!({ "field": field });
+
+        assert_eq!(
+            j(&aggregations[0]),
+            json!({
+                "terms": terms("status"),
+                "aggs": {
+                    "by_voter": {
+                        "nested": { "path": "comments" },
+                        "aggs": {
+                            "by_voter": {
+                                "nested": { "path": "comments.votes" },
+                                "aggs": {
+                                    "by_voter": { "terms": terms("comments.votes.user") },
+                                },
+                            },
+                        },
+                    },
+                },
+            })
+        );
+        assert_eq!(
+            j(&aggregations[1]),
+            json!({
+                "nested": { "path": "comments" },
+                "aggs": {
+                    "by_author": {
+                        "terms": terms("comments.author"),
+                        "aggs": {
+                            "avg_age": {
+                                "reverse_nested": {},
+                                "aggs": { "avg_age": { "avg": { "field": "age" } } },
+                            },
+                        },
+                    },
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! [Field collapsing] options.
+//!
+//! [Field collapsing]: https://www.elastic.co/guide/en/elasticsearch/reference/current/collapse-search-results.html
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::search::InnerHits;
+#[cfg(feature = "graphql")]
+use crate::search::InnerHitsInput;
+
+/// The options for [collapsing] the hits by the value of a field, i.e. only
+/// returning the top hit of each value (e.g. of each product family).
+///
+/// The collapsed hits are returned with the value of the field in their
+/// `fields`, and the other hits of their group can be returned as `inner_hits`.
+///
+/// [collapsing]: https://www.elastic.co/guide/en/elasticsearch/reference/current/collapse-search-results.html
+#[cfg(feature = "graphql")]
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[derive(async_graphql::InputObject, Serialize, Clone, Debug)]
+pub struct CollapseInput {
+    /// The field to collapse the hits by, which must be a single-valued
+    /// `keyword` or numeric field with doc values.
+    #[cfg_attr(feature = "builder", builder(setter(into)))]
+    pub field: String,
+
+    /// The inner hits to return for each collapsed hit, e.g. the top hits of
+    /// its group by different sorts. Each must have a unique `name`.
+    #[graphql(default)]
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub inner_hits: Vec<InnerHitsInput>,
+
+    /// The maximum number of concurrent requests to retrieve the `inner_hits`
+    /// of the groups with, per collapsed hit.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_group_searches: Option<u64>,
+}
+
+#[cfg(feature = "graphql")]
+impl From<Collapse> for CollapseInput {
+    #[inline]
+    fn from(collapse: Collapse) -> Self {
+        CollapseInput {
+            field: collapse.field,
+            inner_hits: collapse.inner_hits.into_iter().map(Into::into).collect(),
+            max_concurrent_group_searches: collapse.max_concurrent_group_searches,
+        }
+    }
+}
+
+/// The options for [collapsing] the hits by the value of a field, i.e. only
+/// returning the top hit of each value (e.g. of each product family).
+///
+/// The collapsed hits are returned with the value of the field in their
+/// `fields`, and the other hits of their group can be returned as `inner_hits`.
+///
+/// [collapsing]: https://www.elastic.co/guide/en/elasticsearch/reference/current/collapse-search-results.html
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Collapse {
+    /// The field to collapse the hits by, which must be a single-valued
+    /// `keyword` or numeric field with doc values.
+    #[cfg_attr(feature = "builder", builder(setter(into)))]
+    pub field: String,
+
+    /// The inner hits to return for each collapsed hit, e.g. the top hits of
+    /// its group by different sorts. Each must have a unique `name`.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(
+        default,
+        deserialize_with = "inner_hits",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub inner_hits: Vec<InnerHits>,
+
+    /// The maximum number of concurrent requests to retrieve the `inner_hits`
+    /// of the groups with, per collapsed hit.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_group_searches: Option<u64>,
+}
+
+impl Collapse {
+    /// Constructs a new `Collapse` by `field`, without inner hits.
+    #[inline]
+    pub fn new(field: impl Into<String>) -> Collapse {
+        Collapse {
+            field: field.into(),
+            inner_hits: vec![],
+            max_concurrent_group_searches: None,
+        }
+    }
+}
+
+#[cfg(feature = "graphql")]
+impl From<CollapseInput> for Collapse {
+    #[inline]
+    fn from(input: CollapseInput) -> Self {
+        Collapse {
+            field: input.field,
+            inner_hits: input.inner_hits.into_iter().map(Into::into).collect(),
+            max_concurrent_group_searches: input.max_concurrent_group_searches,
+        }
+    }
+}
+
+/// Deserializes a list of inner hits, or a single one as a list of one.
+fn inner_hits<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<InnerHits>, D::Error> {
+    /// Either a single inner hits or a list of them.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(Box<InnerHits>),
+        Many(Vec<InnerHits>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(inner_hits) => vec![*inner_hits],
+        OneOrMany::Many(inner_hits) => inner_hits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::search::Sort;
+
+    #[test]
+    fn can_round_trip_collapse() {
+        let collapse = Collapse {
+            field: "family".to_string(),
+            inner_hits: vec![
+                InnerHits {
+                    name: Some("cheapest".to_string()),
+                    size: Some(3),
+                    sort: vec![Sort::new("price")],
+                    ..InnerHits::default()
+                },
+                InnerHits {
+                    name: Some("newest".to_string()),
+                    size: Some(1),
+                    ..InnerHits::default()
+                },
+            ],
+            max_concurrent_group_searches: Some(4),
+        };
+        let j = json!({
+            "field": "family",
+            "inner_hits": [
+                { "name": "cheapest", "size": 3, "sort": [{ "price": { "unmapped_type": "keyword" } }] },
+                { "name": "newest", "size": 1 },
+            ],
+            "max_concurrent_group_searches": 4,
+        });
+
+        assert_eq!(serde_json::to_value(&collapse).unwrap(), j);
+        assert_eq!(serde_json::from_value::<Collapse>(j).unwrap(), collapse);
+
+        assert_eq!(
+            serde_json::to_value(Collapse::new("family")).unwrap(),
+            json!({ "field": "family" })
+        );
+        let collapse: Collapse = serde_json::from_value(json!({
+            "field": "family",
+            "inner_hits": { "name": "cheapest" },
+        }))
+        .unwrap();
+        assert_eq!(collapse.inner_hits[0].name.as_deref(), Some("cheapest"));
+    }
+}
@@ -0,0 +1,181 @@
+//! Options for [retrieving selected fields] of matching documents, other than
+//! (or instead of) their `_source`.
+//!
+//! [retrieving selected fields]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-fields.html
+
+use serde::{
+    ser::{SerializeMap, Serializer},
+    Deserialize, Deserializer, Serialize,
+};
+
+/// A field to return, e.g. in the `fields` or `docvalue_fields` of a search
+/// request, and optionally the format to return its values in.
+///
+/// Serialized as just the field if no `format` is given.
+#[cfg(feature = "graphql")]
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[derive(async_graphql::InputObject, Clone, Debug)]
+pub struct FieldAndFormatInput {
+    /// The field to return, which may contain wildcards (`*`).
+    #[cfg_attr(feature = "builder", builder(setter(into)))]
+    pub field: String,
+
+    /// The format of the values, e.g. `epoch_millis` for dates.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub format: Option<String>,
+}
+
+#[cfg(feature = "graphql")]
+impl From<FieldAndFormat> for FieldAndFormatInput {
+    #[inline]
+    fn from(field: FieldAndFormat) -> Self {
+        FieldAndFormatInput {
+            field: field.field,
+            format: field.format,
+        }
+    }
+}
+
+#[cfg(feature = "graphql")]
+impl Serialize for FieldAndFormatInput {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.field, self.format.as_deref(), serializer)
+    }
+}
+
+/// A field to return, e.g. in the `fields` or `docvalue_fields` of a search
+/// request, and optionally the format to return its values in.
+///
+/// Serialized as just the field if no `format` is given.
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[derive(Clone, Debug)]
+pub struct FieldAndFormat {
+    /// The field to return, which may contain wildcards (`*`).
+    #[cfg_attr(feature = "builder", builder(setter(into)))]
+    pub field: String,
+
+    /// The format of the values, e.g. `epoch_millis` for dates.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub format: Option<String>,
+}
+
+impl FieldAndFormat {
+    /// Constructs a new `FieldAndFormat` which returns `field` in its default
+    /// format.
+    #[inline]
+    pub fn new(field: impl Into<String>) -> FieldAndFormat {
+        FieldAndFormat {
+            field: field.into(),
+            format: None,
+        }
+    }
+
+    /// Sets the format to return the values of the field in.
+    #[inline]
+    pub fn with_format(mut self, format: impl Into<String>) -> FieldAndFormat {
+        self.format = Some(format.into());
+        self
+    }
+}
+
+impl From<&str> for FieldAndFormat {
+    #[inline]
+    fn from(field: &str) -> Self {
+        FieldAndFormat::new(field)
+    }
+}
+
+impl From<String> for FieldAndFormat {
+    #[inline]
+    fn from(field: String) -> Self {
+        FieldAndFormat::new(field)
+    }
+}
+
+#[cfg(feature = "graphql")]
+impl From<FieldAndFormatInput> for FieldAndFormat {
+    #[inline]
+    fn from(input: FieldAndFormatInput) -> Self {
+        FieldAndFormat {
+            field: input.field,
+            format: input.format,
+        }
+    }
+}
+
+impl Serialize for FieldAndFormat {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.field, self.format.as_deref(), serializer)
+    }
+}
+
+/// Serializes a field and its format in its most compact form.
+fn serialize<S: Serializer>(
+    field: &str,
+    format: Option<&str>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match format {
+        None => serializer.serialize_str(field),
+        Some(format) => {
+            let mut map = serializer.serialize_map(Some(2))?;
+            map.serialize_entry("field", field)?;
+            map.serialize_entry("format", format)?;
+            map.end()
+        }
+    }
+}
+
+/// All the forms of a field accepted by Elasticsearch.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum InnerFieldAndFormat {
+    Field(String),
+    FieldAndFormat {
+        field: String,
+        #[serde(default)]
+        format: Option<String>,
+    },
+}
+
+impl<'de> Deserialize<'de> for FieldAndFormat {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match InnerFieldAndFormat::deserialize(deserializer)? {
+            InnerFieldAndFormat::Field(field) => FieldAndFormat::new(field),
+            InnerFieldAndFormat::FieldAndFormat { field, format } => {
+                FieldAndFormat { field, format }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn can_round_trip_fields() {
+        let fields = vec![
+            FieldAndFormat::new("user.*"),
+            FieldAndFormat::new("created_at").with_format("epoch_millis"),
+        ];
+        let j = json!(["user.*", { "field": "created_at", "format": "epoch_millis" }]);
+
+        assert_eq!(serde_json::to_value(&fields).unwrap(), j);
+        assert_eq!(
+            serde_json::from_value::<Vec<FieldAndFormat>>(j).unwrap(),
+            fields
+        );
+        assert_eq!(
+            serde_json::from_value::<FieldAndFormat>(json!({ "field": "title" })).unwrap(),
+            FieldAndFormat::new("title")
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! [Inner hits] options.
+//!
+//! [Inner hits]: https://www.elastic.co/guide/en/elasticsearch/reference/current/inner-hits.html
+
+use serde::{Deserialize, Serialize};
+
+use crate::search::{HighlightOptions, Sort, SourceFilter};
+#[cfg(feature = "graphql")]
+use crate::search::{HighlightOptionsInput, SortInput, SourceFilterInput};
+
+/// The options for returning the [inner hits] of e.g. a `nested` query, i.e.
+/// the nested documents that caused a document to match.
+///
+/// [inner hits]: https://www.elastic.co/guide/en/elasticsearch/reference/current/inner-hits.html
+#[cfg(feature = "graphql")]
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[derive(async_graphql::InputObject, Serialize, Default, Clone, Debug)]
+#[graphql(name = "InnerHitsOptionsInput")]
+pub struct InnerHitsInput {
+    /// The name to use for the inner hits in the response. Defaults to the
+    /// `path` of the nested query.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// The offset from where the first inner hit to return. Defaults to `0`.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<u64>,
+
+    /// The maximum number of inner hits to return. Defaults to `3`.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+
+    /// Sorts the inner hits, which are sorted by score by default.
+    #[graphql(default)]
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sort: Vec<SortInput>,
+
+    /// Which fields of the inner hits' `_source` to return.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(rename = "_source", skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceFilterInput>,
+
+    /// The [highlighted] snippets of the part(s) of the field(s) matching the
+    /// query.
+    ///
+    /// [highlighted]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-request-highlighting.html
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<HighlightOptionsInput>,
+}
+
+#[cfg(feature = "graphql")]
+impl From<InnerHits> for InnerHitsInput {
+    #[inline]
+    fn from(inner_hits: InnerHits) -> Self {
+        InnerHitsInput {
+            name: inner_hits.name,
+            from: inner_hits.from,
+            size: inner_hits.size,
+            sort: inner_hits.sort.into_iter().map(Into::into).collect(),
+            source: inner_hits.source.map(Into::into),
+            highlight: inner_hits.highlight.map(Into::into),
+        }
+    }
+}
+
+/// The options for returning the [inner hits] of e.g. a `nested` query, i.e.
+/// the nested documents that caused a document to match.
+///
+/// [inner hits]: https://www.elastic.co/guide/en/elasticsearch/reference/current/inner-hits.html
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+#[cfg_attr(feature = "graphql", graphql(name = "InnerHitsOptions"))]
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+pub struct InnerHits {
+    /// The name to use for the inner hits in the response. Defaults to the
+    /// `path` of the nested query.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// The offset from where the first inner hit to return. Defaults to `0`.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<u64>,
+
+    /// The maximum number of inner hits to return. Defaults to `3`.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+
+    /// Sorts the inner hits, which are sorted by score by default.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sort: Vec<Sort>,
+
+    /// Which fields of the inner hits' `_source` to return.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, rename = "_source", skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceFilter>,
+
+    /// The [highlighted] snippets of the part(s) of the field(s) matching the
+    /// query.
+    ///
+    /// [highlighted]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-request-highlighting.html
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<HighlightOptions>,
+}
+
+#[cfg(feature = "graphql")]
+impl From<InnerHitsInput> for InnerHits {
+    #[inline]
+    fn from(input: InnerHitsInput) -> Self {
+        InnerHits {
+            name: input.name,
+            from: input.from,
+            size: input.size,
+            sort: input.sort.into_iter().map(Into::into).collect(),
+            source: input.source.map(Into::into),
+            highlight: input.highlight.map(Into::into),
+        }
+    }
+}
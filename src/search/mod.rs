@@ -3,10 +3,16 @@
 //! [searching]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-search.html
 //! [Query DSL]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl.html
 
-pub use self::{request::*, response::*, script::*, sort::*};
+pub use self::{
+    collapse::*, fields::*, inner_hits::*, request::*, response::*, script::*, sort::*, source::*,
+};
 
+mod collapse;
+mod fields;
+mod inner_hits;
 pub mod query;
 mod request;
 mod response;
 mod script;
 mod sort;
+mod source;
@@ -14,6 +14,12 @@ pub struct ExistsQueryInput {
     /// The name of the field to query.
     #[cfg_attr(feature = "builder", builder(setter(into)))]
     pub field: String,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(rename = "_name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 #[cfg(feature = "graphql")]
@@ -23,6 +29,7 @@ impl ExistsQueryInput {
     pub fn new(field: impl Into<String>) -> ExistsQueryInput {
         ExistsQueryInput {
             field: field.into(),
+            name: None,
         }
     }
 }
@@ -31,7 +38,10 @@ impl ExistsQueryInput {
 impl From<ExistsQuery> for ExistsQueryInput {
     #[inline]
     fn from(query: ExistsQuery) -> Self {
-        Self { field: query.field }
+        Self {
+            field: query.field,
+            name: query.name,
+        }
     }
 }
 
@@ -44,10 +54,17 @@ impl From<ExistsQuery> for ExistsQueryInput {
 #[cfg_attr(feature = "graphql", graphql(name = "ExistsFilter"))]
 #[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct ExistsQuery {
     /// The name of the field to query.
     #[cfg_attr(feature = "builder", builder(setter(into)))]
     pub field: String,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, rename = "_name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 impl ExistsQuery {
@@ -56,6 +73,7 @@ impl ExistsQuery {
     pub fn new(field: impl Into<String>) -> ExistsQuery {
         ExistsQuery {
             field: field.into(),
+            name: None,
         }
     }
 }
@@ -64,6 +82,9 @@ impl ExistsQuery {
 impl From<ExistsQueryInput> for ExistsQuery {
     #[inline]
     fn from(input: ExistsQueryInput) -> ExistsQuery {
-        ExistsQuery { field: input.field }
+        ExistsQuery {
+            field: input.field,
+            name: input.name,
+        }
     }
 }
@@ -0,0 +1,218 @@
+//! Bitsets of named flags which are serialized as pipe-delimited strings, e.g.
+//! `AND|OR|PREFIX`.
+
+use std::fmt;
+
+/// An error encountered while parsing a pipe-delimited string of flags.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseFlagsError {
+    /// The flag that is not known.
+    pub flag: String,
+}
+
+impl fmt::Display for ParseFlagsError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown flag `{}`", self.flag)
+    }
+}
+
+impl std::error::Error for ParseFlagsError {}
+
+/// Defines a bitset of named flags, which has `ALL` and `NONE` constants, can
+/// be combined using `|` and is (de)serialized as a pipe-delimited string.
+/// Aliases are accepted when parsing, but never displayed.
+macro_rules! flags {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[$flag_meta:meta])*
+                const $flag:ident = $bits:expr;
+            )*
+        }
+        $(
+            aliases {
+                $(
+                    $(#[$alias_meta:meta])*
+                    const $alias:ident = $target:ident;
+                )*
+            }
+        )?
+    ) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, PartialEq, Eq, Hash)]
+        pub struct $name(u32);
+
+        impl $name {
+            $(
+                $(#[$flag_meta])*
+                pub const $flag: $name = $name($bits);
+            )*
+            $($(
+                $(#[$alias_meta])*
+                pub const $alias: $name = $name::$target;
+            )*)?
+
+            /// No flags at all.
+            pub const NONE: $name = $name(0);
+
+            /// All flags.
+            pub const ALL: $name = $name(0 $(| $bits)*);
+
+            const FLAGS: &'static [(&'static str, $name)] = &[
+                $((stringify!($flag), $name::$flag),)*
+            ];
+
+            const ALIASES: &'static [(&'static str, $name)] = &[
+                $($((stringify!($alias), $name::$target),)*)?
+            ];
+
+            /// Returns the raw bits of these flags.
+            #[inline]
+            pub fn bits(self) -> u32 {
+                self.0
+            }
+
+            /// Returns `true` if no flags are set.
+            #[inline]
+            pub fn is_empty(self) -> bool {
+                self.0 == 0
+            }
+
+            /// Returns `true` if all flags in `other` are set.
+            #[inline]
+            pub fn contains(self, other: $name) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            /// Sets all flags in `other`.
+            #[inline]
+            pub fn insert(&mut self, other: $name) {
+                self.0 |= other.0;
+            }
+
+            /// Unsets all flags in `other`.
+            #[inline]
+            pub fn remove(&mut self, other: $name) {
+                self.0 &= !other.0;
+            }
+        }
+
+        impl std::ops::BitOr for $name {
+            type Output = $name;
+
+            #[inline]
+            fn bitor(self, other: $name) -> $name {
+                $name(self.0 | other.0)
+            }
+        }
+
+        impl std::ops::BitOrAssign for $name {
+            #[inline]
+            fn bitor_assign(&mut self, other: $name) {
+                self.insert(other)
+            }
+        }
+
+        impl std::ops::BitAnd for $name {
+            type Output = $name;
+
+            #[inline]
+            fn bitand(self, other: $name) -> $name {
+                $name(self.0 & other.0)
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                if *self == $name::ALL {
+                    return f.write_str("ALL");
+                }
+                if self.is_empty() {
+                    return f.write_str("NONE");
+                }
+                let mut first = true;
+                for &(name, flag) in $name::FLAGS {
+                    if self.contains(flag) {
+                        if !first {
+                            f.write_str("|")?;
+                        }
+                        f.write_str(name)?;
+                        first = false;
+                    }
+                }
+                Ok(())
+            }
+        }
+
+        impl std::fmt::Debug for $name {
+            #[inline]
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{}({})", stringify!($name), self)
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = $crate::search::query::ParseFlagsError;
+
+            /// Parses a pipe-delimited, case-insensitive string of flags.
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let mut flags = $name::NONE;
+                for name in s.split('|').map(str::trim) {
+                    flags |= if name.eq_ignore_ascii_case("ALL") {
+                        $name::ALL
+                    } else if name.eq_ignore_ascii_case("NONE") {
+                        $name::NONE
+                    } else {
+                        $name::FLAGS
+                            .iter()
+                            .chain($name::ALIASES)
+                            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                            .map(|&(_, flag)| flag)
+                            .ok_or_else(|| $crate::search::query::ParseFlagsError {
+                                flag: name.to_string(),
+                            })?
+                    };
+                }
+                Ok(flags)
+            }
+        }
+
+        impl serde::Serialize for $name {
+            #[inline]
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            #[inline]
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+
+        #[cfg(feature = "graphql")]
+        #[async_graphql::Scalar]
+        impl async_graphql::ScalarType for $name {
+            #[inline]
+            fn parse(value: async_graphql::Value) -> async_graphql::InputValueResult<Self> {
+                match value {
+                    async_graphql::Value::String(ref s) => s
+                        .parse()
+                        .map_err(|err: $crate::search::query::ParseFlagsError| {
+                            async_graphql::InputValueError::custom(err)
+                        }),
+                    _ => Err(async_graphql::InputValueError::expected_type(value)),
+                }
+            }
+
+            #[inline]
+            fn to_value(&self) -> async_graphql::Value {
+                async_graphql::Value::String(self.to_string())
+            }
+        }
+    };
+}
@@ -0,0 +1,680 @@
+//! Validation of the Lucene [classic query syntax] used by [`QueryStringQuery`].
+//!
+//! [classic query syntax]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-query-string-query.html#query-string-syntax
+//! [`QueryStringQuery`]: super::QueryStringQuery
+
+use std::fmt;
+
+/// Characters that must be escaped with a `\` to be used literally.
+const RESERVED: &str = "+-=&|!(){}[]^\"~*?:\\/";
+
+/// The reason a query string is not valid Lucene query syntax.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueryStringErrorKind {
+    /// A phrase is missing its closing `"`.
+    UnterminatedPhrase,
+
+    /// A regular expression is missing its closing `/`.
+    UnterminatedRegex,
+
+    /// A range is missing its closing `]` or `}`.
+    UnterminatedRange,
+
+    /// A range is not of the form `[lower TO upper]`.
+    InvalidRange,
+
+    /// The query string ends with a single `\`.
+    TrailingBackslash,
+
+    /// A `)` without a matching `(`.
+    UnmatchedParenthesis,
+
+    /// A `(` without a matching `)`.
+    UnclosedParenthesis,
+
+    /// An operator, e.g. `AND`, is not followed by a clause.
+    MissingOperand(String),
+
+    /// A field, i.e. `field:`, is not followed by a value.
+    MissingValue,
+
+    /// A token that is not valid at this position.
+    UnexpectedToken(String),
+
+    /// The query string ended while a clause was expected.
+    UnexpectedEnd,
+
+    /// A `^` is not followed by a number.
+    InvalidBoost,
+
+    /// A term starts with `*` or `?` while `allow_leading_wildcard` is
+    /// `false`.
+    LeadingWildcard,
+}
+
+impl fmt::Display for QueryStringErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryStringErrorKind::UnterminatedPhrase => f.write_str("unterminated phrase"),
+            QueryStringErrorKind::UnterminatedRegex => {
+                f.write_str("unterminated regular expression")
+            }
+            QueryStringErrorKind::UnterminatedRange => f.write_str("unterminated range"),
+            QueryStringErrorKind::InvalidRange => {
+                f.write_str("expected a range of the form `[lower TO upper]`")
+            }
+            QueryStringErrorKind::TrailingBackslash => f.write_str("trailing backslash"),
+            QueryStringErrorKind::UnmatchedParenthesis => f.write_str("unmatched `)`"),
+            QueryStringErrorKind::UnclosedParenthesis => f.write_str("unclosed `(`"),
+            QueryStringErrorKind::MissingOperand(op) => {
+                write!(f, "expected a clause after `{}`", op)
+            }
+            QueryStringErrorKind::MissingValue => f.write_str("expected a value after `:`"),
+            QueryStringErrorKind::UnexpectedToken(token) => {
+                write!(f, "unexpected `{}`", token)
+            }
+            QueryStringErrorKind::UnexpectedEnd => {
+                f.write_str("unexpected end of the query string")
+            }
+            QueryStringErrorKind::InvalidBoost => f.write_str("expected a number after `^`"),
+            QueryStringErrorKind::LeadingWildcard => {
+                f.write_str("leading wildcards are not allowed")
+            }
+        }
+    }
+}
+
+/// An error in the syntax of a query string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryStringError {
+    /// The reason the query string is invalid.
+    pub kind: QueryStringErrorKind,
+
+    /// The byte offset in the query string the error refers to.
+    pub position: usize,
+}
+
+impl QueryStringError {
+    #[inline]
+    fn new(kind: QueryStringErrorKind, position: usize) -> Self {
+        QueryStringError { kind, position }
+    }
+}
+
+impl fmt::Display for QueryStringError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.kind, self.position)
+    }
+}
+
+impl std::error::Error for QueryStringError {}
+
+/// Escapes `literal` so it is matched as-is, see
+/// [`QueryStringQuery::escape`](super::QueryStringQuery::escape).
+pub(crate) fn escape(literal: &str) -> String {
+    let mut escaped = String::with_capacity(literal.len());
+    // keywords are separated by any whitespace, which is kept as-is
+    let mut separators = literal.matches(char::is_whitespace);
+    for word in literal.split(char::is_whitespace) {
+        if matches!(word, "AND" | "OR" | "NOT") {
+            escaped.push('\\');
+        }
+        for c in word.chars() {
+            match c {
+                // these can't be escaped, only removed
+                '<' | '>' => {}
+                c if RESERVED.contains(c) => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                c => escaped.push(c),
+            }
+        }
+        if let Some(separator) = separators.next() {
+            escaped.push_str(separator);
+        }
+    }
+    escaped
+}
+
+/// Checks that `query` is valid Lucene query syntax.
+pub(crate) fn validate(query: &str, allow_leading_wildcard: bool) -> Result<(), QueryStringError> {
+    let tokens = tokenize(query)?;
+    Validator {
+        query,
+        tokens: &tokens,
+        pos: 0,
+        allow_leading_wildcard,
+    }
+    .validate()
+}
+
+/// Removes the leading wildcards of every term in `query`. A term consisting of
+/// nothing but wildcards is replaced by `*`, which Elasticsearch allows.
+pub(crate) fn strip_leading_wildcards(query: &str) -> Result<String, QueryStringError> {
+    let mut stripped = String::with_capacity(query.len());
+    let mut last = 0;
+    for token in tokenize(query)? {
+        if let TokenKind::Term { leading_wildcards } = token.kind {
+            if leading_wildcards > 0 && &query[token.start..token.end] != "*" {
+                stripped.push_str(&query[last..token.start]);
+                if token.start + leading_wildcards == token.end {
+                    stripped.push('*');
+                }
+                last = token.start + leading_wildcards;
+            }
+        }
+    }
+    stripped.push_str(&query[last..]);
+    Ok(stripped)
+}
+
+/// Returns the (unescaped) fields referenced in `query` by `field:value`, as
+/// well as the fields checked by `_exists_:field`.
+pub(crate) fn fields(query: &str) -> Result<Vec<String>, QueryStringError> {
+    let tokens = tokenize(query)?;
+    let mut fields = vec![];
+    for (i, pair) in tokens.windows(2).enumerate() {
+        if !matches!(pair[0].kind, TokenKind::Term { .. }) || pair[1].kind != TokenKind::Colon {
+            continue;
+        }
+        let field = unescape(&query[pair[0].start..pair[0].end]);
+        if field == "_exists_" {
+            if let Some(value) = tokens.get(i + 2) {
+                if let TokenKind::Term { .. } = value.kind {
+                    fields.push(unescape(&query[value.start..value.end]));
+                    continue;
+                }
+            }
+        }
+        fields.push(field);
+    }
+    Ok(fields)
+}
+
+/// Returns the regular expressions in `query`, e.g. `.*son` for `name:/.*son/`,
+/// without their delimiting `/`.
+pub(crate) fn regexes(query: &str) -> Result<Vec<&str>, QueryStringError> {
+    Ok(tokenize(query)?
+        .into_iter()
+        .filter(|token| token.kind == TokenKind::Regex)
+        .map(|token| &query[token.start + 1..token.end - 1])
+        .collect())
+}
+
+/// Removes the `\` escaping the characters of `term`.
+fn unescape(term: &str) -> String {
+    let mut unescaped = String::with_capacity(term.len());
+    let mut chars = term.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum TokenKind {
+    /// A (possibly wildcard) term, with the length in bytes of its leading
+    /// unescaped `*` and `?` characters.
+    Term {
+        leading_wildcards: usize,
+    },
+    Phrase,
+    Regex,
+    Range,
+    LeftParen,
+    RightParen,
+    Colon,
+    /// `+`, `-`, `!` or `NOT`
+    Prefix,
+    /// `AND`, `&&`, `OR` or `||`
+    Binary,
+    /// `^` followed by a number
+    Boost,
+    /// `~`, optionally followed by a number
+    Fuzzy,
+    /// `>`, `>=`, `<` or `<=`
+    Comparison,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+/// Returns `true` if `c` ends a term, unless it is escaped.
+#[inline]
+fn is_term_end(c: char) -> bool {
+    c.is_whitespace() || "!():^[]\"{}~\\/".contains(c)
+}
+
+/// Returns the byte offset just past the closing `delimiter`, skipping escaped
+/// characters.
+fn find_closing(query: &str, from: usize, delimiter: char) -> Option<usize> {
+    let mut chars = query[from..].char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            c if c == delimiter => return Some(from + i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the byte offset just past the digits (and an optional fraction)
+/// starting at `from`.
+fn number_end(query: &str, from: usize) -> usize {
+    let digits = |from: usize| {
+        from + query[from..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(query.len() - from)
+    };
+    let end = digits(from);
+    if end > from && query[end..].starts_with('.') && digits(end + 1) > end + 1 {
+        digits(end + 1)
+    } else {
+        end
+    }
+}
+
+/// Checks the contents of a range (without its brackets) are of the form
+/// `lower TO upper`.
+fn is_valid_range(contents: &str) -> bool {
+    let mut parts = vec![];
+    let mut rest = contents.trim_start();
+    while !rest.is_empty() {
+        let len = if rest.starts_with('"') {
+            match find_closing(rest, 1, '"') {
+                Some(len) => len,
+                None => return false,
+            }
+        } else {
+            rest.find(char::is_whitespace).unwrap_or(rest.len())
+        };
+        parts.push(&rest[..len]);
+        rest = rest[len..].trim_start();
+    }
+    matches!(parts.as_slice(), [_, "TO", _])
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, QueryStringError> {
+    use QueryStringErrorKind::*;
+
+    let mut tokens = vec![];
+    let mut start = 0;
+    while let Some(c) = query[start..].chars().next() {
+        let rest = &query[start..];
+        let (kind, end) = match c {
+            c if c.is_whitespace() => {
+                start += c.len_utf8();
+                continue;
+            }
+            '"' => match find_closing(query, start + 1, '"') {
+                Some(end) => (TokenKind::Phrase, end),
+                None => return Err(QueryStringError::new(UnterminatedPhrase, start)),
+            },
+            '/' => match find_closing(query, start + 1, '/') {
+                Some(end) => (TokenKind::Regex, end),
+                None => return Err(QueryStringError::new(UnterminatedRegex, start)),
+            },
+            '[' | '{' => {
+                let closing = rest[1..].find(&[']', '}'][..]).map(|i| start + 1 + i + 1);
+                match closing {
+                    Some(end) if is_valid_range(&query[start + 1..end - 1]) => {
+                        (TokenKind::Range, end)
+                    }
+                    Some(_) => return Err(QueryStringError::new(InvalidRange, start)),
+                    None => return Err(QueryStringError::new(UnterminatedRange, start)),
+                }
+            }
+            ']' | '}' => return Err(QueryStringError::new(UnexpectedToken(c.to_string()), start)),
+            '(' => (TokenKind::LeftParen, start + 1),
+            ')' => (TokenKind::RightParen, start + 1),
+            ':' => (TokenKind::Colon, start + 1),
+            '+' | '-' | '!' => (TokenKind::Prefix, start + 1),
+            '^' => {
+                let end = number_end(query, start + 1);
+                if end == start + 1 {
+                    return Err(QueryStringError::new(InvalidBoost, start));
+                }
+                (TokenKind::Boost, end)
+            }
+            '~' => (TokenKind::Fuzzy, number_end(query, start + 1)),
+            '>' | '<' if rest[1..].starts_with('=') => (TokenKind::Comparison, start + 2),
+            '>' | '<' => (TokenKind::Comparison, start + 1),
+            _ if rest.starts_with("&&") || rest.starts_with("||") => (TokenKind::Binary, start + 2),
+            _ => {
+                let mut end = start;
+                let mut leading_wildcards = 0;
+                let mut chars = rest.char_indices().peekable();
+                while let Some((i, c)) = chars.next() {
+                    match c {
+                        '\\' => match chars.next() {
+                            Some((j, escaped)) => end = start + j + escaped.len_utf8(),
+                            None => {
+                                return Err(QueryStringError::new(TrailingBackslash, start + i))
+                            }
+                        },
+                        // `+` and `-` only end a term when they start it
+                        c if is_term_end(c) => break,
+                        c => {
+                            if (c == '*' || c == '?') && end - start == leading_wildcards {
+                                leading_wildcards += 1;
+                            }
+                            end = start + i + c.len_utf8();
+                        }
+                    }
+                }
+                let kind = match &query[start..end] {
+                    "AND" | "OR" => TokenKind::Binary,
+                    "NOT" => TokenKind::Prefix,
+                    _ => TokenKind::Term { leading_wildcards },
+                };
+                (kind, end)
+            }
+        };
+        tokens.push(Token { kind, start, end });
+        start = end;
+    }
+    Ok(tokens)
+}
+
+struct Validator<'a> {
+    query: &'a str,
+    tokens: &'a [Token],
+    pos: usize,
+    allow_leading_wildcard: bool,
+}
+
+impl<'a> Validator<'a> {
+    fn validate(mut self) -> Result<(), QueryStringError> {
+        if self.tokens.is_empty() {
+            return Ok(());
+        }
+        self.clauses()?;
+        match self.peek() {
+            Some(token) => Err(QueryStringError::new(
+                QueryStringErrorKind::UnmatchedParenthesis,
+                token.start,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    #[inline]
+    fn text(&self, token: &Token) -> &'a str {
+        &self.query[token.start..token.end]
+    }
+
+    /// Parses clauses up to the end of the input or a `)`.
+    fn clauses(&mut self) -> Result<(), QueryStringError> {
+        self.clause(None)?;
+        while let Some(token) = self.peek() {
+            match token.kind {
+                TokenKind::RightParen => break,
+                TokenKind::Binary => {
+                    self.pos += 1;
+                    self.clause(Some(token))?;
+                }
+                _ => self.clause(None)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a single, possibly prefixed, clause. `after` is the operator
+    /// preceding it, if any.
+    fn clause(&mut self, mut after: Option<&'a Token>) -> Result<(), QueryStringError> {
+        while let Some(token) = self.peek().filter(|t| t.kind == TokenKind::Prefix) {
+            self.pos += 1;
+            after = Some(token);
+        }
+
+        let token = match self.next() {
+            Some(token) => token,
+            None => return Err(self.missing_operand(after)),
+        };
+        match token.kind {
+            TokenKind::Term { .. } if self.peek().map(|t| &t.kind) == Some(&TokenKind::Colon) => {
+                let colon = self.next().unwrap();
+                self.value(colon)?;
+            }
+            TokenKind::Term { .. } | TokenKind::Phrase | TokenKind::Regex | TokenKind::Range => {
+                self.check_wildcard(token)?
+            }
+            TokenKind::LeftParen => self.group(token)?,
+            TokenKind::Comparison => self.comparison(token)?,
+            TokenKind::RightParen if after.is_some() => return Err(self.missing_operand(after)),
+            _ => {
+                return Err(QueryStringError::new(
+                    QueryStringErrorKind::UnexpectedToken(self.text(token).to_string()),
+                    token.start,
+                ))
+            }
+        }
+        self.modifiers();
+        Ok(())
+    }
+
+    /// Parses the value of a `field:value` clause.
+    fn value(&mut self, colon: &'a Token) -> Result<(), QueryStringError> {
+        let token = match self.next() {
+            Some(token) => token,
+            None => {
+                return Err(QueryStringError::new(
+                    QueryStringErrorKind::MissingValue,
+                    colon.start,
+                ))
+            }
+        };
+        match token.kind {
+            TokenKind::Term { .. } | TokenKind::Phrase | TokenKind::Regex | TokenKind::Range => {
+                self.check_wildcard(token)
+            }
+            TokenKind::LeftParen => self.group(token),
+            TokenKind::Comparison => self.comparison(token),
+            _ => Err(QueryStringError::new(
+                QueryStringErrorKind::UnexpectedToken(self.text(token).to_string()),
+                token.start,
+            )),
+        }
+    }
+
+    /// Parses the contents of a group after its opening `(`.
+    fn group(&mut self, open: &'a Token) -> Result<(), QueryStringError> {
+        if self.peek().is_none() {
+            return Err(QueryStringError::new(
+                QueryStringErrorKind::UnclosedParenthesis,
+                open.start,
+            ));
+        }
+        self.clauses()?;
+        match self.next() {
+            Some(token) if token.kind == TokenKind::RightParen => Ok(()),
+            _ => Err(QueryStringError::new(
+                QueryStringErrorKind::UnclosedParenthesis,
+                open.start,
+            )),
+        }
+    }
+
+    /// Parses the value after a `>`, `>=`, `<` or `<=`.
+    fn comparison(&mut self, operator: &'a Token) -> Result<(), QueryStringError> {
+        match self.next() {
+            Some(token) if matches!(token.kind, TokenKind::Term { .. } | TokenKind::Phrase) => {
+                Ok(())
+            }
+            Some(token) => Err(QueryStringError::new(
+                QueryStringErrorKind::UnexpectedToken(self.text(token).to_string()),
+                token.start,
+            )),
+            None => Err(self.missing_operand(Some(operator))),
+        }
+    }
+
+    fn modifiers(&mut self) {
+        while matches!(
+            self.peek().map(|t| &t.kind),
+            Some(TokenKind::Boost) | Some(TokenKind::Fuzzy)
+        ) {
+            self.pos += 1;
+        }
+    }
+
+    fn check_wildcard(&self, token: &Token) -> Result<(), QueryStringError> {
+        match token.kind {
+            TokenKind::Term { leading_wildcards }
+                if leading_wildcards > 0
+                    && !self.allow_leading_wildcard
+                    && self.text(token) != "*" =>
+            {
+                Err(QueryStringError::new(
+                    QueryStringErrorKind::LeadingWildcard,
+                    token.start,
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn missing_operand(&self, after: Option<&Token>) -> QueryStringError {
+        match after {
+            Some(token) => QueryStringError::new(
+                QueryStringErrorKind::MissingOperand(self.text(token).to_string()),
+                token.start,
+            ),
+            None => QueryStringError::new(QueryStringErrorKind::UnexpectedEnd, self.query.len()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn err(query: &str) -> (QueryStringErrorKind, usize) {
+        let err = validate(query, false).unwrap_err();
+        (err.kind, err.position)
+    }
+
+    #[test]
+    fn can_validate() {
+        let queries = [
+            "",
+            "   ",
+            "quick brown fox",
+            r#"title:"the quick"~2 AND (body:fox^2 OR body:dog~) && NOT status:draft"#,
+            "+title:rust -tags:java !tags:go",
+            "age:[18 TO 30} AND date:{* TO now]",
+            r#"name:["a b" TO z]"#,
+            "age:>=10 AND price:<5.5",
+            "path:/jo[hn]+/ AND user:ki*y AND qu?ck",
+            r"a\:b c\\ \(d\)",
+            "foo-bar a&b a|b",
+            "*",
+            "title:*",
+            "(a OR (b AND c))",
+            "fox^2.5",
+        ];
+        for query in &queries {
+            assert_eq!(validate(query, false), Ok(()), "{}", query);
+        }
+    }
+
+    #[test]
+    fn invalid_syntax_is_err() {
+        use QueryStringErrorKind::*;
+
+        assert_eq!(err(r#"title:"quick"#), (UnterminatedPhrase, 6));
+        assert_eq!(err("a/b"), (UnterminatedRegex, 1));
+        assert_eq!(err("age:[1 TO 5"), (UnterminatedRange, 4));
+        assert_eq!(err("age:[1 5]"), (InvalidRange, 4));
+        assert_eq!(err(r"foo\"), (TrailingBackslash, 3));
+        assert_eq!(err("a OR b)"), (UnmatchedParenthesis, 6));
+        assert_eq!(err("(a OR b"), (UnclosedParenthesis, 0));
+        assert_eq!(err("a AND"), (MissingOperand("AND".to_string()), 2));
+        assert_eq!(err("(a ||)"), (MissingOperand("||".to_string()), 3));
+        assert_eq!(err("a -"), (MissingOperand("-".to_string()), 2));
+        assert_eq!(err("title:"), (MissingValue, 5));
+        assert_eq!(err("OR a"), (UnexpectedToken("OR".to_string()), 0));
+        assert_eq!(err("a ]"), (UnexpectedToken("]".to_string()), 2));
+        assert_eq!(err("title:^2"), (UnexpectedToken("^2".to_string()), 6));
+        assert_eq!(err("fox^"), (InvalidBoost, 3));
+        assert_eq!(err("title:*ox"), (LeadingWildcard, 6));
+        assert_eq!(err("a ?ox"), (LeadingWildcard, 2));
+    }
+
+    #[test]
+    fn leading_wildcards_can_be_allowed() {
+        assert_eq!(validate("title:*ox ?ox", true), Ok(()));
+        assert_eq!(validate(r"title:\*ox", false), Ok(()));
+    }
+
+    #[test]
+    fn can_strip_leading_wildcards() {
+        assert_eq!(
+            strip_leading_wildcards(r#"title:*ox AND ?ox "*ox" * body:** \*a"#).unwrap(),
+            r#"title:ox AND ox "*ox" * body:* \*a"#
+        );
+    }
+
+    #[test]
+    fn can_extract_fields() {
+        assert_eq!(
+            fields(r"title:rust AND (-ssn:123 OR first\ name:jo*) && body : x").unwrap(),
+            vec!["title", "ssn", "first name", "body"]
+        );
+        assert_eq!(fields(r"_exists_:ssn a\:b *:x").unwrap(), vec!["ssn", "*"]);
+        assert!(fields("quick brown fox").unwrap().is_empty());
+        assert!(fields(r#"ssn:"123"#).is_err());
+    }
+
+    #[test]
+    fn can_extract_regexes() {
+        assert_eq!(
+            regexes(r"name:/.*son/ OR /jo[a-z]+/ AND \/a\/ b:c").unwrap(),
+            vec![".*son", "jo[a-z]+"]
+        );
+        assert_eq!(regexes(r"/a\/b/").unwrap(), vec![r"a\/b"]);
+        assert!(regexes("/.*son").is_err());
+    }
+
+    #[test]
+    fn can_escape() {
+        assert_eq!(
+            escape(r#"(1+1)=2 && "a:b" || c\d/e <f> AND not"#),
+            r#"\(1\+1\)\=2 \&\& \"a\:b\" \|\| c\\d\/e f \AND not"#
+        );
+        let escaped = escape(r#"[a] {b} ^c ~d *e ?f !g -h OR"#);
+        assert_eq!(validate(&escaped, false), Ok(()));
+    }
+
+    #[test]
+    fn can_escape_keywords_after_any_whitespace() {
+        assert_eq!(escape("x\tOR\ty"), "x\t\\OR\ty");
+        assert_eq!(escape("x\nAND\r\nNOT  y"), "x\n\\AND\r\n\\NOT  y");
+        assert_eq!(escape(" OR "), " \\OR ");
+        assert_eq!(escape(""), "");
+    }
+}
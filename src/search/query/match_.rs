@@ -1,9 +1,24 @@
 //! [Match query](https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-match-query.html#query-dsl-match-query)
 
-use std::{collections::HashMap, fmt};
+use std::fmt;
 
 use serde::de::{self, MapAccess, Visitor};
-use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+
+use super::{de::Shorthand, QueryStringBooleanOperator};
+
+#[allow(clippy::missing_docs_in_private_items)]
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InnerMatchQuery {
+    #[serde(deserialize_with = "super::de::scalar")]
+    query: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    operator: Option<QueryStringBooleanOperator>,
+    #[serde(default, rename = "_name", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+}
 
 // TODO: add additional options
 /// A [Match query] returns documents that match a provided text, number, date
@@ -32,6 +47,16 @@ pub struct MatchQueryInput {
     /// [match query]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-match-query.html#query-dsl-match-query
     #[cfg_attr(feature = "builder", builder(setter(into)))]
     pub query: String,
+
+    /// Whether *any* (the default) or *all* of the tokens of the analyzed
+    /// `query` must match.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub operator: Option<QueryStringBooleanOperator>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub name: Option<String>,
 }
 
 #[cfg(feature = "graphql")]
@@ -42,6 +67,8 @@ impl MatchQueryInput {
         MatchQueryInput {
             field: field.into(),
             query: query.into(),
+            operator: None,
+            name: None,
         }
     }
 }
@@ -53,6 +80,8 @@ impl From<MatchQuery> for MatchQueryInput {
         Self {
             field: query.field,
             query: query.query,
+            operator: query.operator,
+            name: query.name,
         }
     }
 }
@@ -63,10 +92,13 @@ impl Serialize for MatchQueryInput {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         let mut map = serializer.serialize_map(Some(1))?;
 
-        let mut values: HashMap<&str, &str> = HashMap::new();
-        values.insert("query", &self.query);
+        let inner = InnerMatchQuery {
+            query: self.query.to_owned(),
+            operator: self.operator.clone(),
+            name: self.name.to_owned(),
+        };
 
-        map.serialize_entry(&self.field, &values)?;
+        map.serialize_entry(&self.field, &inner)?;
 
         map.end()
     }
@@ -100,6 +132,16 @@ pub struct MatchQuery {
     /// [match query]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-match-query.html#query-dsl-match-query
     #[cfg_attr(feature = "builder", builder(setter(into)))]
     pub query: String,
+
+    /// Whether *any* (the default) or *all* of the tokens of the analyzed
+    /// `query` must match.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub operator: Option<QueryStringBooleanOperator>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub name: Option<String>,
 }
 
 impl MatchQuery {
@@ -109,6 +151,8 @@ impl MatchQuery {
         MatchQuery {
             field: field.into(),
             query: query.into(),
+            operator: None,
+            name: None,
         }
     }
 }
@@ -120,6 +164,8 @@ impl From<MatchQueryInput> for MatchQuery {
         MatchQuery {
             field: input.field,
             query: input.query,
+            operator: input.operator,
+            name: input.name,
         }
     }
 }
@@ -130,10 +176,13 @@ impl Serialize for MatchQuery {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         let mut map = serializer.serialize_map(Some(1))?;
 
-        let mut values: HashMap<&str, &str> = HashMap::new();
-        values.insert("query", &self.query);
+        let inner = InnerMatchQuery {
+            query: self.query.to_owned(),
+            operator: self.operator,
+            name: self.name.to_owned(),
+        };
 
-        map.serialize_entry(&self.field, &values)?;
+        map.serialize_entry(&self.field, &inner)?;
 
         map.end()
     }
@@ -167,14 +216,21 @@ impl<'de> Visitor<'de> for MatchQueryVisitor {
             .next_key::<String>()?
             .ok_or_else(|| de::Error::missing_field("field"))?;
 
-        let values: HashMap<String, String> = map.next_value()?;
-
-        let query = values
-            .get("query")
-            .ok_or_else(|| de::Error::missing_field("query"))?
-            .to_string();
+        let inner = match map.next_value()? {
+            Shorthand::Options(inner) => inner,
+            Shorthand::Value(query) => InnerMatchQuery {
+                query,
+                operator: None,
+                name: None,
+            },
+        };
 
-        Ok(MatchQuery { field, query })
+        Ok(MatchQuery {
+            field,
+            query: inner.query,
+            operator: inner.operator,
+            name: inner.name,
+        })
     }
 }
 
@@ -204,16 +260,48 @@ mod tests {
 
     test_case!(
         simple:
-        MatchQuery { field: "testMessage".to_string(), query: "this is a test".to_string() },
+        MatchQuery::new("testMessage", "this is a test"),
         json!({ "testMessage": { "query": "this is a test" } })
     );
 
+    test_case!(
+        named:
+        MatchQuery {
+            name: Some("message".to_string()),
+            ..MatchQuery::new("testMessage", "this is a test")
+        },
+        json!({ "testMessage": { "query": "this is a test", "_name": "message" } })
+    );
+
+    test_case!(
+        with_operator:
+        MatchQuery {
+            operator: Some(QueryStringBooleanOperator::And),
+            ..MatchQuery::new("testMessage", "this is a test")
+        },
+        json!({ "testMessage": { "query": "this is a test", "operator": "AND" } })
+    );
+
     #[test]
-    fn missing_query_is_err() {
-        // TODO: should we support this Elasticsearch schema?
-        let j = r#"{ "message": "missing" }"#;
-        assert!(serde_json::from_str::<MatchQuery>(j).is_err(), "{}", &j);
+    fn can_deserialize_lowercase_operator() {
+        let j = json!({ "message": { "query": "a b", "operator": "and" } });
+        assert_eq!(
+            serde_json::from_value::<MatchQuery>(j).unwrap().operator,
+            Some(QueryStringBooleanOperator::And)
+        );
+    }
 
+    #[test]
+    fn can_deserialize_shorthand() {
+        let j = r#"{ "message": "this is a test" }"#;
+        assert_eq!(
+            serde_json::from_str::<MatchQuery>(j).unwrap(),
+            MatchQuery::new("message", "this is a test")
+        );
+    }
+
+    #[test]
+    fn missing_query_is_err() {
         let j = r#"{ "message": null }"#;
         assert!(serde_json::from_str::<MatchQuery>(j).is_err(), "{}", &j);
 
@@ -222,19 +310,38 @@ mod tests {
     }
 
     #[test]
-    fn invalid_query_is_err() {
-        // TODO: should we support this Elasticsearch schema?
-
-        let j = r#"{ "message": { "query": 1.1 } }"#;
-        assert!(serde_json::from_str::<MatchQuery>(j).is_err(), "{}", &j);
+    fn can_deserialize_numbers_and_booleans() {
+        for (j, query) in &[
+            (json!({ "message": { "query": 1.1 } }), "1.1"),
+            (json!({ "message": { "query": 999 } }), "999"),
+            (json!({ "message": false }), "false"),
+        ] {
+            let expected = MatchQuery::new("message", *query);
+            assert_eq!(
+                serde_json::from_value::<MatchQuery>(j.clone()).unwrap(),
+                expected
+            );
+        }
+    }
 
-        let j = r#"{ "message": { "query": 1 } }"#;
+    #[test]
+    fn invalid_query_is_err() {
+        let j = r#"{ "message": { "query": null } }"#;
         assert!(serde_json::from_str::<MatchQuery>(j).is_err(), "{}", &j);
 
-        let j = r#"{ "message": { "query": 999 } }"#;
+        let j = r#"{ "message": { "query": ["a"] } }"#;
         assert!(serde_json::from_str::<MatchQuery>(j).is_err(), "{}", &j);
+    }
 
-        let j = r#"{ "message": { "query": null } }"#;
-        assert!(serde_json::from_str::<MatchQuery>(j).is_err(), "{}", &j);
+    #[test]
+    fn unsupported_options_are_err() {
+        for j in &[
+            json!({ "message": { "query": "a b", "fuzziness": "AUTO" } }),
+            json!({ "message": { "query": "a b", "analyzer": "standard" } }),
+            json!({ "message": { "query": "a b", "bogus": 1 } }),
+        ] {
+            let err = serde_json::from_value::<MatchQuery>(j.clone()).unwrap_err();
+            assert!(err.to_string().starts_with("unknown field"), "{}", err);
+        }
     }
 }
@@ -7,16 +7,21 @@ use std::default::Default;
 use serde::{Deserialize, Serialize};
 
 pub use self::{
-    exists::*, match_::*, nested::*, prefix::*, query_string::*, range::*, regexp::*,
-    simple_query_string::*, term::*, terms::*,
+    exists::*, flags::*, lucene::*, match_::*, nested::*, prefix::*, query_string::*, range::*,
+    raw::*, regexp::*, simple_query_string::*, term::*, terms::*,
 };
 
+mod de;
 mod exists;
+#[macro_use]
+mod flags;
+mod lucene;
 mod match_;
 mod nested;
 mod prefix;
 mod query_string;
 mod range;
+mod raw;
 mod regexp;
 mod simple_query_string;
 mod term;
@@ -67,6 +72,7 @@ impl CompoundQueryInput {
                 must_not: vec![],
                 minimum_should_match: None,
                 boost: None,
+                name: None,
             })
         }
     }
@@ -109,7 +115,7 @@ impl From<CompoundQuery> for CompoundQueryInput {
 #[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
 #[cfg_attr(feature = "graphql", graphql(name = "CompoundFilter"))]
 #[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
-#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[derive(Serialize, Default, Clone, Debug)]
 pub struct CompoundQuery {
     /// The default query for combining multiple leaf or compound query clauses,
     /// as must, should, must_not, or filter clauses. The must and should
@@ -143,11 +149,36 @@ impl CompoundQuery {
                 must_not: vec![],
                 minimum_should_match: None,
                 boost: None,
+                name: None,
             })
         }
     }
 }
 
+impl<'de> Deserialize<'de> for CompoundQuery {
+    /// Deserializes any query. A query other than a `bool` query becomes the
+    /// single `must` clause of a `bool` query, which matches and scores the
+    /// same documents.
+    #[inline]
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut query = Query::deserialize(deserializer)?;
+        let boolean = query.boolean.take();
+
+        Ok(match boolean {
+            boolean if query.is_empty() => CompoundQuery { boolean },
+            boolean => {
+                query.boolean = boolean;
+                CompoundQuery {
+                    boolean: Some(BooleanQuery {
+                        must: vec![query],
+                        ..BooleanQuery::default()
+                    }),
+                }
+            }
+        })
+    }
+}
+
 impl<T: Into<BooleanQuery>> From<T> for CompoundQuery {
     #[inline]
     fn from(filter: T) -> CompoundQuery {
@@ -242,6 +273,12 @@ pub struct BooleanQueryInput {
     #[cfg_attr(feature = "builder", builder(default))]
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub boost: Option<f64>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, rename = "_name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 #[cfg(feature = "graphql")]
@@ -274,6 +311,7 @@ impl<T: Into<QueryInput>> From<T> for BooleanQueryInput {
             must_not: vec![],
             minimum_should_match: None,
             boost: None,
+            name: None,
         }
     }
 }
@@ -289,6 +327,7 @@ impl From<BooleanQuery> for BooleanQueryInput {
             must_not: query.must_not.into_iter().map(Into::into).collect(),
             minimum_should_match: query.minimum_should_match.map(Into::into),
             boost: query.boost.map(Into::into),
+            name: query.name,
         }
     }
 }
@@ -302,12 +341,17 @@ impl From<BooleanQuery> for BooleanQueryInput {
 #[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
 #[cfg_attr(feature = "graphql", graphql(name = "BooleanFilter"))]
 #[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct BooleanQuery {
     /// The clause (query) **must** appear in matching documents and *will
     /// contribute to the score* of this query.
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[serde(
+        default,
+        deserialize_with = "de::one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
     pub must: Vec<Query>,
 
     /// The clause (query) **must** appear in matching documents. However unlike
@@ -317,12 +361,20 @@ pub struct BooleanQuery {
     ///
     /// [filter context]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-filter-context.html
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[serde(
+        default,
+        deserialize_with = "de::one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
     pub filter: Vec<Query>,
 
     /// The clause (query) **should** appear in the matching document.
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[serde(
+        default,
+        deserialize_with = "de::one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
     pub should: Vec<Query>,
 
     /// The clause (query) **must not** appear in the matching documents. Clauses
@@ -332,7 +384,11 @@ pub struct BooleanQuery {
     ///
     /// [filter context]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-filter-context.html
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[serde(
+        default,
+        deserialize_with = "de::one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
     pub must_not: Vec<Query>,
 
     /// [Controls] how many optional (`should`) parameters must match.
@@ -348,7 +404,11 @@ pub struct BooleanQuery {
     ///
     /// [Controls]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-minimum-should-match.html
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "de::string_or_number",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub minimum_should_match: Option<String>,
 
     /// Floating point number used to decrease or increase the
@@ -365,6 +425,12 @@ pub struct BooleanQuery {
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub boost: Option<f64>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, rename = "_name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 impl BooleanQuery {
@@ -397,6 +463,7 @@ impl From<BooleanQueryInput> for BooleanQuery {
             must_not: input.must_not.into_iter().map(Into::into).collect(),
             minimum_should_match: input.minimum_should_match.map(Into::into),
             boost: input.boost.map(Into::into),
+            name: input.name,
         }
     }
 }
@@ -411,6 +478,7 @@ impl<T: Into<Query>> From<T> for BooleanQuery {
             must_not: vec![],
             minimum_should_match: None,
             boost: None,
+            name: None,
         }
     }
 }
@@ -476,8 +544,23 @@ pub struct QueryInput {
     #[cfg_attr(feature = "builder", builder(setter(into)))]
     #[serde(rename = "bool", default, skip_serializing_if = "Option::is_none")]
     pub boolean: Option<BooleanQueryInput>,
+
+    /// A query that is serialized verbatim, for queries without a typed
+    /// equivalent.
+    ///
+    /// **Note**: this allows clients to send *any* query, so it is only
+    /// available with the `graphql-raw-query` feature.
+    #[cfg(feature = "graphql-raw-query")]
+    #[cfg_attr(feature = "builder", builder(default))]
+    #[graphql(name = "raw")]
+    #[serde(flatten)]
+    pub raw_query: Option<RawQuery>,
 }
 
+/// **Note**: a [`QueryInput`] can only hold a raw query with the
+/// `graphql-raw-query` feature, otherwise the `raw_query` is dropped, e.g. a
+/// [`Query::raw`] is converted to an empty `QueryInput`, which matches *all*
+/// documents.
 #[cfg(feature = "graphql")]
 impl From<Query> for QueryInput {
     #[inline]
@@ -494,6 +577,8 @@ impl From<Query> for QueryInput {
             query_string: query.query_string.map(Into::into),
             nested: query.nested.map(Into::into),
             boolean: query.boolean.map(Into::into),
+            #[cfg(feature = "graphql-raw-query")]
+            raw_query: query.raw_query,
         }
     }
 }
@@ -514,6 +599,8 @@ impl From<ExistsQueryInput> for QueryInput {
             query_string: None,
             nested: None,
             boolean: None,
+            #[cfg(feature = "graphql-raw-query")]
+            raw_query: None,
         }
     }
 }
@@ -534,6 +621,8 @@ impl From<TermQueryInput> for QueryInput {
             query_string: None,
             nested: None,
             boolean: None,
+            #[cfg(feature = "graphql-raw-query")]
+            raw_query: None,
         }
     }
 }
@@ -554,6 +643,8 @@ impl From<TermsQueryInput> for QueryInput {
             query_string: None,
             nested: None,
             boolean: None,
+            #[cfg(feature = "graphql-raw-query")]
+            raw_query: None,
         }
     }
 }
@@ -574,6 +665,8 @@ impl From<RangeQueryInput> for QueryInput {
             query_string: None,
             nested: None,
             boolean: None,
+            #[cfg(feature = "graphql-raw-query")]
+            raw_query: None,
         }
     }
 }
@@ -594,6 +687,8 @@ impl From<PrefixQueryInput> for QueryInput {
             query_string: None,
             nested: None,
             boolean: None,
+            #[cfg(feature = "graphql-raw-query")]
+            raw_query: None,
         }
     }
 }
@@ -614,6 +709,8 @@ impl From<RegexpQueryInput> for QueryInput {
             query_string: None,
             nested: None,
             boolean: None,
+            #[cfg(feature = "graphql-raw-query")]
+            raw_query: None,
         }
     }
 }
@@ -634,6 +731,8 @@ impl From<MatchQueryInput> for QueryInput {
             query_string: None,
             nested: None,
             boolean: None,
+            #[cfg(feature = "graphql-raw-query")]
+            raw_query: None,
         }
     }
 }
@@ -654,6 +753,8 @@ impl From<SimpleQueryStringQueryInput> for QueryInput {
             query_string: None,
             nested: None,
             boolean: None,
+            #[cfg(feature = "graphql-raw-query")]
+            raw_query: None,
         }
     }
 }
@@ -674,6 +775,8 @@ impl From<QueryStringQueryInput> for QueryInput {
             query_string: Some(filter),
             nested: None,
             boolean: None,
+            #[cfg(feature = "graphql-raw-query")]
+            raw_query: None,
         }
     }
 }
@@ -694,6 +797,8 @@ impl From<NestedQueryInput> for QueryInput {
             query_string: None,
             nested: Some(filter),
             boolean: None,
+            #[cfg(feature = "graphql-raw-query")]
+            raw_query: None,
         }
     }
 }
@@ -701,12 +806,16 @@ impl From<NestedQueryInput> for QueryInput {
 /// A single search query.
 ///
 /// **Note**: This should *never* have more than *one* defined (and non-null) field.
+///
+/// A query without a typed equivalent is deserialized as a [`RawQuery`], but
+/// an option without one (e.g. the `analyzer` of a `match` query) is an error
+/// rather than being dropped, as is an unknown option.
 #[allow(missing_docs)]
 #[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
 #[cfg_attr(feature = "graphql", graphql(name = "Filter"))]
 #[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub struct Query {
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -752,6 +861,50 @@ pub struct Query {
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     #[serde(rename = "bool", default, skip_serializing_if = "Option::is_none")]
     pub boolean: Option<BooleanQuery>,
+
+    /// A query that is serialized verbatim, for queries without a typed
+    /// equivalent.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[cfg_attr(feature = "graphql", graphql(name = "raw"))]
+    #[serde(flatten)]
+    pub raw_query: Option<RawQuery>,
+}
+
+impl Query {
+    /// Returns `true` if no query is set at all.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.exists.is_none()
+            && self.term.is_none()
+            && self.terms.is_none()
+            && self.range.is_none()
+            && self.prefix.is_none()
+            && self.regexp.is_none()
+            && self.match_.is_none()
+            && self.simple_query_string.is_none()
+            && self.query_string.is_none()
+            && self.nested.is_none()
+            && self.boolean.is_none()
+            && self.raw_query.is_none()
+    }
+
+    /// Constructs a new `Query` which is serialized verbatim as the `query`
+    /// JSON object.
+    ///
+    /// ```
+    /// use elastiql::search::query::Query;
+    /// use serde_json::json;
+    ///
+    /// let query = Query::raw(json!({ "geo_distance": { "distance": "12km", "location": [-70, 40] } }));
+    /// assert_eq!(
+    ///     serde_json::to_value(&query).unwrap(),
+    ///     json!({ "geo_distance": { "distance": "12km", "location": [-70, 40] } })
+    /// );
+    /// ```
+    #[inline]
+    pub fn raw(query: impl Into<RawQuery>) -> Query {
+        query.into().into()
+    }
 }
 
 #[cfg(feature = "graphql")]
@@ -770,6 +923,10 @@ impl From<QueryInput> for Query {
             query_string: input.query_string.map(Into::into),
             nested: input.nested.map(Into::into),
             boolean: input.boolean.map(Into::into),
+            #[cfg(feature = "graphql-raw-query")]
+            raw_query: input.raw_query,
+            #[cfg(not(feature = "graphql-raw-query"))]
+            raw_query: None,
         }
     }
 }
@@ -789,6 +946,7 @@ impl From<ExistsQuery> for Query {
             query_string: None,
             nested: None,
             boolean: None,
+            raw_query: None,
         }
     }
 }
@@ -808,6 +966,7 @@ impl From<TermQuery> for Query {
             query_string: None,
             nested: None,
             boolean: None,
+            raw_query: None,
         }
     }
 }
@@ -827,6 +986,7 @@ impl From<TermsQuery> for Query {
             query_string: None,
             nested: None,
             boolean: None,
+            raw_query: None,
         }
     }
 }
@@ -846,11 +1006,11 @@ impl From<RangeQuery> for Query {
             query_string: None,
             nested: None,
             boolean: None,
+            raw_query: None,
         }
     }
 }
 
-#[cfg(feature = "graphql")]
 impl From<PrefixQuery> for Query {
     #[inline]
     fn from(filter: PrefixQuery) -> Query {
@@ -866,6 +1026,7 @@ impl From<PrefixQuery> for Query {
             query_string: None,
             nested: None,
             boolean: None,
+            raw_query: None,
         }
     }
 }
@@ -885,6 +1046,7 @@ impl From<RegexpQuery> for Query {
             query_string: None,
             nested: None,
             boolean: None,
+            raw_query: None,
         }
     }
 }
@@ -905,6 +1067,7 @@ impl From<MatchQuery> for Query {
             query_string: None,
             nested: None,
             boolean: None,
+            raw_query: None,
         }
     }
 }
@@ -924,6 +1087,7 @@ impl From<SimpleQueryStringQuery> for Query {
             query_string: None,
             nested: None,
             boolean: None,
+            raw_query: None,
         }
     }
 }
@@ -943,6 +1107,7 @@ impl From<QueryStringQuery> for Query {
             query_string: Some(filter),
             nested: None,
             boolean: None,
+            raw_query: None,
         }
     }
 }
@@ -962,6 +1127,27 @@ impl From<NestedQuery> for Query {
             query_string: None,
             nested: Some(filter),
             boolean: None,
+            raw_query: None,
+        }
+    }
+}
+
+impl From<RawQuery> for Query {
+    #[inline]
+    fn from(filter: RawQuery) -> Query {
+        Query {
+            exists: None,
+            term: None,
+            terms: None,
+            range: None,
+            prefix: None,
+            regexp: None,
+            match_: None,
+            simple_query_string: None,
+            query_string: None,
+            nested: None,
+            boolean: None,
+            raw_query: Some(filter),
         }
     }
 }
@@ -989,3 +1175,492 @@ impl QueryField {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::{collection::vec, option, prelude::*};
+    use serde::de::DeserializeOwned;
+    use serde_json::json;
+
+    use crate::search::InnerHits;
+
+    /// Asserts that `value` is unchanged after serializing and deserializing it.
+    fn assert_round_trips<T>(value: &T)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        let j = serde_json::to_value(value).unwrap();
+        let actual: T = serde_json::from_value(j.clone())
+            .unwrap_or_else(|err| panic!("{} can't be deserialized: {}", j, err));
+        assert_eq!(&actual, value, "{}", j);
+    }
+
+    /// Every kind of leaf query, both with only its required options and with
+    /// every option set.
+    fn leaf_queries() -> Vec<Query> {
+        vec![
+            ExistsQuery::new("tags").into(),
+            ExistsQuery {
+                name: Some("has_tags".to_string()),
+                ..ExistsQuery::new("tags")
+            }
+            .into(),
+            TermQuery::new("status", "active").into(),
+            TermQuery {
+                boost: Some(1.5),
+                name: Some("status".to_string()),
+                ..TermQuery::new("status", "active")
+            }
+            .into(),
+            TermsQuery::new("tags", vec!["a", "b"]).into(),
+            TermsQuery {
+                boost: Some(0.5),
+                name: Some("tags".to_string()),
+                ..TermsQuery::new("tags", Vec::<String>::new())
+            }
+            .into(),
+            RangeQuery::builder()
+                .field("age".to_string())
+                .greater_than(Some("1".to_string()))
+                .build()
+                .into(),
+            RangeQuery::builder()
+                .field("age".to_string())
+                .greater_than_or_equal_to(Some("1".to_string()))
+                .less_than(Some("5".to_string()))
+                .less_than_or_equal_to(Some("4".to_string()))
+                .boost(Some(2.0))
+                .name(Some("age".to_string()))
+                .build()
+                .into(),
+            PrefixQuery::new("name", "jo").into(),
+            PrefixQuery {
+                case_insensitive: true,
+                name: Some("name".to_string()),
+                ..PrefixQuery::new("name", "jo")
+            }
+            .into(),
+            RegexpQuery::new("name", "jo.*", None).into(),
+            RegexpQuery {
+                case_insensitive: true,
+                max_determinized_states: Some(100),
+                rewrite: Some("constant_score".to_string()),
+                name: Some("name".to_string()),
+                ..RegexpQuery::new("name", "jo.*", Some(RegexpFlags::COMPLEMENT))
+            }
+            .into(),
+            MatchQuery::new("title", "rust").into(),
+            MatchQuery {
+                name: Some("title".to_string()),
+                ..MatchQuery::new("title", "rust")
+            }
+            .into(),
+            SimpleQueryStringQuery::new(vec!["title"], "rust -java").into(),
+            SimpleQueryStringQuery::builder()
+                .fields(vec!["title^2".to_string(), "body".to_string()])
+                .query("rust -java")
+                .default_operator(QueryStringBooleanOperator::And)
+                .analyzer(Some("english".to_string()))
+                .analyze_wildcard(true)
+                .auto_generate_synonyms_phrase_query(false)
+                .flags(Some(
+                    SimpleQueryStringFlags::AND | SimpleQueryStringFlags::NOT,
+                ))
+                .fuzzy_max_expansions(10u64)
+                .fuzzy_prefix_length(1u64)
+                .fuzzy_transpositions(false)
+                .lenient(true)
+                .minimum_should_match(Some("75%".to_string()))
+                .quote_field_suffix(Some(".exact".to_string()))
+                .name(Some("text".to_string()))
+                .build()
+                .into(),
+            QueryStringQuery::builder()
+                .query("status:active")
+                .fields(vec![])
+                .build()
+                .into(),
+            QueryStringQuery::builder()
+                .query("rust AND java")
+                .fields(vec!["title".to_string()])
+                .default_field(Some("body".to_string()))
+                .allow_leading_wildcard(false)
+                .analyze_wildcard(true)
+                .analyzer(Some("english".to_string()))
+                .quote_analyzer(Some("standard".to_string()))
+                .auto_generate_synonyms_phrase_query(false)
+                .boost(2.5)
+                .default_operator(QueryStringBooleanOperator::And)
+                .enable_position_increments(false)
+                .fuzziness(Some("AUTO".to_string()))
+                .fuzzy_max_expansions(10)
+                .fuzzy_prefix_length(1)
+                .fuzzy_transpositions(false)
+                .lenient(true)
+                .max_determinized_states(100)
+                .minimum_should_match(Some("2".to_string()))
+                .phrase_slop(3)
+                .quote_field_suffix(Some(".exact".to_string()))
+                .rewrite(Some("constant_score".to_string()))
+                .time_zone(Some("+01:00".to_string()))
+                .name(Some("text".to_string()))
+                .build()
+                .into(),
+            Query::raw(json!({ "geo_distance": { "distance": "12km", "location": [-70, 40] } })),
+        ]
+    }
+
+    /// Every kind of compound query, wrapping each of the given queries.
+    fn compound_queries(queries: &[Query]) -> Vec<Query> {
+        let mut compound = vec![];
+        for query in queries {
+            let boolean = BooleanQuery {
+                must: vec![query.clone()],
+                filter: vec![query.clone()],
+                should: vec![query.clone(), query.clone()],
+                must_not: vec![query.clone()],
+                minimum_should_match: Some("1".to_string()),
+                boost: Some(1.5),
+                name: Some("bool".to_string()),
+            };
+            compound.push(Query::builder().boolean(boolean).build());
+
+            compound.push(NestedQuery::new("comments", query.clone(), false).into());
+            compound.push(
+                NestedQuery {
+                    score_mode: Some(NestedScoreMode::Max),
+                    inner_hits: Some(InnerHits {
+                        name: Some("comments".to_string()),
+                        size: Some(5),
+                        ..InnerHits::default()
+                    }),
+                    name: Some("comments".to_string()),
+                    ..NestedQuery::new("comments", query.clone(), true)
+                }
+                .into(),
+            );
+        }
+        compound
+    }
+
+    #[test]
+    fn every_leaf_query_round_trips() {
+        for query in leaf_queries() {
+            assert_round_trips(&query);
+        }
+    }
+
+    #[test]
+    fn every_compound_query_round_trips() {
+        let queries = compound_queries(&leaf_queries());
+        for query in &queries {
+            assert_round_trips(query);
+        }
+
+        // and once more, to nest compound queries within compound queries
+        for query in compound_queries(&queries[..6]) {
+            assert_round_trips(&query);
+        }
+    }
+
+    #[test]
+    fn can_deserialize_shorthands() {
+        let query: Query = serde_json::from_value(json!({ "bool": {
+            "must": { "term": { "status": "active" } },
+            "should": [{ "match": { "title": "rust" } }, { "prefix": { "name": "jo" } }],
+            "minimum_should_match": 1
+        } }))
+        .unwrap();
+
+        let expected = BooleanQuery {
+            must: vec![TermQuery::new("status", "active").into()],
+            should: vec![
+                MatchQuery::new("title", "rust").into(),
+                PrefixQuery::new("name", "jo").into(),
+            ],
+            minimum_should_match: Some("1".to_string()),
+            ..BooleanQuery::default()
+        };
+        assert_eq!(query, Query::builder().boolean(expected).build());
+
+        let query: QueryStringQuery = serde_json::from_value(json!({
+            "query": "rust~",
+            "fuzziness": 2,
+            "minimum_should_match": 1
+        }))
+        .unwrap();
+        assert_eq!(query.fuzziness.as_deref(), Some("2"));
+        assert_eq!(query.minimum_should_match.as_deref(), Some("1"));
+    }
+
+    /// Generates an arbitrary field name, e.g. `comments.author`.
+    fn field() -> impl Strategy<Value = String> {
+        "[a-z_]{1,8}(\\.[a-z_]{1,8})?"
+    }
+
+    /// Generates an arbitrary leaf query with arbitrary options.
+    fn leaf_query() -> impl Strategy<Value = Query> {
+        let name = || option::of("[a-z]{1,8}");
+        let boost = || option::of(-1e6..1e6f64);
+        prop_oneof![
+            (field(), name()).prop_map(|(field, name)| ExistsQuery {
+                name,
+                ..ExistsQuery::new(field)
+            }
+            .into()),
+            (field(), any::<String>(), boost(), name()).prop_map(|(field, value, boost, name)| {
+                TermQuery {
+                    boost,
+                    name,
+                    ..TermQuery::new(field, value)
+                }
+                .into()
+            }),
+            (field(), vec(any::<String>(), 0..4), boost(), name()).prop_map(
+                |(field, values, boost, name)| {
+                    TermsQuery {
+                        boost,
+                        name,
+                        ..TermsQuery::new(field, values)
+                    }
+                    .into()
+                }
+            ),
+            (
+                field(),
+                vec(option::of(any::<String>()), 4),
+                boost(),
+                name()
+            )
+                .prop_map(|(field, bounds, boost, name)| {
+                    RangeQuery {
+                        field,
+                        greater_than: bounds[0].clone(),
+                        greater_than_or_equal_to: bounds[1].clone(),
+                        less_than: bounds[2].clone(),
+                        less_than_or_equal_to: bounds[3].clone(),
+                        boost,
+                        name,
+                    }
+                    .into()
+                }),
+            (field(), any::<String>(), any::<bool>(), name()).prop_map(
+                |(field, value, case_insensitive, name)| {
+                    PrefixQuery {
+                        case_insensitive,
+                        name,
+                        ..PrefixQuery::new(field, value)
+                    }
+                    .into()
+                }
+            ),
+            (
+                field(),
+                any::<String>(),
+                option::of(prop_oneof![
+                    Just(RegexpFlags::ALL),
+                    Just(RegexpFlags::NONE),
+                    Just(RegexpFlags::COMPLEMENT | RegexpFlags::INTERVAL),
+                ]),
+                any::<bool>(),
+                option::of(any::<u64>()),
+                name(),
+            )
+                .prop_map(
+                    |(field, value, flags, case_insensitive, max_determinized_states, name)| {
+                        RegexpQuery {
+                            case_insensitive,
+                            max_determinized_states,
+                            name,
+                            ..RegexpQuery::new(field, value, flags)
+                        }
+                        .into()
+                    }
+                ),
+            (
+                field(),
+                any::<String>(),
+                option::of(prop_oneof![
+                    Just(QueryStringBooleanOperator::Or),
+                    Just(QueryStringBooleanOperator::And)
+                ]),
+                name()
+            )
+                .prop_map(|(field, query, operator, name)| {
+                    MatchQuery {
+                        operator,
+                        name,
+                        ..MatchQuery::new(field, query)
+                    }
+                    .into()
+                }),
+            (
+                vec(field(), 0..3),
+                any::<String>(),
+                any::<bool>(),
+                option::of("[0-9]{1,2}%?")
+            )
+                .prop_map(|(fields, query, lenient, minimum_should_match)| {
+                    SimpleQueryStringQuery {
+                        lenient,
+                        minimum_should_match,
+                        ..SimpleQueryStringQuery::new(fields, query)
+                    }
+                    .into()
+                }),
+            (
+                vec(field(), 0..3),
+                option::of(field()),
+                any::<String>(),
+                any::<bool>(),
+                0.0..1e3f32
+            )
+                .prop_map(
+                    |(fields, default_field, query, allow_leading_wildcard, boost)| {
+                        QueryStringQuery::builder()
+                            .query(query)
+                            .fields(fields)
+                            .default_field(default_field)
+                            .allow_leading_wildcard(allow_leading_wildcard)
+                            .boost(boost)
+                            .build()
+                            .into()
+                    }
+                ),
+            (field(), any::<String>())
+                .prop_map(|(field, value)| Query::raw(json!({ "wildcard": { field: value } }))),
+        ]
+    }
+
+    /// Generates an arbitrary tree of `bool` and `nested` queries.
+    fn query() -> impl Strategy<Value = Query> {
+        leaf_query().prop_recursive(3, 24, 4, |inner| {
+            prop_oneof![
+                (
+                    vec(inner.clone(), 0..3),
+                    vec(inner.clone(), 0..3),
+                    vec(inner.clone(), 0..3),
+                    vec(inner.clone(), 0..3),
+                    option::of("[0-9]{1,2}"),
+                )
+                    .prop_map(
+                        |(must, filter, should, must_not, minimum_should_match)| {
+                            let boolean = BooleanQuery {
+                                must,
+                                filter,
+                                should,
+                                must_not,
+                                minimum_should_match,
+                                ..BooleanQuery::default()
+                            };
+                            Query::builder().boolean(boolean).build()
+                        }
+                    ),
+                (field(), inner, any::<bool>()).prop_map(|(path, query, ignore_unmapped)| {
+                    NestedQuery::new(path, query, ignore_unmapped).into()
+                }),
+            ]
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_queries_round_trip(query in query()) {
+            let j = serde_json::to_value(&query).unwrap();
+            let actual: Query = serde_json::from_value(j.clone()).unwrap();
+            prop_assert_eq!(actual, query, "{}", j);
+        }
+
+        #[test]
+        fn numbers_and_booleans_are_strings(
+            field in field(),
+            number in any::<i64>(),
+            float in -1e9..1e9f64,
+            boolean in any::<bool>(),
+        ) {
+            let j = json!({ "bool": { "filter": [
+                { "term": { &field: number } },
+                { "term": { &field: { "value": boolean, "boost": 2 } } },
+                { "terms": { &field: ["a", number, float, boolean] } },
+                { "range": { &field: { "gte": number, "lt": float } } },
+                { "match": { &field: float } },
+            ] } });
+            let query: Query = serde_json::from_value(j).unwrap();
+            let filter = query.boolean.clone().unwrap().filter;
+
+            prop_assert_eq!(&filter[0].term.as_ref().unwrap().value, &number.to_string());
+            prop_assert_eq!(&filter[1].term.as_ref().unwrap().value, &boolean.to_string());
+            let values = vec!["a".to_string(), number.to_string(), float.to_string(), boolean.to_string()];
+            prop_assert_eq!(&filter[2].terms.as_ref().unwrap().values, &values);
+            let range = filter[3].range.as_ref().unwrap();
+            prop_assert_eq!(range.greater_than_or_equal_to.clone(), Some(number.to_string()));
+            prop_assert_eq!(range.less_than.clone(), Some(float.to_string()));
+            prop_assert_eq!(&filter[4].match_.as_ref().unwrap().query, &float.to_string());
+
+            // and they stay the same once serialized as strings
+            let j = serde_json::to_value(&query).unwrap();
+            prop_assert_eq!(serde_json::from_value::<Query>(j).unwrap(), query);
+        }
+    }
+
+    #[test]
+    fn unsupported_options_are_err() {
+        for j in &[
+            json!({ "match": { "title": { "query": "rust", "fuzziness": "AUTO" } } }),
+            json!({ "range": { "created_at": { "gte": "now-1d", "format": "date" } } }),
+            json!({ "term": { "status": { "value": "active", "bogus": 1 } } }),
+            json!({ "bool": { "filter": [{ "exists": { "field": "a", "bogus": 1 } }] } }),
+            json!({ "nested": { "path": "a", "query": {}, "bogus": 1 } }),
+            json!({ "query_string": { "query": "rust", "bogus": 1 } }),
+        ] {
+            let err = serde_json::from_value::<Query>(j.clone()).unwrap_err();
+            assert!(
+                err.to_string().starts_with("unknown field"),
+                "{}: {}",
+                j,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn compound_query_wraps_other_queries() {
+        let query: CompoundQuery =
+            serde_json::from_value(json!({ "term": { "status": "active" } })).unwrap();
+        assert_eq!(
+            query,
+            BooleanQuery {
+                must: vec![TermQuery::new("status", "active").into()],
+                ..BooleanQuery::default()
+            }
+            .into()
+        );
+
+        let query: CompoundQuery = serde_json::from_value(json!({})).unwrap();
+        assert!(query.is_empty());
+    }
+
+    #[cfg(feature = "graphql")]
+    #[test]
+    fn inputs_only_keep_raw_queries_with_the_raw_query_feature() {
+        let term: Query = TermQuery::new("status", "active").into();
+        let query = Query {
+            raw_query: Query::raw(json!({ "geo_distance": { "distance": "12km" } })).raw_query,
+            ..term.clone()
+        };
+
+        let converted = Query::from(QueryInput::from(query.clone()));
+        #[cfg(feature = "graphql-raw-query")]
+        assert_eq!(
+            serde_json::to_value(&converted).unwrap(),
+            serde_json::to_value(&query).unwrap()
+        );
+        #[cfg(not(feature = "graphql-raw-query"))]
+        assert_eq!(
+            serde_json::to_value(&converted).unwrap(),
+            serde_json::to_value(&term).unwrap()
+        );
+    }
+}
@@ -5,6 +5,9 @@ use serde::{Deserialize, Serialize};
 use super::super::query::CompoundQuery;
 #[cfg(feature = "graphql")]
 use super::super::query::CompoundQueryInput;
+use crate::search::InnerHits;
+#[cfg(feature = "graphql")]
+use crate::search::InnerHitsInput;
 
 /// A [Nested query] wraps another query to search [nested] fields.
 ///
@@ -40,6 +43,26 @@ pub struct NestedQueryInput {
     #[graphql(default)]
     #[cfg_attr(feature = "builder", builder(default))]
     pub ignore_unmapped: bool,
+
+    /// How the scores of matching nested objects affect the root parent
+    /// document's relevance score. Defaults to `avg`.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score_mode: Option<NestedScoreMode>,
+
+    /// Returns the nested objects that matched, as the [inner hits] of each
+    /// search hit.
+    ///
+    /// [inner hits]: https://www.elastic.co/guide/en/elasticsearch/reference/current/inner-hits.html
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inner_hits: Option<InnerHitsInput>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(rename = "_name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 #[cfg(feature = "graphql")]
@@ -55,6 +78,9 @@ impl NestedQueryInput {
             path: path.into(),
             query: query.into(),
             ignore_unmapped,
+            score_mode: None,
+            inner_hits: None,
+            name: None,
         }
     }
 }
@@ -67,6 +93,9 @@ impl From<NestedQuery> for NestedQueryInput {
             path: query.path,
             query: query.query.into(),
             ignore_unmapped: query.ignore_unmapped,
+            score_mode: query.score_mode,
+            inner_hits: query.inner_hits.map(Into::into),
+            name: query.name,
         }
     }
 }
@@ -84,6 +113,7 @@ impl From<NestedQuery> for NestedQueryInput {
 #[cfg_attr(feature = "graphql", graphql(name = "NestedFilter"))]
 #[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct NestedQuery {
     /// Path to the nested object to search.
     #[cfg_attr(feature = "builder", builder(setter(into)))]
@@ -104,7 +134,28 @@ pub struct NestedQuery {
     /// Indicates whether to ignore an unmapped path and not return any
     /// documents instead of an error.
     #[cfg_attr(feature = "builder", builder(default))]
+    #[serde(default)]
     pub ignore_unmapped: bool,
+
+    /// How the scores of matching nested objects affect the root parent
+    /// document's relevance score. Defaults to `avg`.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_mode: Option<NestedScoreMode>,
+
+    /// Returns the nested objects that matched, as the [inner hits] of each
+    /// search hit.
+    ///
+    /// [inner hits]: https://www.elastic.co/guide/en/elasticsearch/reference/current/inner-hits.html
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inner_hits: Option<InnerHits>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, rename = "_name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 impl NestedQuery {
@@ -119,6 +170,9 @@ impl NestedQuery {
             path: path.into(),
             query: query.into(),
             ignore_unmapped,
+            score_mode: None,
+            inner_hits: None,
+            name: None,
         }
     }
 }
@@ -131,6 +185,114 @@ impl From<NestedQueryInput> for NestedQuery {
             path: input.path,
             query: input.query.into(),
             ignore_unmapped: input.ignore_unmapped,
+            score_mode: input.score_mode,
+            inner_hits: input.inner_hits.map(Into::into),
+            name: input.name,
         }
     }
 }
+
+/// How the scores of matching nested objects affect the root parent document's
+/// [relevance score].
+///
+/// [relevance score]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-filter-context.html#relevance-scores
+#[cfg_attr(all(test, not(feature = "graphql")), derive(PartialEq))]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum, Eq, PartialEq, Copy))]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum NestedScoreMode {
+    /// Use the mean relevance score of all matching child objects.
+    Avg,
+
+    /// Uses the highest relevance score of all matching child objects.
+    Max,
+
+    /// Uses the lowest relevance score of all matching child objects.
+    Min,
+
+    /// Do not use the relevance scores of matching child objects. The query
+    /// assigns parent documents a score of `0`.
+    None,
+
+    /// Add together the relevance scores of all matching child objects.
+    Sum,
+}
+
+impl Default for NestedScoreMode {
+    #[inline]
+    fn default() -> Self {
+        NestedScoreMode::Avg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::search::{
+        query::{Query, TermQuery},
+        SourceFilter,
+    };
+
+    #[test]
+    fn can_serialize_with_inner_hits() {
+        let mut query =
+            NestedQuery::new("comments", TermQuery::new("comments.author", "jane"), false);
+        query.score_mode = Some(NestedScoreMode::Max);
+        query.inner_hits = Some(InnerHits {
+            name: Some("matched_comments".to_string()),
+            size: Some(5),
+            source: Some(SourceFilter::new(
+                vec!["comments.text"],
+                Vec::<String>::new(),
+            )),
+            ..InnerHits::default()
+        });
+
+        let expected = json!({
+            "path": "comments",
+            "query": { "bool": { "filter": [{ "term": { "comments.author": { "value": "jane" } } }] } },
+            "ignore_unmapped": false,
+            "score_mode": "max",
+            "inner_hits": {
+                "name": "matched_comments",
+                "size": 5,
+                "_source": ["comments.text"]
+            }
+        });
+        assert_eq!(serde_json::to_value(&query).unwrap(), expected);
+        assert_eq!(
+            serde_json::from_value::<NestedQuery>(expected).unwrap(),
+            query
+        );
+    }
+
+    #[test]
+    fn can_deserialize_without_ignore_unmapped() {
+        let query: Query = serde_json::from_value(json!({
+            "bool": {
+                "filter": {
+                    "nested": {
+                        "path": "comments",
+                        "query": { "term": { "comments.author": "jane" } }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let nested = query.boolean.unwrap().filter.remove(0).nested.unwrap();
+        assert_eq!(nested.path, "comments");
+        assert!(!nested.ignore_unmapped);
+    }
+
+    #[test]
+    fn deserialize_invalid_nested_query_is_err() {
+        let err = serde_json::from_value::<Query>(json!({
+            "bool": { "filter": [{ "nested": { "query": { "match_all": {} } } }] }
+        }))
+        .unwrap_err();
+        assert_eq!(err.to_string(), "missing field `path`");
+    }
+}
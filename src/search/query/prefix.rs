@@ -6,12 +6,17 @@ use serde::de::{self, MapAccess, Visitor};
 use serde::ser::{SerializeMap, Serializer};
 use serde::{Deserialize, Serialize};
 
+use super::de::Shorthand;
+
 #[allow(clippy::missing_docs_in_private_items)]
 #[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct InnerPrefixQuery {
     value: String,
     #[serde(default)]
     case_insensitive: bool,
+    #[serde(default, rename = "_name", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
 }
 
 /// A [Prefix query] returns documents that contain a specific prefix in a
@@ -37,6 +42,11 @@ pub struct PrefixQueryInput {
     #[graphql(default)]
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     pub case_insensitive: bool,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub name: Option<String>,
 }
 
 #[cfg(feature = "graphql")]
@@ -48,6 +58,7 @@ impl PrefixQueryInput {
             field: field.into(),
             value: value.into(),
             case_insensitive: false,
+            name: None,
         }
     }
 }
@@ -60,6 +71,7 @@ impl From<PrefixQuery> for PrefixQueryInput {
             field: query.field,
             value: query.value,
             case_insensitive: query.case_insensitive,
+            name: query.name,
         }
     }
 }
@@ -73,6 +85,7 @@ impl Serialize for PrefixQueryInput {
         let inner = InnerPrefixQuery {
             value: self.value.to_owned(),
             case_insensitive: self.case_insensitive,
+            name: self.name.to_owned(),
         };
 
         map.serialize_entry(&self.field, &inner)?;
@@ -104,6 +117,11 @@ pub struct PrefixQuery {
     /// sensitivity of matching depends on the underlying field’s mapping.
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     pub case_insensitive: bool,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub name: Option<String>,
 }
 
 impl PrefixQuery {
@@ -114,6 +132,7 @@ impl PrefixQuery {
             field: field.into(),
             value: value.into(),
             case_insensitive: false,
+            name: None,
         }
     }
 }
@@ -126,6 +145,7 @@ impl From<PrefixQueryInput> for PrefixQuery {
             field: input.field,
             value: input.value,
             case_insensitive: input.case_insensitive,
+            name: input.name,
         }
     }
 }
@@ -139,6 +159,7 @@ impl Serialize for PrefixQuery {
         let inner = InnerPrefixQuery {
             value: self.value.to_owned(),
             case_insensitive: self.case_insensitive,
+            name: self.name.to_owned(),
         };
 
         map.serialize_entry(&self.field, &inner)?;
@@ -175,12 +196,20 @@ impl<'de> Visitor<'de> for PrefixQueryVisitor {
             .next_key::<String>()?
             .ok_or_else(|| de::Error::missing_field("field"))?;
 
-        let inner: InnerPrefixQuery = map.next_value()?;
+        let inner = match map.next_value()? {
+            Shorthand::Options(inner) => inner,
+            Shorthand::Value(value) => InnerPrefixQuery {
+                value,
+                case_insensitive: false,
+                name: None,
+            },
+        };
 
         Ok(PrefixQuery {
             field,
             value: inner.value.to_owned(),
             case_insensitive: inner.case_insensitive,
+            name: inner.name,
         })
     }
 }
@@ -215,6 +244,7 @@ mod tests {
             field: "userProfile".to_string(),
             value: "Kimchy".to_string(),
             case_insensitive: false,
+            name: None,
         },
         json!({ "userProfile": { "value": "Kimchy", "case_insensitive": false } })
     );
@@ -225,6 +255,7 @@ mod tests {
             field: "user".to_string(),
             value: "Kimchy".to_string(),
             case_insensitive: true,
+            name: None,
         },
         json!({ "user": { "value": "Kimchy", "case_insensitive": true } })
     );
@@ -235,6 +266,7 @@ mod tests {
             field: "user".to_string(),
             value: "Kimchy".to_string(),
             case_insensitive: false,
+            name: None,
         },
         json!({ "user": { "value": "Kimchy", "case_insensitive": false } })
     );
@@ -258,11 +290,16 @@ mod tests {
     }
 
     #[test]
-    fn deserialize_missing_values_is_err() {
-        // TODO: should we support this Elasticsearch schema?
-        let j = r#"{ "user": "missing" }"#;
-        assert!(serde_json::from_str::<PrefixQuery>(j).is_err(), "{}", &j);
+    fn can_deserialize_shorthand() {
+        let j = r#"{ "user": "ki" }"#;
+        assert_eq!(
+            serde_json::from_str::<PrefixQuery>(j).unwrap(),
+            PrefixQuery::new("user", "ki")
+        );
+    }
 
+    #[test]
+    fn deserialize_missing_values_is_err() {
         let j = r#"{ "user": null }"#;
         assert!(serde_json::from_str::<PrefixQuery>(j).is_err(), "{}", &j);
 
@@ -2,6 +2,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use super::QueryStringError;
+
 // NOTE: some fields require `skip_serializing_if` otherwise Elasticsearch
 // will return an error if e.g. `null` is used
 
@@ -252,6 +254,12 @@ pub struct QueryStringQueryInput {
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub time_zone: Option<String>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, rename = "_name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 #[cfg(feature = "graphql")]
@@ -281,6 +289,7 @@ impl From<QueryStringQuery> for QueryStringQueryInput {
             quote_field_suffix: query.quote_field_suffix,
             rewrite: query.rewrite,
             time_zone: query.time_zone,
+            name: query.name,
         }
     }
 }
@@ -300,6 +309,7 @@ impl From<QueryStringQuery> for QueryStringQueryInput {
 #[cfg_attr(feature = "graphql", graphql(name = "QueryStringFilter"))]
 #[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct QueryStringQuery {
     /// The query to run in the [simple query string syntax](https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-simple-query-string-query.html#simple-query-string-syntax).
     #[cfg_attr(feature = "builder", builder(setter(into)))]
@@ -414,7 +424,11 @@ pub struct QueryStringQuery {
     ///
     /// [Fuzziness]: https://www.elastic.co/guide/en/elasticsearch/reference/current/common-options.html#fuzziness
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "super::de::string_or_number",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub fuzziness: Option<String>,
 
     /// Maximum number of terms to which the query expands for fuzzy matching.
@@ -471,7 +485,11 @@ pub struct QueryStringQuery {
     /// [`minimum_should_match` parameter]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-minimum-should-match.html
     /// [How `minimum_should_match` works]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-query-string-query.html#query-string-min-should-match
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "super::de::string_or_number",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub minimum_should_match: Option<String>,
 
     /// Maximum number of positions allowed between matching tokens for phrases. Defaults to `0`. If `0`, exact phrase matches are required. Transposed terms have a slop of `2`.
@@ -517,20 +535,99 @@ pub struct QueryStringQuery {
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub time_zone: Option<String>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, rename = "_name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl QueryStringQuery {
+    /// Escapes the reserved characters of `literal`, so it can be used in a
+    /// query string as-is.
+    ///
+    /// The reserved characters `+ - = & | ! ( ) { } [ ] ^ " ~ * ? : \ /` are
+    /// prefixed with a `\`, as are the `AND`, `OR` and `NOT` keywords. `<` and
+    /// `>` can't be escaped at all, so they are removed.
+    ///
+    /// ```
+    /// use elastiql::search::query::QueryStringQuery;
+    ///
+    /// assert_eq!(QueryStringQuery::escape("(1+1):2"), r"\(1\+1\)\:2");
+    /// assert_eq!(QueryStringQuery::escape("x\tOR\ty"), "x\t\\OR\ty");
+    /// ```
+    #[inline]
+    pub fn escape(literal: &str) -> String {
+        super::lucene::escape(literal)
+    }
+
+    /// Checks that the `query` is valid Lucene [query string syntax], so it
+    /// won't be rejected by Elasticsearch.
+    ///
+    /// Leading wildcards are an error when `allow_leading_wildcard` is
+    /// `false`.
+    ///
+    /// [query string syntax]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-query-string-query.html#query-string-syntax
+    #[inline]
+    pub fn validate(&self) -> Result<(), QueryStringError> {
+        super::lucene::validate(&self.query, self.allow_leading_wildcard)
+    }
+
+    /// Returns the fields referenced *inside* the `query`, e.g. `title` for
+    /// `title:rust`, or `ssn` for `_exists_:ssn`. Field names may contain
+    /// wildcards, e.g. `*:rust` searches every field.
+    ///
+    /// ```
+    /// use elastiql::search::query::QueryStringQuery;
+    /// use serde_json::json;
+    ///
+    /// let query: QueryStringQuery = serde_json::from_value(json!({
+    ///     "query": "title:rust AND (body:web OR _exists_:tags)",
+    /// }))
+    /// .unwrap();
+    /// assert_eq!(query.query_fields().unwrap(), vec!["title", "body", "tags"]);
+    /// ```
+    #[inline]
+    pub fn query_fields(&self) -> Result<Vec<String>, QueryStringError> {
+        super::lucene::fields(&self.query)
+    }
+
+    /// Returns the regular expressions inside the `query`, e.g. `.*son` for
+    /// `name:/.*son/`.
+    #[inline]
+    pub(crate) fn regexes(&self) -> Result<Vec<&str>, QueryStringError> {
+        super::lucene::regexes(&self.query)
+    }
+
+    /// Strips the leading wildcards of all terms in the `query` when
+    /// `allow_leading_wildcard` is `false`, then [validates](Self::validate)
+    /// it. A term consisting of only wildcards becomes `*`.
+    ///
+    /// The `query` is left untouched if it can't be tokenized, e.g. because of
+    /// an unterminated phrase.
+    pub fn sanitize(&mut self) -> Result<(), QueryStringError> {
+        if !self.allow_leading_wildcard {
+            self.query = super::lucene::strip_leading_wildcards(&self.query)?;
+        }
+        self.validate()
+    }
 }
 
 /// Boolean logic operator used to interpret/combine words in the query string.
 #[cfg_attr(all(test, not(feature = "graphql")), derive(PartialEq))]
-#[cfg_attr(feature = "graphql", derive(async_graphql::Enum, Eq, PartialEq, Copy))]
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum, Eq, PartialEq))]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum QueryStringBooleanOperator {
     /// For example, a query string of `capital of Hungary` is interpreted as
     /// `capital OR of OR Hungary`.
+    #[serde(alias = "or")]
     Or,
 
     /// For example, a query string of `capital of Hungary` is interpreted as
     /// `capital AND of AND Hungary`.
+    #[serde(alias = "and")]
     And,
 }
 
@@ -567,15 +664,16 @@ impl From<QueryStringQueryInput> for QueryStringQuery {
             quote_field_suffix: input.quote_field_suffix,
             rewrite: input.rewrite,
             time_zone: input.time_zone,
+            name: input.name,
         }
     }
 }
 
-fn default_true() -> bool {
+pub(crate) fn default_true() -> bool {
     true
 }
 
-fn default_fifty_u64() -> u64 {
+pub(super) fn default_fifty_u64() -> u64 {
     50
 }
 
@@ -12,21 +12,44 @@ use serde::{
 
 #[allow(clippy::missing_docs_in_private_items)]
 #[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct InnerRangeQuery {
-    #[serde(rename = "gt", default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        rename = "gt",
+        default,
+        deserialize_with = "super::de::string_or_number",
+        skip_serializing_if = "Option::is_none"
+    )]
     greater_than: Option<String>,
 
-    #[serde(rename = "gte", default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        rename = "gte",
+        default,
+        deserialize_with = "super::de::string_or_number",
+        skip_serializing_if = "Option::is_none"
+    )]
     greater_than_or_equal_to: Option<String>,
 
-    #[serde(rename = "lt", default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        rename = "lt",
+        default,
+        deserialize_with = "super::de::string_or_number",
+        skip_serializing_if = "Option::is_none"
+    )]
     less_than: Option<String>,
 
-    #[serde(rename = "lte", default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        rename = "lte",
+        default,
+        deserialize_with = "super::de::string_or_number",
+        skip_serializing_if = "Option::is_none"
+    )]
     less_than_or_equal_to: Option<String>,
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     boost: Option<f64>,
+    #[serde(default, rename = "_name", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
 }
 
 /// A [Range query] returns documents that contain terms within a provided range.
@@ -38,6 +61,7 @@ struct InnerRangeQuery {
 #[graphql(name = "RangeFilterInput")]
 pub struct RangeQueryInput {
     /// The name of the field to query.
+    #[cfg_attr(feature = "builder", builder(setter(into)))]
     pub field: String,
 
     /// Greater than.
@@ -81,6 +105,11 @@ pub struct RangeQueryInput {
     /// [relevance scores]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-filter-context.html#relevance-scores
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     pub boost: Option<f64>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub name: Option<String>,
 }
 
 #[cfg(feature = "graphql")]
@@ -94,6 +123,7 @@ impl From<RangeQuery> for RangeQueryInput {
             less_than: query.less_than,
             less_than_or_equal_to: query.less_than_or_equal_to,
             boost: query.boost,
+            name: query.name,
         }
     }
 }
@@ -110,6 +140,7 @@ impl Serialize for RangeQueryInput {
             less_than: self.less_than.as_ref().map(|v| v.to_owned()),
             less_than_or_equal_to: self.less_than_or_equal_to.as_ref().map(|v| v.to_owned()),
             boost: self.boost,
+            name: self.name.to_owned(),
         };
 
         map.serialize_entry(&self.field, &inner)?;
@@ -128,6 +159,7 @@ impl Serialize for RangeQueryInput {
 #[derive(Clone, Debug)]
 pub struct RangeQuery {
     /// The name of the field to query.
+    #[cfg_attr(feature = "builder", builder(setter(into)))]
     pub field: String,
 
     /// Greater than.
@@ -171,6 +203,11 @@ pub struct RangeQuery {
     /// [relevance scores]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-filter-context.html#relevance-scores
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     pub boost: Option<f64>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub name: Option<String>,
 }
 
 #[cfg(feature = "graphql")]
@@ -184,6 +221,7 @@ impl From<RangeQueryInput> for RangeQuery {
             less_than: input.less_than,
             less_than_or_equal_to: input.less_than_or_equal_to,
             boost: input.boost,
+            name: input.name,
         }
     }
 }
@@ -200,6 +238,7 @@ impl Serialize for RangeQuery {
             less_than: self.less_than.as_ref().map(|v| v.to_owned()),
             less_than_or_equal_to: self.less_than_or_equal_to.as_ref().map(|v| v.to_owned()),
             boost: self.boost,
+            name: self.name.to_owned(),
         };
 
         map.serialize_entry(&self.field, &inner)?;
@@ -245,6 +284,7 @@ impl<'de> Visitor<'de> for RangeQueryVisitor {
             less_than: inner.less_than,
             less_than_or_equal_to: inner.less_than_or_equal_to,
             boost: inner.boost,
+            name: inner.name,
         };
 
         Ok(filter)
@@ -284,6 +324,7 @@ mod tests {
             less_than: None,
             less_than_or_equal_to: Some("20".to_string()),
             boost: None,
+            name: None,
         },
         json!({ "currentAge": { "gte": "10", "lte": "20" } })
     );
@@ -297,6 +338,7 @@ mod tests {
             less_than: None,
             less_than_or_equal_to: Some("20".to_string()),
             boost: Some(2.0),
+            name: None,
         },
         json!({ "age": { "gte": "10", "lte": "20", "boost": 2.0 } })
     );
@@ -310,6 +352,7 @@ mod tests {
             less_than: None,
             less_than_or_equal_to: Some("20".to_string()),
             boost: None,
+            name: None,
         },
         json!({ "age": { "gte": "10", "lte": "20" } })
     );
@@ -377,27 +420,28 @@ mod tests {
     }
 
     #[test]
-    fn deserialize_invalid_ranges_is_err() {
-        // TODO: should we support this Elasticsearch schema?
-        let j = r#"{ "age": { "gt": 1.1 } }"#;
-        assert!(
-            serde_json::from_str::<RangeQuery>(j).is_err(),
-            "test case: {}",
-            &j
-        );
+    fn can_deserialize_numbers() {
+        let j = json!({ "age": { "gt": 1.1, "gte": 30, "lt": 40.5, "lte": 999 } });
+        let query = serde_json::from_value::<RangeQuery>(j).unwrap();
+        assert_eq!(query.greater_than.as_deref(), Some("1.1"));
+        assert_eq!(query.greater_than_or_equal_to.as_deref(), Some("30"));
+        assert_eq!(query.less_than.as_deref(), Some("40.5"));
+        assert_eq!(query.less_than_or_equal_to.as_deref(), Some("999"));
+    }
 
-        let j = r#"{ "age": { "gte": 1 } }"#;
+    #[test]
+    fn deserialize_invalid_ranges_is_err() {
+        let j = r#"{ "age": { "gt": [1] } }"#;
         assert!(
             serde_json::from_str::<RangeQuery>(j).is_err(),
             "test case: {}",
             &j
         );
 
-        let j = r#"{ "age": { "lt": 999 } }"#;
-        assert!(
-            serde_json::from_str::<RangeQuery>(j).is_err(),
-            "test case: {}",
-            &j
-        );
+        for option in &["format", "time_zone", "relation", "bogus"] {
+            let j = json!({ "created_at": { "gte": "now-1d", *option: "x" } });
+            let err = serde_json::from_value::<RangeQuery>(j).unwrap_err();
+            assert!(err.to_string().starts_with("unknown field"), "{}", err);
+        }
     }
 }
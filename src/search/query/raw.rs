@@ -0,0 +1,120 @@
+//! Raw JSON queries, an escape hatch for queries without a typed equivalent.
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::scalars::Map;
+
+/// A query that is serialized verbatim, e.g.
+/// `{ "geo_distance": { "distance": "12km", "location": [-70, 40] } }`.
+///
+/// This is an escape hatch for queries elastiql doesn't support (yet), so a
+/// single unsupported clause doesn't force the whole request to be built as
+/// raw JSON.
+///
+/// **Note**: a raw query is *not* inspected, e.g. by an [`AccessPolicy`] or
+/// when rendering a query as a filter expression. GraphQL clients may only
+/// send raw queries if the `graphql-raw-query` feature is enabled.
+///
+/// [`AccessPolicy`]: crate::policy::AccessPolicy
+#[derive(Clone, PartialEq, Debug)]
+pub struct RawQuery(pub Map);
+
+impl RawQuery {
+    /// Constructs a new `RawQuery` from a JSON object.
+    #[inline]
+    pub fn new(query: impl Into<Map>) -> RawQuery {
+        RawQuery(query.into())
+    }
+}
+
+impl From<Map> for RawQuery {
+    #[inline]
+    fn from(query: Map) -> Self {
+        RawQuery(query)
+    }
+}
+
+impl From<serde_json::Value> for RawQuery {
+    #[inline]
+    fn from(query: serde_json::Value) -> Self {
+        RawQuery(query.into())
+    }
+}
+
+impl Serialize for RawQuery {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RawQuery {
+    /// Deserializes any non-empty JSON object.
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let query = Map::deserialize(deserializer)?;
+        if query.is_empty() {
+            return Err(de::Error::invalid_length(0, &"a non-empty JSON object"));
+        }
+        Ok(RawQuery(query))
+    }
+}
+
+#[cfg(feature = "graphql")]
+#[async_graphql::Scalar(name = "RawFilter")]
+impl async_graphql::ScalarType for RawQuery {
+    #[inline]
+    fn parse(value: async_graphql::Value) -> async_graphql::InputValueResult<Self> {
+        let query = <Map as async_graphql::ScalarType>::parse(value)
+            .map_err(async_graphql::InputValueError::propagate)?;
+        Ok(RawQuery(query))
+    }
+
+    #[inline]
+    fn to_value(&self) -> async_graphql::Value {
+        async_graphql::ScalarType::to_value(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::search::query::{CompoundQuery, Query, TermQuery};
+
+    #[test]
+    fn is_serialized_verbatim() {
+        let mut query = CompoundQuery::default();
+        query.push(TermQuery::new("status", "active"));
+        query.push(Query::raw(
+            json!({ "wildcard": { "name": { "value": "*son" } } }),
+        ));
+
+        assert_eq!(
+            serde_json::to_value(&query).unwrap(),
+            json!({ "bool": { "filter": [
+                { "term": { "status": { "value": "active" } } },
+                { "wildcard": { "name": { "value": "*son" } } },
+            ] } })
+        );
+    }
+
+    #[test]
+    fn unknown_queries_are_deserialized_as_raw() {
+        let j = json!({ "geo_distance": { "distance": "12km", "location": [-70, 40] } });
+        let query: Query = serde_json::from_value(j.clone()).unwrap();
+        assert_eq!(query, Query::raw(j));
+
+        let query: Query =
+            serde_json::from_value(json!({ "term": { "status": { "value": "active" } } })).unwrap();
+        assert_eq!(query, TermQuery::new("status", "active").into());
+    }
+
+    #[test]
+    fn empty_object_is_err() {
+        assert!(serde_json::from_value::<RawQuery>(json!({})).is_err());
+        assert!(serde_json::from_value::<RawQuery>(json!([])).is_err());
+    }
+}
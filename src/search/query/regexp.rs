@@ -1,12 +1,43 @@
 //! [Regexp query](https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-regexp-query.html#query-dsl-regexp-query)
 
-use std::{collections::HashMap, fmt};
+use std::fmt;
+#[cfg(feature = "eval")]
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
+};
 
 use serde::{
     de::{self, MapAccess, Visitor},
-    ser::{Serialize, SerializeMap, Serializer},
+    ser::{SerializeMap, Serializer},
+    Deserialize, Serialize,
 };
 
+use super::de::Shorthand;
+
+#[allow(clippy::missing_docs_in_private_items)]
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InnerRegexpQuery {
+    value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    flags: Option<RegexpFlags>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    case_insensitive: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_determinized_states: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rewrite: Option<String>,
+    #[serde(default, rename = "_name", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+#[inline]
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
 /// A [Regexp query] returns documents that contain terms matching a
 /// [regular expression].
 ///
@@ -36,13 +67,45 @@ pub struct RegexpQueryInput {
     /// Enables optional operators for the regular expression. For valid values
     /// and more information, see [Regular expression syntax].
     ///
-    /// To enable multiple operators, use a `|` separator. For example, a flags
-    /// value of `COMPLEMENT|INTERVAL` enables the `COMPLEMENT` and `INTERVAL`
-    /// operators.
+    /// Multiple operators are combined using `|`, e.g.
+    /// `RegexpFlags::COMPLEMENT | RegexpFlags::INTERVAL`, which is serialized
+    /// as `COMPLEMENT|INTERVAL`. Defaults to `ALL`.
     ///
     /// [Regular expression syntax]: https://www.elastic.co/guide/en/elasticsearch/reference/current/regexp-syntax.html#regexp-optional-operators
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    pub flags: Option<String>,
+    pub flags: Option<RegexpFlags>,
+
+    /// If `true`, allows case insensitive matching of the regular expression
+    /// value with the indexed field values. Defaults to `false`.
+    #[graphql(default)]
+    #[cfg_attr(feature = "builder", builder(default))]
+    pub case_insensitive: bool,
+
+    /// Maximum number of [automaton states] required for the query. Defaults
+    /// to `10000`.
+    ///
+    /// Elasticsearch uses [Apache Lucene] internally to parse regular
+    /// expressions. Lucene converts each regular expression to a finite
+    /// automaton containing a number of determinized states. You can use this
+    /// parameter to prevent that conversion from unintentionally consuming too
+    /// many resources.
+    ///
+    /// [automaton states]: https://en.wikipedia.org/wiki/Deterministic_finite_automaton
+    /// [Apache Lucene]: https://lucene.apache.org/core/
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub max_determinized_states: Option<u64>,
+
+    /// Method used to rewrite the query. For valid values and more information,
+    /// see the [`rewrite` parameter].
+    ///
+    /// [`rewrite` parameter]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-multi-term-rewrite.html
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub rewrite: Option<String>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub name: Option<String>,
 }
 
 #[cfg(feature = "graphql")]
@@ -52,12 +115,16 @@ impl RegexpQueryInput {
     pub fn new(
         field: impl Into<String>,
         value: impl Into<String>,
-        flags: Option<impl Into<String>>,
+        flags: Option<RegexpFlags>,
     ) -> RegexpQueryInput {
         RegexpQueryInput {
             field: field.into(),
             value: value.into(),
-            flags: flags.map(Into::into),
+            flags,
+            case_insensitive: false,
+            max_determinized_states: None,
+            rewrite: None,
+            name: None,
         }
     }
 }
@@ -70,6 +137,10 @@ impl From<RegexpQuery> for RegexpQueryInput {
             field: query.field,
             value: query.value,
             flags: query.flags,
+            case_insensitive: query.case_insensitive,
+            max_determinized_states: query.max_determinized_states,
+            rewrite: query.rewrite,
+            name: query.name,
         }
     }
 }
@@ -80,15 +151,16 @@ impl Serialize for RegexpQueryInput {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         let mut map = serializer.serialize_map(Some(1))?;
 
-        let mut values: HashMap<&str, &str> = HashMap::new();
-
-        values.insert("value", &self.value);
-
-        if let Some(flags) = &self.flags {
-            values.insert("flags", flags);
-        }
+        let inner = InnerRegexpQuery {
+            value: self.value.to_owned(),
+            flags: self.flags,
+            case_insensitive: self.case_insensitive,
+            max_determinized_states: self.max_determinized_states,
+            rewrite: self.rewrite.to_owned(),
+            name: self.name.to_owned(),
+        };
 
-        map.serialize_entry(&self.field, &values)?;
+        map.serialize_entry(&self.field, &inner)?;
 
         map.end()
     }
@@ -124,13 +196,44 @@ pub struct RegexpQuery {
     /// Enables optional operators for the regular expression. For valid values
     /// and more information, see [Regular expression syntax].
     ///
-    /// To enable multiple operators, use a `|` separator. For example, a flags
-    /// value of `COMPLEMENT|INTERVAL` enables the `COMPLEMENT` and `INTERVAL`
-    /// operators.
+    /// Multiple operators are combined using `|`, e.g.
+    /// `RegexpFlags::COMPLEMENT | RegexpFlags::INTERVAL`, which is serialized
+    /// as `COMPLEMENT|INTERVAL`. Defaults to `ALL`.
     ///
     /// [Regular expression syntax]: https://www.elastic.co/guide/en/elasticsearch/reference/current/regexp-syntax.html#regexp-optional-operators
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    pub flags: Option<String>,
+    pub flags: Option<RegexpFlags>,
+
+    /// If `true`, allows case insensitive matching of the regular expression
+    /// value with the indexed field values. Defaults to `false`.
+    #[cfg_attr(feature = "builder", builder(default))]
+    pub case_insensitive: bool,
+
+    /// Maximum number of [automaton states] required for the query. Defaults
+    /// to `10000`.
+    ///
+    /// Elasticsearch uses [Apache Lucene] internally to parse regular
+    /// expressions. Lucene converts each regular expression to a finite
+    /// automaton containing a number of determinized states. You can use this
+    /// parameter to prevent that conversion from unintentionally consuming too
+    /// many resources.
+    ///
+    /// [automaton states]: https://en.wikipedia.org/wiki/Deterministic_finite_automaton
+    /// [Apache Lucene]: https://lucene.apache.org/core/
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub max_determinized_states: Option<u64>,
+
+    /// Method used to rewrite the query. For valid values and more information,
+    /// see the [`rewrite` parameter].
+    ///
+    /// [`rewrite` parameter]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-multi-term-rewrite.html
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub rewrite: Option<String>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub name: Option<String>,
 }
 
 impl RegexpQuery {
@@ -139,14 +242,44 @@ impl RegexpQuery {
     pub fn new(
         field: impl Into<String>,
         value: impl Into<String>,
-        flags: Option<impl Into<String>>,
+        flags: Option<RegexpFlags>,
     ) -> RegexpQuery {
         RegexpQuery {
             field: field.into(),
             value: value.into(),
-            flags: flags.map(Into::into),
+            flags,
+            case_insensitive: false,
+            max_determinized_states: None,
+            rewrite: None,
+            name: None,
         }
     }
+
+    /// Checks that the `value` is a valid Lucene [regular expression], taking
+    /// the enabled `flags` into account, so it won't be rejected by
+    /// Elasticsearch.
+    ///
+    /// ```
+    /// use elastiql::search::query::{RegexpErrorKind, RegexpQuery};
+    ///
+    /// assert!(RegexpQuery::new("user", "k.*y", None).validate().is_ok());
+    ///
+    /// let err = RegexpQuery::new("user", "k(i|y", None).validate().unwrap_err();
+    /// assert_eq!(err.kind, RegexpErrorKind::Expected(')'));
+    /// assert_eq!(err.position, 5);
+    /// ```
+    ///
+    /// [regular expression]: https://www.elastic.co/guide/en/elasticsearch/reference/current/regexp-syntax.html
+    #[inline]
+    pub fn validate(&self) -> Result<(), RegexpError> {
+        validate(&self.value, self.flags.unwrap_or(RegexpFlags::ALL))
+    }
+
+    /// Parses the regular expression, e.g. to evaluate it in memory.
+    #[inline]
+    pub(crate) fn parse(&self) -> Result<Regexp, RegexpError> {
+        parse(&self.value, self.flags.unwrap_or(RegexpFlags::ALL))
+    }
 }
 
 #[cfg(feature = "graphql")]
@@ -157,6 +290,10 @@ impl From<RegexpQueryInput> for RegexpQuery {
             field: input.field,
             value: input.value,
             flags: input.flags,
+            case_insensitive: input.case_insensitive,
+            max_determinized_states: input.max_determinized_states,
+            rewrite: input.rewrite,
+            name: input.name,
         }
     }
 }
@@ -167,16 +304,16 @@ impl Serialize for RegexpQuery {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         let mut map = serializer.serialize_map(Some(1))?;
 
-        let mut values: HashMap<&str, &str> = HashMap::new();
-
-        values.insert("value", &self.value);
-
-        // TODO: should we check for invalid flags?
-        if let Some(flags) = &self.flags {
-            values.insert("flags", flags);
-        }
+        let inner = InnerRegexpQuery {
+            value: self.value.to_owned(),
+            flags: self.flags,
+            case_insensitive: self.case_insensitive,
+            max_determinized_states: self.max_determinized_states,
+            rewrite: self.rewrite.to_owned(),
+            name: self.name.to_owned(),
+        };
 
-        map.serialize_entry(&self.field, &values)?;
+        map.serialize_entry(&self.field, &inner)?;
 
         map.end()
     }
@@ -210,22 +347,716 @@ impl<'de> Visitor<'de> for RegexpQueryVisitor {
             .next_key::<String>()?
             .ok_or_else(|| de::Error::missing_field("field"))?;
 
-        let values: HashMap<String, String> = map.next_value()?;
-
-        let value = values
-            .get("value")
-            .ok_or_else(|| de::Error::missing_field("value"))?
-            .to_string();
-
-        // TODO: should we check for invalid flags?
-        let flags = values.get("flags").cloned();
+        let inner = match map.next_value()? {
+            Shorthand::Options(inner) => inner,
+            Shorthand::Value(value) => InnerRegexpQuery {
+                value,
+                flags: None,
+                case_insensitive: false,
+                max_determinized_states: None,
+                rewrite: None,
+                name: None,
+            },
+        };
 
         Ok(RegexpQuery {
             field,
-            value,
-            flags,
+            value: inner.value,
+            flags: inner.flags,
+            case_insensitive: inner.case_insensitive,
+            max_determinized_states: inner.max_determinized_states,
+            rewrite: inner.rewrite,
+            name: inner.name,
+        })
+    }
+}
+
+flags! {
+    /// The [optional operators] enabled for a regular expression, combined
+    /// using `|`, e.g. `RegexpFlags::COMPLEMENT | RegexpFlags::INTERVAL`.
+    ///
+    /// Serialized as a pipe-delimited string, e.g. `"COMPLEMENT|INTERVAL"`.
+    ///
+    /// [optional operators]: https://www.elastic.co/guide/en/elasticsearch/reference/current/regexp-syntax.html#regexp-optional-operators
+    pub struct RegexpFlags {
+        /// Enables the `&` operator, which acts as an AND operator, e.g.
+        /// `aaa.+&.+bbb` matches `aaabbb`.
+        const INTERSECTION = 1;
+
+        /// Enables the `~` operator, which negates the shortest following
+        /// pattern, e.g. `a~bc` matches `adc` but not `abc`.
+        const COMPLEMENT = 1 << 1;
+
+        /// Enables the `#` (empty language) operator, which matches nothing.
+        const EMPTY = 1 << 2;
+
+        /// Enables the `@` operator, which matches any entire string.
+        const ANYSTRING = 1 << 3;
+
+        /// Enables the `<>` operators, which match a numeric range, e.g.
+        /// `foo<1-100>` matches `foo1` and `foo100`.
+        const INTERVAL = 1 << 5;
+    }
+}
+
+/// The reason a regular expression is not valid Lucene regular expression
+/// syntax.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegexpErrorKind {
+    /// The regular expression ended while more was expected, e.g. after a
+    /// trailing `\`.
+    UnexpectedEnd,
+
+    /// The given character was expected, e.g. a closing `)`.
+    Expected(char),
+
+    /// A character that is not valid at this position, e.g. an unmatched `)`.
+    UnexpectedCharacter(char),
+
+    /// A repetition, i.e. `{n,m}`, is missing an integer.
+    IntegerExpected,
+
+    /// A repetition, i.e. `{n,m}`, whose maximum is smaller than its minimum.
+    InvalidRepetition,
+
+    /// A character class range whose start is after its end, e.g. `[z-a]`.
+    InvalidCharacterRange(char, char),
+
+    /// A numeric interval, i.e. `<n-m>`, that is not made up of two integers.
+    InvalidInterval,
+
+    /// The expression is nested deeper than the given maximum, e.g. by groups
+    /// or repetitions.
+    TooDeep(usize),
+}
+
+impl fmt::Display for RegexpErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegexpErrorKind::UnexpectedEnd => f.write_str("unexpected end of the expression"),
+            RegexpErrorKind::Expected(c) => write!(f, "expected `{}`", c),
+            RegexpErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected `{}`", c),
+            RegexpErrorKind::IntegerExpected => f.write_str("expected an integer"),
+            RegexpErrorKind::InvalidRepetition => {
+                f.write_str("the maximum of a repetition must not be smaller than its minimum")
+            }
+            RegexpErrorKind::InvalidCharacterRange(from, to) => {
+                write!(f, "invalid character range `{}-{}`", from, to)
+            }
+            RegexpErrorKind::InvalidInterval => {
+                f.write_str("expected a numeric interval of the form `<n-m>`")
+            }
+            RegexpErrorKind::TooDeep(max) => {
+                write!(f, "expression is nested deeper than {} levels", max)
+            }
+        }
+    }
+}
+
+/// An error in the syntax of a regular expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegexpError {
+    /// The reason the regular expression is invalid.
+    pub kind: RegexpErrorKind,
+
+    /// The byte offset in the regular expression the error refers to.
+    pub position: usize,
+}
+
+impl fmt::Display for RegexpError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.kind, self.position)
+    }
+}
+
+impl std::error::Error for RegexpError {}
+
+/// The maximum depth of groups, complements, intersections and repetitions.
+const MAX_DEPTH: usize = 32;
+
+/// Checks that `pattern` is valid using the same grammar as Lucene's `RegExp`.
+#[inline]
+fn validate(pattern: &str, flags: RegexpFlags) -> Result<(), RegexpError> {
+    parse(pattern, flags).map(|_| ())
+}
+
+/// Parses `pattern` using the same grammar as Lucene's `RegExp`.
+fn parse(pattern: &str, flags: RegexpFlags) -> Result<Regexp, RegexpError> {
+    if pattern.is_empty() {
+        return Ok(Regexp::Empty);
+    }
+    let mut parser = RegexpParser {
+        pattern,
+        chars: pattern.char_indices().collect(),
+        pos: 0,
+        flags,
+        depth: 0,
+    };
+    let regexp = parser.union()?;
+    match parser.peek() {
+        Some(c) => Err(parser.error(RegexpErrorKind::UnexpectedCharacter(c))),
+        None => Ok(regexp),
+    }
+}
+
+/// A parsed Lucene regular expression.
+#[cfg_attr(not(feature = "eval"), allow(dead_code))]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Regexp {
+    /// `()`, which matches the empty string.
+    Empty,
+
+    /// `#`, which matches no string at all.
+    Nothing,
+
+    /// `.`, which matches any character.
+    AnyChar,
+
+    /// `@`, which matches any string.
+    AnyString,
+
+    /// A single (possibly escaped) character.
+    Char(char),
+
+    /// A quoted string, e.g. `"a.b"`, which is matched literally.
+    String(String),
+
+    /// A character class, e.g. `[^a-z]`.
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+
+    /// A numeric interval, e.g. `<1-100>`.
+    Interval(u64, u64),
+
+    /// `ab`
+    Concatenation(Vec<Regexp>),
+
+    /// `a|b`
+    Union(Vec<Regexp>),
+
+    /// `a&b`
+    Intersection(Box<Regexp>, Box<Regexp>),
+
+    /// `~a`
+    Complement(Box<Regexp>),
+
+    /// `a?`, `a*`, `a+` or `a{n,m}`
+    Repeat {
+        regexp: Box<Regexp>,
+        min: u32,
+        max: Option<u32>,
+    },
+}
+
+/// A recursive descent parser for the Lucene regular expression syntax.
+struct RegexpParser<'a> {
+    pattern: &'a str,
+    chars: Vec<(usize, char)>,
+    pos: usize,
+    flags: RegexpFlags,
+
+    /// The current depth of groups, complements, intersections and
+    /// repetitions.
+    depth: usize,
+}
+
+impl<'a> RegexpParser<'a> {
+    /// The byte offset of the current character.
+    #[inline]
+    fn position(&self) -> usize {
+        self.chars
+            .get(self.pos)
+            .map_or(self.pattern.len(), |&(i, _)| i)
+    }
+
+    #[inline]
+    fn error(&self, kind: RegexpErrorKind) -> RegexpError {
+        RegexpError {
+            kind,
+            position: self.position(),
+        }
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).map(|&(_, c)| c)
+    }
+
+    #[inline]
+    fn eat(&mut self, c: char) -> bool {
+        let matches = self.peek() == Some(c);
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    /// Consumes `c` if the operator `flag` is enabled.
+    #[inline]
+    fn eat_operator(&mut self, flag: RegexpFlags, c: char) -> bool {
+        self.flags.contains(flag) && self.eat(c)
+    }
+
+    #[inline]
+    fn expect(&mut self, c: char) -> Result<(), RegexpError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(RegexpErrorKind::Expected(c)))
+        }
+    }
+
+    /// Increases the depth, failing at the operator at `position` if it
+    /// exceeds the maximum.
+    #[inline]
+    fn descend(&mut self, position: usize) -> Result<(), RegexpError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            Err(RegexpError {
+                kind: RegexpErrorKind::TooDeep(MAX_DEPTH),
+                position,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    #[inline]
+    fn next(&mut self) -> Result<char, RegexpError> {
+        let c = self
+            .peek()
+            .ok_or_else(|| self.error(RegexpErrorKind::UnexpectedEnd))?;
+        self.pos += 1;
+        Ok(c)
+    }
+
+    /// `inter ( '|' inter )*`
+    fn union(&mut self) -> Result<Regexp, RegexpError> {
+        let mut alternatives = vec![self.intersection()?];
+        while self.eat('|') {
+            alternatives.push(self.intersection()?);
+        }
+        Ok(if alternatives.len() == 1 {
+            alternatives.remove(0)
+        } else {
+            Regexp::Union(alternatives)
         })
     }
+
+    /// `concat ( '&' inter )?`
+    fn intersection(&mut self) -> Result<Regexp, RegexpError> {
+        let regexp = self.concatenation()?;
+        let start = self.position();
+        if self.eat_operator(RegexpFlags::INTERSECTION, '&') {
+            self.descend(start)?;
+            let other = self.intersection()?;
+            self.depth -= 1;
+            return Ok(Regexp::Intersection(Box::new(regexp), Box::new(other)));
+        }
+        Ok(regexp)
+    }
+
+    /// `repeat+`
+    fn concatenation(&mut self) -> Result<Regexp, RegexpError> {
+        let mut parts = vec![self.repeat()?];
+        while let Some(c) = self.peek() {
+            if c == ')' || c == '|' || (c == '&' && self.flags.contains(RegexpFlags::INTERSECTION))
+            {
+                break;
+            }
+            parts.push(self.repeat()?);
+        }
+        Ok(if parts.len() == 1 {
+            parts.remove(0)
+        } else {
+            Regexp::Concatenation(parts)
+        })
+    }
+
+    /// `complement ( '?' | '*' | '+' | '{' n ( ',' m? )? '}' )*`
+    fn repeat(&mut self) -> Result<Regexp, RegexpError> {
+        let mut regexp = self.complement()?;
+        let depth = self.depth;
+        loop {
+            let start = self.position();
+            let (min, max) = if self.eat('?') {
+                (0, Some(1))
+            } else if self.eat('*') {
+                (0, None)
+            } else if self.eat('+') {
+                (1, None)
+            } else if self.eat('{') {
+                let min = self.integer()?;
+                let max = if self.eat(',') {
+                    match self.peek() {
+                        Some(c) if c.is_ascii_digit() => Some(self.integer()?),
+                        _ => None,
+                    }
+                } else {
+                    Some(min)
+                };
+                self.expect('}')?;
+                if matches!(max, Some(max) if max < min) {
+                    return Err(RegexpError {
+                        kind: RegexpErrorKind::InvalidRepetition,
+                        position: start,
+                    });
+                }
+                (min, max)
+            } else {
+                // each repetition nested the expression one level deeper
+                self.depth = depth;
+                return Ok(regexp);
+            };
+            self.descend(start)?;
+            regexp = Regexp::Repeat {
+                regexp: Box::new(regexp),
+                min,
+                max,
+            };
+        }
+    }
+
+    /// `'~' complement | class`
+    fn complement(&mut self) -> Result<Regexp, RegexpError> {
+        let start = self.position();
+        if self.eat_operator(RegexpFlags::COMPLEMENT, '~') {
+            self.descend(start)?;
+            let regexp = self.complement()?;
+            self.depth -= 1;
+            Ok(Regexp::Complement(Box::new(regexp)))
+        } else {
+            self.class()
+        }
+    }
+
+    /// `'[' '^'? ( char ( '-' char )? )+ ']' | simple`
+    fn class(&mut self) -> Result<Regexp, RegexpError> {
+        if !self.eat('[') {
+            return self.simple();
+        }
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        loop {
+            let from = self.character()?;
+            let position = self.position();
+            let to = if self.eat('-') {
+                let to = self.character()?;
+                if from > to {
+                    return Err(RegexpError {
+                        kind: RegexpErrorKind::InvalidCharacterRange(from, to),
+                        position,
+                    });
+                }
+                to
+            } else {
+                from
+            };
+            ranges.push((from, to));
+            if matches!(self.peek(), None | Some(']')) {
+                break;
+            }
+        }
+        self.expect(']')?;
+        Ok(Regexp::Class { negated, ranges })
+    }
+
+    /// `'.' | '#' | '@' | '"' string '"' | '(' union? ')' | '<' n '-' m '>' | char`
+    fn simple(&mut self) -> Result<Regexp, RegexpError> {
+        let start = self.position();
+        if self.eat('.') {
+            Ok(Regexp::AnyChar)
+        } else if self.eat_operator(RegexpFlags::EMPTY, '#') {
+            Ok(Regexp::Nothing)
+        } else if self.eat_operator(RegexpFlags::ANYSTRING, '@') {
+            Ok(Regexp::AnyString)
+        } else if self.eat('"') {
+            let mut string = String::new();
+            while let Some(c) = self.peek().filter(|&c| c != '"') {
+                string.push(c);
+                self.pos += 1;
+            }
+            self.expect('"')?;
+            Ok(Regexp::String(string))
+        } else if self.eat('(') {
+            if self.eat(')') {
+                return Ok(Regexp::Empty);
+            }
+            self.descend(start)?;
+            let regexp = self.union()?;
+            self.expect(')')?;
+            self.depth -= 1;
+            Ok(regexp)
+        } else if self.flags.contains(RegexpFlags::INTERVAL) && self.peek() == Some('<') {
+            self.pos += 1;
+            let interval_start = self.pos;
+            while matches!(self.peek(), Some(c) if c != '>') {
+                self.pos += 1;
+            }
+            let interval: String = self.chars[interval_start..self.pos]
+                .iter()
+                .map(|&(_, c)| c)
+                .collect();
+            self.expect('>')?;
+            let integer = |s: &str| {
+                Some(s)
+                    .filter(|s| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()))
+                    .and_then(|s| s.parse().ok())
+            };
+            let bounds = interval
+                .find('-')
+                .and_then(|i| Some((integer(&interval[..i])?, integer(&interval[i + 1..])?)));
+            match bounds {
+                Some((from, to)) => Ok(Regexp::Interval(from, to)),
+                None => Err(RegexpError {
+                    kind: RegexpErrorKind::InvalidInterval,
+                    position: start,
+                }),
+            }
+        } else {
+            self.character().map(Regexp::Char)
+        }
+    }
+
+    /// `'\' any | any`
+    #[inline]
+    fn character(&mut self) -> Result<char, RegexpError> {
+        self.eat('\\');
+        self.next()
+    }
+
+    /// `[0-9]+`
+    fn integer(&mut self) -> Result<u32, RegexpError> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let digits: String = self.chars[start..self.pos]
+            .iter()
+            .map(|&(_, c)| c)
+            .collect();
+        digits.parse().map_err(|_| RegexpError {
+            kind: RegexpErrorKind::IntegerExpected,
+            position: self
+                .chars
+                .get(start)
+                .map_or(self.pattern.len(), |&(i, _)| i),
+        })
+    }
+}
+
+impl Regexp {
+    /// Returns `true` if a match may start with a wildcard, i.e. this regular
+    /// expression does not have a literal prefix, e.g. `.*son`, `[a-z]+son` or
+    /// `~(jo)`.
+    ///
+    /// A character class is only considered literal if it lists single
+    /// characters, e.g. `[Jj]`.
+    pub(crate) fn has_leading_wildcard(&self) -> bool {
+        match self {
+            Regexp::Empty | Regexp::Nothing | Regexp::Char(_) | Regexp::String(_) => false,
+            Regexp::AnyChar | Regexp::AnyString | Regexp::Interval(..) | Regexp::Complement(_) => {
+                true
+            }
+            Regexp::Class { negated, ranges } => {
+                *negated || ranges.iter().any(|(from, to)| from != to)
+            }
+            Regexp::Concatenation(parts) => {
+                for part in parts {
+                    if part.has_leading_wildcard() {
+                        return true;
+                    }
+                    if !part.is_nullable() {
+                        return false;
+                    }
+                }
+                false
+            }
+            Regexp::Union(alternatives) => alternatives.iter().any(Regexp::has_leading_wildcard),
+            // a match has to start with the prefix of both
+            Regexp::Intersection(a, b) => a.has_leading_wildcard() && b.has_leading_wildcard(),
+            Regexp::Repeat { regexp, .. } => regexp.has_leading_wildcard(),
+        }
+    }
+
+    /// Returns `true` if this regular expression matches the empty string.
+    fn is_nullable(&self) -> bool {
+        match self {
+            Regexp::Empty | Regexp::AnyString => true,
+            Regexp::String(string) => string.is_empty(),
+            Regexp::Nothing
+            | Regexp::AnyChar
+            | Regexp::Char(_)
+            | Regexp::Class { .. }
+            | Regexp::Interval(..) => false,
+            Regexp::Concatenation(parts) => parts.iter().all(Regexp::is_nullable),
+            Regexp::Union(alternatives) => alternatives.iter().any(Regexp::is_nullable),
+            Regexp::Intersection(a, b) => a.is_nullable() && b.is_nullable(),
+            Regexp::Complement(regexp) => !regexp.is_nullable(),
+            Regexp::Repeat { regexp, min, .. } => *min == 0 || regexp.is_nullable(),
+        }
+    }
+}
+
+#[cfg(feature = "eval")]
+impl Regexp {
+    /// Returns `true` if the *whole* `value` matches this regular expression.
+    pub(crate) fn is_match(&self, value: &str, case_insensitive: bool) -> bool {
+        let chars: Vec<char> = value.chars().collect();
+        let matcher = RegexpMatcher {
+            chars: &chars,
+            case_insensitive,
+            memo: RefCell::default(),
+        };
+        matcher.ends(self, 0).contains(&chars.len())
+    }
+}
+
+/// Matches a [`Regexp`] by computing every position a (sub-)expression can end
+/// at, which keeps complements & intersections simple.
+#[cfg(feature = "eval")]
+struct RegexpMatcher<'a> {
+    chars: &'a [char],
+    case_insensitive: bool,
+
+    /// The ends of each (sub-)expression by its start, so that e.g. nested
+    /// repetitions don't match the same sub-expression over and over again.
+    memo: RefCell<HashMap<(*const Regexp, usize), BTreeSet<usize>>>,
+}
+
+#[cfg(feature = "eval")]
+impl<'a> RegexpMatcher<'a> {
+    /// Returns `true` if the characters `a` and `b` are considered equal.
+    #[inline]
+    fn eq(&self, a: char, b: char) -> bool {
+        a == b || (self.case_insensitive && a.to_lowercase().eq(b.to_lowercase()))
+    }
+
+    /// Returns every position `regexp` can end at when starting at `start`.
+    fn ends(&self, regexp: &Regexp, start: usize) -> BTreeSet<usize> {
+        let key: (*const Regexp, usize) = (regexp, start);
+        if let Some(ends) = self.memo.borrow().get(&key) {
+            return ends.clone();
+        }
+
+        let ends = self.compute_ends(regexp, start);
+        self.memo.borrow_mut().insert(key, ends.clone());
+        ends
+    }
+
+    /// Returns every position `regexp` can end at when starting at any of the
+    /// `starts`.
+    fn ends_from(&self, regexp: &Regexp, starts: &BTreeSet<usize>) -> BTreeSet<usize> {
+        starts
+            .iter()
+            .flat_map(|&start| self.ends(regexp, start))
+            .collect()
+    }
+
+    /// Computes every position `regexp` can end at when starting at `start`.
+    fn compute_ends(&self, regexp: &Regexp, start: usize) -> BTreeSet<usize> {
+        let len = self.chars.len();
+        let next = self.chars.get(start).copied();
+        match regexp {
+            Regexp::Empty => std::iter::once(start).collect(),
+            Regexp::Nothing => BTreeSet::new(),
+            Regexp::AnyChar => next.map(|_| start + 1).into_iter().collect(),
+            Regexp::AnyString => (start..=len).collect(),
+            Regexp::Char(c) => next
+                .filter(|&n| self.eq(n, *c))
+                .map(|_| start + 1)
+                .into_iter()
+                .collect(),
+            Regexp::String(string) => {
+                let mut end = start;
+                for c in string.chars() {
+                    match self.chars.get(end) {
+                        Some(&n) if self.eq(n, c) => end += 1,
+                        _ => return BTreeSet::new(),
+                    }
+                }
+                std::iter::once(end).collect()
+            }
+            Regexp::Class { negated, ranges } => {
+                let in_class = |c: char| {
+                    let candidates = if self.case_insensitive {
+                        c.to_lowercase().chain(c.to_uppercase()).collect()
+                    } else {
+                        vec![c]
+                    };
+                    candidates
+                        .into_iter()
+                        .any(|c| ranges.iter().any(|&(from, to)| from <= c && c <= to))
+                };
+                next.filter(|&n| in_class(n) != *negated)
+                    .map(|_| start + 1)
+                    .into_iter()
+                    .collect()
+            }
+            Regexp::Interval(from, to) => {
+                let (min, max) = (from.min(to), from.max(to));
+                (start + 1..=len)
+                    .take_while(|&end| self.chars[end - 1].is_ascii_digit())
+                    .filter(|&end| {
+                        let digits: String = self.chars[start..end].iter().collect();
+                        matches!(digits.parse::<u64>(), Ok(n) if *min <= n && n <= *max)
+                    })
+                    .collect()
+            }
+            Regexp::Concatenation(parts) => parts
+                .iter()
+                .fold(std::iter::once(start).collect(), |starts, part| {
+                    self.ends_from(part, &starts)
+                }),
+            Regexp::Union(alternatives) => alternatives
+                .iter()
+                .flat_map(|alternative| self.ends(alternative, start))
+                .collect(),
+            Regexp::Intersection(a, b) => {
+                let b = self.ends(b, start);
+                self.ends(a, start)
+                    .into_iter()
+                    .filter(|end| b.contains(end))
+                    .collect()
+            }
+            Regexp::Complement(regexp) => {
+                let ends = self.ends(regexp, start);
+                (start..=len).filter(|end| !ends.contains(end)).collect()
+            }
+            Regexp::Repeat { regexp, min, max } => {
+                // the positions after exactly `min` repetitions, which stay the
+                // same once a repetition doesn't change them anymore
+                let mut current: BTreeSet<usize> = std::iter::once(start).collect();
+                for _ in 0..*min {
+                    let next = self.ends_from(regexp, &current);
+                    if next == current {
+                        break;
+                    }
+                    current = next;
+                }
+
+                // every optional repetition only has to continue from the
+                // positions no fewer repetitions reached, so this stops after at
+                // most `len` repetitions
+                let optional = max.map(|max| max.saturating_sub(*min));
+                let mut ends = current.clone();
+                let mut frontier = current;
+                let mut count = 0;
+                while !frontier.is_empty() && optional != Some(count) {
+                    frontier = self
+                        .ends_from(regexp, &frontier)
+                        .into_iter()
+                        .filter(|&end| ends.insert(end))
+                        .collect();
+                    count += 1;
+                }
+                ends
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -258,6 +1089,10 @@ mod tests {
             field: "userProfile".to_string(),
             value: "k.*y".to_string(),
             flags: None,
+            case_insensitive: false,
+            max_determinized_states: None,
+            rewrite: None,
+            name: None,
         },
         json!({ "userProfile": { "value": "k.*y" } })
     );
@@ -267,7 +1102,11 @@ mod tests {
         RegexpQuery {
             field: "user".to_string(),
             value: "k.*y".to_string(),
-            flags: Some("ALL".to_string()),
+            flags: Some(RegexpFlags::ALL),
+            case_insensitive: false,
+            max_determinized_states: None,
+            rewrite: None,
+            name: None,
         },
         json!({ "user": { "value": "k.*y", "flags": "ALL" } })
     );
@@ -278,16 +1117,163 @@ mod tests {
             field: "user".to_string(),
             value: "k.*y".to_string(),
             flags: None,
+            case_insensitive: false,
+            max_determinized_states: None,
+            rewrite: None,
+            name: None,
         },
         json!({ "user": { "value": "k.*y" } })
     );
 
+    test_case!(
+        with_all_options:
+        RegexpQuery {
+            field: "user".to_string(),
+            value: "k<1-9>~y".to_string(),
+            flags: Some(RegexpFlags::COMPLEMENT | RegexpFlags::INTERVAL),
+            case_insensitive: true,
+            max_determinized_states: Some(10_000),
+            rewrite: Some("constant_score".to_string()),
+            name: None,
+        },
+        json!({
+            "user": {
+                "value": "k<1-9>~y",
+                "flags": "COMPLEMENT|INTERVAL",
+                "case_insensitive": true,
+                "max_determinized_states": 10000,
+                "rewrite": "constant_score"
+            }
+        })
+    );
+
     #[test]
-    fn deserialize_missing_values_is_err() {
-        // TODO: should we support this Elasticsearch schema?
-        let j = r#"{ "user": "missing" }"#;
-        assert!(serde_json::from_str::<RegexpQuery>(j).is_err(), "{}", &j);
+    fn can_validate() {
+        let patterns = [
+            "",
+            "k.*y",
+            "ab?c+d*",
+            "a{2}b{2,}c{2,3}",
+            "(ab|cd)+|ef",
+            "[a-z0-9_]+[^-.]",
+            r#""a.b"c"#,
+            r"\(\)\[\\",
+            "()",
+            "@&.*bbb",
+            "a~bc",
+            "#|foo<1-100>",
+        ];
+        for pattern in &patterns {
+            assert_eq!(validate(pattern, RegexpFlags::ALL), Ok(()), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn invalid_pattern_is_err() {
+        use RegexpErrorKind::*;
+
+        let err = |pattern| {
+            let err = validate(pattern, RegexpFlags::ALL).unwrap_err();
+            (err.kind, err.position)
+        };
+
+        assert_eq!(err("k(i|y"), (Expected(')'), 5));
+        assert_eq!(err("ky)"), (UnexpectedCharacter(')'), 2));
+        assert_eq!(err("[a-z"), (Expected(']'), 4));
+        assert_eq!(err("[z-a]"), (InvalidCharacterRange('z', 'a'), 2));
+        assert_eq!(err(r#""abc"#), (Expected('"'), 4));
+        assert_eq!(err("a{,2}"), (IntegerExpected, 2));
+        assert_eq!(err("a{3,2}"), (InvalidRepetition, 1));
+        assert_eq!(err("a{2"), (Expected('}'), 3));
+        assert_eq!(err(r"ab\"), (UnexpectedEnd, 3));
+        assert_eq!(err("a&"), (UnexpectedEnd, 2));
+        assert_eq!(err("a|"), (UnexpectedEnd, 2));
+        assert_eq!(err("foo<1-x>"), (InvalidInterval, 3));
+        assert_eq!(err("foo<1-2"), (Expected('>'), 7));
+    }
+
+    #[test]
+    fn too_deep_is_err() {
+        let patterns = [
+            "(".repeat(200_000),
+            format!("{}a{}", "(".repeat(40), ")".repeat(40)),
+            format!("{}a", "~".repeat(200_000)),
+            format!("{}a", "a&".repeat(200_000)),
+            format!("a{}", "*".repeat(200_000)),
+        ];
+        for pattern in &patterns {
+            let err = RegexpQuery::new("user", pattern.as_str(), None)
+                .validate()
+                .unwrap_err();
+            assert_eq!(err.kind, RegexpErrorKind::TooDeep(MAX_DEPTH));
+        }
+        let err = validate(&"(".repeat(40), RegexpFlags::ALL).unwrap_err();
+        assert_eq!(err.position, 32);
+
+        let pattern = format!("{}a{}{}", "(".repeat(31), ")".repeat(31), "?".repeat(32));
+        assert_eq!(validate(&pattern, RegexpFlags::ALL), Ok(()));
+    }
 
+    #[test]
+    fn detects_leading_wildcards() {
+        let leading_wildcard = |pattern| {
+            parse(pattern, RegexpFlags::ALL)
+                .unwrap()
+                .has_leading_wildcard()
+        };
+
+        for pattern in &[
+            ".*son",
+            "@son",
+            "(.*)son",
+            "[a-z]*son",
+            "[^j]o",
+            "~(a)",
+            "a*.*",
+            "a?b?.*",
+            "(jo|.)",
+            "<1-9>0",
+            ".*&.*",
+        ] {
+            assert!(leading_wildcard(pattern), "{}", pattern);
+        }
+        for pattern in &[
+            "",
+            "jo.*",
+            "(jo)+.*",
+            "[Jj]o.*",
+            "\"jo\".*",
+            "(jo|ki).*",
+            "j.*&.*",
+            "a+.*",
+            "a{1,2}.*",
+        ] {
+            assert!(!leading_wildcard(pattern), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn disabled_operators_are_literals() {
+        for pattern in &["a&", "foo<1-x>", "foo<1-2", "~", "#@"] {
+            assert_eq!(validate(pattern, RegexpFlags::NONE), Ok(()), "{}", pattern);
+        }
+        assert_eq!(
+            RegexpQuery::new("user", "a&", Some(RegexpFlags::COMPLEMENT)).validate(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn can_deserialize_shorthand() {
+        let j = r#"{ "user": "k.*y" }"#;
+        assert_eq!(
+            serde_json::from_str::<RegexpQuery>(j).unwrap(),
+            RegexpQuery::new("user", "k.*y", None)
+        );
+    }
+
+    #[test]
+    fn deserialize_missing_values_is_err() {
         let j = r#"{ "user": null }"#;
         assert!(serde_json::from_str::<RegexpQuery>(j).is_err(), "{}", &j);
 
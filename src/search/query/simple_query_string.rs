@@ -2,7 +2,11 @@
 
 use serde::{Deserialize, Serialize};
 
-// TODO: add additional options
+use super::{
+    query_string::{default_fifty_u64, default_true},
+    QueryStringBooleanOperator,
+};
+
 /// A [Simple query string] returns documents based on a provided query string,
 /// using a parser with a limited but fault-tolerant syntax.
 ///
@@ -39,6 +43,105 @@ pub struct SimpleQueryStringQueryInput {
     /// The query to run in the [simple query string syntax](https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-simple-query-string-query.html#simple-query-string-syntax).
     #[cfg_attr(feature = "builder", builder(setter(into)))]
     pub query: String,
+
+    /// Default boolean logic used to interpret text in the query string if no
+    /// operators are specified. Defaults to `OR`.
+    #[cfg_attr(feature = "builder", builder(default))]
+    #[serde(default)]
+    #[graphql(default)]
+    pub default_operator: QueryStringBooleanOperator,
+
+    /// [Analyzer] used to convert text in the query string into tokens.
+    /// Defaults to the [index-time analyzer] mapped for the default_field. If
+    /// no analyzer is mapped, the index’s default analyzer is used.
+    ///
+    /// [Analyzer]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis.html
+    /// [index-time analyzer]: https://www.elastic.co/guide/en/elasticsearch/reference/current/specify-analyzer.html#specify-index-time-analyzer
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analyzer: Option<String>,
+
+    /// If `true`, the query attempts to analyze wildcard terms in the query
+    /// string. Defaults to `false`.
+    #[cfg_attr(feature = "builder", builder(default))]
+    #[serde(default)]
+    #[graphql(default)]
+    pub analyze_wildcard: bool,
+
+    /// If `true`, the parser creates a [`match_phrase`] query for each
+    /// [multi-position token]. Defaults to `true`. For examples, see
+    /// [Multi-position tokens].
+    ///
+    /// [`match_phrase`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-match-query-phrase.html
+    /// [multi-position token]: https://www.elastic.co/guide/en/elasticsearch/reference/current/token-graphs.html#token-graphs-multi-position-tokens
+    /// [Multi-position tokens]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-simple-query-string-query.html#simple-query-string-synonyms
+    #[cfg_attr(feature = "builder", builder(default_code = "true"))]
+    #[serde(default = "default_true")]
+    #[graphql(default = true)]
+    pub auto_generate_synonyms_phrase_query: bool,
+
+    /// List of enabled operators for the [simple query string syntax].
+    /// Defaults to `ALL` (all operators). See [Limit operators] for valid
+    /// values.
+    ///
+    /// [simple query string syntax]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-simple-query-string-query.html#simple-query-string-syntax
+    /// [Limit operators]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-simple-query-string-query.html#supported-flags
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flags: Option<SimpleQueryStringFlags>,
+
+    /// Maximum number of terms to which the query expands for fuzzy matching.
+    /// Defaults to `50`.
+    #[cfg_attr(feature = "builder", builder(default_code = "default_fifty_u64()"))]
+    #[serde(default = "default_fifty_u64")]
+    #[graphql(default_with = "default_fifty_u64()")]
+    pub fuzzy_max_expansions: u64,
+
+    /// Number of beginning characters left unchanged for fuzzy matching.
+    /// Defaults to `0`.
+    #[cfg_attr(feature = "builder", builder(default))]
+    #[serde(default)]
+    #[graphql(default)]
+    pub fuzzy_prefix_length: u64,
+
+    /// If `true`, edits for fuzzy matching include transpositions of two
+    /// adjacent characters (`ab` → `ba`). Defaults to `true`.
+    #[cfg_attr(feature = "builder", builder(default_code = "true"))]
+    #[serde(default = "default_true")]
+    #[graphql(default = true)]
+    pub fuzzy_transpositions: bool,
+
+    /// If `true`, format-based errors, such as providing a text value for a
+    /// [numeric] field, are ignored. Defaults to `false`.
+    ///
+    /// [numeric]: https://www.elastic.co/guide/en/elasticsearch/reference/current/number.html
+    #[cfg_attr(feature = "builder", builder(default))]
+    #[serde(default)]
+    #[graphql(default)]
+    pub lenient: bool,
+
+    /// Minimum number of clauses that must match for a document to be returned.
+    /// See the [`minimum_should_match` parameter] for valid values and more
+    /// information.
+    ///
+    /// [`minimum_should_match` parameter]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-minimum-should-match.html
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum_should_match: Option<String>,
+
+    /// Suffix appended to quoted text in the query string.
+    ///
+    /// You can use this suffix to use a different analysis method for exact
+    /// matches. See [Mixing exact search with stemming](https://www.elastic.co/guide/en/elasticsearch/reference/current/mixing-exact-search-with-stemming.html).
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_field_suffix: Option<String>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, rename = "_name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 #[cfg(feature = "graphql")]
@@ -53,6 +156,18 @@ impl SimpleQueryStringQueryInput {
         SimpleQueryStringQueryInput {
             fields: fields.into_iter().map(|f| f.into()).collect(),
             query: query.into(),
+            default_operator: Default::default(),
+            analyzer: None,
+            analyze_wildcard: false,
+            auto_generate_synonyms_phrase_query: true,
+            flags: None,
+            fuzzy_max_expansions: default_fifty_u64(),
+            fuzzy_prefix_length: 0,
+            fuzzy_transpositions: true,
+            lenient: false,
+            minimum_should_match: None,
+            quote_field_suffix: None,
+            name: None,
         }
     }
 }
@@ -64,11 +179,22 @@ impl From<SimpleQueryStringQuery> for SimpleQueryStringQueryInput {
         Self {
             fields: query.fields,
             query: query.query,
+            default_operator: query.default_operator,
+            analyzer: query.analyzer,
+            analyze_wildcard: query.analyze_wildcard,
+            auto_generate_synonyms_phrase_query: query.auto_generate_synonyms_phrase_query,
+            flags: query.flags,
+            fuzzy_max_expansions: query.fuzzy_max_expansions,
+            fuzzy_prefix_length: query.fuzzy_prefix_length,
+            fuzzy_transpositions: query.fuzzy_transpositions,
+            lenient: query.lenient,
+            minimum_should_match: query.minimum_should_match,
+            quote_field_suffix: query.quote_field_suffix,
+            name: query.name,
         }
     }
 }
 
-// TODO: add additional options
 /// A [Simple query string] returns documents based on a provided query string,
 /// using a parser with a limited but fault-tolerant syntax.
 ///
@@ -88,6 +214,7 @@ impl From<SimpleQueryStringQuery> for SimpleQueryStringQueryInput {
 #[cfg_attr(feature = "graphql", graphql(name = "SimpleQueryStringFilter"))]
 #[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct SimpleQueryStringQuery {
     /// The name of the fields to query.
     ///
@@ -105,6 +232,102 @@ pub struct SimpleQueryStringQuery {
     /// The query to run in the [simple query string syntax](https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-simple-query-string-query.html#simple-query-string-syntax).
     #[cfg_attr(feature = "builder", builder(setter(into)))]
     pub query: String,
+
+    /// Default boolean logic used to interpret text in the query string if no
+    /// operators are specified. Defaults to `OR`.
+    #[cfg_attr(feature = "builder", builder(default))]
+    #[serde(default)]
+    pub default_operator: QueryStringBooleanOperator,
+
+    /// [Analyzer] used to convert text in the query string into tokens.
+    /// Defaults to the [index-time analyzer] mapped for the default_field. If
+    /// no analyzer is mapped, the index’s default analyzer is used.
+    ///
+    /// [Analyzer]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis.html
+    /// [index-time analyzer]: https://www.elastic.co/guide/en/elasticsearch/reference/current/specify-analyzer.html#specify-index-time-analyzer
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analyzer: Option<String>,
+
+    /// If `true`, the query attempts to analyze wildcard terms in the query
+    /// string. Defaults to `false`.
+    #[cfg_attr(feature = "builder", builder(default))]
+    #[serde(default)]
+    pub analyze_wildcard: bool,
+
+    /// If `true`, the parser creates a [`match_phrase`] query for each
+    /// [multi-position token]. Defaults to `true`. For examples, see
+    /// [Multi-position tokens].
+    ///
+    /// [`match_phrase`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-match-query-phrase.html
+    /// [multi-position token]: https://www.elastic.co/guide/en/elasticsearch/reference/current/token-graphs.html#token-graphs-multi-position-tokens
+    /// [Multi-position tokens]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-simple-query-string-query.html#simple-query-string-synonyms
+    #[cfg_attr(feature = "builder", builder(default_code = "true"))]
+    #[serde(default = "default_true")]
+    pub auto_generate_synonyms_phrase_query: bool,
+
+    /// List of enabled operators for the [simple query string syntax].
+    /// Defaults to `ALL` (all operators). See [Limit operators] for valid
+    /// values.
+    ///
+    /// [simple query string syntax]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-simple-query-string-query.html#simple-query-string-syntax
+    /// [Limit operators]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-simple-query-string-query.html#supported-flags
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flags: Option<SimpleQueryStringFlags>,
+
+    /// Maximum number of terms to which the query expands for fuzzy matching.
+    /// Defaults to `50`.
+    #[cfg_attr(feature = "builder", builder(default_code = "default_fifty_u64()"))]
+    #[serde(default = "default_fifty_u64")]
+    pub fuzzy_max_expansions: u64,
+
+    /// Number of beginning characters left unchanged for fuzzy matching.
+    /// Defaults to `0`.
+    #[cfg_attr(feature = "builder", builder(default))]
+    #[serde(default)]
+    pub fuzzy_prefix_length: u64,
+
+    /// If `true`, edits for fuzzy matching include transpositions of two
+    /// adjacent characters (`ab` → `ba`). Defaults to `true`.
+    #[cfg_attr(feature = "builder", builder(default_code = "true"))]
+    #[serde(default = "default_true")]
+    pub fuzzy_transpositions: bool,
+
+    /// If `true`, format-based errors, such as providing a text value for a
+    /// [numeric] field, are ignored. Defaults to `false`.
+    ///
+    /// [numeric]: https://www.elastic.co/guide/en/elasticsearch/reference/current/number.html
+    #[cfg_attr(feature = "builder", builder(default))]
+    #[serde(default)]
+    pub lenient: bool,
+
+    /// Minimum number of clauses that must match for a document to be returned.
+    /// See the [`minimum_should_match` parameter] for valid values and more
+    /// information.
+    ///
+    /// [`minimum_should_match` parameter]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-minimum-should-match.html
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(
+        default,
+        deserialize_with = "super::de::string_or_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub minimum_should_match: Option<String>,
+
+    /// Suffix appended to quoted text in the query string.
+    ///
+    /// You can use this suffix to use a different analysis method for exact
+    /// matches. See [Mixing exact search with stemming](https://www.elastic.co/guide/en/elasticsearch/reference/current/mixing-exact-search-with-stemming.html).
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_field_suffix: Option<String>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, rename = "_name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 impl SimpleQueryStringQuery {
@@ -118,6 +341,18 @@ impl SimpleQueryStringQuery {
         SimpleQueryStringQuery {
             fields: fields.into_iter().map(|f| f.into()).collect(),
             query: query.into(),
+            default_operator: Default::default(),
+            analyzer: None,
+            analyze_wildcard: false,
+            auto_generate_synonyms_phrase_query: true,
+            flags: None,
+            fuzzy_max_expansions: default_fifty_u64(),
+            fuzzy_prefix_length: 0,
+            fuzzy_transpositions: true,
+            lenient: false,
+            minimum_should_match: None,
+            quote_field_suffix: None,
+            name: None,
         }
     }
 }
@@ -129,6 +364,141 @@ impl From<SimpleQueryStringQueryInput> for SimpleQueryStringQuery {
         SimpleQueryStringQuery {
             fields: input.fields,
             query: input.query,
+            default_operator: input.default_operator,
+            analyzer: input.analyzer,
+            analyze_wildcard: input.analyze_wildcard,
+            auto_generate_synonyms_phrase_query: input.auto_generate_synonyms_phrase_query,
+            flags: input.flags,
+            fuzzy_max_expansions: input.fuzzy_max_expansions,
+            fuzzy_prefix_length: input.fuzzy_prefix_length,
+            fuzzy_transpositions: input.fuzzy_transpositions,
+            lenient: input.lenient,
+            minimum_should_match: input.minimum_should_match,
+            quote_field_suffix: input.quote_field_suffix,
+            name: input.name,
         }
     }
 }
+
+flags! {
+    /// The operators enabled for the [simple query string syntax], combined
+    /// using `|`, e.g. `SimpleQueryStringFlags::AND | SimpleQueryStringFlags::OR`.
+    ///
+    /// Serialized as a pipe-delimited string, e.g. `"AND|OR"`.
+    ///
+    /// [simple query string syntax]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-simple-query-string-query.html#supported-flags
+    pub struct SimpleQueryStringFlags {
+        /// Enables the `+` AND operator.
+        const AND = 1;
+
+        /// Enables the `-` NOT operator.
+        const NOT = 1 << 1;
+
+        /// Enables the `\|` OR operator.
+        const OR = 1 << 2;
+
+        /// Enables the `*` prefix operator.
+        const PREFIX = 1 << 3;
+
+        /// Enables the `"` quotes operator used to search for phrases.
+        const PHRASE = 1 << 4;
+
+        /// Enables the `(` and `)` operators to control operator precedence.
+        const PRECEDENCE = 1 << 5;
+
+        /// Enables `\` as an escape character.
+        const ESCAPE = 1 << 6;
+
+        /// Enables whitespace as split characters.
+        const WHITESPACE = 1 << 7;
+
+        /// Enables the `~N` operator after a word, where `N` is an integer
+        /// denoting the allowed edit distance for matching.
+        const FUZZY = 1 << 8;
+
+        /// Enables the `~N` operator, after a phrase where `N` is the maximum
+        /// number of positions allowed between matching tokens.
+        const NEAR = 1 << 9;
+    }
+
+    aliases {
+        /// Synonymous to `NEAR`.
+        const SLOP = NEAR;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::query::ParseFlagsError;
+
+    use serde_json::json;
+
+    #[test]
+    fn can_serialize_flags() {
+        let mut query = SimpleQueryStringQuery::new(vec!["title"], "quick -fox");
+        query.flags = Some(SimpleQueryStringFlags::OR | SimpleQueryStringFlags::NOT);
+        query.default_operator = QueryStringBooleanOperator::And;
+        query.minimum_should_match = Some("2".to_string());
+
+        let expected = json!({
+            "fields": ["title"],
+            "query": "quick -fox",
+            "default_operator": "AND",
+            "analyze_wildcard": false,
+            "auto_generate_synonyms_phrase_query": true,
+            "flags": "NOT|OR",
+            "fuzzy_max_expansions": 50,
+            "fuzzy_prefix_length": 0,
+            "fuzzy_transpositions": true,
+            "lenient": false,
+            "minimum_should_match": "2",
+        });
+        assert_eq!(serde_json::to_value(&query).unwrap(), expected);
+        assert_eq!(
+            serde_json::from_value::<SimpleQueryStringQuery>(expected).unwrap(),
+            query
+        );
+    }
+
+    #[test]
+    fn can_parse_flags() {
+        use SimpleQueryStringFlags as Flags;
+
+        assert_eq!("ALL".parse(), Ok(Flags::ALL));
+        assert_eq!("NONE".parse(), Ok(Flags::NONE));
+        assert_eq!(
+            "and | Prefix|SLOP".parse(),
+            Ok(Flags::AND | Flags::PREFIX | Flags::NEAR)
+        );
+        assert_eq!(
+            "AND|MAYBE".parse::<Flags>(),
+            Err(ParseFlagsError {
+                flag: "MAYBE".to_string()
+            })
+        );
+
+        assert_eq!(Flags::ALL.to_string(), "ALL");
+        assert_eq!(Flags::NONE.to_string(), "NONE");
+        assert_eq!((Flags::NEAR | Flags::AND).to_string(), "AND|NEAR");
+
+        let mut flags = Flags::ALL;
+        flags.remove(Flags::FUZZY | Flags::SLOP);
+        assert!(!flags.contains(Flags::FUZZY));
+        assert!(flags.contains(Flags::AND | Flags::WHITESPACE));
+        assert_eq!(
+            flags.to_string(),
+            "AND|NOT|OR|PREFIX|PHRASE|PRECEDENCE|ESCAPE|WHITESPACE"
+        );
+    }
+
+    #[test]
+    fn defaults_are_applied_when_deserializing() {
+        let query: SimpleQueryStringQuery =
+            serde_json::from_value(json!({ "query": "fox" })).unwrap();
+        assert_eq!(
+            query,
+            SimpleQueryStringQuery::new(Vec::<String>::new(), "fox")
+        );
+    }
+}
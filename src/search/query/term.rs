@@ -6,12 +6,18 @@ use serde::de::{self, MapAccess, Visitor};
 use serde::ser::{SerializeMap, Serializer};
 use serde::{Deserialize, Serialize};
 
+use super::de::Shorthand;
+
 #[allow(clippy::missing_docs_in_private_items)]
 #[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct InnerTermQuery {
+    #[serde(deserialize_with = "super::de::scalar")]
     value: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     boost: Option<f64>,
+    #[serde(default, rename = "_name", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
 }
 
 /// A [Term query] returns documents that contain an **exact** term in a provided field.
@@ -48,6 +54,11 @@ pub struct TermQueryInput {
     /// [relevance scores]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-filter-context.html#relevance-scores
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     pub boost: Option<f64>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub name: Option<String>,
 }
 
 #[cfg(feature = "graphql")]
@@ -59,6 +70,7 @@ impl TermQueryInput {
             field: field.into(),
             value: value.into(),
             boost: None,
+            name: None,
         }
     }
 }
@@ -71,6 +83,7 @@ impl From<TermQuery> for TermQueryInput {
             field: query.field,
             value: query.value,
             boost: query.boost,
+            name: query.name,
         }
     }
 }
@@ -84,6 +97,7 @@ impl Serialize for TermQueryInput {
         let inner = InnerTermQuery {
             value: self.value.to_owned(),
             boost: self.boost,
+            name: self.name.to_owned(),
         };
 
         map.serialize_entry(&self.field, &inner)?;
@@ -127,6 +141,11 @@ pub struct TermQuery {
     /// [relevance scores]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-filter-context.html#relevance-scores
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     pub boost: Option<f64>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub name: Option<String>,
 }
 
 impl TermQuery {
@@ -137,6 +156,7 @@ impl TermQuery {
             field: field.into(),
             value: value.into(),
             boost: None,
+            name: None,
         }
     }
 }
@@ -149,6 +169,7 @@ impl From<TermQueryInput> for TermQuery {
             field: input.field,
             value: input.value,
             boost: input.boost,
+            name: input.name,
         }
     }
 }
@@ -162,6 +183,7 @@ impl Serialize for TermQuery {
         let inner = InnerTermQuery {
             value: self.value.to_owned(),
             boost: self.boost,
+            name: self.name.to_owned(),
         };
 
         map.serialize_entry(&self.field, &inner)?;
@@ -198,12 +220,20 @@ impl<'de> Visitor<'de> for TermQueryVisitor {
             .next_key::<String>()?
             .ok_or_else(|| de::Error::missing_field("field"))?;
 
-        let inner: InnerTermQuery = map.next_value()?;
+        let inner = match map.next_value()? {
+            Shorthand::Options(inner) => inner,
+            Shorthand::Value(value) => InnerTermQuery {
+                value,
+                boost: None,
+                name: None,
+            },
+        };
 
         Ok(TermQuery {
             field,
             value: inner.value.to_owned(),
             boost: inner.boost,
+            name: inner.name,
         })
     }
 }
@@ -238,6 +268,7 @@ mod tests {
             field: "userProfile".to_string(),
             value: "Kimchy".to_string(),
             boost: None,
+            name: None,
         },
         json!({ "userProfile": { "value": "Kimchy" } })
     );
@@ -248,6 +279,7 @@ mod tests {
             field: "user".to_string(),
             value: "Kimchy".to_string(),
             boost: Some(1.1),
+            name: None,
         },
         json!({ "user": { "value": "Kimchy", "boost": 1.1 } })
     );
@@ -258,10 +290,20 @@ mod tests {
             field: "user".to_string(),
             value: "Kimchy".to_string(),
             boost: None,
+            name: None,
         },
         json!({ "user": { "value": "Kimchy" } })
     );
 
+    test_case!(
+        named:
+        TermQuery {
+            name: Some("user".to_string()),
+            ..TermQuery::new("user", "Kimchy")
+        },
+        json!({ "user": { "value": "Kimchy", "_name": "user" } })
+    );
+
     #[test]
     fn deserialize_invalid_boost_is_err() {
         let j = r#"{ "user": { "value": "Kimchy", "boost": "nan" } }"#;
@@ -281,11 +323,16 @@ mod tests {
     }
 
     #[test]
-    fn deserialize_missing_values_is_err() {
-        // TODO: should we support this Elasticsearch schema?
-        let j = r#"{ "user": "missing" }"#;
-        assert!(serde_json::from_str::<TermQuery>(j).is_err(), "{}", &j);
+    fn can_deserialize_shorthand() {
+        let j = r#"{ "user": "Kimchy" }"#;
+        assert_eq!(
+            serde_json::from_str::<TermQuery>(j).unwrap(),
+            TermQuery::new("user", "Kimchy")
+        );
+    }
 
+    #[test]
+    fn deserialize_missing_values_is_err() {
         let j = r#"{ "user": null }"#;
         assert!(serde_json::from_str::<TermQuery>(j).is_err(), "{}", &j);
 
@@ -294,17 +341,39 @@ mod tests {
     }
 
     #[test]
-    fn deserialize_invalid_values_is_err() {
-        let j = r#"{ "user": { "value": null } }"#;
-        assert!(serde_json::from_str::<TermQuery>(j).is_err(), "{}", &j);
+    fn can_deserialize_numbers_and_booleans() {
+        let mut query = TermQuery::new("age", "30");
+        query.boost = Some(2.0);
+        let j = json!({ "age": { "value": 30, "boost": 2 } });
+        assert_eq!(serde_json::from_value::<TermQuery>(j).unwrap(), query);
+
+        for (j, value) in &[
+            (json!({ "age": 30 }), "30"),
+            (json!({ "price": 1.1 }), "1.1"),
+            (json!({ "flag": true }), "true"),
+        ] {
+            let query = serde_json::from_value::<TermQuery>(j.clone()).unwrap();
+            assert_eq!(query.value, *value);
+        }
+    }
 
-        let j = r#"{ "user": { "value": 1.1 } }"#;
-        assert!(serde_json::from_str::<TermQuery>(j).is_err(), "{}", &j);
+    #[test]
+    fn unsupported_options_are_err() {
+        let j = json!({ "user": { "value": "Kimchy", "bogus": 1 } });
+        let err = serde_json::from_value::<TermQuery>(j).unwrap_err();
+        assert!(
+            err.to_string().starts_with("unknown field `bogus`"),
+            "{}",
+            err
+        );
+    }
 
-        let j = r#"{ "user": { "value": 1 } }"#;
+    #[test]
+    fn deserialize_invalid_values_is_err() {
+        let j = r#"{ "user": { "value": null } }"#;
         assert!(serde_json::from_str::<TermQuery>(j).is_err(), "{}", &j);
 
-        let j = r#"{ "user": { "value": 999 } }"#;
+        let j = r#"{ "user": null }"#;
         assert!(serde_json::from_str::<TermQuery>(j).is_err(), "{}", &j);
 
         let j = r#"{ "user": { "value": [null] } }"#;
@@ -6,6 +6,8 @@ use serde::de::{self, MapAccess, Visitor};
 use serde::ser::{SerializeMap, Serializer};
 use serde::Serialize;
 
+use super::de::Scalar;
+
 /// A [Terms query] returns documents that contain one or more **exact** terms
 /// in a provided field.
 ///
@@ -41,6 +43,11 @@ pub struct TermsQueryInput {
     /// [relevance scores]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-filter-context.html#relevance-scores
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     pub boost: Option<f64>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub name: Option<String>,
 }
 
 #[cfg(feature = "graphql")]
@@ -56,6 +63,7 @@ impl TermsQueryInput {
             field: field.into(),
             values: values.into_iter().map(Into::into).collect::<Vec<String>>(),
             boost: None,
+            name: None,
         }
     }
 }
@@ -68,6 +76,7 @@ impl From<TermsQuery> for TermsQueryInput {
             field: query.field,
             values: query.values,
             boost: query.boost,
+            name: query.name,
         }
     }
 }
@@ -81,6 +90,9 @@ impl Serialize for TermsQueryInput {
         if let Some(boost) = &self.boost {
             map.serialize_entry("boost", &boost)?;
         }
+        if let Some(name) = &self.name {
+            map.serialize_entry("_name", name)?;
+        }
         map.end()
     }
 }
@@ -121,6 +133,11 @@ pub struct TermsQuery {
     /// [relevance scores]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-filter-context.html#relevance-scores
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     pub boost: Option<f64>,
+
+    /// The name of the query, which is returned in the `matched_queries` of
+    /// the hits it matched.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub name: Option<String>,
 }
 
 impl TermsQuery {
@@ -135,6 +152,7 @@ impl TermsQuery {
             field: field.into(),
             values: values.into_iter().map(Into::into).collect::<Vec<String>>(),
             boost: None,
+            name: None,
         }
     }
 }
@@ -147,6 +165,7 @@ impl From<TermsQueryInput> for TermsQuery {
             field: input.field,
             values: input.values,
             boost: input.boost,
+            name: input.name,
         }
     }
 }
@@ -160,6 +179,9 @@ impl Serialize for TermsQuery {
         if let Some(boost) = &self.boost {
             map.serialize_entry("boost", &boost)?;
         }
+        if let Some(name) = &self.name {
+            map.serialize_entry("_name", name)?;
+        }
         map.end()
     }
 }
@@ -188,25 +210,31 @@ impl<'de> Visitor<'de> for TermsQueryVisitor {
     where
         A: MapAccess<'de>,
     {
-        let boost_field = "boost".to_string();
-
         let mut field: Option<String> = None;
         let mut values: Option<Vec<String>> = None;
         let mut boost: Option<f64> = None;
+        let mut name: Option<String> = None;
         while let Some(key) = access.next_key::<String>()? {
-            if key == boost_field {
+            if key == "boost" {
                 if boost.is_some() {
                     return Err(de::Error::duplicate_field("boost"));
                 }
 
                 boost = Some(access.next_value::<f64>()?);
+            } else if key == "_name" {
+                if name.is_some() {
+                    return Err(de::Error::duplicate_field("_name"));
+                }
+
+                name = Some(access.next_value::<String>()?);
             } else {
                 if field.is_some() {
                     return Err(de::Error::duplicate_field("field"));
                 }
 
                 field = Some(key);
-                values = Some(access.next_value::<Vec<String>>()?);
+                let scalars = access.next_value::<Vec<Scalar>>()?;
+                values = Some(scalars.into_iter().map(|scalar| scalar.0).collect());
             }
         }
 
@@ -217,6 +245,7 @@ impl<'de> Visitor<'de> for TermsQueryVisitor {
             field,
             values,
             boost,
+            name,
         })
     }
 }
@@ -251,6 +280,7 @@ mod tests {
             field: "userProfile".to_string(),
             values: vec!["Kimchy".to_string(), "elasticsearch".to_string()],
             boost: None,
+            name: None,
         },
         json!({ "userProfile": ["Kimchy", "elasticsearch"] })
     );
@@ -261,6 +291,7 @@ mod tests {
             field: "user".to_string(),
             values: vec!["Kimchy".to_string(), "elasticsearch".to_string()],
             boost: Some(1.1),
+            name: None,
         },
         json!({ "user": ["Kimchy", "elasticsearch"], "boost": 1.1 })
     );
@@ -271,10 +302,20 @@ mod tests {
             field: "user".to_string(),
             values: vec!["Kimchy".to_string(), "elasticsearch".to_string()],
             boost: None,
+            name: None,
         },
         json!({ "user": ["Kimchy", "elasticsearch"] })
     );
 
+    test_case!(
+        named:
+        TermsQuery {
+            name: Some("users".to_string()),
+            ..TermsQuery::new("user", vec!["Kimchy", "elasticsearch"])
+        },
+        json!({ "user": ["Kimchy", "elasticsearch"], "_name": "users" })
+    );
+
     #[test]
     fn deserialize_invalid_boost_is_err() {
         let j = r#"{ "user": { "value": "Kimchy", "boost": "nan" } }"#;
@@ -6,10 +6,15 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 #[cfg(feature = "graphql")]
-use crate::search::{query::CompoundQueryInput, SortInput};
+use crate::search::{
+    query::CompoundQueryInput, CollapseInput, FieldAndFormatInput, SortInput, SourceFilterInput,
+};
 use crate::{
     scalars::SortedValue,
-    search::{query::CompoundQuery, Sort},
+    search::{
+        query::{default_true, CompoundQuery},
+        Collapse, FieldAndFormat, Sort, SourceFilter,
+    },
 };
 
 /// The [request body] for an Elasticsearch search request.
@@ -24,6 +29,16 @@ pub struct RequestInput {
     #[serde(skip_serializing_if = "CompoundQueryInput::is_empty")]
     pub query: CompoundQueryInput,
 
+    /// A query to filter the hits by *after* the aggregations have been
+    /// computed, which therefore don't take it into account; used e.g. for
+    /// [faceted navigation].
+    ///
+    /// [faceted navigation]: https://www.elastic.co/guide/en/elasticsearch/reference/current/filter-search-results.html#post-filter
+    #[graphql(default)]
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(skip_serializing_if = "CompoundQueryInput::is_empty")]
+    pub post_filter: CompoundQueryInput,
+
     /// Sorts the results.
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     #[serde(skip_serializing_if = "Vec::is_empty")]
@@ -34,6 +49,17 @@ pub struct RequestInput {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub size: Option<u64>,
 
+    /// The offset of the first result to return, for [pagination]. Defaults to
+    /// `0`.
+    ///
+    /// **Note**: `from + size` may not exceed the `max_result_window` of the
+    /// index, which is `10000` by default; use `after` to page further.
+    ///
+    /// [pagination]: https://www.elastic.co/guide/en/elasticsearch/reference/current/paginate-search-results.html
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<u64>,
+
     /// The maximum number of documents to collect for each shard, upon reaching
     /// which the query execution will terminate early.
     ///
@@ -74,6 +100,45 @@ pub struct RequestInput {
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub highlight: Option<HighlightOptionsInput>,
+
+    /// Which fields of the matching documents' `_source` to return, if any.
+    /// Defaults to the whole `_source`.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(rename = "_source", skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceFilterInput>,
+
+    /// The [stored fields] to return, which must be mapped with `store`.
+    ///
+    /// [stored fields]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-fields.html#stored-fields
+    #[graphql(default)]
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stored_fields: Vec<String>,
+
+    /// The fields to return the [doc values] of.
+    ///
+    /// [doc values]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-fields.html#docvalue-fields
+    #[graphql(default)]
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub docvalue_fields: Vec<FieldAndFormatInput>,
+
+    /// The fields to return the values of, as [retrieved] from the `_source`
+    /// according to the mapping (e.g. including runtime and multi-fields).
+    ///
+    /// [retrieved]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-fields.html#search-fields-param
+    #[graphql(default)]
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldAndFormatInput>,
+
+    /// [Collapses] the hits by the value of a field, e.g. to only return the
+    /// top hit of each product family.
+    ///
+    /// [Collapses]: https://www.elastic.co/guide/en/elasticsearch/reference/current/collapse-search-results.html
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collapse: Option<CollapseInput>,
 }
 
 #[cfg(feature = "graphql")]
@@ -87,42 +152,75 @@ impl RequestInput {
 
 /// The [request body] for an Elasticsearch search request.
 ///
+/// Options that aren't supported (e.g. `min_score`) are rejected when
+/// deserializing instead of being dropped. The aggregations of a request body
+/// are separate [`aggregation::Request`]s.
+///
 /// [request body]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-request-body.html
+/// [`aggregation::Request`]: crate::aggregation::Request
+#[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
 #[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct Request {
     /// The query to perform in this search request.
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    #[serde(skip_serializing_if = "CompoundQuery::is_empty")]
+    #[serde(default, skip_serializing_if = "CompoundQuery::is_empty")]
     pub query: CompoundQuery,
 
+    /// A query to filter the hits by *after* the aggregations have been
+    /// computed, which therefore don't take it into account; used e.g. for
+    /// [faceted navigation].
+    ///
+    /// [faceted navigation]: https://www.elastic.co/guide/en/elasticsearch/reference/current/filter-search-results.html#post-filter
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "CompoundQuery::is_empty")]
+    pub post_filter: CompoundQuery,
+
     /// Sorts the results.
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub sort: Vec<Sort>,
 
     /// The number of results to return.
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub size: Option<u64>,
 
+    /// The offset of the first result to return, for [pagination]. Defaults to
+    /// `0`.
+    ///
+    /// **Note**: `from + size` may not exceed the `max_result_window` of the
+    /// index, which is `10000` by default; use `after` to page further.
+    ///
+    /// [pagination]: https://www.elastic.co/guide/en/elasticsearch/reference/current/paginate-search-results.html
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<u64>,
+
     /// The maximum number of documents to collect for each shard, upon reaching
     /// which the query execution will terminate early.
     ///
     /// Defaults to `0`, which does not terminate query execution early.
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default)]
     pub terminate_after: u64,
 
     /// The live cursor from which to search after to fascilitate [pagination].
     ///
     /// [pagination]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-request-body.html#request-body-search-search-after
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    #[serde(rename = "search_after", skip_serializing_if = "Vec::is_empty")]
+    #[serde(
+        rename = "search_after",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
     pub after: Vec<SortedValue>,
 
     /// Whether or not to include the document version in the search results.
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default)]
     pub version: bool,
 
     /// Whether or not to include the [sequence number & primary term] in the
@@ -130,12 +228,13 @@ pub struct Request {
     ///
     /// [sequence number & primary term]: https://www.elastic.co/guide/en/elasticsearch/reference/current/optimistic-concurrency-control.html
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default)]
     pub seq_no_primary_term: bool,
 
     // TODO: could also be a bool...
     /// The lower bound for the number of hits to track
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub track_total_hits: Option<u64>,
 
     // TODO: figure out a way to not use this for queries that don't support it like `count`
@@ -144,8 +243,44 @@ pub struct Request {
     ///
     /// [highlighted]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-request-highlighting.html
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub highlight: Option<HighlightOptions>,
+
+    /// Which fields of the matching documents' `_source` to return, if any.
+    /// Defaults to the whole `_source`.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, rename = "_source", skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceFilter>,
+
+    /// The [stored fields] to return, which must be mapped with `store`.
+    ///
+    /// [stored fields]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-fields.html#stored-fields
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stored_fields: Vec<String>,
+
+    /// The fields to return the [doc values] of.
+    ///
+    /// [doc values]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-fields.html#docvalue-fields
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub docvalue_fields: Vec<FieldAndFormat>,
+
+    /// The fields to return the values of, as [retrieved] from the `_source`
+    /// according to the mapping (e.g. including runtime and multi-fields).
+    ///
+    /// [retrieved]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-fields.html#search-fields-param
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldAndFormat>,
+
+    /// [Collapses] the hits by the value of a field, e.g. to only return the
+    /// top hit of each product family.
+    ///
+    /// [Collapses]: https://www.elastic.co/guide/en/elasticsearch/reference/current/collapse-search-results.html
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collapse: Option<Collapse>,
 }
 
 impl Request {
@@ -222,38 +357,42 @@ pub struct HighlightOptionsInput {
 
 /// The [options] for highlighting.
 ///
-/// **TODO**: add more options...
+/// **TODO**: add more options, the others are rejected when deserializing...
 ///
 /// [options]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-request-highlighting.html#highlighting-settings
 #[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
 #[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct HighlightOptions {
     /// The field names and their options to highlight.
     pub fields: crate::scalars::Map,
 
     /// The highligher type to use.
-    #[serde(rename = "type")]
+    #[serde(rename = "type", default)]
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     pub ty: HighlighterType,
 
     /// The maximum number of fragments to return.
     #[cfg_attr(feature = "builder", builder(default = 5, setter(into)))]
+    #[serde(default = "default_number_of_fragments")]
     pub number_of_fragments: u64,
 
     /// The size of the highlighted fragment in characters.
     #[cfg_attr(feature = "builder", builder(default = 100, setter(into)))]
+    #[serde(default = "default_fragment_size")]
     pub fragment_size: u32,
 
     /// How far to scan for boundary characters.
     #[cfg_attr(feature = "builder", builder(default = 20, setter(into)))]
+    #[serde(default = "default_boundary_max_scan")]
     pub boundary_max_scan: u32,
 
     // TODO: should be an enum?
     /// Set to [`styled`] to use the built-in tag schema.
     ///
     /// [`styled`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-request-highlighting.html
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
     pub tags_schema: Option<String>,
 
@@ -261,22 +400,41 @@ pub struct HighlightOptions {
     /// the highlighted text. By default, highlighted text is wrapped in `<em>`
     /// and `</em>` tags.
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    #[serde(skip_serializing_if = "Vec::is_empty")] // es errors without this
+    #[serde(default, skip_serializing_if = "Vec::is_empty")] // es errors without this
     pub pre_tags: Vec<String>,
 
     /// Use in conjunction with `pre_tags` to define the HTML tags to use for
     /// the highlighted text. By default, highlighted text is wrapped in `<em>`
     /// and `</em>` tags.
     #[cfg_attr(feature = "builder", builder(default, setter(into)))]
-    #[serde(skip_serializing_if = "Vec::is_empty")] // es errors without this
+    #[serde(default, skip_serializing_if = "Vec::is_empty")] // es errors without this
     pub post_tags: Vec<String>,
 
     /// By default, only fields that contains a query match are highlighted. Set
     /// `require_field_match` to `false` to highlight all fields.
     #[cfg_attr(feature = "builder", builder(default = true, setter(into)))]
+    #[serde(default = "default_true")]
     pub require_field_match: bool,
 }
 
+/// The default `number_of_fragments` of `HighlightOptions`.
+#[inline]
+fn default_number_of_fragments() -> u64 {
+    5
+}
+
+/// The default `fragment_size` of `HighlightOptions`.
+#[inline]
+fn default_fragment_size() -> u32 {
+    100
+}
+
+/// The default `boundary_max_scan` of `HighlightOptions`.
+#[inline]
+fn default_boundary_max_scan() -> u32 {
+    20
+}
+
 impl Default for HighlightOptions {
     #[inline]
     fn default() -> Self {
@@ -294,9 +452,45 @@ impl Default for HighlightOptions {
     }
 }
 
+#[cfg(feature = "graphql")]
+impl From<HighlightOptions> for HighlightOptionsInput {
+    #[inline]
+    fn from(options: HighlightOptions) -> Self {
+        HighlightOptionsInput {
+            fields: options.fields,
+            ty: options.ty,
+            number_of_fragments: options.number_of_fragments,
+            fragment_size: options.fragment_size,
+            boundary_max_scan: options.boundary_max_scan,
+            tags_schema: options.tags_schema,
+            pre_tags: options.pre_tags,
+            post_tags: options.post_tags,
+            require_field_match: options.require_field_match,
+        }
+    }
+}
+
+#[cfg(feature = "graphql")]
+impl From<HighlightOptionsInput> for HighlightOptions {
+    #[inline]
+    fn from(input: HighlightOptionsInput) -> Self {
+        HighlightOptions {
+            fields: input.fields,
+            ty: input.ty,
+            number_of_fragments: input.number_of_fragments,
+            fragment_size: input.fragment_size,
+            boundary_max_scan: input.boundary_max_scan,
+            tags_schema: input.tags_schema,
+            pre_tags: input.pre_tags,
+            post_tags: input.post_tags,
+            require_field_match: input.require_field_match,
+        }
+    }
+}
+
 /// The different supported highlighter types/algorithm.
-#[cfg_attr(feature = "graphql", derive(async_graphql::Enum, Eq, PartialEq, Copy))]
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum HighlighterType {
     /// The `unified` highlighter uses the Lucene Unified Highlighter. This
@@ -333,3 +527,140 @@ impl Default for HighlighterType {
         Self::Unified
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::search::{
+        query::{MatchQuery, TermQuery},
+        InnerHits,
+    };
+
+    /// Asserts that `request` is unchanged after serializing and deserializing
+    /// it.
+    fn assert_round_trips(request: &Request) {
+        let j = serde_json::to_value(request).unwrap();
+        let actual: Request = serde_json::from_value(j.clone())
+            .unwrap_or_else(|err| panic!("{} can't be deserialized: {}", j, err));
+        assert_eq!(&actual, request, "{}", j);
+    }
+
+    #[test]
+    fn empty_request_round_trips() {
+        assert_round_trips(&Request::builder().build());
+    }
+
+    #[test]
+    fn full_request_round_trips() {
+        let mut query = CompoundQuery::default();
+        query.push(TermQuery::new("status", "active"));
+        query.push(MatchQuery::new("title", "rust"));
+
+        let sort: Sort = serde_json::from_value(json!({ "created_at": "desc" })).unwrap();
+
+        let mut post_filter = CompoundQuery::default();
+        post_filter.push(TermQuery::new("tags", "async"));
+
+        let request = Request::builder()
+            .query(query)
+            .post_filter(post_filter)
+            .sort(vec![sort, Sort::default()])
+            .size(Some(10))
+            .from(Some(20))
+            .terminate_after(1000u64)
+            .after(vec![
+                SortedValue::Int(1),
+                SortedValue::Float(1.5),
+                SortedValue::String("a".to_string()),
+                SortedValue::Null,
+            ])
+            .version(true)
+            .seq_no_primary_term(true)
+            .track_total_hits(Some(100))
+            .highlight(Some(HighlightOptions {
+                ty: HighlighterType::Fvh,
+                tags_schema: None,
+                pre_tags: vec!["<b>".to_string()],
+                post_tags: vec!["</b>".to_string()],
+                require_field_match: false,
+                ..HighlightOptions::default()
+            }))
+            .source(Some(SourceFilter::new(vec!["title"], vec!["secret"])))
+            .stored_fields(vec!["body".to_string()])
+            .docvalue_fields(vec![FieldAndFormat::new("status")])
+            .fields(vec![
+                FieldAndFormat::new("title"),
+                FieldAndFormat::new("created_at").with_format("epoch_millis"),
+            ])
+            .collapse(Some(Collapse {
+                field: "author".to_string(),
+                inner_hits: vec![InnerHits {
+                    name: Some("latest".to_string()),
+                    size: Some(2),
+                    ..InnerHits::default()
+                }],
+                max_concurrent_group_searches: Some(2),
+            }))
+            .build();
+
+        assert_round_trips(&request);
+        assert_round_trips(&Request {
+            highlight: Some(HighlightOptions::default()),
+            ..request
+        });
+    }
+
+    #[test]
+    fn can_serialize_source_and_fields() {
+        let request = Request::builder()
+            .from(Some(10))
+            .source(Some(SourceFilter::disabled()))
+            .fields(vec![FieldAndFormat::new("created_at").with_format("yyyy")])
+            .build();
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({
+                "from": 10,
+                "terminate_after": 0,
+                "version": false,
+                "seq_no_primary_term": false,
+                "_source": false,
+                "fields": [{ "field": "created_at", "format": "yyyy" }],
+            })
+        );
+    }
+
+    #[test]
+    fn can_deserialize_partial_highlight_options() {
+        let highlight: HighlightOptions =
+            serde_json::from_value(json!({ "fields": { "title": {} } })).unwrap();
+        assert_eq!(
+            highlight,
+            HighlightOptions {
+                fields: json!({ "title": {} }).into(),
+                tags_schema: None,
+                ..HighlightOptions::default()
+            }
+        );
+    }
+
+    #[test]
+    fn unsupported_options_are_err() {
+        for j in &[
+            json!({ "min_score": 0.5 }),
+            json!({ "explain": true }),
+            json!({ "timeout": "1s" }),
+            json!({ "highlight": { "fields": { "title": {} }, "order": "score" } }),
+        ] {
+            let err = serde_json::from_value::<Request>(j.clone()).unwrap_err();
+            assert!(
+                err.to_string().starts_with("unknown field"),
+                "{}: {}",
+                j,
+                err
+            );
+        }
+    }
+}
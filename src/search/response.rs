@@ -3,11 +3,19 @@ use std::collections::HashMap;
 use serde::Deserialize;
 
 /// The database response for performing a `Search`.
+///
+/// `T` is the type of the documents and `I` the type of the nested objects
+/// (or of the documents, for [collapsed] hits) returned as [inner hits], if
+/// any.
+///
+/// [collapsed]: https://www.elastic.co/guide/en/elasticsearch/reference/current/collapse-search-results.html
+///
+/// [inner hits]: https://www.elastic.co/guide/en/elasticsearch/reference/current/inner-hits.html
 #[derive(Deserialize, Debug)]
 #[serde(untagged)]
-pub enum Response<T> {
+pub enum Response<T, I = serde_json::Value> {
     /// An `Ok` response.
-    Ok(OkResponse<T>),
+    Ok(OkResponse<T, I>),
 
     /// An `Err` response.
     Err {
@@ -51,7 +59,7 @@ pub struct ErrResponse {
 
 /// The response for performing a successful `Search`.
 #[derive(Deserialize, Debug)]
-pub struct OkResponse<T> {
+pub struct OkResponse<T, I = serde_json::Value> {
     /// Time it took for the database to process the request.
     pub took: u64,
 
@@ -59,12 +67,12 @@ pub struct OkResponse<T> {
     pub timed_out: bool,
 
     /// The hits matched by the search query.
-    pub hits: Hits<T>,
+    pub hits: Hits<T, I>,
 }
 
 /// The hits/matches from performing a Elasticsearch search.
 #[derive(Deserialize, Default, Debug)]
-pub struct Hits<T> {
+pub struct Hits<T, I = serde_json::Value> {
     /// The total count of the hits/matches.
     #[serde(default, rename = "total")]
     pub total_count: Count,
@@ -76,20 +84,20 @@ pub struct Hits<T> {
     // XXX: must be `Vec::new` instead of normal `default`
     /// The search hits.
     #[serde(default = "Vec::new")]
-    pub hits: Vec<Hit<T>>,
+    pub hits: Vec<Hit<T, I>>,
 }
 
-impl<T> Hits<T> {
+impl<T, I> Hits<T, I> {
     /// Gets the first document's source (if any).
     #[inline]
     pub fn first_doc(&self) -> Option<&T> {
-        self.hits.get(0).map(|hit| &hit.source)
+        self.hits.first().and_then(|hit| hit.source.as_ref())
     }
 }
 
 /// An individual Elasticsearch search hit/match.
 #[derive(Deserialize, Debug)]
-pub struct Hit<T> {
+pub struct Hit<T, I = serde_json::Value> {
     // TODO: should this be a different type?
     /// The database Id of this `Document`.
     #[serde(rename = "_id")]
@@ -101,9 +109,10 @@ pub struct Hit<T> {
     #[serde(rename = "_index")]
     pub index: String,
 
-    /// The actual `Document` of this search hit/match.
+    /// The actual `Document` of this search hit/match, unless the request
+    /// disabled `_source` or the index doesn't store it.
     #[serde(rename = "_source")]
-    pub source: T,
+    pub source: Option<T>,
 
     /// The [version] number of this `Document`.
     ///
@@ -142,6 +151,56 @@ pub struct Hit<T> {
     /// [pagination]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-request-body.html#request-body-search-search-after
     #[serde(default)]
     pub sort: Vec<serde_json::Value>,
+
+    /// The values of the fields requested with `fields`, `docvalue_fields`
+    /// or `stored_fields`, by field, as well as the value of the field the
+    /// hits are collapsed by. Values are always returned as arrays.
+    #[serde(default = "HashMap::new")]
+    pub fields: HashMap<String, Vec<serde_json::Value>>,
+
+    /// The names of the [named queries] that matched this hit, i.e. the `name`
+    /// of the queries in the request that this `Document` matched.
+    ///
+    /// [named queries]: https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-bool-query.html#named-queries
+    #[serde(default)]
+    pub matched_queries: Vec<String>,
+
+    /// The location of this hit within its parent document, if this is an
+    /// inner hit of a `nested` query.
+    #[serde(default, rename = "_nested")]
+    pub nested: Option<NestedIdentity>,
+
+    /// The [inner hits] of this hit, by the name of the `inner_hits` they
+    /// were requested with (the nested `path` by default), e.g. the other
+    /// hits of its group if the hits are collapsed.
+    ///
+    /// [inner hits]: https://www.elastic.co/guide/en/elasticsearch/reference/current/inner-hits.html
+    #[serde(default = "HashMap::new")]
+    pub inner_hits: HashMap<String, InnerHitsResponse<I>>,
+}
+
+/// The [inner hits] of a search hit.
+///
+/// [inner hits]: https://www.elastic.co/guide/en/elasticsearch/reference/current/inner-hits.html
+#[derive(Deserialize, Debug)]
+pub struct InnerHitsResponse<T> {
+    /// The nested objects which matched.
+    pub hits: Hits<T>,
+}
+
+/// The location of a nested object within its parent document.
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub struct NestedIdentity {
+    /// The path of the nested field within its parent.
+    pub field: String,
+
+    /// The index of the nested object within the field's array of objects.
+    pub offset: u64,
+
+    /// The location of a nested object within this nested object, for
+    /// multi-level nested inner hits.
+    #[serde(default, rename = "_nested")]
+    pub nested: Option<Box<NestedIdentity>>,
 }
 
 /// The type of count.
@@ -177,4 +236,191 @@ pub struct Count {
     pub value: u64,
 }
 
-// TODO: add tests!
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Post {
+        title: String,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Comment {
+        author: String,
+    }
+
+    #[test]
+    fn can_deserialize_inner_hits() {
+        let j = json!({
+            "took": 3,
+            "timed_out": false,
+            "hits": {
+                "total": { "value": 1, "relation": "eq" },
+                "max_score": 1.0,
+                "hits": [{
+                    "_index": "posts",
+                    "_id": "1",
+                    "_score": 1.0,
+                    "_source": { "title": "Hello" },
+                    "inner_hits": {
+                        "comments": {
+                            "hits": {
+                                "total": { "value": 1, "relation": "eq" },
+                                "max_score": 1.0,
+                                "hits": [{
+                                    "_index": "posts",
+                                    "_id": "1",
+                                    "_nested": { "field": "comments", "offset": 2 },
+                                    "_score": 1.0,
+                                    "_source": { "author": "jane" }
+                                }]
+                            }
+                        }
+                    }
+                }]
+            }
+        });
+
+        let response: OkResponse<Post, Comment> = serde_json::from_value(j).unwrap();
+        let hit = &response.hits.hits[0];
+        assert_eq!(
+            hit.source,
+            Some(Post {
+                title: "Hello".to_string()
+            })
+        );
+        assert_eq!(hit.nested, None);
+
+        let comments = &hit.inner_hits["comments"].hits;
+        assert_eq!(comments.total_count.value, 1);
+        assert_eq!(
+            comments.first_doc(),
+            Some(&Comment {
+                author: "jane".to_string()
+            })
+        );
+        assert_eq!(
+            comments.hits[0].nested,
+            Some(NestedIdentity {
+                field: "comments".to_string(),
+                offset: 2,
+                nested: None,
+            })
+        );
+    }
+
+    #[test]
+    fn inner_hits_default_to_json() {
+        let j = json!({
+            "_index": "posts",
+            "_id": "1",
+            "_source": { "title": "Hello" },
+            "inner_hits": {
+                "comments": { "hits": { "hits": [{
+                    "_index": "posts",
+                    "_id": "1",
+                    "_nested": { "field": "comments", "offset": 0 },
+                    "_source": { "author": "jane" }
+                }] } }
+            }
+        });
+
+        let hit: Hit<Post> = serde_json::from_value(j).unwrap();
+        assert_eq!(
+            hit.inner_hits["comments"].hits.first_doc(),
+            Some(&json!({ "author": "jane" }))
+        );
+    }
+
+    #[test]
+    fn can_deserialize_matched_queries() {
+        let j = json!({
+            "_index": "posts",
+            "_id": "1",
+            "_source": { "title": "Hello" },
+            "matched_queries": ["title", "tags"]
+        });
+
+        let hit: Hit<Post> = serde_json::from_value(j).unwrap();
+        assert_eq!(hit.matched_queries, vec!["title", "tags"]);
+
+        let j = json!({ "_index": "posts", "_id": "2", "_source": { "title": "Bye" } });
+        let hit: Hit<Post> = serde_json::from_value(j).unwrap();
+        assert!(hit.matched_queries.is_empty());
+    }
+
+    #[test]
+    fn can_deserialize_hits_without_source() {
+        let j = json!({
+            "took": 1,
+            "timed_out": false,
+            "hits": {
+                "total": { "value": 1, "relation": "eq" },
+                "hits": [{
+                    "_index": "posts",
+                    "_id": "1",
+                    "_score": 1.0,
+                    "fields": {
+                        "title": ["Hello"],
+                        "created_at": ["1600000000000"],
+                    },
+                }]
+            }
+        });
+
+        let response: OkResponse<Post> = serde_json::from_value(j).unwrap();
+        let hit = &response.hits.hits[0];
+        assert_eq!(hit.source, None);
+        assert_eq!(hit.fields["title"], vec![json!("Hello")]);
+        assert_eq!(response.hits.first_doc(), None);
+    }
+
+    #[test]
+    fn can_deserialize_collapsed_hits() {
+        let j = json!({
+            "took": 2,
+            "timed_out": false,
+            "hits": {
+                "total": { "value": 3, "relation": "eq" },
+                "hits": [{
+                    "_index": "posts",
+                    "_id": "2",
+                    "_score": null,
+                    "_source": { "title": "Newest" },
+                    "sort": [1_600_000_000_000_u64],
+                    "fields": { "author": ["jane"] },
+                    "inner_hits": {
+                        "oldest": {
+                            "hits": {
+                                "total": { "value": 2, "relation": "eq" },
+                                "hits": [{
+                                    "_index": "posts",
+                                    "_id": "1",
+                                    "_score": null,
+                                    "_source": { "title": "Oldest" },
+                                    "sort": [1_500_000_000_000_u64],
+                                }],
+                            },
+                        },
+                    },
+                }],
+            },
+        });
+
+        let response: OkResponse<Post, Post> = serde_json::from_value(j).unwrap();
+        let hit = &response.hits.hits[0];
+        assert_eq!(hit.fields["author"], vec![json!("jane")]);
+
+        let oldest = &hit.inner_hits["oldest"].hits;
+        assert_eq!(oldest.total_count.value, 2);
+        assert_eq!(
+            oldest.first_doc(),
+            Some(&Post {
+                title: "Oldest".to_string()
+            })
+        );
+    }
+}
@@ -9,9 +9,8 @@ use serde::ser::{SerializeMap, Serializer};
 use serde::{Deserialize, Serialize};
 
 /// The [sort order](https://www.elastic.co/guide/en/elasticsearch/reference/current/search-request-sort.html#_sort_order)
-#[cfg_attr(all(test, not(feature = "graphql")), derive(PartialEq))]
-#[cfg_attr(feature = "graphql", derive(async_graphql::Enum, Eq, PartialEq, Copy))]
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum SortOrder {
     /// Sort in ascending order
@@ -22,9 +21,8 @@ pub enum SortOrder {
 }
 
 /// The [sort mode](https://www.elastic.co/guide/en/elasticsearch/reference/current/search-request-sort.html#_sort_mode_option)
-#[cfg_attr(all(test, not(feature = "graphql")), derive(PartialEq))]
-#[cfg_attr(feature = "graphql", derive(async_graphql::Enum, Eq, PartialEq, Copy))]
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum SortMode {
     /// Pick the lowest value.
@@ -120,9 +118,8 @@ impl Serialize for SortInput {
 ///
 /// **NOTE**: the `id` field will always be used as a tie breaker or a default,
 /// regardless of any value specified.
-#[cfg_attr(all(test, not(feature = "graphql")), derive(PartialEq))]
-#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject, PartialEq))]
-#[derive(Clone, Debug)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+#[derive(Clone, PartialEq, Debug)]
 pub struct Sort {
     /// The field to sort by.
     ///
@@ -142,6 +139,44 @@ pub struct Sort {
     mode: Option<SortMode>,
 }
 
+impl Sort {
+    /// Sorts by `field` (in the default order).
+    #[inline]
+    pub fn new(field: impl Into<String>) -> Sort {
+        Sort {
+            field: field.into(),
+            order: None,
+            mode: None,
+        }
+    }
+
+    /// Sets the order to sort in.
+    #[inline]
+    pub fn with_order(mut self, order: SortOrder) -> Sort {
+        self.order = Some(order);
+        self
+    }
+
+    /// Sets the mode to sort with.
+    #[inline]
+    pub fn with_mode(mut self, mode: SortMode) -> Sort {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// The field to sort by.
+    #[inline]
+    pub fn field_name(&self) -> &str {
+        &self.field
+    }
+
+    /// The order to sort in, if any.
+    #[inline]
+    pub fn sort_order(&self) -> Option<&SortOrder> {
+        self.order.as_ref()
+    }
+}
+
 impl Default for Sort {
     /// Returns the "default value" for a `Sort`, which consists of only
     /// sorting on the tie-breaker field (`id`).
@@ -168,6 +203,18 @@ impl From<SortInput> for Sort {
     }
 }
 
+#[cfg(feature = "graphql")]
+impl From<Sort> for SortInput {
+    #[inline]
+    fn from(sort: Sort) -> Self {
+        SortInput {
+            field: sort.field,
+            order: sort.order,
+            mode: sort.mode,
+        }
+    }
+}
+
 // TODO: re-use the serializer from the input type
 impl Serialize for Sort {
     #[inline]
@@ -202,6 +249,14 @@ impl<'de> Deserialize<'de> for Sort {
                 formatter.write_str("a `Sort`")
             }
 
+            fn visit_str<E: de::Error>(self, field: &str) -> Result<Self::Value, E> {
+                Ok(Sort {
+                    field: field.to_string(),
+                    order: None,
+                    mode: None,
+                })
+            }
+
             fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
             where
                 A: serde::de::MapAccess<'de>,
@@ -210,20 +265,36 @@ impl<'de> Deserialize<'de> for Sort {
                     .next_key::<String>()?
                     .ok_or_else(|| de::Error::missing_field("field"))?;
 
-                let inner: InnerSortValue = map.next_value()?;
-
-                Ok(Sort {
-                    field,
-                    order: inner.order,
-                    mode: inner.mode,
-                })
+                let sort = match map.next_value()? {
+                    ShorthandSortValue::Order(order) => Sort {
+                        field,
+                        order: Some(order),
+                        mode: None,
+                    },
+                    ShorthandSortValue::Options(inner) => Sort {
+                        field,
+                        order: inner.order,
+                        mode: inner.mode,
+                    },
+                };
+
+                Ok(sort)
             }
         }
 
-        deserializer.deserialize_map(SortVisitor)
+        deserializer.deserialize_any(SortVisitor)
     }
 }
 
+/// Sorting criteria, which Elasticsearch also accepts as just the order, e.g.
+/// `{ "created_at": "desc" }`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ShorthandSortValue {
+    Order(SortOrder),
+    Options(InnerSortValue),
+}
+
 /// Sorting criteria in a format suitable for Elasticsearch.
 #[derive(Serialize, Deserialize)]
 struct InnerSortValue {
@@ -390,4 +461,23 @@ mod tests {
 
         assert_eq!(actual, expected, "{:#?}", &actual);
     }
+
+    #[test]
+    fn can_deserialize_shorthands() {
+        let actual: Sort = serde_json::from_value(json!("created_at")).unwrap();
+        let expected = Sort {
+            field: "created_at".to_string(),
+            mode: None,
+            order: None,
+        };
+        assert_eq!(actual, expected, "{:#?}", &actual);
+
+        let actual: Sort = serde_json::from_value(json!({ "created_at": "desc" })).unwrap();
+        let expected = Sort {
+            field: "created_at".to_string(),
+            mode: None,
+            order: Some(SortOrder::Desc),
+        };
+        assert_eq!(actual, expected, "{:#?}", &actual);
+    }
 }
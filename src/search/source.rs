@@ -0,0 +1,305 @@
+//! [Source filtering] options.
+//!
+//! [Source filtering]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-fields.html#source-filtering
+
+use serde::{
+    ser::{SerializeMap, Serializer},
+    Deserialize, Deserializer, Serialize,
+};
+
+/// [Source filtering] options, i.e. whether or not to return the `_source` of
+/// matching documents and which of its fields.
+///
+/// Serialized as `false` if disabled, `true` if no fields are given, a list of
+/// fields if only `includes` are given and an object otherwise.
+///
+/// [Source filtering]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-fields.html#source-filtering
+#[cfg(feature = "graphql")]
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[derive(async_graphql::InputObject, Clone, Debug)]
+pub struct SourceFilterInput {
+    /// Whether or not to return the `_source` at all. Defaults to `true`.
+    #[graphql(default = true)]
+    #[cfg_attr(feature = "builder", builder(default_code = "true"))]
+    pub enabled: bool,
+
+    /// The fields to return, which may contain wildcards (`*`). Defaults to
+    /// all fields.
+    #[graphql(default)]
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub includes: Vec<String>,
+
+    /// The fields to exclude from the returned `includes`, which may contain
+    /// wildcards (`*`).
+    #[graphql(default)]
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub excludes: Vec<String>,
+}
+
+#[cfg(feature = "graphql")]
+impl From<SourceFilter> for SourceFilterInput {
+    #[inline]
+    fn from(filter: SourceFilter) -> Self {
+        SourceFilterInput {
+            enabled: filter.enabled,
+            includes: filter.includes,
+            excludes: filter.excludes,
+        }
+    }
+}
+
+#[cfg(feature = "graphql")]
+impl Serialize for SourceFilterInput {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(self.enabled, &self.includes, &self.excludes, serializer)
+    }
+}
+
+/// [Source filtering] options, i.e. whether or not to return the `_source` of
+/// matching documents and which of its fields.
+///
+/// Serialized as `false` if disabled, `true` if no fields are given, a list of
+/// fields if only `includes` are given and an object otherwise.
+///
+/// [Source filtering]: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-fields.html#source-filtering
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[derive(Clone, PartialEq, Debug)]
+pub struct SourceFilter {
+    /// Whether or not to return the `_source` at all. Defaults to `true`.
+    #[cfg_attr(feature = "builder", builder(default_code = "true"))]
+    pub enabled: bool,
+
+    /// The fields to return, which may contain wildcards (`*`). Defaults to
+    /// all fields.
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub includes: Vec<String>,
+
+    /// The fields to exclude from the returned `includes`, which may contain
+    /// wildcards (`*`).
+    #[cfg_attr(feature = "builder", builder(default, setter(into)))]
+    pub excludes: Vec<String>,
+}
+
+impl SourceFilter {
+    /// Constructs a new `SourceFilter` which returns the `includes` fields,
+    /// except for the `excludes` fields.
+    #[inline]
+    pub fn new<I, E, T, U>(includes: I, excludes: E) -> SourceFilter
+    where
+        I: IntoIterator<Item = T>,
+        E: IntoIterator<Item = U>,
+        T: Into<String>,
+        U: Into<String>,
+    {
+        SourceFilter {
+            enabled: true,
+            includes: includes.into_iter().map(Into::into).collect(),
+            excludes: excludes.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Constructs a new `SourceFilter` which doesn't return the `_source` at
+    /// all.
+    #[inline]
+    pub fn disabled() -> SourceFilter {
+        SourceFilter {
+            enabled: false,
+            includes: vec![],
+            excludes: vec![],
+        }
+    }
+}
+
+impl Default for SourceFilter {
+    #[inline]
+    fn default() -> Self {
+        SourceFilter {
+            enabled: true,
+            includes: vec![],
+            excludes: vec![],
+        }
+    }
+}
+
+impl From<bool> for SourceFilter {
+    #[inline]
+    fn from(enabled: bool) -> Self {
+        if enabled {
+            SourceFilter::default()
+        } else {
+            SourceFilter::disabled()
+        }
+    }
+}
+
+impl From<Vec<String>> for SourceFilter {
+    #[inline]
+    fn from(includes: Vec<String>) -> Self {
+        SourceFilter {
+            includes,
+            ..SourceFilter::default()
+        }
+    }
+}
+
+#[cfg(feature = "graphql")]
+impl From<SourceFilterInput> for SourceFilter {
+    #[inline]
+    fn from(input: SourceFilterInput) -> Self {
+        SourceFilter {
+            enabled: input.enabled,
+            includes: input.includes,
+            excludes: input.excludes,
+        }
+    }
+}
+
+impl Serialize for SourceFilter {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(self.enabled, &self.includes, &self.excludes, serializer)
+    }
+}
+
+/// Serializes a source filter in its most compact form.
+fn serialize<S: Serializer>(
+    enabled: bool,
+    includes: &[String],
+    excludes: &[String],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if !enabled {
+        serializer.serialize_bool(false)
+    } else if excludes.is_empty() {
+        if includes.is_empty() {
+            serializer.serialize_bool(true)
+        } else {
+            includes.serialize(serializer)
+        }
+    } else {
+        let mut map = serializer.serialize_map(None)?;
+        if !includes.is_empty() {
+            map.serialize_entry("includes", includes)?;
+        }
+        map.serialize_entry("excludes", excludes)?;
+        map.end()
+    }
+}
+
+/// Either a single field or a list of fields.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Default for OneOrMany {
+    #[inline]
+    fn default() -> Self {
+        OneOrMany::Many(vec![])
+    }
+}
+
+impl From<OneOrMany> for Vec<String> {
+    #[inline]
+    fn from(fields: OneOrMany) -> Self {
+        match fields {
+            OneOrMany::One(field) => vec![field],
+            OneOrMany::Many(fields) => fields,
+        }
+    }
+}
+
+/// All the forms of `_source` accepted by Elasticsearch.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum InnerSourceFilter {
+    Enabled(bool),
+    Includes(OneOrMany),
+    Filter {
+        #[serde(default, alias = "include")]
+        includes: OneOrMany,
+        #[serde(default, alias = "exclude")]
+        excludes: OneOrMany,
+    },
+}
+
+impl<'de> Deserialize<'de> for SourceFilter {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match InnerSourceFilter::deserialize(deserializer)? {
+            InnerSourceFilter::Enabled(enabled) => enabled.into(),
+            InnerSourceFilter::Includes(includes) => Vec::from(includes).into(),
+            InnerSourceFilter::Filter { includes, excludes } => {
+                SourceFilter::new(Vec::from(includes), Vec::from(excludes))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    macro_rules! test_case {
+        ($name:ident : $f:expr, $j:expr) => {
+            mod $name {
+                use super::*;
+
+                #[test]
+                fn can_serialize() {
+                    assert_eq!(serde_json::to_value(&$f).unwrap(), $j);
+                }
+
+                #[test]
+                fn can_deserialize() {
+                    assert_eq!(serde_json::from_value::<SourceFilter>($j).unwrap(), $f);
+                }
+            }
+        };
+    }
+
+    test_case!(enabled: SourceFilter::default(), json!(true));
+
+    test_case!(disabled: SourceFilter::disabled(), json!(false));
+
+    test_case!(
+        includes:
+        SourceFilter::new(vec!["user.*", "title"], Vec::<String>::new()),
+        json!(["user.*", "title"])
+    );
+
+    test_case!(
+        excludes:
+        SourceFilter::new(Vec::<String>::new(), vec!["*.secret"]),
+        json!({ "excludes": ["*.secret"] })
+    );
+
+    test_case!(
+        includes_and_excludes:
+        SourceFilter::new(vec!["user.*"], vec!["user.password"]),
+        json!({ "includes": ["user.*"], "excludes": ["user.password"] })
+    );
+
+    #[test]
+    fn can_deserialize_shorthands() {
+        let filter: SourceFilter = serde_json::from_value(json!("title")).unwrap();
+        assert_eq!(
+            filter,
+            SourceFilter::new(vec!["title"], Vec::<String>::new())
+        );
+
+        let filter: SourceFilter =
+            serde_json::from_value(json!({ "include": "user.*", "exclude": ["user.password"] }))
+                .unwrap();
+        assert_eq!(
+            filter,
+            SourceFilter::new(vec!["user.*"], vec!["user.password"])
+        );
+    }
+}
@@ -0,0 +1,867 @@
+//! [Text analysis] components, i.e. analyzers, tokenizers, token filters,
+//! char filters and normalizers.
+//!
+//! [Text analysis]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis.html
+
+use std::collections::BTreeMap;
+
+use serde::{
+    de::{self, DeserializeOwned, Deserializer},
+    ser::{self, Serializer},
+    Deserialize, Serialize,
+};
+use serde_json::{Map, Value};
+
+use super::lenient;
+
+/// The custom [analysis] components of an index, by name.
+///
+/// [analysis]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct Analysis {
+    /// The analyzers, which may be referenced by `text` fields or queries.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub analyzer: BTreeMap<String, Analyzer>,
+
+    /// The tokenizers, which may be referenced by custom analyzers.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tokenizer: BTreeMap<String, Tokenizer>,
+
+    /// The token filters, which may be referenced by custom analyzers or
+    /// normalizers.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub filter: BTreeMap<String, TokenFilter>,
+
+    /// The char filters, which may be referenced by custom analyzers or
+    /// normalizers.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub char_filter: BTreeMap<String, CharFilter>,
+
+    /// The normalizers, which may be referenced by `keyword` fields.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub normalizer: BTreeMap<String, Normalizer>,
+}
+
+/// Defines an enum of analysis components that are internally tagged by
+/// `type`, falling back to `Other` for the types without a variant.
+macro_rules! tagged {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident($ty:ty) = $tag:literal,
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(PartialEq, Clone, Debug)]
+        pub enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant($ty),
+            )*
+
+            /// Any other type, with its raw parameters.
+            Other {
+                /// The type, e.g. `fingerprint`.
+                ty: String,
+
+                /// The parameters, excluding `type`.
+                parameters: Map<String, Value>,
+            },
+        }
+
+        impl $name {
+            /// The name of the type, e.g. `custom`.
+            pub fn ty(&self) -> &str {
+                match self {
+                    $($name::$variant(_) => $tag,)*
+                    $name::Other { ty, .. } => ty,
+                }
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let parameters = match self {
+                    $($name::$variant(parameters) => serde_json::to_value(parameters),)*
+                    $name::Other { parameters, .. } => Ok(Value::Object(parameters.clone())),
+                };
+                let mut parameters = match parameters.map_err(ser::Error::custom)? {
+                    Value::Object(parameters) => parameters,
+                    _ => unreachable!("parameters are always objects"),
+                };
+                parameters.insert("type".to_string(), self.ty().into());
+                parameters.serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let mut parameters = Map::deserialize(deserializer)?;
+                let ty = match parameters.remove("type") {
+                    Some(Value::String(ty)) => ty,
+                    // analyzers with a `tokenizer` are `custom` by default
+                    None if parameters.contains_key("tokenizer") => "custom".to_string(),
+                    _ => return Err(de::Error::missing_field("type")),
+                };
+                Ok(match ty.as_str() {
+                    $($tag => $name::$variant(from_parameters(parameters)?),)*
+                    _ => $name::Other { ty, parameters },
+                })
+            }
+        }
+    };
+}
+
+/// Deserializes the `parameters` of a known type.
+#[inline]
+fn from_parameters<T: DeserializeOwned, E: de::Error>(
+    parameters: Map<String, Value>,
+) -> Result<T, E> {
+    serde_json::from_value(Value::Object(parameters)).map_err(E::custom)
+}
+
+/// A list of [stop words], either predefined (e.g. `_english_`) or explicit.
+///
+/// [stop words]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-stop-tokenfilter.html
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(untagged)]
+pub enum Stopwords {
+    /// A predefined list, e.g. `_english_` or `_none_`.
+    Predefined(String),
+
+    /// An explicit list.
+    List(Vec<String>),
+}
+
+tagged! {
+    /// An [analyzer], which converts text into tokens, e.g. for `text` fields.
+    ///
+    /// [analyzer]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-analyzers.html
+    pub enum Analyzer {
+        /// A [`custom`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-custom-analyzer.html)
+        /// analyzer, which combines a tokenizer with char and token filters.
+        Custom(CustomAnalyzer) = "custom",
+
+        /// A configured [`standard`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-standard-analyzer.html)
+        /// analyzer.
+        Standard(StandardAnalyzer) = "standard",
+
+        /// A configured [`stop`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-stop-analyzer.html)
+        /// analyzer.
+        Stop(StopAnalyzer) = "stop",
+
+        /// A configured [`pattern`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-pattern-analyzer.html)
+        /// analyzer.
+        Pattern(PatternAnalyzer) = "pattern",
+    }
+}
+
+/// The parameters of a [`custom`] analyzer.
+///
+/// [`custom`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-custom-analyzer.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct CustomAnalyzer {
+    /// The name of a built-in or custom tokenizer.
+    #[cfg_attr(feature = "builder", builder(!default))]
+    pub tokenizer: String,
+
+    /// The names of the built-in or custom char filters to apply before
+    /// tokenizing, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub char_filter: Vec<String>,
+
+    /// The names of the built-in or custom token filters to apply after
+    /// tokenizing, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filter: Vec<String>,
+
+    /// The number of fake positions between the values of multi-valued
+    /// fields. Defaults to `100`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub position_increment_gap: Option<u32>,
+
+    /// Any other parameters, e.g. `position_offset_gap` of older versions.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [`standard`] analyzer.
+///
+/// [`standard`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-standard-analyzer.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct StandardAnalyzer {
+    /// The maximum length of a token; longer tokens are split. Defaults to
+    /// `255`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_token_length: Option<u32>,
+
+    /// The stop words to remove. Defaults to `_none_`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stopwords: Option<Stopwords>,
+
+    /// Any other parameters, e.g. `stopwords_path`.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [`stop`] analyzer.
+///
+/// [`stop`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-stop-analyzer.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct StopAnalyzer {
+    /// The stop words to remove. Defaults to `_english_`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stopwords: Option<Stopwords>,
+
+    /// The path to a file of stop words, relative to the `config` directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stopwords_path: Option<String>,
+
+    /// Any other parameters.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [`pattern`] analyzer.
+///
+/// [`pattern`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-pattern-analyzer.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct PatternAnalyzer {
+    /// The (Java) regular expression that matches the separators between
+    /// tokens. Defaults to `\W+`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+
+    /// The (Java) regular expression flags, separated by `|`, e.g.
+    /// `CASE_INSENSITIVE|COMMENTS`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flags: Option<String>,
+
+    /// Whether to lowercase the tokens. Defaults to `true`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub lowercase: Option<bool>,
+
+    /// The stop words to remove. Defaults to `_none_`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stopwords: Option<Stopwords>,
+
+    /// Any other parameters, e.g. `stopwords_path`.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+tagged! {
+    /// A [tokenizer], which splits text into tokens.
+    ///
+    /// [tokenizer]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-tokenizers.html
+    pub enum Tokenizer {
+        /// A configured [`standard`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-standard-tokenizer.html)
+        /// tokenizer.
+        Standard(StandardTokenizer) = "standard",
+
+        /// A [`pattern`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-pattern-tokenizer.html)
+        /// tokenizer.
+        Pattern(PatternTokenizer) = "pattern",
+
+        /// An [`ngram`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-ngram-tokenizer.html)
+        /// tokenizer.
+        NGram(NGramTokenizer) = "ngram",
+
+        /// An [`edge_ngram`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-edgengram-tokenizer.html)
+        /// tokenizer.
+        EdgeNGram(NGramTokenizer) = "edge_ngram",
+    }
+}
+
+/// The parameters of a [`standard`] tokenizer.
+///
+/// [`standard`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-standard-tokenizer.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct StandardTokenizer {
+    /// The maximum length of a token; longer tokens are split. Defaults to
+    /// `255`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_token_length: Option<u32>,
+
+    /// Any other parameters.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [`pattern`] tokenizer.
+///
+/// [`pattern`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-pattern-tokenizer.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct PatternTokenizer {
+    /// The (Java) regular expression. Defaults to `\W+`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+
+    /// The (Java) regular expression flags, separated by `|`, e.g.
+    /// `CASE_INSENSITIVE|COMMENTS`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flags: Option<String>,
+
+    /// Which capture group to use as tokens; `-1` (the default) splits on
+    /// matches instead.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub group: Option<i32>,
+
+    /// Any other parameters.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of an [`ngram`] or [`edge_ngram`] tokenizer.
+///
+/// [`ngram`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-ngram-tokenizer.html
+/// [`edge_ngram`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-edgengram-tokenizer.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct NGramTokenizer {
+    /// The minimum length of a gram. Defaults to `1`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub min_gram: Option<u32>,
+
+    /// The maximum length of a gram. Defaults to `2`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_gram: Option<u32>,
+
+    /// The classes of characters to keep in tokens, e.g. `letter` or
+    /// `digit`. Defaults to all characters.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub token_chars: Vec<String>,
+
+    /// Any other parameters, e.g. `custom_token_chars`.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+tagged! {
+    /// A [token filter], which adds, removes or changes tokens.
+    ///
+    /// [token filter]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-tokenfilters.html
+    pub enum TokenFilter {
+        /// A [`stop`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-stop-tokenfilter.html)
+        /// filter.
+        Stop(StopTokenFilter) = "stop",
+
+        /// A [`synonym`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-synonym-tokenfilter.html)
+        /// filter.
+        Synonym(SynonymTokenFilter) = "synonym",
+
+        /// A [`synonym_graph`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-synonym-graph-tokenfilter.html)
+        /// filter, for search analyzers.
+        SynonymGraph(SynonymTokenFilter) = "synonym_graph",
+
+        /// A [`stemmer`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-stemmer-tokenfilter.html)
+        /// filter.
+        Stemmer(StemmerTokenFilter) = "stemmer",
+
+        /// A [`length`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-length-tokenfilter.html)
+        /// filter.
+        Length(LengthTokenFilter) = "length",
+
+        /// An [`ngram`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-ngram-tokenfilter.html)
+        /// filter.
+        NGram(NGramTokenFilter) = "ngram",
+
+        /// An [`edge_ngram`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-edgengram-tokenfilter.html)
+        /// filter.
+        EdgeNGram(NGramTokenFilter) = "edge_ngram",
+
+        /// A [`shingle`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-shingle-tokenfilter.html)
+        /// filter.
+        Shingle(ShingleTokenFilter) = "shingle",
+
+        /// A configured [`asciifolding`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-asciifolding-tokenfilter.html)
+        /// filter.
+        AsciiFolding(AsciiFoldingTokenFilter) = "asciifolding",
+    }
+}
+
+/// The parameters of a [`stop`] token filter.
+///
+/// [`stop`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-stop-tokenfilter.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct StopTokenFilter {
+    /// The stop words to remove. Defaults to `_english_`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stopwords: Option<Stopwords>,
+
+    /// The path to a file of stop words, relative to the `config` directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stopwords_path: Option<String>,
+
+    /// Whether to match stop words case-insensitively. Defaults to `false`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub ignore_case: Option<bool>,
+
+    /// Whether to remove the last token if it's a stop word. Defaults to
+    /// `true`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub remove_trailing: Option<bool>,
+
+    /// Any other parameters.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [`synonym`] or [`synonym_graph`] token filter.
+///
+/// [`synonym`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-synonym-tokenfilter.html
+/// [`synonym_graph`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-synonym-graph-tokenfilter.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct SynonymTokenFilter {
+    /// The synonym rules, e.g. `i-pod, i pod => ipod`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub synonyms: Vec<String>,
+
+    /// The path to a file of synonym rules, relative to the `config`
+    /// directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synonyms_path: Option<String>,
+
+    /// Whether equivalent synonyms (i.e. without `=>`) map to all of each
+    /// other. Defaults to `true`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub expand: Option<bool>,
+
+    /// Whether to ignore rules that can't be parsed. Defaults to `false`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub lenient: Option<bool>,
+
+    /// Whether the synonyms can be reloaded without reopening the index,
+    /// which requires using the filter in a search analyzer only.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub updateable: Option<bool>,
+
+    /// Any other parameters, e.g. the `format` of the rules (`solr` or
+    /// `wordnet`).
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [`stemmer`] token filter.
+///
+/// [`stemmer`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-stemmer-tokenfilter.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct StemmerTokenFilter {
+    /// The language, e.g. `light_german`. Defaults to `english`.
+    #[serde(default, alias = "name", skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    /// Any other parameters.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [`length`] token filter.
+///
+/// [`length`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-length-tokenfilter.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct LengthTokenFilter {
+    /// The minimum length of a token. Defaults to `0`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub min: Option<u32>,
+
+    /// The maximum length of a token. Defaults to `2^31 - 1`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max: Option<u32>,
+
+    /// Any other parameters.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of an [`ngram`] or [`edge_ngram`] token filter.
+///
+/// [`ngram`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-ngram-tokenfilter.html
+/// [`edge_ngram`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-edgengram-tokenfilter.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct NGramTokenFilter {
+    /// The minimum length of a gram. Defaults to `1`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub min_gram: Option<u32>,
+
+    /// The maximum length of a gram. Defaults to `2`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_gram: Option<u32>,
+
+    /// Whether to keep the original token too. Defaults to `false`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub preserve_original: Option<bool>,
+
+    /// Any other parameters.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [`shingle`] token filter.
+///
+/// [`shingle`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-shingle-tokenfilter.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct ShingleTokenFilter {
+    /// The minimum number of tokens in a shingle. Defaults to `2`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub min_shingle_size: Option<u32>,
+
+    /// The maximum number of tokens in a shingle. Defaults to `2`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_shingle_size: Option<u32>,
+
+    /// Whether to keep the original (single) tokens too. Defaults to `true`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_unigrams: Option<bool>,
+
+    /// The separator between the tokens of a shingle. Defaults to a space.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_separator: Option<String>,
+
+    /// Any other parameters, e.g. `filler_token`.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of an [`asciifolding`] token filter.
+///
+/// [`asciifolding`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-asciifolding-tokenfilter.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct AsciiFoldingTokenFilter {
+    /// Whether to keep the original token too. Defaults to `false`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub preserve_original: Option<bool>,
+
+    /// Any other parameters.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+tagged! {
+    /// A [char filter], which preprocesses text before it's tokenized.
+    ///
+    /// [char filter]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-charfilters.html
+    pub enum CharFilter {
+        /// An [`html_strip`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-htmlstrip-charfilter.html)
+        /// filter.
+        HtmlStrip(HtmlStripCharFilter) = "html_strip",
+
+        /// A [`mapping`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-mapping-charfilter.html)
+        /// filter.
+        Mapping(MappingCharFilter) = "mapping",
+
+        /// A [`pattern_replace`](https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-pattern-replace-charfilter.html)
+        /// filter.
+        PatternReplace(PatternReplaceCharFilter) = "pattern_replace",
+    }
+}
+
+/// The parameters of an [`html_strip`] char filter.
+///
+/// [`html_strip`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-htmlstrip-charfilter.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct HtmlStripCharFilter {
+    /// The HTML elements (without `<` and `>`) not to strip.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub escaped_tags: Vec<String>,
+
+    /// Any other parameters.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [`mapping`] char filter.
+///
+/// [`mapping`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-mapping-charfilter.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct MappingCharFilter {
+    /// The mappings, e.g. `٠ => 0`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mappings: Vec<String>,
+
+    /// The path to a file of mappings, relative to the `config` directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mappings_path: Option<String>,
+
+    /// Any other parameters.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The parameters of a [`pattern_replace`] char filter.
+///
+/// [`pattern_replace`]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-pattern-replace-charfilter.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+pub struct PatternReplaceCharFilter {
+    /// The (Java) regular expression.
+    #[cfg_attr(feature = "builder", builder(!default))]
+    pub pattern: String,
+
+    /// The replacement, which may refer to capture groups, e.g. `$1`.
+    /// Defaults to an empty string.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
+
+    /// The (Java) regular expression flags, separated by `|`, e.g.
+    /// `CASE_INSENSITIVE|COMMENTS`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flags: Option<String>,
+
+    /// Any other parameters.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// A [normalizer], which is like an analyzer that produces a single token,
+/// e.g. to lowercase `keyword` fields.
+///
+/// [normalizer]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-normalizers.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+#[serde(tag = "type", rename = "custom")]
+pub struct Normalizer {
+    /// The names of the built-in or custom char filters to apply, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub char_filter: Vec<String>,
+
+    /// The names of the built-in or custom token filters to apply, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filter: Vec<String>,
+
+    /// Any other parameters.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn can_round_trip_analysis() {
+        let j = json!({
+            "analyzer": {
+                "autocomplete": {
+                    "type": "custom",
+                    "tokenizer": "autocomplete",
+                    "char_filter": ["html_strip"],
+                    "filter": ["lowercase", "synonyms"],
+                },
+                "english_stop": { "type": "standard", "stopwords": "_english_" },
+                "words": { "type": "stop", "stopwords": ["a", "the"] },
+                "csv": { "type": "pattern", "pattern": ",", "lowercase": false },
+                "fingerprint": { "type": "fingerprint", "max_output_size": 255 },
+            },
+            "tokenizer": {
+                "autocomplete": {
+                    "type": "edge_ngram",
+                    "min_gram": 2,
+                    "max_gram": 10,
+                    "token_chars": ["letter", "digit"],
+                },
+                "dashes": { "type": "pattern", "pattern": "-", "group": -1 },
+                "short": { "type": "standard", "max_token_length": 5 },
+            },
+            "filter": {
+                "synonyms": {
+                    "type": "synonym_graph",
+                    "synonyms": ["i-pod, i pod => ipod"],
+                    "updateable": true,
+                },
+                "german": { "type": "stemmer", "language": "light_german" },
+                "short": { "type": "length", "max": 10 },
+                "shingles": { "type": "shingle", "max_shingle_size": 3 },
+                "folding": { "type": "asciifolding", "preserve_original": true },
+                "stop": { "type": "stop", "ignore_case": true },
+            },
+            "char_filter": {
+                "digits": { "type": "mapping", "mappings": ["٠ => 0"] },
+                "dashes": { "type": "pattern_replace", "pattern": "-", "replacement": "_" },
+                "html": { "type": "html_strip", "escaped_tags": ["b"] },
+            },
+            "normalizer": {
+                "lowercase": { "type": "custom", "filter": ["lowercase", "asciifolding"] },
+            },
+        });
+
+        let analysis: Analysis = serde_json::from_value(j.clone()).unwrap();
+        assert_eq!(analysis.analyzer["fingerprint"].ty(), "fingerprint");
+        assert_eq!(analysis.tokenizer["autocomplete"].ty(), "edge_ngram");
+        assert_eq!(
+            analysis.filter["german"],
+            TokenFilter::Stemmer(StemmerTokenFilter {
+                language: Some("light_german".to_string()),
+                ..StemmerTokenFilter::default()
+            })
+        );
+        assert_eq!(serde_json::to_value(&analysis).unwrap(), j);
+    }
+
+    #[test]
+    fn can_round_trip_unknown_parameters() {
+        let j = json!({
+            "analyzer": {
+                "autocomplete": {
+                    "type": "custom",
+                    "tokenizer": "standard",
+                    "position_offset_gap": 10,
+                },
+            },
+            "tokenizer": {
+                "grams": { "type": "ngram", "token_chars": ["custom"], "custom_token_chars": "+-" },
+            },
+            "filter": {
+                "synonyms": {
+                    "type": "synonym",
+                    "synonyms_path": "wordnet.pl",
+                    "format": "wordnet",
+                },
+                "shingles": { "type": "shingle", "filler_token": "" },
+            },
+            "normalizer": {
+                "lowercase": { "type": "custom", "filter": ["lowercase"], "bogus": 1 },
+            },
+        });
+
+        let analysis: Analysis = serde_json::from_value(j.clone()).unwrap();
+        match &analysis.filter["synonyms"] {
+            TokenFilter::Synonym(synonym) => assert_eq!(synonym.other["format"], "wordnet"),
+            filter => panic!("unexpected filter: {:?}", filter),
+        }
+        assert_eq!(serde_json::to_value(&analysis).unwrap(), j);
+    }
+
+    #[test]
+    fn analyzers_with_a_tokenizer_are_custom() {
+        let analyzer: Analyzer =
+            serde_json::from_value(json!({ "tokenizer": "whitespace" })).unwrap();
+        assert_eq!(analyzer.ty(), "custom");
+
+        assert!(serde_json::from_value::<TokenFilter>(json!({ "min": 1 })).is_err());
+    }
+}
@@ -0,0 +1,204 @@
+//! [Index settings], including the [analysis] (i.e. custom analyzers,
+//! tokenizers, token filters, char filters and normalizers) of an index.
+//!
+//! ```
+//! use elastiql::settings::{Analysis, Analyzer, CustomAnalyzer, IndexSettings};
+//! use serde_json::json;
+//!
+//! let mut analysis = Analysis::default();
+//! analysis.analyzer.insert(
+//!     "folding".to_string(),
+//!     Analyzer::Custom(CustomAnalyzer {
+//!         tokenizer: "standard".to_string(),
+//!         filter: vec!["lowercase".to_string(), "asciifolding".to_string()],
+//!         ..CustomAnalyzer::default()
+//!     }),
+//! );
+//!
+//! let settings = IndexSettings {
+//!     number_of_shards: Some(1),
+//!     analysis: Some(analysis),
+//!     ..IndexSettings::default()
+//! };
+//! assert_eq!(
+//!     serde_json::to_value(&settings).unwrap(),
+//!     json!({
+//!         "number_of_shards": 1,
+//!         "analysis": {
+//!             "analyzer": {
+//!                 "folding": {
+//!                     "type": "custom",
+//!                     "tokenizer": "standard",
+//!                     "filter": ["lowercase", "asciifolding"],
+//!                 },
+//!             },
+//!         },
+//!     })
+//! );
+//! ```
+//!
+//! [Index settings]: https://www.elastic.co/guide/en/elasticsearch/reference/current/index-modules.html#index-modules-settings
+//! [analysis]: https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis.html
+
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+
+pub use self::analysis::*;
+
+mod analysis;
+
+/// The [settings] of an index.
+///
+/// Deserializes the forms returned by Elasticsearch too, i.e. settings nested
+/// under `index` (or prefixed with `index.`) and numbers as strings.
+///
+/// [settings]: https://www.elastic.co/guide/en/elasticsearch/reference/current/index-modules.html#index-modules-settings
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+#[serde(remote = "Self")]
+pub struct IndexSettings {
+    /// The number of primary shards; can only be set when creating an index.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub number_of_shards: Option<u32>,
+
+    /// The number of replicas of each primary shard.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub number_of_replicas: Option<u32>,
+
+    /// How often to make recent changes visible to search, e.g. `1s` (the
+    /// default) or `-1` to disable refreshing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_interval: Option<String>,
+
+    /// The maximum value of `from + size` when searching. Defaults to
+    /// `10000`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_result_window: Option<u64>,
+
+    /// The custom analyzers, tokenizers, token filters, char filters and
+    /// normalizers of the index.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analysis: Option<Analysis>,
+
+    /// Any other settings, e.g. `mapping.total_fields.limit`.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Serialize for IndexSettings {
+    #[inline]
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        IndexSettings::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for IndexSettings {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut settings = Map::deserialize(deserializer)?;
+
+        // `{ "index": { ... } }` and `{ "index.refresh_interval": ... }`
+        if let Some(Value::Object(index)) = settings.remove("index") {
+            settings.extend(index);
+        }
+        let settings: Map<String, Value> = settings
+            .into_iter()
+            .map(|(key, value)| match key.strip_prefix("index.") {
+                Some(key) => (key.to_string(), value),
+                None => (key, value),
+            })
+            .collect();
+
+        IndexSettings::deserialize(Value::Object(settings)).map_err(de::Error::custom)
+    }
+}
+
+/// Deserializes an optional number or boolean that may be a string, e.g.
+/// `"1"`, as Elasticsearch returns all settings as strings.
+pub(crate) fn lenient<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    /// A value or its string representation.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ValueOrString<T> {
+        Value(T),
+        String(String),
+    }
+
+    match Option::<ValueOrString<T>>::deserialize(deserializer)? {
+        Some(ValueOrString::Value(value)) => Ok(Some(value)),
+        Some(ValueOrString::String(value)) => value.parse().map(Some).map_err(de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn can_round_trip_settings() {
+        let j = json!({
+            "number_of_shards": 3,
+            "number_of_replicas": 1,
+            "refresh_interval": "30s",
+            "max_result_window": 50000,
+            "mapping.total_fields.limit": 2000,
+        });
+        let settings: IndexSettings = serde_json::from_value(j.clone()).unwrap();
+        assert_eq!(settings.number_of_shards, Some(3));
+        assert_eq!(settings.other["mapping.total_fields.limit"], 2000);
+        assert_eq!(serde_json::to_value(&settings).unwrap(), j);
+    }
+
+    #[test]
+    fn can_deserialize_settings_from_elasticsearch() {
+        let settings: IndexSettings = serde_json::from_value(json!({
+            "index": {
+                "number_of_shards": "1",
+                "number_of_replicas": "0",
+                "analysis": {
+                    "filter": {
+                        "autocomplete": { "type": "edge_ngram", "min_gram": "2", "max_gram": "10" },
+                    },
+                },
+            },
+            "index.refresh_interval": "-1",
+        }))
+        .unwrap();
+
+        assert_eq!(settings.number_of_shards, Some(1));
+        assert_eq!(settings.number_of_replicas, Some(0));
+        assert_eq!(settings.refresh_interval.as_deref(), Some("-1"));
+        assert_eq!(
+            settings.analysis.unwrap().filter["autocomplete"],
+            TokenFilter::EdgeNGram(NGramTokenFilter {
+                min_gram: Some(2),
+                max_gram: Some(10),
+                ..NGramTokenFilter::default()
+            })
+        );
+
+        assert!(
+            serde_json::from_value::<IndexSettings>(json!({ "number_of_shards": "x" })).is_err()
+        );
+    }
+}
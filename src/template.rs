@@ -0,0 +1,311 @@
+//! Request and response types for [index templates] and [component
+//! templates], which apply settings, mappings and aliases to new indices
+//! (e.g. rolled over ones) whose names match a pattern.
+//!
+//! ```
+//! use elastiql::{
+//!     mapping::{FieldMapping, Mapping},
+//!     settings::IndexSettings,
+//!     template::{IndexTemplate, Template},
+//! };
+//! use serde_json::json;
+//!
+//! let mut mappings = Mapping::default();
+//! mappings.properties.insert("@timestamp".to_string(), FieldMapping::date());
+//!
+//! let template = IndexTemplate {
+//!     index_patterns: vec!["logs-*".to_string()],
+//!     priority: Some(100),
+//!     composed_of: vec!["logs-settings".to_string()],
+//!     template: Some(Template {
+//!         settings: Some(IndexSettings {
+//!             number_of_replicas: Some(1),
+//!             ..IndexSettings::default()
+//!         }),
+//!         mappings: Some(mappings),
+//!         ..Template::default()
+//!     }),
+//!     ..IndexTemplate::default()
+//! };
+//! assert_eq!(
+//!     serde_json::to_value(&template).unwrap(),
+//!     json!({
+//!         "index_patterns": ["logs-*"],
+//!         "priority": 100,
+//!         "composed_of": ["logs-settings"],
+//!         "template": {
+//!             "settings": { "number_of_replicas": 1 },
+//!             "mappings": { "properties": { "@timestamp": { "type": "date" } } },
+//!         },
+//!     })
+//! );
+//! ```
+//!
+//! [index templates]: https://www.elastic.co/guide/en/elasticsearch/reference/current/index-templates.html
+//! [component templates]: https://www.elastic.co/guide/en/elasticsearch/reference/current/indices-component-template.html
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{mapping::Mapping, search::query::CompoundQuery, settings::IndexSettings};
+
+/// An [index template], i.e. the body of a `PUT _index_template/<name>`
+/// request.
+///
+/// [index template]: https://www.elastic.co/guide/en/elasticsearch/reference/current/indices-put-template.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct IndexTemplate {
+    /// The patterns (e.g. `logs-*`) of the names of the indices (or data
+    /// streams) that the template applies to.
+    #[cfg_attr(feature = "builder", builder(!default))]
+    #[serde(deserialize_with = "patterns")]
+    pub index_patterns: Vec<String>,
+
+    /// The priority of the template when more than one matches a new index;
+    /// only the one with the highest priority is applied. Defaults to `0`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u64>,
+
+    /// The names of the component templates to merge, in order, before the
+    /// `template` of this template.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub composed_of: Vec<String>,
+
+    /// The settings, mappings and aliases to apply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<Template>,
+
+    /// A version number for managing the template externally; unused by
+    /// Elasticsearch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+
+    /// Arbitrary metadata about the template; unused by Elasticsearch.
+    #[serde(default, rename = "_meta", skip_serializing_if = "Map::is_empty")]
+    pub meta: Map<String, Value>,
+}
+
+/// A [component template], i.e. the body of a `PUT _component_template/<name>`
+/// request, which is a reusable building block of index templates.
+///
+/// [component template]: https://www.elastic.co/guide/en/elasticsearch/reference/current/indices-component-template.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct ComponentTemplate {
+    /// The settings, mappings and aliases to apply.
+    #[serde(default)]
+    pub template: Template,
+
+    /// A version number for managing the template externally; unused by
+    /// Elasticsearch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+
+    /// Arbitrary metadata about the template; unused by Elasticsearch.
+    #[serde(default, rename = "_meta", skip_serializing_if = "Map::is_empty")]
+    pub meta: Map<String, Value>,
+}
+
+/// The settings, mappings and aliases of an index or component template.
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct Template {
+    /// The settings of the index.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<IndexSettings>,
+
+    /// The mapping of the index.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mappings: Option<Mapping>,
+
+    /// The aliases of the index, by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, Alias>,
+}
+
+/// An [alias] of an index.
+///
+/// [alias]: https://www.elastic.co/guide/en/elasticsearch/reference/current/aliases.html
+#[cfg_attr(feature = "builder", derive(typed_builder::TypedBuilder))]
+#[cfg_attr(feature = "builder", builder(field_defaults(default, setter(into))))]
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct Alias {
+    /// Limits the documents that can be accessed through the alias.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<CompoundQuery>,
+
+    /// The routing of both indexing and search operations through the alias.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routing: Option<String>,
+
+    /// The routing of indexing operations through the alias.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_routing: Option<String>,
+
+    /// The routing of search operations through the alias.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_routing: Option<String>,
+
+    /// Whether indexing operations through the alias go to this index, when
+    /// the alias points to more than one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_write_index: Option<bool>,
+
+    /// Whether the alias is hidden, i.e. excluded from wildcard expressions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_hidden: Option<bool>,
+}
+
+/// The response of a `GET _index_template` request.
+#[derive(Deserialize, Debug)]
+pub struct IndexTemplatesResponse {
+    /// The matching index templates.
+    #[serde(default)]
+    pub index_templates: Vec<NamedIndexTemplate>,
+}
+
+/// An index template and its name.
+#[derive(Deserialize, Debug)]
+pub struct NamedIndexTemplate {
+    /// The name of the template.
+    pub name: String,
+
+    /// The template.
+    pub index_template: IndexTemplate,
+}
+
+/// The response of a `GET _component_template` request.
+#[derive(Deserialize, Debug)]
+pub struct ComponentTemplatesResponse {
+    /// The matching component templates.
+    #[serde(default)]
+    pub component_templates: Vec<NamedComponentTemplate>,
+}
+
+/// A component template and its name.
+#[derive(Deserialize, Debug)]
+pub struct NamedComponentTemplate {
+    /// The name of the template.
+    pub name: String,
+
+    /// The template.
+    pub component_template: ComponentTemplate,
+}
+
+/// Deserializes a list of index patterns, or a single pattern as a list of
+/// one.
+fn patterns<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    /// Either a single pattern or a list of patterns.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(pattern) => vec![pattern],
+        OneOrMany::Many(patterns) => patterns,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::mapping::FieldMapping;
+
+    #[test]
+    fn can_round_trip_index_templates() {
+        let j = json!({
+            "index_patterns": ["logs-*", "metrics-*"],
+            "priority": 200,
+            "composed_of": ["base", "lifecycle"],
+            "template": {
+                "settings": {
+                    "number_of_shards": 2,
+                    "lifecycle.name": "rollover-7d",
+                    "lifecycle.rollover_alias": "logs",
+                },
+                "mappings": {
+                    "dynamic": "strict",
+                    "properties": { "message": { "type": "text" } },
+                },
+                "aliases": {
+                    "logs": { "is_write_index": true },
+                    "errors": {
+                        "filter": { "bool": { "filter": [{ "term": { "level": { "value": "error" } } }] } },
+                        "routing": "1",
+                    },
+                },
+            },
+            "version": 3,
+            "_meta": { "owner": "search" },
+        });
+
+        let template: IndexTemplate = serde_json::from_value(j.clone()).unwrap();
+        assert_eq!(template.priority, Some(200));
+        let inner = template.template.as_ref().unwrap();
+        assert_eq!(
+            inner.settings.as_ref().unwrap().other["lifecycle.name"],
+            "rollover-7d"
+        );
+        assert_eq!(
+            inner.mappings.as_ref().unwrap().properties["message"],
+            FieldMapping::text()
+        );
+        assert_eq!(inner.aliases["logs"].is_write_index, Some(true));
+        assert_eq!(serde_json::to_value(&template).unwrap(), j);
+    }
+
+    #[test]
+    fn can_deserialize_templates_from_elasticsearch() {
+        let response: IndexTemplatesResponse = serde_json::from_value(json!({
+            "index_templates": [{
+                "name": "logs",
+                "index_template": {
+                    "index_patterns": "logs-*",
+                    "composed_of": [],
+                    "template": {
+                        "settings": { "index": { "number_of_replicas": "0" } },
+                        "aliases": { "logs": {} },
+                    },
+                },
+            }],
+        }))
+        .unwrap();
+        let template = &response.index_templates[0];
+        assert_eq!(template.name, "logs");
+        assert_eq!(template.index_template.index_patterns, vec!["logs-*"]);
+        let inner = template.index_template.template.as_ref().unwrap();
+        assert_eq!(inner.settings.as_ref().unwrap().number_of_replicas, Some(0));
+        assert!(inner.aliases.contains_key("logs"));
+
+        let response: ComponentTemplatesResponse = serde_json::from_value(json!({
+            "component_templates": [{
+                "name": "base",
+                "component_template": {
+                    "template": { "mappings": { "properties": { "@timestamp": { "type": "date" } } } },
+                    "version": 1,
+                    "_meta": { "managed": true },
+                },
+            }],
+        }))
+        .unwrap();
+        let template = &response.component_templates[0].component_template;
+        assert_eq!(template.version, Some(1));
+        assert_eq!(template.meta["managed"], true);
+        assert_eq!(
+            serde_json::to_value(template).unwrap()["template"],
+            json!({ "mappings": { "properties": { "@timestamp": { "type": "date" } } } })
+        );
+    }
+}